@@ -0,0 +1,64 @@
+//! Compares the legacy binary encoding with the columnar one on large flat lists.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fast_desmos2_comms::{List, Serde, Value};
+use glam::DVec2;
+
+const LENS: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn numbers(len: usize) -> Value {
+    Value::Number(List::Flat((0..len).map(|x| x as f64 * 0.5).collect()))
+}
+
+fn points(len: usize) -> Value {
+    Value::Point(List::Flat(
+        (0..len)
+            .map(|x| DVec2::new(x as f64, -(x as f64)))
+            .collect(),
+    ))
+}
+
+fn bench_value(c: &mut Criterion, name: &str, make: fn(usize) -> Value) {
+    let mut group = c.benchmark_group(name);
+    for len in LENS {
+        let value = make(len);
+        let legacy = value.serialize();
+        let columnar = value.to_columnar();
+        group.throughput(Throughput::Elements(len as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("legacy/encode", len),
+            &value,
+            |b, value| b.iter(|| black_box(value).serialize()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("columnar/encode", len),
+            &value,
+            |b, value| b.iter(|| black_box(value).to_columnar()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("legacy/decode", len),
+            &legacy,
+            |b, data| b.iter(|| Value::deserialize(black_box(data))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("columnar/decode", len),
+            &columnar,
+            |b, data| b.iter(|| Value::from_columnar(black_box(data)).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("columnar/view", len),
+            &columnar,
+            |b, data| b.iter(|| Value::view_columnar(black_box(data)).unwrap().kind()),
+        );
+    }
+    group.finish();
+}
+
+fn encoding(c: &mut Criterion) {
+    bench_value(c, "numbers", numbers);
+    bench_value(c, "points", points);
+}
+
+criterion_group!(benches, encoding);
+criterion_main!(benches);
@@ -1,102 +1,100 @@
 #![allow(unused, clippy::self_named_constructors)]
 
+use message::{Ack, Message, MessageBody};
+#[cfg(feature = "server")]
+pub use server::{ListenAddr, QueuePolicy, RetryPolicy, Server, ServerConfig, ServerStats, Update};
+use std::io;
 use std::io::{ErrorKind, Read, Write};
-use std::mem::replace;
-use std::net::{Ipv4Addr, TcpListener, TcpStream, ToSocketAddrs};
-use std::sync::mpsc;
-use std::sync::mpsc::TryRecvError;
-use std::thread::JoinHandle;
-use std::time::Duration;
-use std::{io, thread};
-pub use value::{List, Serde, TypeMismatch, Value};
+use std::net::{Ipv4Addr, Shutdown, TcpStream, ToSocketAddrs};
+pub use value::{ColumnarError, List, Serde, SerdeError, TextError, TypeMismatch, Value};
 
+pub mod message;
+#[cfg(feature = "server")]
+mod server;
 pub mod value;
 
-#[cfg(feature = "server")]
-pub enum Server {
-    Dead,
-    Alive {
-        join_handle: JoinHandle<io::Error>,
-        rx: mpsc::Receiver<Value>,
-    },
+#[cfg(feature = "client")]
+pub fn send_value_raw<A: ToSocketAddrs>(to: A, numbers: Value) -> io::Result<()> {
+    let mut conn = TcpStream::connect(to)?;
+    conn.write_all(&numbers.serialize())
 }
 
-#[cfg(feature = "server")]
-impl Server {
-    pub fn new_local(port: u16) -> io::Result<Self> {
-        Self::new((Ipv4Addr::LOCALHOST, port))
-    }
-
-    pub fn new<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
-        let listener = TcpListener::bind(addr)?;
-        let (tx, rx) = mpsc::channel();
-        let join_handle = thread::spawn(move || {
-            macro_rules! bail {
-                ($e: expr) => {
-                    match $e {
-                        Ok(e) => e,
-                        Err(e) => return e,
-                    }
-                };
-            }
+#[cfg(feature = "client")]
+pub fn send_columnar_raw<A: ToSocketAddrs>(to: A, numbers: &Value) -> io::Result<()> {
+    let mut conn = TcpStream::connect(to)?;
+    conn.write_all(&numbers.to_columnar())
+}
 
-            loop {
-                let (mut conn, _) = bail!(listener.accept());
-                conn.set_read_timeout(Some(Duration::from_millis(1000)))
-                    // according to documentation, only crashes if duration is zero.
-                    .unwrap_or_else(|_| unreachable!());
+/// Sends a message and waits for the server to acknowledge it.
+#[cfg(feature = "client")]
+pub fn send_message_raw<A: ToSocketAddrs>(to: A, message: &Message) -> io::Result<Ack> {
+    let mut conn = TcpStream::connect(to)?;
+    conn.write_all(&message.encode())?;
+    conn.shutdown(Shutdown::Write)?;
 
-                let mut value = Vec::new();
-                bail!(conn.read_to_end(&mut value));
+    let mut ack = [0];
+    conn.read_exact(&mut ack)?;
+    Ack::from_byte(ack[0]).ok_or_else(|| ErrorKind::InvalidData.into())
+}
 
-                let value = Value::deserialize(&value);
-                bail!(tx.send(value).map_err(io::Error::other));
-            }
-        });
-        Ok(Self::Alive { join_handle, rx })
-    }
+/// Streams values to one channel, sending only what changed since the previous value and falling
+/// back to the full value whenever the server lost track.
+#[cfg(feature = "client")]
+pub struct ChannelSender<A> {
+    addr: A,
+    channel: String,
+    seq: u64,
+    last: Option<Value>,
+}
 
-    fn check_thread_died(&mut self) -> io::Result<()> {
-        if let Server::Alive { join_handle, .. } = self {
-            if join_handle.is_finished() {
-                let old_self = replace(self, Server::Dead);
-                let Server::Alive { join_handle, .. } = old_self else {
-                    unreachable!()
-                };
-                return Err(join_handle.join().unwrap_or_else(|_| unreachable!()));
-            }
+#[cfg(feature = "client")]
+impl<A: ToSocketAddrs> ChannelSender<A> {
+    pub fn new(addr: A, channel: impl Into<String>) -> Self {
+        Self {
+            addr,
+            channel: channel.into(),
+            seq: 0,
+            last: None,
         }
-        Ok(())
     }
 
-    pub fn try_accept_value(&mut self) -> io::Result<Value> {
-        self.check_thread_died()?;
-        match self {
-            Server::Dead => Err(ErrorKind::NotConnected.into()),
-            Server::Alive { join_handle, rx } => rx.try_recv().map_err(|err| match err {
-                TryRecvError::Empty => ErrorKind::WouldBlock.into(),
-                // this is required since the thread can die between checking and receiving.
-                TryRecvError::Disconnected => ErrorKind::WouldBlock.into(),
-            }),
+    pub fn send(&mut self, value: Value) -> io::Result<()> {
+        if let Some(last) = &self.last {
+            self.seq += 1;
+            let message = Message::patch(&self.channel, self.seq, value.diff(last));
+            match send_message_raw(&self.addr, &message) {
+                Ok(Ack::Applied) => {
+                    self.last = Some(value);
+                    return Ok(());
+                }
+                Ok(Ack::Resync) => {}
+                Err(err) => {
+                    self.last = None;
+                    return Err(err);
+                }
+            }
         }
-    }
 
-    /// Blocking
-    pub fn accept_value(&mut self) -> io::Result<Value> {
-        self.check_thread_died()?;
-        match self {
-            Server::Dead => Err(ErrorKind::NotConnected.into()),
-            Server::Alive { join_handle, rx } => {
-                rx.recv().map_err(|_| ErrorKind::WouldBlock.into())
+        self.seq += 1;
+        self.last = None;
+        let message = Message::full(&self.channel, self.seq, value);
+        match send_message_raw(&self.addr, &message)? {
+            Ack::Applied => {
+                let MessageBody::Full(value) = message.body else {
+                    unreachable!()
+                };
+                self.last = Some(value);
+                Ok(())
             }
+            Ack::Resync => Err(ErrorKind::InvalidData.into()),
         }
     }
 }
 
 #[cfg(feature = "client")]
-pub fn send_value_raw<A: ToSocketAddrs>(to: A, numbers: Value) -> io::Result<()> {
+pub fn send_text_raw<A: ToSocketAddrs>(to: A, numbers: &Value) -> io::Result<()> {
     let mut conn = TcpStream::connect(to)?;
-    conn.write_all(&numbers.serialize())
+    conn.write_all(numbers.to_text().as_bytes())
 }
 
 #[cfg(feature = "client")]
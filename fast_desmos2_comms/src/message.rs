@@ -0,0 +1,319 @@
+//! Messages addressed to a named channel, carrying either a full value or patches to the last one.
+//!
+//! A message is `MESSAGE_MAGIC`, `MESSAGE_VERSION`, the channel name, the sequence number and then
+//! the body. Lengths, indices and the sequence number are little-endian `u64`s, and values use the
+//! columnar encoding behind a length. Each message on a channel must have the sequence number
+//! right after the previous one; a patch that doesn't is refused and answered with
+//! [`Ack::Resync`], after which the sender has to send a full value again.
+
+use crate::value::{Patch, PatchOp};
+use crate::{ColumnarError, Value};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+#[cfg(test)]
+mod test;
+
+/// First byte of every message, distinct from all the bare value encodings.
+pub const MESSAGE_MAGIC: u8 = 0xC1;
+pub const MESSAGE_VERSION: u8 = 1;
+
+const FULL_TAG: u8 = 0;
+const PATCH_TAG: u8 = 1;
+
+const SET_RANGE_TAG: u8 = 0;
+const APPEND_TAG: u8 = 1;
+const TRUNCATE_TAG: u8 = 2;
+const REPLACE_TAG: u8 = 3;
+
+pub fn is_message_start(byte: u8) -> bool {
+    byte == MESSAGE_MAGIC
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub channel: String,
+    pub seq: u64,
+    pub body: MessageBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageBody {
+    Full(Value),
+    Patch(Vec<Patch>),
+}
+
+/// The server's answer to a message, sent back as a single byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ack {
+    Applied,
+    /// The message couldn't be applied, and the next one on the channel has to be a full value.
+    Resync,
+}
+
+impl Ack {
+    pub const fn to_byte(self) -> u8 {
+        match self {
+            Self::Applied => 0,
+            Self::Resync => 1,
+        }
+    }
+
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Applied),
+            1 => Some(Self::Resync),
+            _ => None,
+        }
+    }
+}
+
+fn write_u64(x: u64, data: &mut Vec<u8>) {
+    data.extend(x.to_le_bytes());
+}
+
+fn write_len(len: usize, data: &mut Vec<u8>) {
+    write_u64(len as u64, data);
+}
+
+fn write_value(value: &Value, data: &mut Vec<u8>) {
+    let encoded = value.to_columnar();
+    write_len(encoded.len(), data);
+    data.extend(encoded);
+}
+
+impl Message {
+    pub fn full(channel: impl Into<String>, seq: u64, value: Value) -> Self {
+        Self {
+            channel: channel.into(),
+            seq,
+            body: MessageBody::Full(value),
+        }
+    }
+
+    pub fn patch(channel: impl Into<String>, seq: u64, patches: Vec<Patch>) -> Self {
+        Self {
+            channel: channel.into(),
+            seq,
+            body: MessageBody::Patch(patches),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![MESSAGE_MAGIC, MESSAGE_VERSION];
+        write_len(self.channel.len(), &mut data);
+        data.extend(self.channel.as_bytes());
+        write_u64(self.seq, &mut data);
+
+        match &self.body {
+            MessageBody::Full(value) => {
+                data.push(FULL_TAG);
+                write_value(value, &mut data);
+            }
+            MessageBody::Patch(patches) => {
+                data.push(PATCH_TAG);
+                write_len(patches.len(), &mut data);
+                for patch in patches {
+                    write_len(patch.path.len(), &mut data);
+                    for &index in &patch.path {
+                        write_len(index, &mut data);
+                    }
+                    match &patch.op {
+                        PatchOp::SetRange { start, items } => {
+                            data.push(SET_RANGE_TAG);
+                            write_len(*start, &mut data);
+                            write_value(items, &mut data);
+                        }
+                        PatchOp::Append(items) => {
+                            data.push(APPEND_TAG);
+                            write_value(items, &mut data);
+                        }
+                        PatchOp::Truncate(len) => {
+                            data.push(TRUNCATE_TAG);
+                            write_len(*len, &mut data);
+                        }
+                        PatchOp::Replace(value) => {
+                            data.push(REPLACE_TAG);
+                            write_value(value, &mut data);
+                        }
+                    }
+                }
+            }
+        }
+        data
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, MessageError> {
+        let mut reader = MessageReader { data, at: 0 };
+        if reader.read_u8()? != MESSAGE_MAGIC {
+            return Err(MessageError::NotMessage);
+        }
+        match reader.read_u8()? {
+            MESSAGE_VERSION => {}
+            version => return Err(MessageError::UnsupportedVersion(version)),
+        }
+
+        let channel_len = reader.read_len()?;
+        let at = reader.at;
+        let channel = std::str::from_utf8(reader.take(channel_len)?)
+            .map_err(|_| MessageError::BadChannel { at })?
+            .to_string();
+        let seq = reader.read_u64()?;
+
+        let at = reader.at;
+        let body = match reader.read_u8()? {
+            FULL_TAG => MessageBody::Full(reader.read_value()?),
+            PATCH_TAG => {
+                let count = reader.read_len()?;
+                let mut patches = Vec::new();
+                for _ in 0..count {
+                    patches.push(reader.read_patch()?);
+                }
+                MessageBody::Patch(patches)
+            }
+            _ => return Err(MessageError::UnknownTag { at }),
+        };
+
+        match reader.at == data.len() {
+            true => Ok(Self { channel, seq, body }),
+            false => Err(MessageError::Trailing { at: reader.at }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageError {
+    NotMessage,
+    UnsupportedVersion(u8),
+    BadChannel { at: usize },
+    UnknownTag { at: usize },
+    Truncated { at: usize },
+    Trailing { at: usize },
+    Value { at: usize, err: ColumnarError },
+}
+
+impl Display for MessageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotMessage => write!(f, "not a channel message"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported message version {version}")
+            }
+            Self::BadChannel { at } => write!(f, "channel name at byte {at} isn't utf-8"),
+            Self::UnknownTag { at } => write!(f, "unknown tag at byte {at}"),
+            Self::Truncated { at } => write!(f, "message ends early at byte {at}"),
+            Self::Trailing { at } => write!(f, "unexpected trailing data at byte {at}"),
+            Self::Value { at, err } => write!(f, "bad value at byte {at}: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+struct MessageReader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> MessageReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MessageError> {
+        let truncated = MessageError::Truncated { at: self.at };
+        let end = self.at.checked_add(len).ok_or(truncated)?;
+        let bytes = self.data.get(self.at..end).ok_or(truncated)?;
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, MessageError> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn read_u64(&mut self) -> Result<u64, MessageError> {
+        let bytes = self.take(8)?.try_into().unwrap_or_else(|_| unreachable!());
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_len(&mut self) -> Result<usize, MessageError> {
+        let at = self.at;
+        usize::try_from(self.read_u64()?).map_err(|_| MessageError::Truncated { at })
+    }
+
+    fn read_value(&mut self) -> Result<Value, MessageError> {
+        let len = self.read_len()?;
+        let at = self.at;
+        Value::from_columnar(self.take(len)?).map_err(|err| MessageError::Value { at, err })
+    }
+
+    fn read_patch(&mut self) -> Result<Patch, MessageError> {
+        let path_len = self.read_len()?;
+        let mut path = Vec::new();
+        for _ in 0..path_len {
+            path.push(self.read_len()?);
+        }
+
+        let at = self.at;
+        let op = match self.read_u8()? {
+            SET_RANGE_TAG => PatchOp::SetRange {
+                start: self.read_len()?,
+                items: self.read_value()?,
+            },
+            APPEND_TAG => PatchOp::Append(self.read_value()?),
+            TRUNCATE_TAG => PatchOp::Truncate(self.read_len()?),
+            REPLACE_TAG => PatchOp::Replace(self.read_value()?),
+            _ => return Err(MessageError::UnknownTag { at }),
+        };
+        Ok(Patch { path, op })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ChannelState {
+    seq: u64,
+    value: Value,
+}
+
+/// The last value received on each channel, which incoming patches are applied to.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelStates {
+    channels: HashMap<String, ChannelState>,
+}
+
+impl ChannelStates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, channel: &str) -> Option<&Value> {
+        self.channels.get(channel).map(|state| &state.value)
+    }
+
+    /// Applies a message to its channel, returning the channel's new value. Patches that skip a
+    /// sequence number or don't fit the current value drop the channel until it gets resynced.
+    pub fn receive(&mut self, message: Message) -> Result<&Value, Ack> {
+        let Message { channel, seq, body } = message;
+        let patches = match body {
+            MessageBody::Full(value) => {
+                let state = ChannelState { seq, value };
+                let state = self.channels.entry(channel).insert_entry(state).into_mut();
+                return Ok(&state.value);
+            }
+            MessageBody::Patch(patches) => patches,
+        };
+
+        let Some(state) = self.channels.get_mut(&channel) else {
+            return Err(Ack::Resync);
+        };
+        let in_order = state.seq.checked_add(1) == Some(seq);
+        if !in_order || state.value.apply_patches(patches).is_err() {
+            self.channels.remove(&channel);
+            return Err(Ack::Resync);
+        }
+
+        let state = self
+            .channels
+            .get_mut(&channel)
+            .unwrap_or_else(|| unreachable!());
+        state.seq = seq;
+        Ok(&state.value)
+    }
+}
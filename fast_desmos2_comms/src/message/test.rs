@@ -0,0 +1,100 @@
+use crate::message::{Ack, ChannelStates, Message, MessageError, MESSAGE_MAGIC};
+use crate::value::{Patch, PatchOp};
+use crate::{List, Value};
+use glam::DVec2;
+
+fn numbers(xs: &[f64]) -> Value {
+    Value::Number(List::Flat(xs.to_vec()))
+}
+
+#[test]
+fn message_round_trip() {
+    let messages = [
+        Message::full("", 0, Value::one_number(1.0)),
+        Message::full("points", u64::MAX, Value::one_point(DVec2::Y)),
+        Message::patch("ünïcode", 3, Vec::new()),
+        Message::patch(
+            "numbers",
+            7,
+            vec![
+                Patch::root(PatchOp::SetRange {
+                    start: 4,
+                    items: numbers(&[1.0, 2.0]),
+                }),
+                Patch {
+                    path: vec![1, 2],
+                    op: PatchOp::Append(numbers(&[3.0])),
+                },
+                Patch::root(PatchOp::Truncate(2)),
+                Patch {
+                    path: vec![0],
+                    op: PatchOp::Replace(Value::Number(List::Staggered(vec![]))),
+                },
+            ],
+        ),
+    ];
+    for message in messages {
+        let data = message.encode();
+        assert_eq!(Message::decode(&data), Ok(message.clone()));
+        assert!(matches!(
+            Message::decode(&data[..data.len() - 1]),
+            Err(MessageError::Truncated { .. })
+        ));
+    }
+
+    assert_eq!(Message::decode(&[0]), Err(MessageError::NotMessage));
+    assert_eq!(
+        Message::decode(&[MESSAGE_MAGIC, 0]),
+        Err(MessageError::UnsupportedVersion(0))
+    );
+}
+
+#[test]
+fn channel_sequencing() {
+    let mut channels = ChannelStates::new();
+    let set = |start, items: &[f64]| {
+        vec![Patch::root(PatchOp::SetRange {
+            start,
+            items: numbers(items),
+        })]
+    };
+
+    assert_eq!(
+        channels.receive(Message::patch("a", 1, set(0, &[1.0]))),
+        Err(Ack::Resync)
+    );
+    assert_eq!(
+        channels.receive(Message::full("a", 5, numbers(&[0.0, 0.0]))),
+        Ok(&numbers(&[0.0, 0.0]))
+    );
+    assert_eq!(
+        channels.receive(Message::full("b", 0, Value::one_number(1.0))),
+        Ok(&Value::one_number(1.0))
+    );
+    assert_eq!(
+        channels.receive(Message::patch("a", 6, set(1, &[2.0]))),
+        Ok(&numbers(&[0.0, 2.0]))
+    );
+
+    // patch 7 got lost.
+    assert_eq!(
+        channels.receive(Message::patch("a", 8, set(0, &[3.0]))),
+        Err(Ack::Resync)
+    );
+    assert_eq!(channels.get("a"), None);
+    assert_eq!(
+        channels.receive(Message::patch("a", 9, set(0, &[3.0]))),
+        Err(Ack::Resync)
+    );
+    assert_eq!(
+        channels.receive(Message::full("a", 10, numbers(&[4.0]))),
+        Ok(&numbers(&[4.0]))
+    );
+
+    // patches that don't fit also need a resync.
+    assert_eq!(
+        channels.receive(Message::patch("a", 11, set(5, &[3.0]))),
+        Err(Ack::Resync)
+    );
+    assert_eq!(channels.get("b"), Some(&Value::one_number(1.0)));
+}
@@ -0,0 +1,398 @@
+use crate::message::{self, Ack, ChannelStates, Message};
+use crate::value;
+use crate::{Serde, Value};
+use listener::{Listener, Stream};
+use queue::{Closed, UpdateQueue};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub use listener::ListenAddr;
+pub use queue::{QueuePolicy, ServerStats};
+
+mod listener;
+mod queue;
+
+#[cfg(test)]
+mod test;
+
+/// How often listener threads check whether the server is shutting down.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A value received by the server, along with the channel it arrived on. Bare values, which don't
+/// name a channel, arrive on the empty channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    pub channel: String,
+    pub value: Value,
+}
+
+/// How a listener gets its socket back after accepting fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    /// The wait before the first attempt, which doubles after each failed one.
+    pub delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 10,
+            delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ServerConfig {
+    pub policy: QueuePolicy,
+    pub retry: RetryPolicy,
+}
+
+/// What all listener threads of a server share.
+struct Shared {
+    queue: UpdateQueue,
+    channels: Mutex<ChannelStates>,
+    shutdown: AtomicBool,
+    retry: RetryPolicy,
+}
+
+struct ListenerHandle {
+    addr: ListenAddr,
+    /// `None` once the thread stopped and got joined.
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+/// Receives values on any number of listeners, each served by its own thread. Dropping the server
+/// shuts it down.
+pub struct Server {
+    shared: Arc<Shared>,
+    listeners: Vec<ListenerHandle>,
+}
+
+impl Server {
+    pub fn new_local(port: u16) -> io::Result<Self> {
+        Self::new((Ipv4Addr::LOCALHOST, port))
+    }
+
+    pub fn new<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Self::with_policy(addr, QueuePolicy::default())
+    }
+
+    pub fn with_policy<A: ToSocketAddrs>(addr: A, policy: QueuePolicy) -> io::Result<Self> {
+        let mut server = Self::with_config(ServerConfig {
+            policy,
+            ..ServerConfig::default()
+        });
+        server.listen_tcp(addr)?;
+        Ok(server)
+    }
+
+    /// Creates a server without any listeners, to be added with [`Server::listen`].
+    pub fn with_config(config: ServerConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                queue: UpdateQueue::new(config.policy),
+                channels: Mutex::new(ChannelStates::new()),
+                shutdown: AtomicBool::new(false),
+                retry: config.retry,
+            }),
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Starts listening on another address, returning the address that was actually bound.
+    pub fn listen(&mut self, addr: ListenAddr) -> io::Result<ListenAddr> {
+        let (listener, addr) = Listener::bind(&addr)?;
+        let thread = Some(self.spawn(listener, addr.clone()));
+        self.listeners.push(ListenerHandle {
+            addr: addr.clone(),
+            thread,
+        });
+        Ok(addr)
+    }
+
+    /// Listens on the first of the addresses that can be bound.
+    pub fn listen_tcp<A: ToSocketAddrs>(&mut self, addr: A) -> io::Result<SocketAddr> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            match self.listen(ListenAddr::Tcp(addr)) {
+                Ok(ListenAddr::Tcp(addr)) => return Ok(addr),
+                Ok(_) => unreachable!(),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| ErrorKind::InvalidInput.into()))
+    }
+
+    #[cfg(unix)]
+    pub fn listen_unix(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        self.listen(ListenAddr::Unix(path.into())).map(|_| ())
+    }
+
+    fn spawn(&self, listener: Listener, addr: ListenAddr) -> JoinHandle<io::Result<()>> {
+        let shared = self.shared.clone();
+        shared.queue.add_writer();
+        thread::spawn(move || {
+            let _writer = WriterGuard(&shared.queue);
+            serve(&shared, listener, &addr)
+        })
+    }
+
+    /// The addresses listened on, including ones whose thread stopped.
+    pub fn listen_addrs(&self) -> impl Iterator<Item = &ListenAddr> {
+        self.listeners.iter().map(|listener| &listener.addr)
+    }
+
+    /// Whether any listener is still running.
+    pub fn is_alive(&self) -> bool {
+        self.listeners.iter().any(|listener| {
+            listener
+                .thread
+                .as_ref()
+                .is_some_and(|thread| !thread.is_finished())
+        })
+    }
+
+    /// Joins the listener threads that stopped, returning the first error one of them died with.
+    fn check_threads_died(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        for listener in &mut self.listeners {
+            if !listener
+                .thread
+                .as_ref()
+                .is_some_and(JoinHandle::is_finished)
+            {
+                continue;
+            }
+            let thread = listener.thread.take().unwrap_or_else(|| unreachable!());
+            let thread_result = join_listener(thread);
+            if result.is_ok() {
+                result = thread_result;
+            }
+        }
+        result
+    }
+
+    /// Stops all listeners and waits for their threads. Updates that were already queued can
+    /// still be accepted afterwards.
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        // wakes up threads waiting for room in a bounded queue.
+        self.shared.queue.close();
+
+        let mut result = Ok(());
+        for listener in &mut self.listeners {
+            let Some(thread) = listener.thread.take() else {
+                continue;
+            };
+            let thread_result = join_listener(thread);
+            if result.is_ok() {
+                result = thread_result;
+            }
+        }
+        result
+    }
+
+    /// Binds every listener that isn't running again, after a shutdown or after it died.
+    pub fn restart(&mut self) -> io::Result<()> {
+        // whatever the dead threads died of is what restarting is about.
+        let _ = self.check_threads_died();
+        self.shared.shutdown.store(false, Ordering::Relaxed);
+        self.shared.queue.reopen();
+
+        for index in 0..self.listeners.len() {
+            if self.listeners[index].thread.is_some() {
+                continue;
+            }
+            let (listener, addr) = Listener::bind(&self.listeners[index].addr)?;
+            self.listeners[index].thread = Some(self.spawn(listener, addr));
+        }
+        Ok(())
+    }
+
+    pub fn try_accept_value(&mut self) -> io::Result<Value> {
+        self.try_accept_update().map(|update| update.value)
+    }
+
+    /// Blocking
+    pub fn accept_value(&mut self) -> io::Result<Value> {
+        self.accept_update().map(|update| update.value)
+    }
+
+    pub fn try_accept_update(&mut self) -> io::Result<Update> {
+        self.check_threads_died()?;
+        match self.shared.queue.try_pop() {
+            Some(update) => Ok(update),
+            None if self.is_alive() => Err(ErrorKind::WouldBlock.into()),
+            None => Err(ErrorKind::NotConnected.into()),
+        }
+    }
+
+    /// Blocking
+    pub fn accept_update(&mut self) -> io::Result<Update> {
+        self.check_threads_died()?;
+        match self.shared.queue.pop() {
+            Some(update) => Ok(update),
+            // the queue only runs dry for good once every listener stopped.
+            None => {
+                self.check_threads_died()?;
+                Err(ErrorKind::NotConnected.into())
+            }
+        }
+    }
+
+    /// Statistics for the connection-health display.
+    pub fn stats(&self) -> ServerStats {
+        self.shared.queue.stats()
+    }
+
+    /// How many updates are waiting to be accepted.
+    pub fn pending(&self) -> usize {
+        self.shared.queue.len()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// Unregisters a listener thread from the queue however it stops, so that readers blocked on an
+/// empty queue don't wait for it forever.
+struct WriterGuard<'a>(&'a UpdateQueue);
+
+impl Drop for WriterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.remove_writer();
+    }
+}
+
+fn join_listener(thread: JoinHandle<io::Result<()>>) -> io::Result<()> {
+    thread
+        .join()
+        .unwrap_or_else(|_| Err(io::Error::other("listener thread panicked")))
+}
+
+fn serve(shared: &Shared, mut listener: Listener, addr: &ListenAddr) -> io::Result<()> {
+    while !shared.shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok(conn) => {
+                if handle(shared, conn).is_err() {
+                    // closed, so shutting down.
+                    return Ok(());
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(err) if is_transient(&err) => {}
+            Err(err) => {
+                // release the socket before binding it again.
+                drop(listener);
+                match rebind(shared, addr, err)? {
+                    Some(new_listener) => listener = new_listener,
+                    None => return Ok(()),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Interrupted | ErrorKind::ConnectionAborted | ErrorKind::ConnectionReset
+    )
+}
+
+/// Tries binding again according to the retry policy, giving `None` if the server shuts down in
+/// the meantime.
+fn rebind(shared: &Shared, addr: &ListenAddr, err: io::Error) -> io::Result<Option<Listener>> {
+    let retry = shared.retry;
+    let mut delay = retry.delay;
+    let mut last_err = err;
+    for _ in 0..retry.attempts {
+        let mut waited = Duration::ZERO;
+        while waited < delay {
+            if shared.shutdown.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL.min(delay - waited));
+            waited += POLL_INTERVAL;
+        }
+
+        match Listener::bind(addr) {
+            Ok((listener, _)) => return Ok(Some(listener)),
+            Err(err) => last_err = err,
+        }
+        delay = (delay * 2).min(retry.max_delay);
+    }
+    Err(last_err)
+}
+
+/// Reads one message from a connection and queues what it decodes to. Connections that
+/// misbehave only lose their own message.
+fn handle(shared: &Shared, mut conn: Stream) -> Result<(), Closed> {
+    // according to documentation, only fails if duration is zero.
+    let _ = conn.set_read_timeout(Some(Duration::from_millis(1000)));
+
+    let mut data = Vec::new();
+    let read = conn.read_to_end(&mut data);
+    shared.queue.record_bytes(data.len());
+    if read.is_err() {
+        return Ok(());
+    }
+
+    match data.first() {
+        Some(&first) if message::is_message_start(first) => {
+            let Ok(message) = Message::decode(&data) else {
+                return Ok(());
+            };
+            let channel = message.channel.clone();
+            let received = shared
+                .channels
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .receive(message)
+                .cloned();
+            let Ok(value) = received else {
+                // the sender doesn't have to wait for the answer.
+                let _ = conn.write_all(&[Ack::Resync.to_byte()]);
+                return Ok(());
+            };
+            // queue before answering, so that a blocking queue holds back the sender too.
+            shared.queue.push(Update { channel, value })?;
+            let _ = conn.write_all(&[Ack::Applied.to_byte()]);
+            Ok(())
+        }
+        _ => match decode_value(&data) {
+            Some(value) => shared.queue.push(Update {
+                channel: String::new(),
+                value,
+            }),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Decodes a message in the binary, columnar or text encoding, telling them apart by the first
+/// byte. Malformed messages are dropped rather than taking the server down.
+fn decode_value(bytes: &[u8]) -> Option<Value> {
+    match bytes.first() {
+        None => None,
+        Some(&first) if value::is_columnar_start(first) => Value::from_columnar(bytes).ok(),
+        Some(&first) if value::is_text_start(first) => {
+            std::str::from_utf8(bytes).ok()?.parse().ok()
+        }
+        Some(_) => Value::try_deserialize(bytes).ok(),
+    }
+}
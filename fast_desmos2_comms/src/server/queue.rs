@@ -0,0 +1,178 @@
+//! The queue between the server thread and whoever accepts updates, along with its statistics.
+
+use super::Update;
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+/// What the server does with updates that arrive faster than they're accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum QueuePolicy {
+    /// Keeps every update, however far behind the reader falls.
+    #[default]
+    Unbounded,
+    /// Holds at most this many updates, and stops reading connections until there's room again.
+    BoundedBlocking(usize),
+    /// Holds at most this many updates, throwing away the oldest to make room.
+    DropOldest(usize),
+    /// Holds only the newest update of each channel.
+    KeepLatestPerChannel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ServerStats {
+    /// Updates decoded and queued, including ones that were dropped later.
+    pub received: u64,
+    /// Updates thrown away by the queue policy before they were accepted.
+    pub dropped: u64,
+    /// Bytes read from all connections, including malformed messages.
+    pub bytes: u64,
+    pub last_receive: Option<Instant>,
+}
+
+impl ServerStats {
+    pub fn since_last_receive(&self) -> Option<Duration> {
+        self.last_receive.map(|last| last.elapsed())
+    }
+}
+
+#[derive(Debug)]
+struct QueueState {
+    policy: QueuePolicy,
+    updates: VecDeque<Update>,
+    stats: ServerStats,
+    closed: bool,
+    /// How many listeners can still push updates.
+    writers: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct UpdateQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+/// Returned by [`UpdateQueue::push`] once the server is shutting down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Closed;
+
+impl UpdateQueue {
+    pub(crate) fn new(policy: QueuePolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                policy,
+                updates: VecDeque::new(),
+                stats: ServerStats::default(),
+                closed: false,
+                writers: 0,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        // the state stays consistent even if a holder panicked.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, QueueState>) -> MutexGuard<'a, QueueState> {
+        self.changed
+            .wait(state)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn record_bytes(&self, bytes: usize) {
+        self.lock().stats.bytes += bytes as u64;
+    }
+
+    /// Queues an update according to the policy, blocking while a bounded queue is full.
+    pub(crate) fn push(&self, update: Update) -> Result<(), Closed> {
+        let mut state = self.lock();
+        if let QueuePolicy::BoundedBlocking(capacity) = state.policy {
+            while state.updates.len() >= capacity.max(1) && !state.closed {
+                state = self.wait(state);
+            }
+        }
+        if state.closed {
+            return Err(Closed);
+        }
+
+        state.stats.received += 1;
+        state.stats.last_receive = Some(Instant::now());
+        match state.policy {
+            QueuePolicy::Unbounded | QueuePolicy::BoundedBlocking(_) => {}
+            QueuePolicy::DropOldest(capacity) => {
+                while state.updates.len() >= capacity.max(1) {
+                    state.updates.pop_front();
+                    state.stats.dropped += 1;
+                }
+            }
+            QueuePolicy::KeepLatestPerChannel => {
+                let old = state
+                    .updates
+                    .iter_mut()
+                    .find(|old| old.channel == update.channel);
+                if let Some(old) = old {
+                    *old = update;
+                    state.stats.dropped += 1;
+                    return Ok(());
+                }
+            }
+        }
+        state.updates.push_back(update);
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    pub(crate) fn try_pop(&self) -> Option<Update> {
+        let update = self.lock().updates.pop_front();
+        self.changed.notify_all();
+        update
+    }
+
+    /// Blocks until there's an update, or returns `None` once the queue is empty with no
+    /// listeners left to fill it.
+    pub(crate) fn pop(&self) -> Option<Update> {
+        let mut state = self.lock();
+        loop {
+            if let Some(update) = state.updates.pop_front() {
+                self.changed.notify_all();
+                return Some(update);
+            }
+            if state.writers == 0 {
+                return None;
+            }
+            state = self.wait(state);
+        }
+    }
+
+    /// Makes pushing fail, including pushes blocked on a full queue.
+    pub(crate) fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+
+    pub(crate) fn reopen(&self) {
+        self.lock().closed = false;
+    }
+
+    pub(crate) fn add_writer(&self) {
+        self.lock().writers += 1;
+    }
+
+    pub(crate) fn remove_writer(&self) {
+        self.lock().writers -= 1;
+        self.changed.notify_all();
+    }
+
+    pub(crate) fn stats(&self) -> ServerStats {
+        self.lock().stats
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.lock().updates.len()
+    }
+}
@@ -0,0 +1,101 @@
+use crate::server::queue::{Closed, QueuePolicy, UpdateQueue};
+use crate::server::Update;
+use crate::Value;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn update(channel: &str, x: f64) -> Update {
+    Update {
+        channel: channel.to_string(),
+        value: Value::one_number(x),
+    }
+}
+
+fn drain(queue: &UpdateQueue) -> Vec<Update> {
+    std::iter::from_fn(|| queue.try_pop()).collect()
+}
+
+#[test]
+fn unbounded() {
+    let queue = UpdateQueue::new(QueuePolicy::Unbounded);
+    for x in 0..100 {
+        queue.push(update("", x as f64)).unwrap();
+    }
+    assert_eq!(queue.len(), 100);
+    assert_eq!(drain(&queue).last(), Some(&update("", 99.0)));
+
+    let stats = queue.stats();
+    assert_eq!((stats.received, stats.dropped), (100, 0));
+    assert!(stats.last_receive.is_some());
+}
+
+#[test]
+fn drop_oldest() {
+    let queue = UpdateQueue::new(QueuePolicy::DropOldest(2));
+    for x in 0..5 {
+        queue.push(update("", x as f64)).unwrap();
+    }
+    assert_eq!(drain(&queue), [update("", 3.0), update("", 4.0)]);
+    let stats = queue.stats();
+    assert_eq!((stats.received, stats.dropped), (5, 3));
+}
+
+#[test]
+fn keep_latest_per_channel() {
+    let queue = UpdateQueue::new(QueuePolicy::KeepLatestPerChannel);
+    queue.push(update("a", 0.0)).unwrap();
+    queue.push(update("b", 1.0)).unwrap();
+    queue.push(update("a", 2.0)).unwrap();
+    queue.push(update("a", 3.0)).unwrap();
+    assert_eq!(drain(&queue), [update("a", 3.0), update("b", 1.0)]);
+    assert_eq!(queue.stats().dropped, 2);
+
+    queue.push(update("a", 4.0)).unwrap();
+    assert_eq!(drain(&queue), [update("a", 4.0)]);
+}
+
+#[test]
+fn bounded_blocking() {
+    let queue = Arc::new(UpdateQueue::new(QueuePolicy::BoundedBlocking(2)));
+    queue.add_writer();
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            for x in 0..10 {
+                queue.push(update("", x as f64)).unwrap();
+            }
+            queue.remove_writer();
+        })
+    };
+
+    let mut received = Vec::new();
+    while let Some(update) = queue.pop() {
+        assert!(queue.len() <= 2);
+        received.push(update);
+    }
+    producer.join().unwrap();
+    let expected: Vec<_> = (0..10).map(|x| update("", x as f64)).collect();
+    assert_eq!(received, expected);
+    assert_eq!(queue.stats().dropped, 0);
+}
+
+#[test]
+fn closing() {
+    let queue = Arc::new(UpdateQueue::new(QueuePolicy::BoundedBlocking(1)));
+    queue.push(update("", 0.0)).unwrap();
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || queue.push(update("", 1.0)))
+    };
+    thread::sleep(Duration::from_millis(20));
+    queue.close();
+    assert_eq!(producer.join().unwrap(), Err(Closed));
+
+    // whatever was queued before closing can still be accepted.
+    assert_eq!(queue.pop(), Some(update("", 0.0)));
+    assert_eq!(queue.pop(), None);
+
+    queue.reopen();
+    assert_eq!(queue.push(update("", 2.0)), Ok(()));
+}
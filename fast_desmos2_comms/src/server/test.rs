@@ -0,0 +1,158 @@
+use crate::server::{rebind, ListenAddr, RetryPolicy, Server, ServerConfig};
+use crate::{send_value_raw, Value};
+use std::io::{ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+fn local_any() -> SocketAddr {
+    (Ipv4Addr::LOCALHOST, 0).into()
+}
+
+fn accept_all(server: &mut Server, count: usize) -> Vec<f64> {
+    let mut xs: Vec<f64> = (0..count)
+        .map(|_| {
+            let value = server.accept_value().unwrap();
+            value.try_number().unwrap().try_term().unwrap()
+        })
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs
+}
+
+#[test]
+fn several_listeners() {
+    let mut server = Server::new_local(0).unwrap();
+    let first = server.listen_addrs().next().cloned().unwrap();
+    let ListenAddr::Tcp(first) = first else {
+        unreachable!()
+    };
+    let second = server.listen_tcp(local_any()).unwrap();
+    assert_ne!(first.port(), second.port());
+
+    send_value_raw(first, Value::one_number(1.0)).unwrap();
+    send_value_raw(second, Value::one_number(2.0)).unwrap();
+    assert_eq!(accept_all(&mut server, 2), [1.0, 2.0]);
+    assert_eq!(server.stats().received, 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_listener() {
+    use std::os::unix::net::UnixStream;
+
+    let path = std::env::temp_dir().join(format!("fast_desmos2_comms_{}", std::process::id()));
+    let mut server = Server::with_config(ServerConfig::default());
+    server.listen_unix(&path).unwrap();
+
+    let mut conn = UnixStream::connect(&path).unwrap();
+    conn.write_all(b"[1, 2]").unwrap();
+    drop(conn);
+    assert_eq!(
+        server.accept_value().unwrap(),
+        Value::Number(crate::List::Flat(vec![1.0, 2.0]))
+    );
+
+    drop(server);
+    assert!(!path.exists());
+}
+
+#[test]
+fn malformed_messages() {
+    let mut server = Server::new_local(0).unwrap();
+    let addr = server.listen_tcp(local_any()).unwrap();
+    // an unknown value kind, then lists nested deeper than the stack allows.
+    let nested = [vec![0], vec![1; 200_000]].concat();
+    for data in [&[5, 0, 0][..], &nested] {
+        let mut conn = TcpStream::connect(addr).unwrap();
+        conn.write_all(data).unwrap();
+    }
+
+    send_value_raw(addr, Value::one_number(1.0)).unwrap();
+    // polled, so that a listener that died fails the test rather than blocking it.
+    let value = loop {
+        match server.try_accept_value() {
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(5));
+            }
+            value => break value.unwrap(),
+        }
+    };
+    assert_eq!(value, Value::one_number(1.0));
+    assert!(server.is_alive());
+}
+
+#[test]
+fn shutdown_and_restart() {
+    let mut server = Server::new_local(0).unwrap();
+    let addr = server.listen_tcp(local_any()).unwrap();
+    send_value_raw(addr, Value::one_number(1.0)).unwrap();
+    while server.pending() == 0 {
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    server.shutdown().unwrap();
+    assert!(!server.is_alive());
+    assert_eq!(server.accept_value().unwrap(), Value::one_number(1.0));
+    assert_eq!(
+        server.accept_value().map_err(|err| err.kind()),
+        Err(ErrorKind::NotConnected)
+    );
+    assert_eq!(
+        server.try_accept_value().map_err(|err| err.kind()),
+        Err(ErrorKind::NotConnected)
+    );
+
+    server.restart().unwrap();
+    assert!(server.is_alive());
+    send_value_raw(addr, Value::one_number(2.0)).unwrap();
+    assert_eq!(server.accept_value().unwrap(), Value::one_number(2.0));
+
+    // dropping joins the threads, which frees the port right away.
+    drop(server);
+    TcpListener::bind(addr).unwrap();
+}
+
+#[test]
+fn rebinding() {
+    let retry = RetryPolicy {
+        attempts: 20,
+        delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(20),
+    };
+    let server = Server::with_config(ServerConfig {
+        retry,
+        ..ServerConfig::default()
+    });
+    let shared = &server.shared;
+
+    let blocker = TcpListener::bind(local_any()).unwrap();
+    let addr = ListenAddr::Tcp(blocker.local_addr().unwrap());
+    let unblock = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        drop(blocker);
+    });
+    let rebound = rebind(shared, &addr, ErrorKind::Other.into()).unwrap();
+    assert!(rebound.is_some());
+    unblock.join().unwrap();
+
+    let server = Server::with_config(ServerConfig {
+        retry: RetryPolicy {
+            attempts: 2,
+            ..retry
+        },
+        ..ServerConfig::default()
+    });
+    let rebound = rebind(&server.shared, &addr, ErrorKind::Other.into());
+    assert_eq!(
+        rebound.err().map(|err| err.kind()),
+        Some(ErrorKind::AddrInUse)
+    );
+
+    let mut server = Server::with_config(ServerConfig::default());
+    server.shutdown().unwrap();
+    assert!(matches!(
+        rebind(&server.shared, &addr, ErrorKind::Other.into()),
+        Ok(None)
+    ));
+}
@@ -1,12 +1,24 @@
+mod columnar;
 #[cfg(feature = "server")]
 pub mod ops;
+mod patch;
 mod serde;
+mod text;
 
 use glam::DVec2;
 use std::fmt::Display;
 use std::ops::Add;
 
-pub use serde::Serde;
+pub use columnar::{
+    is_columnar_start, ColumnarError, ListView, ValueView, COLUMNAR_MAGIC, COLUMNAR_VERSION,
+};
+pub use patch::{Patch, PatchError, PatchOp};
+pub use serde::{Serde, SerdeError};
+pub use text::{is_text_start, TextError};
+
+/// How deeply lists can be nested in a message of any encoding, so that decoding can't run out of
+/// stack.
+const MAX_DEPTH: usize = 256;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ListRef<'a, T> {
@@ -0,0 +1,291 @@
+//! Columnar encoding of [`Value`]s, meant for large flat lists.
+//!
+//! A message is `COLUMNAR_MAGIC`, `COLUMNAR_VERSION`, the kind tag used by [`Serde`] and then the
+//! list. Every list starts with a tag byte: `0` for a single element, `1` for a flat list and `2`
+//! for a staggered list. Lengths are little-endian `u64`s and elements are little-endian `f64`s
+//! (points are `x` then `y`), and both are padded to a multiple of 8 bytes from the start of the
+//! message. That way, a flat list is a single contiguous array that can be borrowed straight from
+//! an aligned buffer (such as a memory map) or otherwise copied out in one go.
+
+use super::*;
+use bytemuck::Pod;
+use std::borrow::Cow;
+use std::fmt::{self, Formatter};
+
+#[cfg(test)]
+mod test;
+
+/// First byte of every columnar message. The legacy binary format starts with a kind tag and the
+/// text format with something printable, so neither can be confused with it.
+pub const COLUMNAR_MAGIC: u8 = 0xC0;
+pub const COLUMNAR_VERSION: u8 = 1;
+
+const TERM_TAG: u8 = 0;
+const FLAT_TAG: u8 = 1;
+const STAGGERED_TAG: u8 = 2;
+
+pub fn is_columnar_start(byte: u8) -> bool {
+    byte == COLUMNAR_MAGIC
+}
+
+trait ColumnarElem: Pod {
+    const KIND: ValueKind;
+}
+
+impl ColumnarElem for f64 {
+    const KIND: ValueKind = ValueKind::Number;
+}
+
+impl ColumnarElem for DVec2 {
+    const KIND: ValueKind = ValueKind::Point;
+}
+
+const fn kind_tag(kind: ValueKind) -> u8 {
+    match kind {
+        ValueKind::Number => 0,
+        ValueKind::Point => 1,
+    }
+}
+
+fn pad(data: &mut Vec<u8>) {
+    data.resize(data.len().next_multiple_of(8), 0);
+}
+
+fn write_len(len: usize, data: &mut Vec<u8>) {
+    pad(data);
+    data.extend((len as u64).to_le_bytes());
+}
+
+fn write_elems<T: ColumnarElem>(xs: &[T], data: &mut Vec<u8>) {
+    pad(data);
+    if cfg!(target_endian = "little") {
+        data.extend_from_slice(bytemuck::cast_slice(xs));
+    } else {
+        for x in bytemuck::cast_slice::<T, f64>(xs) {
+            data.extend(x.to_le_bytes());
+        }
+    }
+}
+
+fn write_list<T: ColumnarElem>(xs: &List<T>, data: &mut Vec<u8>) {
+    match xs {
+        List::Term(x) => {
+            data.push(TERM_TAG);
+            write_elems(std::slice::from_ref(x), data);
+        }
+        List::Flat(xs) => {
+            data.push(FLAT_TAG);
+            write_len(xs.len(), data);
+            write_elems(xs, data);
+        }
+        List::Staggered(xs) => {
+            data.push(STAGGERED_TAG);
+            write_len(xs.len(), data);
+            for x in xs {
+                write_list(x, data);
+            }
+        }
+    }
+}
+
+/// A list decoded from a columnar message, borrowing flat lists from the message where possible.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListView<'a, T: Clone> {
+    Term(T),
+    Flat(Cow<'a, [T]>),
+    Staggered(Vec<ListView<'a, T>>),
+}
+
+impl<T: Clone> ListView<'_, T> {
+    pub fn to_list(self) -> List<T> {
+        match self {
+            Self::Term(x) => List::Term(x),
+            Self::Flat(xs) => List::Flat(xs.into_owned()),
+            Self::Staggered(xs) => List::Staggered(xs.into_iter().map(Self::to_list).collect()),
+        }
+    }
+
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            Self::Term(_) => true,
+            Self::Flat(xs) => matches!(xs, Cow::Borrowed(_)),
+            Self::Staggered(xs) => xs.iter().all(Self::is_borrowed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueView<'a> {
+    Number(ListView<'a, f64>),
+    Point(ListView<'a, DVec2>),
+}
+
+impl ValueView<'_> {
+    pub fn to_value(self) -> Value {
+        match self {
+            Self::Number(xs) => Value::Number(xs.to_list()),
+            Self::Point(xs) => Value::Point(xs.to_list()),
+        }
+    }
+
+    pub const fn kind(&self) -> ValueKind {
+        match self {
+            Self::Number(_) => ValueKind::Number,
+            Self::Point(_) => ValueKind::Point,
+        }
+    }
+
+    /// Whether every flat list is borrowed from the message, which only happens when the message
+    /// is 8-byte aligned on a little-endian machine.
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            Self::Number(xs) => xs.is_borrowed(),
+            Self::Point(xs) => xs.is_borrowed(),
+        }
+    }
+}
+
+impl Value {
+    pub fn to_columnar(&self) -> Vec<u8> {
+        let mut data = vec![COLUMNAR_MAGIC, COLUMNAR_VERSION, kind_tag(self.kind())];
+        match self {
+            Value::Number(xs) => write_list(xs, &mut data),
+            Value::Point(xs) => write_list(xs, &mut data),
+        }
+        data
+    }
+
+    pub fn from_columnar(data: &[u8]) -> Result<Self, ColumnarError> {
+        Self::view_columnar(data).map(ValueView::to_value)
+    }
+
+    /// Decodes a columnar message without copying flat lists out of it, if its alignment allows.
+    pub fn view_columnar(data: &[u8]) -> Result<ValueView<'_>, ColumnarError> {
+        let mut reader = ColumnarReader { data, at: 0 };
+        if reader.read_u8()? != COLUMNAR_MAGIC {
+            return Err(ColumnarError::NotColumnar);
+        }
+        match reader.read_u8()? {
+            COLUMNAR_VERSION => {}
+            version => return Err(ColumnarError::UnsupportedVersion(version)),
+        }
+
+        let at = reader.at;
+        let view = match reader.read_u8()? {
+            0 => ValueView::Number(reader.read_list(0)?),
+            1 => ValueView::Point(reader.read_list(0)?),
+            _ => return Err(ColumnarError::UnknownKind { at }),
+        };
+        match reader.at == data.len() {
+            true => Ok(view),
+            false => Err(ColumnarError::Trailing { at: reader.at }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnarError {
+    NotColumnar,
+    UnsupportedVersion(u8),
+    UnknownKind { at: usize },
+    UnknownTag { at: usize },
+    Truncated { at: usize },
+    Trailing { at: usize },
+    TooDeep { at: usize },
+}
+
+impl Display for ColumnarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotColumnar => write!(f, "not a columnar message"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported columnar version {version}")
+            }
+            Self::UnknownKind { at } => write!(f, "unknown value kind at byte {at}"),
+            Self::UnknownTag { at } => write!(f, "unknown list tag at byte {at}"),
+            Self::Truncated { at } => write!(f, "message ends early at byte {at}"),
+            Self::Trailing { at } => write!(f, "unexpected trailing data at byte {at}"),
+            Self::TooDeep { at } => write!(f, "lists nested too deeply at byte {at}"),
+        }
+    }
+}
+
+impl std::error::Error for ColumnarError {}
+
+struct ColumnarReader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> ColumnarReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ColumnarError> {
+        let truncated = ColumnarError::Truncated { at: self.at };
+        let end = self.at.checked_add(len).ok_or(truncated)?;
+        let bytes = self.data.get(self.at..end).ok_or(truncated)?;
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn skip_padding(&mut self) -> Result<(), ColumnarError> {
+        self.take(self.at.next_multiple_of(8) - self.at).map(|_| ())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ColumnarError> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn read_len(&mut self) -> Result<usize, ColumnarError> {
+        self.skip_padding()?;
+        let at = self.at;
+        let bytes = self.take(8)?.try_into().unwrap_or_else(|_| unreachable!());
+        usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| ColumnarError::Truncated { at })
+    }
+
+    fn read_elems<T: ColumnarElem>(&mut self, len: usize) -> Result<Cow<'a, [T]>, ColumnarError> {
+        self.skip_padding()?;
+        let at = self.at;
+        let byte_len = len
+            .checked_mul(size_of::<T>())
+            .ok_or(ColumnarError::Truncated { at })?;
+        let bytes = self.take(byte_len)?;
+
+        if cfg!(target_endian = "little") {
+            if let Ok(xs) = bytemuck::try_cast_slice(bytes) {
+                return Ok(Cow::Borrowed(xs));
+            }
+        }
+        let mut xs = vec![T::zeroed(); len];
+        bytemuck::cast_slice_mut(&mut xs).copy_from_slice(bytes);
+        if cfg!(target_endian = "big") {
+            for x in bytemuck::cast_slice_mut::<T, u64>(&mut xs) {
+                *x = u64::from_le(*x);
+            }
+        }
+        Ok(Cow::Owned(xs))
+    }
+
+    fn read_list<T: ColumnarElem>(
+        &mut self,
+        depth: usize,
+    ) -> Result<ListView<'a, T>, ColumnarError> {
+        let at = self.at;
+        match self.read_u8()? {
+            TERM_TAG => Ok(ListView::Term(self.read_elems(1)?[0])),
+            FLAT_TAG => {
+                let len = self.read_len()?;
+                self.read_elems(len).map(ListView::Flat)
+            }
+            STAGGERED_TAG if depth == MAX_DEPTH => Err(ColumnarError::TooDeep { at }),
+            STAGGERED_TAG => {
+                let len = self.read_len()?;
+                // every list takes at least a byte, which bounds the allocation by the message.
+                let mut items = Vec::with_capacity(len.min(self.data.len() - self.at));
+                for _ in 0..len {
+                    items.push(self.read_list(depth + 1)?);
+                }
+                Ok(ListView::Staggered(items))
+            }
+            _ => Err(ColumnarError::UnknownTag { at }),
+        }
+    }
+}
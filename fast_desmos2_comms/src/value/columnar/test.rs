@@ -0,0 +1,144 @@
+use crate::value::{ColumnarError, List, ListView, Value, ValueView, COLUMNAR_MAGIC};
+use glam::DVec2;
+use std::borrow::Cow;
+
+/// Copies `data` into a buffer that is 8-byte aligned, then offsets it by `offset` bytes.
+fn with_alignment(data: &[u8], offset: usize, func: impl FnOnce(&[u8])) {
+    let mut words = vec![0u64; (data.len() + offset).div_ceil(8)];
+    let bytes = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut words)[offset..offset + data.len()];
+    bytes.copy_from_slice(data);
+    func(bytes);
+}
+
+fn test_columnar(value: Value) {
+    println!("Running test {value}");
+    let data = value.to_columnar();
+    for offset in 0..8 {
+        with_alignment(&data, offset, |data| {
+            let new_value = Value::from_columnar(data).unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(new_value, value, "at offset {offset}");
+        });
+    }
+}
+
+#[test]
+fn columnar_round_trip() {
+    test_columnar(Value::one_number(1.5));
+    test_columnar(Value::one_point(DVec2::new(-1.0, 2.0)));
+    test_columnar(Value::Number(List::Flat(Vec::new())));
+    test_columnar(Value::Number(List::Flat(
+        (0..1000).map(f64::from).collect(),
+    )));
+    test_columnar(Value::Point(List::Flat(vec![
+        DVec2::X,
+        DVec2::Y,
+        DVec2::MAX,
+    ])));
+    test_columnar(Value::Number(List::Staggered(vec![
+        List::Term(1.0),
+        List::Flat(vec![4.0, 2.0, 3.0]),
+        List::Staggered(vec![List::Flat(Vec::new()), List::Flat(vec![5.0])]),
+    ])));
+}
+
+#[test]
+fn columnar_layout() {
+    let data = Value::Number(List::Flat(vec![1.0, 2.0])).to_columnar();
+    let mut expected = vec![COLUMNAR_MAGIC, 1, 0, 1, 0, 0, 0, 0];
+    expected.extend(2u64.to_le_bytes());
+    expected.extend(1f64.to_le_bytes());
+    expected.extend(2f64.to_le_bytes());
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn columnar_zero_copy() {
+    let value = Value::Point(List::Staggered(vec![
+        List::Flat(vec![DVec2::ONE; 3]),
+        List::Flat(vec![DVec2::ZERO; 2]),
+    ]));
+    let data = value.to_columnar();
+
+    with_alignment(&data, 0, |data| {
+        let view = Value::view_columnar(data).unwrap();
+        assert_eq!(view.is_borrowed(), cfg!(target_endian = "little"));
+        assert_eq!(view.to_value(), value);
+    });
+    with_alignment(&data, 1, |data| {
+        let view = Value::view_columnar(data).unwrap();
+        assert!(!view.is_borrowed());
+        assert_eq!(view.to_value(), value);
+    });
+
+    with_alignment(&Value::one_number(2.0).to_columnar(), 0, |data| {
+        assert_eq!(
+            Value::view_columnar(data),
+            Ok(ValueView::Number(ListView::Term(2.0)))
+        );
+    });
+    with_alignment(
+        &Value::Number(List::Flat(vec![])).to_columnar(),
+        0,
+        |data| {
+            assert_eq!(
+                Value::view_columnar(data),
+                Ok(ValueView::Number(ListView::Flat(Cow::Borrowed(&[]))))
+            );
+        },
+    );
+}
+
+#[test]
+fn columnar_errors() {
+    let data = Value::Number(List::Flat(vec![1.0, 2.0])).to_columnar();
+    assert_eq!(
+        Value::from_columnar(&[]),
+        Err(ColumnarError::Truncated { at: 0 })
+    );
+    assert_eq!(
+        Value::from_columnar(&[0, 0xFF]),
+        Err(ColumnarError::NotColumnar)
+    );
+    assert_eq!(
+        Value::from_columnar(&[COLUMNAR_MAGIC, 2]),
+        Err(ColumnarError::UnsupportedVersion(2))
+    );
+    assert_eq!(
+        Value::from_columnar(&[COLUMNAR_MAGIC, 1, 7]),
+        Err(ColumnarError::UnknownKind { at: 2 })
+    );
+    assert_eq!(
+        Value::from_columnar(&[COLUMNAR_MAGIC, 1, 0, 9]),
+        Err(ColumnarError::UnknownTag { at: 3 })
+    );
+    assert_eq!(
+        Value::from_columnar(&data[..data.len() - 1]),
+        Err(ColumnarError::Truncated { at: 16 })
+    );
+
+    let mut huge = data.clone();
+    huge[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(
+        Value::from_columnar(&huge),
+        Err(ColumnarError::Truncated { at: 16 })
+    );
+
+    let mut trailing = data;
+    trailing.push(0);
+    assert_eq!(
+        Value::from_columnar(&trailing),
+        Err(ColumnarError::Trailing { at: 32 })
+    );
+
+    // staggered lists of one staggered list, far deeper than the stack allows.
+    let mut nested = vec![COLUMNAR_MAGIC, 1, 0];
+    for _ in 0..100_000 {
+        nested.push(2);
+        nested.resize(nested.len().next_multiple_of(8), 0);
+        nested.extend(1u64.to_le_bytes());
+    }
+    assert_eq!(
+        Value::from_columnar(&nested),
+        Err(ColumnarError::TooDeep { at: 16 * 256 })
+    );
+}
@@ -0,0 +1,304 @@
+//! Incremental updates to [`Value`]s, so that big lists don't have to be resent in full.
+//!
+//! A [`Patch`] targets the list found by following `path` through staggered lists, and works on
+//! its items: the elements of a flat list or the sublists of a staggered one. The items of the
+//! value carried by a patch are read the same way, so `[1, 2]` is two items and `[[1, 2]]` is one.
+
+use super::*;
+use std::fmt::{self, Formatter};
+use std::mem;
+use std::ops::Range;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub path: Vec<usize>,
+    pub op: PatchOp,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    /// Overwrites the items starting at `start` with the items of `items`, growing the list if
+    /// they run past its end.
+    SetRange { start: usize, items: Value },
+    /// Adds the items of the value to the end of the list.
+    Append(Value),
+    /// Shortens the list to the given length.
+    Truncate(usize),
+    /// Replaces the list itself, or the whole value if the path is empty.
+    Replace(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchError {
+    /// The path goes through something that isn't a staggered list at the given depth.
+    BadPath {
+        depth: usize,
+    },
+    OutOfRange {
+        index: usize,
+        len: usize,
+    },
+    NotAList,
+    TypeMismatch(TypeMismatch),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadPath { depth } => write!(f, "patch path doesn't exist at depth {depth}"),
+            Self::OutOfRange { index, len } => {
+                write!(f, "index {index} is out of range for a list of {len}")
+            }
+            Self::NotAList => write!(f, "can't patch the items of a single element"),
+            Self::TypeMismatch(mismatch) => write!(
+                f,
+                "can't patch a {} list with a {}",
+                mismatch.expect, mismatch.got
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl Patch {
+    pub fn root(op: PatchOp) -> Self {
+        Self {
+            path: Vec::new(),
+            op,
+        }
+    }
+}
+
+enum ListOp<T> {
+    SetRange { start: usize, items: List<T> },
+    Append(List<T>),
+    Truncate(usize),
+    Replace(List<T>),
+}
+
+impl<T> List<T> {
+    fn into_items(self) -> Vec<Self> {
+        match self {
+            Self::Term(_) => vec![self],
+            Self::Flat(xs) => xs.into_iter().map(Self::Term).collect(),
+            Self::Staggered(xs) => xs,
+        }
+    }
+
+    fn item_count(&self) -> usize {
+        self.len().unwrap_or(1)
+    }
+
+    fn at_path_mut(&mut self, path: &[usize]) -> Result<&mut Self, PatchError> {
+        let mut list = self;
+        for (depth, &index) in path.iter().enumerate() {
+            let Self::Staggered(xs) = list else {
+                return Err(PatchError::BadPath { depth });
+            };
+            list = xs.get_mut(index).ok_or(PatchError::BadPath { depth })?;
+        }
+        Ok(list)
+    }
+
+    fn splice(&mut self, range: Range<usize>, items: Self) -> Result<(), PatchError> {
+        let len = self.len().ok_or(PatchError::NotAList)?;
+        if range.start > len {
+            return Err(PatchError::OutOfRange {
+                index: range.start,
+                len,
+            });
+        }
+        let range = range.start..range.end.min(len);
+
+        match (&mut *self, items) {
+            (Self::Flat(xs), Self::Term(y)) => {
+                xs.splice(range, [y]);
+            }
+            (Self::Flat(xs), Self::Flat(ys)) => {
+                xs.splice(range, ys);
+            }
+            (Self::Staggered(xs), items) => {
+                xs.splice(range, items.into_items());
+                if xs.iter().all(|x| matches!(x, Self::Term(_))) {
+                    *self = Self::list(mem::take(xs));
+                }
+            }
+            (_, items) => {
+                let mut xs = mem::replace(self, Self::empty()).into_items();
+                xs.splice(range, items.into_items());
+                *self = Self::list(xs);
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, path: &[usize], op: ListOp<T>) -> Result<(), PatchError> {
+        if let ListOp::Replace(item) = op {
+            let Some((&index, parent)) = path.split_last() else {
+                *self = item;
+                return Ok(());
+            };
+            let parent = self.at_path_mut(parent)?;
+            return match parent.len() {
+                Some(len) if index < len => {
+                    parent.splice(index..index + 1, Self::Staggered(vec![item]))
+                }
+                Some(len) => Err(PatchError::OutOfRange { index, len }),
+                None => Err(PatchError::BadPath {
+                    depth: path.len() - 1,
+                }),
+            };
+        }
+
+        let list = self.at_path_mut(path)?;
+        let len = list.len().ok_or(PatchError::NotAList)?;
+        match op {
+            ListOp::SetRange { start, items } => {
+                let end = start.saturating_add(items.item_count());
+                list.splice(start..end, items)
+            }
+            ListOp::Append(items) => list.splice(len..len, items),
+            ListOp::Truncate(new_len) => list.splice(new_len..len, Self::empty()),
+            ListOp::Replace(_) => unreachable!(),
+        }
+    }
+}
+
+impl PatchOp {
+    fn value_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            Self::SetRange { items, .. } => Some(items),
+            Self::Append(items) | Self::Replace(items) => Some(items),
+            Self::Truncate(_) => None,
+        }
+    }
+
+    fn into_list_op<T>(self, take: impl FnOnce(Value) -> List<T>) -> ListOp<T> {
+        match self {
+            Self::SetRange { start, items } => ListOp::SetRange {
+                start,
+                items: take(items),
+            },
+            Self::Append(items) => ListOp::Append(take(items)),
+            Self::Truncate(len) => ListOp::Truncate(len),
+            Self::Replace(item) => ListOp::Replace(take(item)),
+        }
+    }
+}
+
+impl Value {
+    pub fn apply_patch(&mut self, patch: Patch) -> Result<(), PatchError> {
+        let Patch { path, mut op } = patch;
+        if let (true, PatchOp::Replace(value)) = (path.is_empty(), &mut op) {
+            *self = mem::replace(value, Value::empty());
+            return Ok(());
+        }
+
+        if let Some(value) = op.value_mut() {
+            // empty lists have no kind of their own, so they fit anywhere.
+            if value.kind() != self.kind() && !value.is_empty() {
+                return Err(PatchError::TypeMismatch(TypeMismatch {
+                    expect: self.kind(),
+                    got: value.kind(),
+                }));
+            }
+        }
+
+        match self {
+            Self::Number(xs) => xs.apply(
+                &path,
+                op.into_list_op(|value| match value {
+                    Self::Number(ys) => ys,
+                    _ => List::empty(),
+                }),
+            ),
+            Self::Point(xs) => xs.apply(
+                &path,
+                op.into_list_op(|value| match value {
+                    Self::Point(ys) => ys,
+                    _ => List::empty(),
+                }),
+            ),
+        }
+    }
+
+    pub fn apply_patches(
+        &mut self,
+        patches: impl IntoIterator<Item = Patch>,
+    ) -> Result<(), PatchError> {
+        patches
+            .into_iter()
+            .try_for_each(|patch| self.apply_patch(patch))
+    }
+
+    /// Returns patches that turn `old` into `self`, covering each changed stretch of a flat list
+    /// with one [`PatchOp::SetRange`].
+    pub fn diff(&self, old: &Value) -> Vec<Patch> {
+        let mut patches = Vec::new();
+        match (old, self) {
+            (Self::Number(old), Self::Number(new)) => {
+                diff_list(old, new, &mut Vec::new(), &mut patches, &Self::Number)
+            }
+            (Self::Point(old), Self::Point(new)) => {
+                diff_list(old, new, &mut Vec::new(), &mut patches, &Self::Point)
+            }
+            _ => patches.push(Patch::root(PatchOp::Replace(self.clone()))),
+        }
+        patches
+    }
+}
+
+fn diff_list<T: Clone + PartialEq>(
+    old: &List<T>,
+    new: &List<T>,
+    path: &mut Vec<usize>,
+    patches: &mut Vec<Patch>,
+    wrap: &impl Fn(List<T>) -> Value,
+) {
+    let push = |patches: &mut Vec<Patch>, path: &[usize], op| {
+        patches.push(Patch {
+            path: path.to_vec(),
+            op,
+        })
+    };
+    let resize =
+        |patches: &mut Vec<Patch>, path: &[usize], new_len: usize, tail: List<T>| match tail
+            .is_empty()
+        {
+            true => push(patches, path, PatchOp::Truncate(new_len)),
+            false => push(patches, path, PatchOp::Append(wrap(tail))),
+        };
+
+    match (old, new) {
+        (List::Flat(old), List::Flat(new)) => {
+            let common = old.len().min(new.len());
+            let changed = |index: &usize| old[*index] != new[*index];
+            if let Some(start) = (0..common).find(changed) {
+                let end = (0..common).rfind(changed).unwrap_or(start) + 1;
+                let items = wrap(List::Flat(new[start..end].to_vec()));
+                push(patches, path, PatchOp::SetRange { start, items });
+            }
+            if old.len() != new.len() {
+                resize(patches, path, new.len(), List::Flat(new[common..].to_vec()));
+            }
+        }
+        (List::Staggered(old), List::Staggered(new)) => {
+            let common = old.len().min(new.len());
+            for index in 0..common {
+                path.push(index);
+                diff_list(&old[index], &new[index], path, patches, wrap);
+                path.pop();
+            }
+            if old.len() != new.len() {
+                let tail = List::Staggered(new[common..].to_vec());
+                resize(patches, path, new.len(), tail);
+            }
+        }
+        (old, new) if old == new => {}
+        (_, new) => push(patches, path, PatchOp::Replace(wrap(new.clone()))),
+    }
+}
@@ -0,0 +1,185 @@
+use crate::value::{List, Patch, PatchError, PatchOp, TypeMismatch, Value, ValueKind};
+use glam::DVec2;
+
+fn numbers(xs: &[f64]) -> Value {
+    Value::Number(List::Flat(xs.to_vec()))
+}
+
+fn patch(path: &[usize], op: PatchOp) -> Patch {
+    Patch {
+        path: path.to_vec(),
+        op,
+    }
+}
+
+fn staggered() -> Value {
+    Value::Number(List::Staggered(vec![
+        List::Flat(vec![1.0, 2.0]),
+        List::Term(3.0),
+        List::Staggered(vec![List::Flat(vec![4.0]), List::Flat(vec![])]),
+    ]))
+}
+
+#[test]
+fn flat_patches() {
+    let mut value = numbers(&[0.0, 1.0, 2.0, 3.0]);
+    let set = PatchOp::SetRange {
+        start: 1,
+        items: numbers(&[5.0, 6.0]),
+    };
+    value.apply_patch(Patch::root(set)).unwrap();
+    assert_eq!(value, numbers(&[0.0, 5.0, 6.0, 3.0]));
+
+    let set = PatchOp::SetRange {
+        start: 3,
+        items: numbers(&[7.0, 8.0]),
+    };
+    value.apply_patch(Patch::root(set)).unwrap();
+    assert_eq!(value, numbers(&[0.0, 5.0, 6.0, 7.0, 8.0]));
+
+    let set = PatchOp::SetRange {
+        start: 0,
+        items: Value::one_number(9.0),
+    };
+    value.apply_patch(Patch::root(set)).unwrap();
+    assert_eq!(value, numbers(&[9.0, 5.0, 6.0, 7.0, 8.0]));
+
+    value
+        .apply_patches([
+            Patch::root(PatchOp::Truncate(2)),
+            Patch::root(PatchOp::Append(numbers(&[1.0]))),
+        ])
+        .unwrap();
+    assert_eq!(value, numbers(&[9.0, 5.0, 1.0]));
+
+    let nested = Value::Number(List::Staggered(vec![List::Flat(vec![2.0])]));
+    value
+        .apply_patch(Patch::root(PatchOp::Append(nested)))
+        .unwrap();
+    assert_eq!(
+        value,
+        Value::Number(List::Staggered(vec![
+            List::Term(9.0),
+            List::Term(5.0),
+            List::Term(1.0),
+            List::Flat(vec![2.0]),
+        ]))
+    );
+    value
+        .apply_patch(Patch::root(PatchOp::Truncate(3)))
+        .unwrap();
+    assert_eq!(value, numbers(&[9.0, 5.0, 1.0]));
+}
+
+#[test]
+fn staggered_patches() {
+    let mut value = staggered();
+    value
+        .apply_patches([
+            patch(&[0], PatchOp::Append(numbers(&[2.5]))),
+            patch(&[2, 1], PatchOp::Append(numbers(&[4.5, 5.0]))),
+            patch(&[2], PatchOp::Replace(numbers(&[6.0]))),
+        ])
+        .unwrap();
+    assert_eq!(
+        value,
+        Value::Number(List::Staggered(vec![
+            List::Flat(vec![1.0, 2.0, 2.5]),
+            List::Term(3.0),
+            List::Flat(vec![6.0]),
+        ]))
+    );
+
+    value
+        .apply_patch(patch(&[0], PatchOp::Replace(Value::one_number(0.0))))
+        .unwrap();
+    value
+        .apply_patch(patch(&[2], PatchOp::Replace(Value::one_number(6.0))))
+        .unwrap();
+    assert_eq!(value, numbers(&[0.0, 3.0, 6.0]));
+
+    value
+        .apply_patch(Patch::root(PatchOp::Replace(Value::one_point(DVec2::ONE))))
+        .unwrap();
+    assert_eq!(value, Value::one_point(DVec2::ONE));
+}
+
+#[test]
+fn patch_errors() {
+    let mut value = staggered();
+    assert_eq!(
+        value.apply_patch(patch(&[1, 0], PatchOp::Truncate(0))),
+        Err(PatchError::BadPath { depth: 1 })
+    );
+    assert_eq!(
+        value.apply_patch(patch(&[5], PatchOp::Truncate(0))),
+        Err(PatchError::BadPath { depth: 0 })
+    );
+    assert_eq!(
+        value.apply_patch(patch(&[1], PatchOp::Truncate(0))),
+        Err(PatchError::NotAList)
+    );
+    assert_eq!(
+        value.apply_patch(patch(&[0], PatchOp::Truncate(3))),
+        Err(PatchError::OutOfRange { index: 3, len: 2 })
+    );
+    assert_eq!(
+        value.apply_patch(patch(&[3], PatchOp::Replace(Value::one_number(0.0)))),
+        Err(PatchError::OutOfRange { index: 3, len: 3 })
+    );
+    assert_eq!(
+        value.apply_patch(patch(&[0], PatchOp::Append(Value::one_point(DVec2::ONE)))),
+        Err(PatchError::TypeMismatch(TypeMismatch {
+            expect: ValueKind::Number,
+            got: ValueKind::Point
+        }))
+    );
+    assert_eq!(value, staggered());
+}
+
+fn test_diff(old: Value, new: Value) {
+    let patches = new.diff(&old);
+    let mut patched = old.clone();
+    patched.apply_patches(patches.clone()).unwrap();
+    assert_eq!(patched, new, "{old} -> {new} with {patches:?}");
+}
+
+#[test]
+fn diff_round_trip() {
+    let big: Vec<f64> = (0..1000).map(f64::from).collect();
+    let mut changed = big.clone();
+    changed[10] = -1.0;
+    changed[12] = -1.0;
+    let patches = numbers(&changed).diff(&numbers(&big));
+    assert_eq!(
+        patches,
+        [Patch::root(PatchOp::SetRange {
+            start: 10,
+            items: numbers(&[-1.0, 11.0, -1.0])
+        })]
+    );
+    assert_eq!(numbers(&big).diff(&numbers(&big)), []);
+
+    test_diff(numbers(&big), numbers(&changed));
+    test_diff(numbers(&big), numbers(&changed[..500]));
+    test_diff(numbers(&[]), numbers(&changed));
+    test_diff(numbers(&[1.0]), staggered());
+    test_diff(staggered(), numbers(&[1.0]));
+    test_diff(staggered(), Value::one_point(DVec2::ZERO));
+    test_diff(
+        staggered(),
+        Value::Number(List::Staggered(vec![
+            List::Flat(vec![1.0, 5.0, 6.0]),
+            List::Flat(vec![3.0]),
+        ])),
+    );
+    test_diff(
+        staggered(),
+        Value::Number(List::Staggered(vec![
+            List::Flat(vec![1.0, 2.0]),
+            List::Term(3.0),
+            List::Staggered(vec![List::Flat(vec![4.0, 4.0]), List::Flat(vec![])]),
+            List::Term(7.0),
+        ])),
+    );
+}
@@ -1,12 +1,13 @@
 use super::*;
 use glam::DVec2;
+use std::fmt::{self, Formatter};
 
 #[cfg(test)]
 mod test;
 
 pub trait Serde: Sized {
     fn serialize_to(&self, data: &mut Vec<u8>);
-    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self;
+    fn try_deserialize_from(at: &mut usize, data: &[u8]) -> Result<Self, SerdeError>;
 
     fn serialize(&self) -> Vec<u8> {
         let mut vec = Vec::new();
@@ -14,9 +15,44 @@ pub trait Serde: Sized {
         vec
     }
 
+    /// Panics if `data` doesn't hold a `Self` at `at`, which is fine for data this program wrote.
+    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self {
+        Self::try_deserialize_from(at, data).unwrap_or_else(|err| panic!("{err}"))
+    }
+
     fn deserialize(data: &[u8]) -> Self {
         Self::deserialize_from(&mut 0, data)
     }
+
+    /// For data from elsewhere, like the network.
+    fn try_deserialize(data: &[u8]) -> Result<Self, SerdeError> {
+        Self::try_deserialize_from(&mut 0, data)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerdeError {
+    UnknownKind { at: usize },
+    Truncated { at: usize },
+    TooDeep { at: usize },
+}
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKind { at } => write!(f, "unknown value kind at byte {at}"),
+            Self::Truncated { at } => write!(f, "message ends early at byte {at}"),
+            Self::TooDeep { at } => write!(f, "lists nested too deeply at byte {at}"),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+fn read_u8(at: &mut usize, data: &[u8]) -> Result<u8, SerdeError> {
+    let byte = *data.get(*at).ok_or(SerdeError::Truncated { at: *at })?;
+    *at += 1;
+    Ok(byte)
 }
 
 impl Serde for f64 {
@@ -24,12 +60,15 @@ impl Serde for f64 {
         data.extend(self.to_le_bytes());
     }
 
-    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self {
-        let my_area: [u8; 8] = data[*at..*at + 8]
+    fn try_deserialize_from(at: &mut usize, data: &[u8]) -> Result<Self, SerdeError> {
+        let my_area: [u8; 8] = data
+            .get(*at..)
+            .and_then(|rest| rest.get(..8))
+            .ok_or(SerdeError::Truncated { at: *at })?
             .try_into()
             .unwrap_or_else(|err| unreachable!("{err}"));
         *at += 8;
-        f64::from_le_bytes(my_area)
+        Ok(f64::from_le_bytes(my_area))
     }
 }
 
@@ -39,10 +78,10 @@ impl Serde for DVec2 {
         self.y.serialize_to(data);
     }
 
-    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self {
-        let x = f64::deserialize_from(at, data);
-        let y = f64::deserialize_from(at, data);
-        Self { x, y }
+    fn try_deserialize_from(at: &mut usize, data: &[u8]) -> Result<Self, SerdeError> {
+        let x = f64::try_deserialize_from(at, data)?;
+        let y = f64::try_deserialize_from(at, data)?;
+        Ok(Self { x, y })
     }
 }
 
@@ -82,33 +121,49 @@ impl<T: Serde> Serde for List<T> {
         }
     }
 
-    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self {
-        let first_byte = data[*at];
-        if first_byte >> 7 > 0 {
-            *at += 1;
-            Self::Term(T::deserialize_from(at, data))
-        } else {
-            let mut data_len = usize::from(first_byte & 0b0011_1111);
-            *at += 1;
-
-            let mut push_by = 6u32;
-            let mut continued = first_byte >> 6 > 0;
-            while continued {
-                let byte = data[*at];
-                continued = byte >> 7 > 0;
-                data_len |= usize::from(byte & 0b0111_1111) << push_by;
-                push_by += 7;
-                *at += 1;
-            }
+    fn try_deserialize_from(at: &mut usize, data: &[u8]) -> Result<Self, SerdeError> {
+        deserialize_list(at, data, 0)
+    }
+}
 
-            let mut items = Vec::with_capacity(data_len);
-            for _ in 0..data_len {
-                items.push(Self::deserialize_from(at, data));
-            }
+fn deserialize_list<T: Serde>(
+    at: &mut usize,
+    data: &[u8],
+    depth: usize,
+) -> Result<List<T>, SerdeError> {
+    let start = *at;
+    let first_byte = read_u8(at, data)?;
+    if first_byte >> 7 > 0 {
+        return T::try_deserialize_from(at, data).map(List::Term);
+    }
+    if depth == MAX_DEPTH {
+        return Err(SerdeError::TooDeep { at: start });
+    }
 
-            List::list(items)
-        }
+    let mut data_len = usize::from(first_byte & 0b0011_1111);
+    let mut push_by = 6u32;
+    let mut continued = first_byte >> 6 > 0;
+    while continued {
+        let byte = read_u8(at, data)?;
+        continued = byte >> 7 > 0;
+        let part = usize::from(byte & 0b0111_1111);
+        data_len |= part
+            .checked_shl(push_by)
+            .filter(|shifted| shifted >> push_by == part)
+            .ok_or(SerdeError::Truncated { at: start })?;
+        push_by += 7;
     }
+    // every item takes at least a byte, so a longer list can't fit in what's left.
+    if data_len > data.len() - *at {
+        return Err(SerdeError::Truncated { at: start });
+    }
+
+    let mut items = Vec::with_capacity(data_len);
+    for _ in 0..data_len {
+        items.push(deserialize_list(at, data, depth + 1)?);
+    }
+
+    Ok(List::list(items))
 }
 
 impl Serde for Value {
@@ -125,12 +180,12 @@ impl Serde for Value {
         }
     }
 
-    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self {
-        let byte = data[*at];
-        *at += 1;
-        match byte {
-            0 => Self::Number(<_>::deserialize_from(at, data)),
-            _ => unreachable!(),
+    fn try_deserialize_from(at: &mut usize, data: &[u8]) -> Result<Self, SerdeError> {
+        let kind_at = *at;
+        match read_u8(at, data)? {
+            0 => <_>::try_deserialize_from(at, data).map(Self::Number),
+            1 => <_>::try_deserialize_from(at, data).map(Self::Point),
+            _ => Err(SerdeError::UnknownKind { at: kind_at }),
         }
     }
 }
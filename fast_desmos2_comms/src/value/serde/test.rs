@@ -1,5 +1,5 @@
-use crate::value::serde::Serde;
-use crate::value::List;
+use crate::value::serde::{Serde, SerdeError};
+use crate::value::{List, Value};
 use glam::DVec2;
 use std::fmt::Debug;
 
@@ -38,8 +38,8 @@ impl Serde for TotalF64 {
     fn serialize_to(&self, data: &mut Vec<u8>) {
         self.0.serialize_to(data);
     }
-    fn deserialize_from(at: &mut usize, data: &[u8]) -> Self {
-        TotalF64(f64::deserialize_from(at, data))
+    fn try_deserialize_from(at: &mut usize, data: &[u8]) -> Result<Self, SerdeError> {
+        f64::try_deserialize_from(at, data).map(TotalF64)
     }
 }
 
@@ -83,3 +83,40 @@ fn list_serde() {
         List::Staggered(vec![List::Term(1.0), List::Flat(vec![4.0, 2.0, 3.0])])
     ]);
 }
+
+#[test]
+fn value_serde() {
+    test_serde_all(list_with_name![
+        Value::one_number(1.5),
+        Value::Number(List::Flat(vec![1.0, -2.0])),
+        Value::Point(List::Term(DVec2::new(1.0, -2.0))),
+        Value::Point(List::Flat(vec![DVec2::ZERO, DVec2::ONE])),
+        Value::Point(List::Staggered(vec![
+            List::Term(DVec2::X),
+            List::Flat(vec![DVec2::Y, DVec2::NEG_ONE])
+        ])),
+    ]);
+}
+
+#[test]
+fn malformed_serde() {
+    let errors = [
+        (vec![], SerdeError::Truncated { at: 0 }),
+        (vec![5, 0, 0], SerdeError::UnknownKind { at: 0 }),
+        (vec![0, u8::MAX, 1, 2], SerdeError::Truncated { at: 2 }),
+        (vec![0, 0b0100_0000], SerdeError::Truncated { at: 2 }),
+        (
+            vec![0, 0b0111_1111, 0xff, 0xff],
+            SerdeError::Truncated { at: 4 },
+        ),
+        (vec![0, 0b0111_1111, 0x7f], SerdeError::Truncated { at: 1 }),
+        // lists of one list, far deeper than the stack allows.
+        (
+            [vec![0], vec![1; 200_000]].concat(),
+            SerdeError::TooDeep { at: 257 },
+        ),
+    ];
+    for (data, error) in errors {
+        assert_eq!(Value::try_deserialize(&data), Err(error));
+    }
+}
@@ -0,0 +1,325 @@
+//! Human-readable encoding of [`Value`]s, for use from scripts and by hand.
+//!
+//! The syntax follows desmos: `1.5`, `(1, 2)`, `[1, 2, 3]`, `[[1], [2, 3]]`. Lists whose element
+//! kind can't be inferred (because they contain no elements at all) are written with a leading
+//! kind, such as `point: []`.
+
+use super::*;
+use std::fmt::{self, Formatter};
+use std::str::FromStr;
+
+#[cfg(test)]
+mod test;
+
+trait TextElem: Sized {
+    const KIND: ValueKind;
+
+    fn write_text(&self, f: &mut Formatter<'_>) -> fmt::Result;
+    fn from_item(item: &Item) -> Option<Self>;
+}
+
+fn write_number(x: f64, f: &mut Formatter<'_>) -> fmt::Result {
+    // `Display` never uses exponents, which makes very big and very small numbers unreadable.
+    if x.is_finite() && x == x.trunc() && x.abs() < 1e16 {
+        write!(f, "{x}")
+    } else {
+        write!(f, "{x:?}")
+    }
+}
+
+impl TextElem for f64 {
+    const KIND: ValueKind = ValueKind::Number;
+
+    fn write_text(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_number(*self, f)
+    }
+
+    fn from_item(item: &Item) -> Option<Self> {
+        match item.kind {
+            ItemKind::Number(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl TextElem for DVec2 {
+    const KIND: ValueKind = ValueKind::Point;
+
+    fn write_text(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        write_number(self.x, f)?;
+        write!(f, ", ")?;
+        write_number(self.y, f)?;
+        write!(f, ")")
+    }
+
+    fn from_item(item: &Item) -> Option<Self> {
+        match item.kind {
+            ItemKind::Point(p) => Some(p),
+            _ => None,
+        }
+    }
+}
+
+fn has_term<T>(xs: &List<T>) -> bool {
+    match xs {
+        List::Term(_) => true,
+        List::Flat(xs) => !xs.is_empty(),
+        List::Staggered(xs) => xs.iter().any(has_term),
+    }
+}
+
+fn write_list<T: TextElem>(xs: &List<T>, f: &mut Formatter<'_>) -> fmt::Result {
+    fn write_all<T>(
+        f: &mut Formatter<'_>,
+        xs: &[T],
+        mut func: impl FnMut(&T, &mut Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        write!(f, "[")?;
+        for (index, x) in xs.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            func(x, f)?;
+        }
+        write!(f, "]")
+    }
+
+    match xs {
+        List::Term(x) => x.write_text(f),
+        List::Flat(xs) => write_all(f, xs, T::write_text),
+        List::Staggered(xs) => write_all(f, xs, write_list),
+    }
+}
+
+fn list_from_item<T: TextElem>(item: &Item) -> Result<List<T>, TextError> {
+    match &item.kind {
+        ItemKind::List(items) => Ok(List::list(
+            items.iter().map(list_from_item).collect::<Result<_, _>>()?,
+        )),
+        _ => T::from_item(item)
+            .map(List::Term)
+            .ok_or(TextError::TypeMismatch {
+                at: item.at,
+                mismatch: TypeMismatch {
+                    expect: T::KIND,
+                    got: item.kind(),
+                },
+            }),
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn write_value<T: TextElem>(xs: &List<T>, f: &mut Formatter<'_>) -> fmt::Result {
+            if !has_term(xs) {
+                write!(f, "{}: ", T::KIND)?;
+            }
+            write_list(xs, f)
+        }
+
+        match self {
+            Value::Number(xs) => write_value(xs, f),
+            Value::Point(xs) => write_value(xs, f),
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = TextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = TextParser { source: s, at: 0 };
+        let kind = parser.parse_kind()?;
+        let item = parser.parse_item(0)?;
+        parser.skip_whitespace();
+        if parser.at != s.len() {
+            return Err(TextError::Trailing { at: parser.at });
+        }
+
+        match kind.or_else(|| item.first_kind()) {
+            None | Some(ValueKind::Number) => list_from_item(&item).map(Value::Number),
+            Some(ValueKind::Point) => list_from_item(&item).map(Value::Point),
+        }
+    }
+}
+
+impl Value {
+    pub fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, TextError> {
+        text.parse()
+    }
+}
+
+/// Returns whether a message starting with `byte` should be read as text.
+///
+/// Binary messages always start with a small kind tag, which is never printable.
+pub fn is_text_start(byte: u8) -> bool {
+    byte.is_ascii_graphic() || byte.is_ascii_whitespace()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextError {
+    Expected { at: usize, what: &'static str },
+    BadNumber { at: usize },
+    UnknownKind { at: usize },
+    TypeMismatch { at: usize, mismatch: TypeMismatch },
+    Trailing { at: usize },
+    TooDeep { at: usize },
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expected { at, what } => write!(f, "expected {what} at byte {at}"),
+            Self::BadNumber { at } => write!(f, "malformed number at byte {at}"),
+            Self::UnknownKind { at } => write!(f, "unknown value kind at byte {at}"),
+            Self::TypeMismatch { at, mismatch } => write!(
+                f,
+                "expected a {} but got a {} at byte {at}",
+                mismatch.expect, mismatch.got
+            ),
+            Self::Trailing { at } => write!(f, "unexpected trailing input at byte {at}"),
+            Self::TooDeep { at } => write!(f, "lists nested too deeply at byte {at}"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+/// A parsed value before its kind is known.
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    at: usize,
+    kind: ItemKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ItemKind {
+    Number(f64),
+    Point(DVec2),
+    List(Vec<Item>),
+}
+
+impl Item {
+    fn kind(&self) -> ValueKind {
+        self.first_kind().unwrap_or(ValueKind::Number)
+    }
+
+    fn first_kind(&self) -> Option<ValueKind> {
+        match &self.kind {
+            ItemKind::Number(_) => Some(ValueKind::Number),
+            ItemKind::Point(_) => Some(ValueKind::Point),
+            ItemKind::List(items) => items.iter().find_map(Item::first_kind),
+        }
+    }
+}
+
+struct TextParser<'a> {
+    source: &'a str,
+    at: usize,
+}
+
+impl<'a> TextParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.at..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        self.skip_whitespace();
+        let found = self.rest().starts_with(ch);
+        if found {
+            self.at += ch.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, ch: char, what: &'static str) -> Result<(), TextError> {
+        match self.eat(ch) {
+            true => Ok(()),
+            false => Err(TextError::Expected { at: self.at, what }),
+        }
+    }
+
+    fn parse_kind(&mut self) -> Result<Option<ValueKind>, TextError> {
+        self.skip_whitespace();
+        let start = self.at;
+        let name_len = self
+            .rest()
+            .find(|ch: char| !ch.is_ascii_alphabetic())
+            .unwrap_or(self.rest().len());
+        let name = &self.rest()[..name_len];
+        self.at += name_len;
+
+        if !self.eat(':') {
+            // not a kind, just a number like `inf`.
+            self.at = start;
+            return Ok(None);
+        }
+        match name {
+            "number" => Ok(Some(ValueKind::Number)),
+            "point" => Ok(Some(ValueKind::Point)),
+            _ => Err(TextError::UnknownKind { at: start }),
+        }
+    }
+
+    fn parse_item(&mut self, depth: usize) -> Result<Item, TextError> {
+        self.skip_whitespace();
+        let at = self.at;
+        let kind = if self.eat('[') {
+            if depth == MAX_DEPTH {
+                return Err(TextError::TooDeep { at });
+            }
+            let mut items = Vec::new();
+            while !self.eat(']') {
+                items.push(self.parse_item(depth + 1)?);
+                if !self.eat(',') {
+                    self.expect(']', "`,` or `]`")?;
+                    break;
+                }
+            }
+            ItemKind::List(items)
+        } else if self.eat('(') {
+            let x = self.parse_number()?;
+            self.expect(',', "`,`")?;
+            let y = self.parse_number()?;
+            self.expect(')', "`)`")?;
+            ItemKind::Point(DVec2::new(x, y))
+        } else {
+            ItemKind::Number(self.parse_number()?)
+        };
+        Ok(Item { at, kind })
+    }
+
+    fn parse_number(&mut self) -> Result<f64, TextError> {
+        self.skip_whitespace();
+        let at = self.at;
+        let len = self
+            .rest()
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '.' | '+' | '-' | '∞')))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(TextError::Expected {
+                at,
+                what: "a number",
+            });
+        }
+        let word = &self.rest()[..len];
+        self.at += len;
+
+        match word {
+            "undefined" => Ok(f64::NAN),
+            "∞" | "+∞" => Ok(f64::INFINITY),
+            "-∞" => Ok(f64::NEG_INFINITY),
+            word => word.parse().map_err(|_| TextError::BadNumber { at }),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::value::{List, TextError, TypeMismatch, Value, ValueKind};
+use glam::DVec2;
+
+fn total_eq(a: &Value, b: &Value) -> bool {
+    fn list_eq<T>(a: &List<T>, b: &List<T>, eq: &impl Fn(&T, &T) -> bool) -> bool {
+        match (a, b) {
+            (List::Term(a), List::Term(b)) => eq(a, b),
+            (List::Flat(a), List::Flat(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| eq(a, b))
+            }
+            (List::Staggered(a), List::Staggered(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| list_eq(a, b, eq))
+            }
+            _ => false,
+        }
+    }
+    fn f64_eq(a: &f64, b: &f64) -> bool {
+        a == b && a.is_sign_negative() == b.is_sign_negative() || (a.is_nan() && b.is_nan())
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => list_eq(a, b, &f64_eq),
+        (Value::Point(a), Value::Point(b)) => {
+            list_eq(a, b, &|a, b| f64_eq(&a.x, &b.x) && f64_eq(&a.y, &b.y))
+        }
+        _ => false,
+    }
+}
+
+fn test_text(value: Value) {
+    let text = value.to_text();
+    println!("Running test {text}");
+    let new_value = Value::from_text(&text).unwrap_or_else(|err| panic!("{text}: {err}"));
+    if !total_eq(&new_value, &value) {
+        panic!("Wrong value: {new_value:?}, should be {value:?}");
+    }
+}
+
+#[test]
+fn number_text() {
+    [
+        0.1,
+        0.0,
+        -0.0,
+        -3.7,
+        1e300,
+        1e-300,
+        123456789.0,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NAN,
+    ]
+    .into_iter()
+    .for_each(|x| test_text(Value::one_number(x)));
+}
+
+#[test]
+fn point_text() {
+    [
+        DVec2::ZERO,
+        DVec2::ONE,
+        DVec2::MAX,
+        DVec2::INFINITY,
+        DVec2::NAN,
+        DVec2::new(-0.5, 1e20),
+    ]
+    .into_iter()
+    .for_each(|p| test_text(Value::one_point(p)));
+}
+
+#[test]
+fn list_text() {
+    test_text(Value::Number(List::Flat(Vec::new())));
+    test_text(Value::Point(List::Flat(Vec::new())));
+    test_text(Value::Number(List::Flat(vec![1.0, 2.5, f64::NAN])));
+    test_text(Value::Point(List::Flat(vec![DVec2::X, DVec2::Y])));
+    test_text(Value::Number(List::Staggered(vec![
+        List::Term(1.0),
+        List::Flat(vec![4.0, 2.0, 3.0]),
+        List::Staggered(vec![List::Flat(Vec::new()), List::Flat(vec![5.0])]),
+    ])));
+    test_text(Value::Point(List::Staggered(vec![
+        List::Flat(Vec::new()),
+        List::Flat(vec![DVec2::NEG_ONE]),
+    ])));
+    test_text(Value::Point(List::Staggered(vec![
+        List::Flat(Vec::new()),
+        List::Flat(Vec::new()),
+    ])));
+}
+
+#[test]
+fn desmos_syntax() {
+    assert_eq!(
+        " [ 1 ,2 ] ".parse::<Value>(),
+        Ok(Value::Number(List::Flat(vec![1.0, 2.0])))
+    );
+    assert_eq!(
+        "[(1, 2), (3, 4),]".parse::<Value>(),
+        Ok(Value::Point(List::Flat(vec![
+            DVec2::new(1.0, 2.0),
+            DVec2::new(3.0, 4.0)
+        ])))
+    );
+    assert_eq!(
+        "[undefined, ∞, -∞]".parse::<Value>().map(|x| x.to_text()),
+        Ok("[NaN, inf, -inf]".to_string())
+    );
+    assert_eq!(
+        "[1, (2, 3)]".parse::<Value>(),
+        Err(TextError::TypeMismatch {
+            at: 4,
+            mismatch: TypeMismatch {
+                expect: ValueKind::Number,
+                got: ValueKind::Point
+            }
+        })
+    );
+    assert_eq!(
+        "[1, 2".parse::<Value>(),
+        Err(TextError::Expected {
+            at: 5,
+            what: "`,` or `]`"
+        })
+    );
+    assert_eq!("[1] 2".parse::<Value>(), Err(TextError::Trailing { at: 4 }));
+    assert_eq!(
+        "[".repeat(200_000).parse::<Value>(),
+        Err(TextError::TooDeep { at: 256 })
+    );
+    let deepest = format!("{}1{}", "[".repeat(256), "]".repeat(256));
+    assert!(deepest.parse::<Value>().is_ok());
+}
@@ -9,7 +9,7 @@ use crate::layout::GlyphInstance;
 
 use super::fonts::PointVerb;
 use color_eyre::Result as EyreResult;
-use fast_desmos2_utils::OptExt;
+use fast_desmos2_utils::{OptExt, ResExt};
 use glam::Vec2;
 use skrifa::metrics::{GlyphMetrics, Metrics};
 use skrifa::outline::{DrawSettings, OutlinePen};
@@ -183,6 +183,10 @@ impl BoundingBox {
         self.size.x == 0.0 || self.size.y == 0.0
     }
 
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min_pos()).all() && point.cmple(self.max_pos()).all()
+    }
+
     /// Scale and offset are applied separately
     pub fn transformed(self, offset: Vec2, scale: Vec2) -> Self {
         Self {
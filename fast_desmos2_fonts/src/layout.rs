@@ -1,9 +1,13 @@
 use std::marker::PhantomData;
 
 use crate::glyph_data::{BoundingBox, CpuGlyphData};
+use fast_desmos2_tree::tree::CursorPath;
 use fast_desmos2_utils::OptExt;
 use glam::Vec2;
 
+#[cfg(test)]
+mod test;
+
 pub trait GlyphInstance {
     fn new(pos: Vec2, size: Vec2, id: u32) -> Self;
     fn offset_by(self, offset: Vec2) -> Self;
@@ -62,6 +66,8 @@ pub struct InstTree<I: GlyphInstance> {
     bbox: BoundingBox,
     pub offset: Vec2,
     kind: InstTreeKind<I>,
+    /// Where the caret goes when the box is clicked, if it was laid out from a part of a tree.
+    path: Option<CursorPath>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +82,27 @@ impl<I: GlyphInstance> InstTree<I> {
         self.bbox.transformed(self.offset, Vec2::ONE)
     }
 
+    pub fn path(&self) -> Option<&CursorPath> {
+        self.path.as_ref()
+    }
+
+    /// The path of the innermost box with one that `point` is in, in the same coordinates as
+    /// [`bbox`](Self::bbox).
+    pub fn hit_test(&self, point: Vec2) -> Option<&CursorPath> {
+        let point = point - self.offset;
+        if !self.bbox.contains(point) {
+            return None;
+        }
+        let inner = match &self.kind {
+            InstTreeKind::Children(children) => children
+                .iter()
+                .rev()
+                .find_map(|child| child.hit_test(point)),
+            InstTreeKind::Nodes(_) | InstTreeKind::Node(_) => None,
+        };
+        inner.or(self.path.as_ref())
+    }
+
     pub fn collect_vec(self) -> Vec<I> {
         let mut result = Vec::new();
         self.for_each_inst(&mut |x| result.push(x), &mut |_, _| {}, 0);
@@ -113,9 +140,15 @@ impl<I: GlyphInstance> InstTree<I> {
             bbox,
             offset: Vec2::ZERO,
             kind,
+            path: None,
         }
     }
 
+    pub fn with_path(mut self, path: CursorPath) -> Self {
+        self.path = Some(path);
+        self
+    }
+
     pub fn new_children(children: Vec<InstTree<I>>) -> Self {
         let bbox = children
             .iter()
@@ -150,6 +183,7 @@ impl<I: GlyphInstance> NodeRenderOutcome<I> {
 
 pub struct LayoutNode<'a> {
     kind: LayoutKind<'a>,
+    path: Option<CursorPath>,
 }
 
 impl<'a> LayoutNode<'a> {
@@ -157,6 +191,16 @@ impl<'a> LayoutNode<'a> {
         &self,
         glyph_data: &CpuGlyphData,
         size: f32,
+    ) -> NodeRenderOutcome<I> {
+        let mut outcome = self.render_kind(glyph_data, size);
+        outcome.instances.path = self.path.clone();
+        outcome
+    }
+
+    fn render_kind<I: GlyphInstance>(
+        &self,
+        glyph_data: &CpuGlyphData,
+        size: f32,
     ) -> NodeRenderOutcome<I> {
         match self.kind {
             LayoutKind::Str(s) => {
@@ -350,7 +394,14 @@ impl<'a> LayoutNode<'a> {
     }
 
     pub fn new(kind: LayoutKind<'a>) -> Self {
-        Self { kind }
+        Self { kind, path: None }
+    }
+
+    /// Marks the node as laid out from the part of a tree that `path` puts the caret at, which
+    /// [`InstTree::hit_test`] gives back for points over it.
+    pub fn with_path(mut self, path: CursorPath) -> Self {
+        self.path = Some(path);
+        self
     }
 
     pub fn str(str: &'a str) -> Self {
@@ -0,0 +1,78 @@
+use crate::glyph_data::BoundingBox;
+use crate::layout::{GlyphInstance, InstTree};
+use fast_desmos2_tree::tree::CursorPath;
+use glam::Vec2;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Glyph(Vec2);
+
+impl GlyphInstance for Glyph {
+    fn new(pos: Vec2, _size: Vec2, _id: u32) -> Self {
+        Self(pos)
+    }
+
+    fn offset_by(self, offset: Vec2) -> Self {
+        Self(self.0 + offset)
+    }
+}
+
+fn bbox(x: f32, y: f32, width: f32, height: f32) -> BoundingBox {
+    BoundingBox {
+        size: Vec2::new(width, height),
+        offset: Vec2::new(x, y),
+    }
+}
+
+fn path(index: usize) -> CursorPath {
+    CursorPath {
+        steps: Vec::new(),
+        index,
+    }
+}
+
+fn glyph(x: f32, y: f32, width: f32, height: f32) -> InstTree<Glyph> {
+    InstTree::new_node(bbox(x, y, width, height), Glyph(Vec2::new(x, y)))
+}
+
+fn at(tree: &InstTree<Glyph>, x: f32, y: f32) -> Option<usize> {
+    tree.hit_test(Vec2::new(x, y)).map(|path| path.index)
+}
+
+#[test]
+fn hit_innermost() {
+    let tree = InstTree::new_children(vec![
+        glyph(0.0, 0.0, 1.0, 1.0).with_path(path(1)),
+        glyph(1.0, 0.0, 1.0, 1.0),
+    ])
+    .with_path(path(0));
+    assert_eq!(at(&tree, 0.5, 0.5), Some(1));
+    // a box without a path is part of the one around it.
+    assert_eq!(at(&tree, 1.5, 0.5), Some(0));
+    assert_eq!(at(&tree, 2.5, 0.5), None);
+    assert_eq!(at(&tree, 0.5, -0.5), None);
+}
+
+#[test]
+fn hit_offset() {
+    let mut inner = InstTree::new_children(vec![glyph(0.0, 0.0, 1.0, 1.0).with_path(path(2))]);
+    inner.offset = Vec2::new(3.0, 1.0);
+    let mut tree =
+        InstTree::new_children(vec![glyph(0.0, 0.0, 1.0, 1.0).with_path(path(1)), inner]);
+    tree.offset = Vec2::new(-1.0, 0.0);
+    // the offsets of every box it's in add up.
+    assert_eq!(at(&tree, 2.5, 1.5), Some(2));
+    assert_eq!(at(&tree, -0.5, 0.5), Some(1));
+    assert_eq!(at(&tree, 0.5, 0.5), None);
+}
+
+#[test]
+fn hit_overlapping() {
+    // what's laid out later is drawn on top, so it's what's clicked.
+    let tree = InstTree::new_children(vec![
+        glyph(0.0, 0.0, 2.0, 1.0).with_path(path(1)),
+        glyph(1.0, 0.0, 2.0, 1.0).with_path(path(2)),
+    ]);
+    assert_eq!(at(&tree, 0.5, 0.5), Some(1));
+    assert_eq!(at(&tree, 1.5, 0.5), Some(2));
+    assert_eq!(at(&tree, 2.5, 0.5), Some(2));
+}
@@ -1,9 +1,11 @@
 #![allow(dead_code)]
 mod lexing;
 mod parsing;
+#[cfg(test)]
+mod tests;
 
 pub use lexing::builtins::*;
-pub use lexing::{IdentId, IdentStorer, Span};
+pub use lexing::{Element, IdentId, IdentStorer, Span};
 pub use parsing::node::*;
 pub use parsing::parse_source as parse;
 pub use parsing::{parse_full_expr, print_tree_err};
@@ -88,7 +88,7 @@ fn parse_latex_group<'a>(input: &mut Input<'a>) -> ParsedAstNode<'a> {
 
 fn parse_identifier<'a>(input: &mut Input<'a>) -> ParsedAstNode<'a> {
     let checkpoint = input.checkpoint();
-    let one_char = peek(any).parse_next(input)?;
+    let one_char = any.parse_next(input)?;
     if let TokenKind::Identifier(id) = one_char.kind {
         Ok(AstNode::new(one_char.span, AstKind::Identifier(id)))
     } else {
@@ -599,7 +599,6 @@ pub fn parse_source<'a>(
     if let Some(err) = err {
         return Err(err);
     }
-    lexing::display_tokens(source, &lexed);
     Ok(ParseOutcome::new(lexed, |lexed| {
         // let idents = Box::new(IdentStorer::default());
         // let mut stream = Stateful { input: lexed.as_slice(), state: ParserState { source, idents: &idents } };
@@ -18,6 +18,13 @@ impl Debug for AstNode {
     }
 }
 
+/// Nodes are equal when their kinds are, wherever they came from in the source.
+impl PartialEq for AstNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
 impl AstNode {
     pub fn span(&self) -> Span {
         self.span
@@ -319,13 +326,13 @@ impl AstNode {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum SumOrProd {
     Sum,
     Prod,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum AstKind {
     Identifier(IdentId),
     Builtins(Builtins),
@@ -405,7 +412,7 @@ pub enum AstKind {
 }
 
 bitflags! {
-    #[derive(Debug, Copy, Clone)]
+    #[derive(Debug, Copy, Clone, PartialEq)]
     pub struct CompSet: u8 {
         const MORE = 0b100;
         const EQUAL = 0b010;
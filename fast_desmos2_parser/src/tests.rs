@@ -0,0 +1,38 @@
+use crate::{parse, AstKind, AstNode, IdentStorer};
+
+fn parsed(idents: &IdentStorer, source: &str, check: impl FnOnce(&AstNode)) {
+    let outcome = parse(idents, source).unwrap_or_else(|err| panic!("{source:?}: {err:?}"));
+    let Ok(ast) = outcome.borrow_dependent() else {
+        panic!("{source:?} doesn't parse");
+    };
+    assert_eq!(
+        ast.span_as_str(source),
+        source,
+        "{source:?} only partly parses"
+    );
+    check(ast);
+}
+
+#[test]
+fn identifiers_are_consumed() {
+    let idents = IdentStorer::default();
+    let x = idents.convert_id("x");
+    let y = idents.convert_id("y");
+
+    parsed(&idents, "x", |ast| {
+        assert_eq!(ast.kind(), &AstKind::Identifier(x));
+    });
+    parsed(&idents, "x+y", |ast| {
+        let AstKind::AddSub(items) = ast.kind() else {
+            panic!("{ast:?} isn't a sum");
+        };
+        assert_eq!(items.len(), 2);
+    });
+    parsed(&idents, "x\\cdot y", |ast| {
+        let AstKind::Multiply(items) = ast.kind() else {
+            panic!("{ast:?} isn't a product");
+        };
+        let kinds: Vec<_> = items.iter().map(AstNode::kind).collect();
+        assert_eq!(kinds, [&AstKind::Identifier(x), &AstKind::Identifier(y)]);
+    });
+}
@@ -0,0 +1,338 @@
+//! Editing a tree with keys, the way the worksheet editor does, but without a terminal.
+//!
+//! The [`Editor`] runs the keys through a [`Keymap`] and edits the tree it's given, leaving
+//! anything about more than that tree, such as going to another cell, to whatever holds it. Names
+//! typed in insert mode are completed out of the candidates it's given, like Desmos does.
+
+use std::mem;
+
+use crate::{
+    keymap::{Binding, EditorCommand, KeyPress, Keymap, KeymapMode, KeymapResult, Lookup},
+    tree::{
+        parse_keys, Completion, CompletionKind, CursorPath, EditorHistory, EditorTreeSeq,
+        KeysParse, Motion, NormalCommand, NormalKind, NormalOutcome, NormalState, Operator,
+        TreeAction, TreeMovable as _,
+    },
+};
+
+/// What happened while handling keys, that whatever holds the tree may care about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorEvent {
+    /// The tree was changed.
+    Edited,
+    /// The caret went past the edge of the tree.
+    Overflow(Motion),
+    /// A command the editor can't run on its own tree.
+    Command(EditorCommand),
+}
+
+/// The tree being edited, with what happened to it so far.
+struct Edit<'a> {
+    tree: &'a mut EditorTreeSeq,
+    history: &'a mut EditorHistory,
+    events: Vec<EditorEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Editor {
+    keymap: Keymap,
+    mode: KeymapMode,
+    /// Where the selection started, in visual mode.
+    anchor: CursorPath,
+    normal: NormalState,
+    /// The keys of a chord or normal mode command typed so far.
+    pending: Vec<KeyPress>,
+    /// The names that can be completed to.
+    candidates: Vec<Completion>,
+    /// The completions of the name being typed.
+    completions: Vec<Completion>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new(Keymap::default())
+    }
+}
+
+impl Editor {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            mode: KeymapMode::Normal,
+            anchor: CursorPath::default(),
+            normal: NormalState::default(),
+            pending: Vec::new(),
+            candidates: Vec::new(),
+            completions: Vec::new(),
+        }
+    }
+
+    pub fn mode(&self) -> KeymapMode {
+        self.mode
+    }
+
+    pub fn pending(&self) -> &[KeyPress] {
+        &self.pending
+    }
+
+    /// Sets the names that can be completed to. The first of several with the same name wins.
+    pub fn set_candidates(&mut self, candidates: impl IntoIterator<Item = Completion>) {
+        self.candidates = candidates.into_iter().collect();
+    }
+
+    /// The completions of the name being typed in insert mode, shortest first.
+    pub fn completions(&self) -> &[Completion] {
+        &self.completions
+    }
+
+    /// What the last `d`, `c`, `y` or `x` took.
+    pub fn register(&self) -> &EditorTreeSeq {
+        &self.normal.register
+    }
+
+    /// Switches to `mode`, in which `tree` is edited.
+    pub fn set_mode(&mut self, mode: KeymapMode, tree: &EditorTreeSeq) {
+        if self.mode == KeymapMode::Insert && mode != KeymapMode::Insert {
+            self.normal.finish_insert();
+        }
+        if mode == KeymapMode::Visual {
+            self.anchor = tree.cursor_path();
+        }
+        self.pending.clear();
+        self.completions.clear();
+        self.mode = mode;
+    }
+
+    /// Moves the caret to `path`, as clicking does, returning whether it's in the tree. What was
+    /// being typed is finished, and the keys of an unfinished chord are dropped.
+    pub fn place_cursor(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        history: &mut EditorHistory,
+        path: &CursorPath,
+    ) -> bool {
+        self.pending.clear();
+        self.completions.clear();
+        self.normal.finish_insert();
+        history.break_group();
+        tree.place_cursor(path)
+    }
+
+    /// Handles a key, running what it's bound to once the keys typed so far make a whole chord.
+    pub fn feed_key(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        history: &mut EditorHistory,
+        key: KeyPress,
+    ) -> Vec<EditorEvent> {
+        self.feed_keys(tree, history, [key])
+    }
+
+    pub fn feed_keys(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        history: &mut EditorHistory,
+        keys: impl IntoIterator<Item = KeyPress>,
+    ) -> Vec<EditorEvent> {
+        let mut edit = Edit {
+            tree,
+            history,
+            events: Vec::new(),
+        };
+        for key in keys {
+            self.feed(&mut edit, key);
+        }
+        edit.events
+    }
+
+    /// Types a script of keys written like `i a / b <Right> + c <Esc>`, see
+    /// [`KeyPress::parse_script`].
+    pub fn run_script(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        history: &mut EditorHistory,
+        script: &str,
+    ) -> KeymapResult<Vec<EditorEvent>> {
+        let keys = KeyPress::parse_script(script)?;
+        Ok(self.feed_keys(tree, history, keys))
+    }
+
+    fn feed(&mut self, edit: &mut Edit, key: KeyPress) {
+        self.pending.push(key);
+        match self.keymap.lookup(self.mode, &self.pending) {
+            Lookup::Bound(binding) => {
+                let binding = binding.clone();
+                self.pending.clear();
+                self.run_binding(edit, binding);
+            }
+            Lookup::Prefix => {}
+            Lookup::Unbound if self.mode == KeymapMode::Normal => self.command_keys(edit),
+            Lookup::Unbound => {
+                // the first key goes on its own, and the rest may start another chord.
+                let mut keys = mem::take(&mut self.pending).into_iter();
+                if let (KeymapMode::Insert, Some(KeyPress::Char(ch))) = (self.mode, keys.next()) {
+                    self.apply_action(edit, TreeAction::Char(ch));
+                }
+                keys.for_each(|key| self.feed(edit, key));
+            }
+        }
+    }
+
+    /// Parses the pending keys as a normal mode command, running it once it's whole.
+    fn command_keys(&mut self, edit: &mut Edit) {
+        let keys: Option<Vec<char>> = self
+            .pending
+            .iter()
+            .map(|key| match key {
+                KeyPress::Char(ch) => Some(*ch),
+                _ => None,
+            })
+            .collect();
+        match keys.as_deref().map(parse_keys) {
+            Some(KeysParse::Pending) => {}
+            Some(KeysParse::Complete(command)) => {
+                self.pending.clear();
+                self.run_normal(edit, command);
+            }
+            Some(KeysParse::Invalid) | None => self.pending.clear(),
+        }
+    }
+
+    fn run_binding(&mut self, edit: &mut Edit, binding: Binding) {
+        match binding {
+            Binding::Move(motion) => self.apply_move(edit, motion),
+            Binding::Action(action) if self.mode == KeymapMode::Visual => {
+                let anchor = self.anchor.clone();
+                edit.history
+                    .edit(edit.tree, |tree| tree.apply_to_selection(&anchor, action));
+                edit.events.push(EditorEvent::Edited);
+                self.mode = match action {
+                    TreeAction::Delete => KeymapMode::Normal,
+                    _ => KeymapMode::Insert,
+                };
+            }
+            Binding::Action(action) => self.apply_action(edit, action),
+            Binding::Command(command) => self.run_command(edit, command),
+            Binding::Keys(keys) => {
+                let chars: Vec<char> = keys.chars().collect();
+                if let KeysParse::Complete(command) = parse_keys(&chars) {
+                    self.run_normal(edit, command);
+                }
+            }
+        }
+    }
+
+    fn run_command(&mut self, edit: &mut Edit, command: EditorCommand) {
+        match command {
+            EditorCommand::NormalMode => {
+                if self.mode == KeymapMode::Insert {
+                    edit.history.break_group();
+                }
+                self.set_mode(KeymapMode::Normal, edit.tree);
+            }
+            EditorCommand::InsertMode => self.set_mode(KeymapMode::Insert, edit.tree),
+            EditorCommand::VisualMode => self.set_mode(KeymapMode::Visual, edit.tree),
+            EditorCommand::Undo => {
+                if edit.history.undo(edit.tree).is_some() {
+                    edit.events.push(EditorEvent::Edited);
+                }
+            }
+            EditorCommand::Redo => {
+                if edit.history.redo(edit.tree).is_some() {
+                    edit.events.push(EditorEvent::Edited);
+                }
+            }
+            EditorCommand::Yank | EditorCommand::Cut if self.mode != KeymapMode::Visual => {}
+            EditorCommand::Yank => {
+                self.normal.register = edit.tree.copy_selection(&self.anchor);
+                self.set_mode(KeymapMode::Normal, edit.tree);
+            }
+            EditorCommand::Cut => {
+                let anchor = &self.anchor;
+                self.normal.register = edit
+                    .history
+                    .edit(edit.tree, |tree| tree.cut_selection(anchor));
+                edit.events.push(EditorEvent::Edited);
+                self.set_mode(KeymapMode::Normal, edit.tree);
+            }
+            command => {
+                // whatever's typed next goes elsewhere.
+                self.normal.finish_insert();
+                edit.events.push(EditorEvent::Command(command));
+            }
+        }
+    }
+
+    fn apply_action(&mut self, edit: &mut Edit, action: TreeAction) {
+        self.normal.record_insert(action);
+        edit.history.apply_action(edit.tree, action);
+        edit.events.push(EditorEvent::Edited);
+        self.completions.clear();
+        if self.mode == KeymapMode::Insert {
+            if let TreeAction::Char(ch) = action {
+                if ch.is_ascii_alphabetic() {
+                    self.complete_typed(edit);
+                }
+            }
+        }
+    }
+
+    /// Finds the completions of the name just typed. A function named by nothing but what's
+    /// typed is completed right away, as its own undoable step, into a call with the caret in its
+    /// parentheses. Single letters are left alone, as they're mostly variables.
+    fn complete_typed(&mut self, edit: &mut Edit) {
+        self.completions = edit.tree.completions(self.candidates.iter().cloned());
+        let Some(prefix) = edit.tree.completion_prefix() else {
+            return;
+        };
+        let typed_whole = prefix.range.len() == prefix.text.chars().count();
+        let completion = match &self.completions[..] {
+            [only]
+                if only.kind == CompletionKind::Function
+                    && only.subscript.is_none()
+                    && only.name == prefix.text
+                    && only.name.len() > 1
+                    && typed_whole =>
+            {
+                only.clone()
+            }
+            _ => return,
+        };
+        self.completions.clear();
+        self.normal.finish_insert();
+        edit.history
+            .edit(edit.tree, |tree| tree.accept_completion(&completion));
+    }
+
+    fn apply_move(&mut self, edit: &mut Edit, motion: Motion) {
+        self.completions.clear();
+        edit.history.break_group();
+        if let Some(rest) = edit.tree.apply_move(motion) {
+            edit.events.push(EditorEvent::Overflow(rest));
+        }
+    }
+
+    fn run_normal(&mut self, edit: &mut Edit, command: NormalCommand) {
+        let changes = !matches!(
+            command.kind,
+            NormalKind::Move(_) | NormalKind::Operate(Operator::Yank, _)
+        );
+        let normal = &mut self.normal;
+        let outcome = match changes {
+            true => {
+                edit.events.push(EditorEvent::Edited);
+                edit.history
+                    .edit(edit.tree, |tree| normal.run(tree, command))
+            }
+            false => {
+                edit.history.break_group();
+                normal.run(edit.tree, command)
+            }
+        };
+        match outcome {
+            NormalOutcome::Done => {}
+            NormalOutcome::Insert => self.mode = KeymapMode::Insert,
+            NormalOutcome::Overflow(motion) => edit.events.push(EditorEvent::Overflow(motion)),
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! What the keys do in each mode of the editor.
+//!
+//! Keymaps are JSON files with an object for each mode, mapping keys to bindings. Keys are
+//! written like Vim's, such as `J`, `<C-r>`, `<A-j>` or `<Esc>`, and a few keys in a row make a
+//! chord like `gg`. The bindings in a file replace the default ones for the same keys, and `null`
+//! removes one:
+//!
+//! ```json
+//! {
+//!     "normal": { "gg": "first_cell", "<C-d>": "keys:dd" },
+//!     "insert": { "jk": "normal_mode", "*": "char:·", "{": null }
+//! }
+//! ```
+//!
+//! Keys without a binding fall back to what the mode does with them: normal mode parses them as
+//! a [`NormalCommand`](crate::tree::NormalCommand) and insert mode types them.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::tree::{parse_keys, KeysParse, Motion, TreeAction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyPress {
+    Char(char),
+    Ctrl(char),
+    Alt(char),
+    Esc,
+    Enter,
+    Backspace,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// The keys with a name, which are written between angle brackets.
+const KEY_NAMES: &[(&str, KeyPress)] = &[
+    ("Esc", KeyPress::Esc),
+    ("CR", KeyPress::Enter),
+    ("Enter", KeyPress::Enter),
+    ("BS", KeyPress::Backspace),
+    ("Tab", KeyPress::Tab),
+    ("Left", KeyPress::Left),
+    ("Right", KeyPress::Right),
+    ("Up", KeyPress::Up),
+    ("Down", KeyPress::Down),
+    ("Space", KeyPress::Char(' ')),
+    ("lt", KeyPress::Char('<')),
+];
+
+impl KeyPress {
+    /// Reads keys written like `d<C-r><Esc>`.
+    pub fn parse_sequence(keys: &str) -> KeymapResult<Vec<Self>> {
+        let invalid = || KeymapError::Key(keys.to_string());
+        let mut sequence = Vec::new();
+        let mut rest = keys;
+        while let Some(ch) = rest.chars().next() {
+            let Some(name) = rest.strip_prefix('<').and_then(|name| name.split_once('>')) else {
+                sequence.push(Self::Char(ch));
+                rest = &rest[ch.len_utf8()..];
+                continue;
+            };
+            let (name, after) = name;
+            let key = KEY_NAMES
+                .iter()
+                .find(|(other, _)| other.eq_ignore_ascii_case(name))
+                .map(|&(_, key)| key);
+            let modified = |prefix: &[&str], make: fn(char) -> Self| {
+                let (modifier, ch) = name.split_once('-')?;
+                let mut chars = ch.chars();
+                let ch = chars.next().filter(|_| chars.next().is_none())?;
+                prefix
+                    .iter()
+                    .any(|prefix| prefix.eq_ignore_ascii_case(modifier))
+                    .then(|| make(ch))
+            };
+            let key = key
+                .or_else(|| modified(&["C"], Self::Ctrl))
+                .or_else(|| modified(&["A", "M"], Self::Alt))
+                .ok_or_else(invalid)?;
+            sequence.push(key);
+            rest = after;
+        }
+        match sequence.is_empty() {
+            true => Err(invalid()),
+            false => Ok(sequence),
+        }
+    }
+
+    /// Reads keys written with spaces between them for legibility, like `i a / b <Right>`. A
+    /// space itself is written `<Space>`.
+    pub fn parse_script(script: &str) -> KeymapResult<Vec<Self>> {
+        let mut keys = Vec::new();
+        for word in script.split_whitespace() {
+            keys.extend(Self::parse_sequence(word)?);
+        }
+        Ok(keys)
+    }
+}
+
+/// Writes the key the way [`KeyPress::parse_sequence`] reads it.
+impl Display for KeyPress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some((name, _)) = KEY_NAMES.iter().find(|(_, key)| key == self) {
+            return write!(f, "<{name}>");
+        }
+        match self {
+            Self::Char(ch) => write!(f, "{ch}"),
+            Self::Ctrl(ch) => write!(f, "<C-{ch}>"),
+            Self::Alt(ch) => write!(f, "<A-{ch}>"),
+            _ => unreachable!("every other key has a name"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapMode {
+    Normal,
+    Insert,
+    Visual,
+}
+
+impl Display for KeymapMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "normal"),
+            Self::Insert => write!(f, "insert"),
+            Self::Visual => write!(f, "visual"),
+        }
+    }
+}
+
+/// What the editor does beyond editing the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorCommand {
+    NormalMode,
+    InsertMode,
+    VisualMode,
+    LatexMode,
+    CommandMode,
+    Undo,
+    Redo,
+    NextCell,
+    PreviousCell,
+    FirstCell,
+    LastCell,
+    MoveCellDown,
+    MoveCellUp,
+    AddCellBelow,
+    AddCellAbove,
+    Complete,
+    /// Copies the selection.
+    Yank,
+    /// Cuts the selection.
+    Cut,
+    ToggleDebug,
+}
+
+const COMMANDS: &[(&str, EditorCommand)] = &[
+    ("normal_mode", EditorCommand::NormalMode),
+    ("insert_mode", EditorCommand::InsertMode),
+    ("visual_mode", EditorCommand::VisualMode),
+    ("latex_mode", EditorCommand::LatexMode),
+    ("command_mode", EditorCommand::CommandMode),
+    ("undo", EditorCommand::Undo),
+    ("redo", EditorCommand::Redo),
+    ("next_cell", EditorCommand::NextCell),
+    ("previous_cell", EditorCommand::PreviousCell),
+    ("first_cell", EditorCommand::FirstCell),
+    ("last_cell", EditorCommand::LastCell),
+    ("move_cell_down", EditorCommand::MoveCellDown),
+    ("move_cell_up", EditorCommand::MoveCellUp),
+    ("add_cell_below", EditorCommand::AddCellBelow),
+    ("add_cell_above", EditorCommand::AddCellAbove),
+    ("complete", EditorCommand::Complete),
+    ("yank", EditorCommand::Yank),
+    ("cut", EditorCommand::Cut),
+    ("toggle_debug", EditorCommand::ToggleDebug),
+];
+
+const MOTIONS: &[(&str, Motion)] = &[
+    ("left", Motion::Left),
+    ("right", Motion::Right),
+    ("up", Motion::Up),
+    ("down", Motion::Down),
+    ("word", Motion::Word),
+    ("back", Motion::Back),
+    ("first", Motion::First),
+    ("last", Motion::Last),
+];
+
+const ACTIONS: &[(&str, TreeAction)] = &[
+    ("fraction", TreeAction::MakeFraction),
+    ("power", TreeAction::MakePower),
+    ("subscript", TreeAction::MakeSubscript),
+    ("paren", TreeAction::MakeParen),
+    ("abs", TreeAction::MakeAbs),
+    ("piecewise", TreeAction::MakePiecewise),
+    ("delete", TreeAction::Delete),
+];
+
+/// The characters [`TreeAction::from_char`] gives a meaning to, which insert mode binds by
+/// default.
+const STRUCTURE_CHARS: &[char] = &['/', '^', '_', '(', '|', '{', '*'];
+
+fn named<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(other, _)| *other == name)
+        .map(|&(_, value)| value)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    Move(Motion),
+    /// Edits at the caret, or around the selection in visual mode.
+    Action(TreeAction),
+    Command(EditorCommand),
+    /// A whole normal mode command, like `dd`.
+    Keys(String),
+}
+
+impl Binding {
+    /// Reads a binding written like `left`, `fraction`, `char:x`, `undo` or `keys:dd`.
+    pub fn parse(binding: &str) -> KeymapResult<Self> {
+        if let Some(motion) = named(MOTIONS, binding) {
+            return Ok(Self::Move(motion));
+        }
+        if let Some(action) = named(ACTIONS, binding) {
+            return Ok(Self::Action(action));
+        }
+        if let Some(command) = named(COMMANDS, binding) {
+            return Ok(Self::Command(command));
+        }
+        if let Some(keys) = binding.strip_prefix("keys:") {
+            let chars: Vec<char> = keys.chars().collect();
+            return match parse_keys(&chars) {
+                KeysParse::Complete(_) => Ok(Self::Keys(keys.to_string())),
+                _ => Err(KeymapError::Binding(binding.to_string())),
+            };
+        }
+        let mut chars = binding.strip_prefix("char:").unwrap_or_default().chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(Self::Action(TreeAction::Char(ch))),
+            _ => Err(KeymapError::Binding(binding.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum KeymapError {
+    #[error("can't access the keymap file: {0}")]
+    Io(#[from] io::Error),
+    #[error("the keymap file is malformed: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("`{0}` isn't a key")]
+    Key(String),
+    #[error("`{0}` isn't something a key can be bound to")]
+    Binding(String),
+    #[error("`{keys}` in {mode} mode hides the longer `{longer}`")]
+    Shadowed {
+        mode: KeymapMode,
+        keys: String,
+        longer: String,
+    },
+}
+
+pub type KeymapResult<T> = Result<T, KeymapError>;
+
+/// What the keys typed so far are bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup<'a> {
+    Bound(&'a Binding),
+    /// The keys start a longer chord.
+    Prefix,
+    Unbound,
+}
+
+type Bindings = BTreeMap<Vec<KeyPress>, Binding>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    normal: Bindings,
+    insert: Bindings,
+    visual: Bindings,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    #[serde(default)]
+    normal: BTreeMap<String, Option<String>>,
+    #[serde(default)]
+    insert: BTreeMap<String, Option<String>>,
+    #[serde(default)]
+    visual: BTreeMap<String, Option<String>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use EditorCommand as C;
+        let key = |key: KeyPress| vec![key];
+        let char = |ch: char| vec![KeyPress::Char(ch)];
+        let arrows = [
+            (key(KeyPress::Left), Binding::Move(Motion::Left)),
+            (key(KeyPress::Right), Binding::Move(Motion::Right)),
+            (key(KeyPress::Up), Binding::Move(Motion::Up)),
+            (key(KeyPress::Down), Binding::Move(Motion::Down)),
+        ];
+
+        let mut normal: Bindings = [
+            (char('J'), C::NextCell),
+            (char('K'), C::PreviousCell),
+            (key(KeyPress::Alt('j')), C::MoveCellDown),
+            (key(KeyPress::Alt('k')), C::MoveCellUp),
+            (char('o'), C::AddCellBelow),
+            (char('O'), C::AddCellAbove),
+            (char('u'), C::Undo),
+            (key(KeyPress::Ctrl('r')), C::Redo),
+            (char('i'), C::InsertMode),
+            (char('v'), C::VisualMode),
+            (char('L'), C::LatexMode),
+            (char(':'), C::CommandMode),
+            (char('D'), C::ToggleDebug),
+        ]
+        .into_iter()
+        .map(|(keys, command)| (keys, Binding::Command(command)))
+        .collect();
+        normal.extend(arrows.clone());
+
+        let mut insert: Bindings = STRUCTURE_CHARS
+            .iter()
+            .map(|&ch| (char(ch), Binding::Action(TreeAction::from_char(ch))))
+            .collect();
+        insert.extend([
+            (key(KeyPress::Esc), Binding::Command(C::NormalMode)),
+            (key(KeyPress::Enter), Binding::Command(C::AddCellBelow)),
+            (key(KeyPress::Tab), Binding::Command(C::Complete)),
+            (
+                key(KeyPress::Backspace),
+                Binding::Action(TreeAction::Delete),
+            ),
+        ]);
+        insert.extend(arrows.clone());
+
+        let mut visual: Bindings = ['/', '^', '(', '|']
+            .into_iter()
+            .map(|ch| (char(ch), Binding::Action(TreeAction::from_char(ch))))
+            .collect();
+        visual.extend([
+            (key(KeyPress::Esc), Binding::Command(C::NormalMode)),
+            (char('h'), Binding::Move(Motion::Left)),
+            (char('j'), Binding::Move(Motion::Down)),
+            (char('k'), Binding::Move(Motion::Up)),
+            (char('l'), Binding::Move(Motion::Right)),
+            (char('0'), Binding::Move(Motion::First)),
+            (char('$'), Binding::Move(Motion::Last)),
+            (char('y'), Binding::Command(C::Yank)),
+            (char('d'), Binding::Command(C::Cut)),
+            (char('x'), Binding::Command(C::Cut)),
+        ]);
+        visual.extend(arrows);
+
+        Self {
+            normal,
+            insert,
+            visual,
+        }
+    }
+}
+
+impl Keymap {
+    pub fn load(path: impl AsRef<Path>) -> KeymapResult<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// The default keymap changed by the bindings in a keymap file.
+    pub fn from_json(json: &str) -> KeymapResult<Self> {
+        let file: KeymapFile = serde_json::from_str(json)?;
+        let mut keymap = Self::default();
+        for (mode, bindings) in [
+            (KeymapMode::Normal, file.normal),
+            (KeymapMode::Insert, file.insert),
+            (KeymapMode::Visual, file.visual),
+        ] {
+            for (keys, binding) in bindings {
+                let keys = KeyPress::parse_sequence(&keys)?;
+                match binding {
+                    Some(binding) => keymap.bind(mode, keys, Binding::parse(&binding)?),
+                    None => keymap.unbind(mode, &keys),
+                }
+            }
+            keymap.check(mode)?;
+        }
+        Ok(keymap)
+    }
+
+    fn bindings(&self, mode: KeymapMode) -> &Bindings {
+        match mode {
+            KeymapMode::Normal => &self.normal,
+            KeymapMode::Insert => &self.insert,
+            KeymapMode::Visual => &self.visual,
+        }
+    }
+
+    fn bindings_mut(&mut self, mode: KeymapMode) -> &mut Bindings {
+        match mode {
+            KeymapMode::Normal => &mut self.normal,
+            KeymapMode::Insert => &mut self.insert,
+            KeymapMode::Visual => &mut self.visual,
+        }
+    }
+
+    pub fn bind(&mut self, mode: KeymapMode, keys: Vec<KeyPress>, binding: Binding) {
+        self.bindings_mut(mode).insert(keys, binding);
+    }
+
+    pub fn unbind(&mut self, mode: KeymapMode, keys: &[KeyPress]) {
+        self.bindings_mut(mode).remove(keys);
+    }
+
+    /// Makes sure every chord can be typed, which it can't be when a shorter one starts it.
+    fn check(&self, mode: KeymapMode) -> KeymapResult<()> {
+        let bindings = self.bindings(mode);
+        // a chord sorts right before the chords it starts.
+        let mut chords = bindings.keys().zip(bindings.keys().skip(1));
+        match chords.find(|(keys, next)| next.starts_with(keys)) {
+            Some((keys, longer)) => Err(KeymapError::Shadowed {
+                mode,
+                keys: keys.iter().map(ToString::to_string).collect(),
+                longer: longer.iter().map(ToString::to_string).collect(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn lookup(&self, mode: KeymapMode, keys: &[KeyPress]) -> Lookup<'_> {
+        let bindings = self.bindings(mode);
+        if let Some(binding) = bindings.get(keys) {
+            return Lookup::Bound(binding);
+        }
+        let is_prefix = bindings
+            .range(keys.to_vec()..)
+            .next()
+            .is_some_and(|(longer, _)| longer.starts_with(keys));
+        match is_prefix {
+            true => Lookup::Prefix,
+            false => Lookup::Unbound,
+        }
+    }
+}
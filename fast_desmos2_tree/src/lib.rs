@@ -1,4 +1,7 @@
+pub mod editor;
+pub mod keymap;
 pub mod tree;
+pub mod worksheet;
 
 #[cfg(test)]
 mod tests;
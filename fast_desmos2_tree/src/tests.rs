@@ -19,6 +19,21 @@ macro_rules! assert_cursors {
     };
 }
 
+mod command;
+mod completion;
+mod editor;
+mod history;
+mod keymap;
+mod latex;
+mod notation;
+mod piecewise;
+mod pretty;
+mod root;
+mod search;
+mod selection;
+mod subscript;
+mod worksheet;
+
 #[test]
 fn right_to_left() {
     let mut tree = TS::new(
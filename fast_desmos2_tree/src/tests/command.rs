@@ -0,0 +1,283 @@
+use crate::tree::{
+    parse_keys, CombinedCursor, EditorTree as T, EditorTreeSeq as TS, FractionIndex as FI,
+    KeysParse, Motion, NormalCommand, NormalKind, NormalOutcome, NormalState, ObjectKind, Operator,
+    SurroundIndex as SI, Target, TextObject, TreeAction, TreeMovable as _,
+};
+
+fn keys(keys: &str) -> KeysParse {
+    parse_keys(&keys.chars().collect::<Vec<_>>())
+}
+
+fn command(keys: &str) -> NormalCommand {
+    match parse_keys(&keys.chars().collect::<Vec<_>>()) {
+        KeysParse::Complete(command) => command,
+        other => panic!("`{keys}` isn't a whole command: {other:?}"),
+    }
+}
+
+fn run(state: &mut NormalState, tree: &mut TS, keys: &str) -> NormalOutcome {
+    state.run(tree, command(keys))
+}
+
+fn seq(cursor: usize, string: &str) -> TS {
+    TS::new(cursor, TS::str(string).children().to_vec())
+}
+
+#[test]
+fn parse_motions_and_counts() {
+    let complete = |count, kind| KeysParse::Complete(NormalCommand { count, kind });
+    assert_eq!(keys("w"), complete(None, NormalKind::Move(Motion::Word)));
+    assert_eq!(
+        keys("3w"),
+        complete(Some(3), NormalKind::Move(Motion::Word))
+    );
+    assert_eq!(keys("0"), complete(None, NormalKind::Move(Motion::First)));
+    assert_eq!(keys("10x"), complete(Some(10), NormalKind::DeleteChar));
+    assert_eq!(keys("2."), complete(Some(2), NormalKind::Repeat));
+    assert_eq!(keys("p"), complete(None, NormalKind::Paste));
+    assert_eq!(keys("3"), KeysParse::Pending);
+    assert_eq!(keys("q"), KeysParse::Invalid);
+    assert_eq!(keys("xx"), KeysParse::Invalid);
+}
+
+#[test]
+fn parse_operators() {
+    let operate = |count, operator, target| {
+        KeysParse::Complete(NormalCommand {
+            count,
+            kind: NormalKind::Operate(operator, target),
+        })
+    };
+    assert_eq!(
+        keys("dw"),
+        operate(None, Operator::Delete, Target::Motion(Motion::Word))
+    );
+    assert_eq!(
+        keys("2d3w"),
+        operate(Some(6), Operator::Delete, Target::Motion(Motion::Word))
+    );
+    assert_eq!(
+        keys("c2b"),
+        operate(Some(2), Operator::Change, Target::Motion(Motion::Back))
+    );
+    assert_eq!(keys("yy"), operate(None, Operator::Yank, Target::Line));
+    assert_eq!(keys("d"), KeysParse::Pending);
+    assert_eq!(keys("d2"), KeysParse::Pending);
+    assert_eq!(keys("dy"), KeysParse::Invalid);
+    assert_eq!(keys("dq"), KeysParse::Invalid);
+}
+
+#[test]
+fn parse_text_objects() {
+    let object = |operator, around, kind| {
+        KeysParse::Complete(NormalCommand::new(NormalKind::Operate(
+            operator,
+            Target::Object(TextObject { around, kind }),
+        )))
+    };
+    assert_eq!(
+        keys("ci("),
+        object(Operator::Change, false, ObjectKind::Paren)
+    );
+    assert_eq!(
+        keys("dab"),
+        object(Operator::Delete, true, ObjectKind::Paren)
+    );
+    assert_eq!(keys("ya|"), object(Operator::Yank, true, ObjectKind::Abs));
+    assert_eq!(
+        keys("dif"),
+        object(Operator::Delete, false, ObjectKind::Fraction)
+    );
+    assert_eq!(keys("di"), KeysParse::Pending);
+    assert_eq!(keys("diq"), KeysParse::Invalid);
+}
+
+#[test]
+fn word_motions() {
+    let mut tree = seq(0, "ab+cd ef");
+    let mut cursors = Vec::new();
+    while tree.apply_move(Motion::Word).is_none() {
+        cursors.push(tree.cursor());
+    }
+    assert_eq!(cursors, [2, 3, 6, 8]);
+
+    cursors.clear();
+    while tree.apply_move(Motion::Back).is_none() {
+        cursors.push(tree.cursor());
+    }
+    assert_eq!(cursors, [6, 3, 2, 0]);
+}
+
+#[test]
+fn word_motion_leaves_structures() {
+    let fraction = T::fraction(FI::Top, seq(1, "ab"), TS::str("c"));
+    let mut tree = TS::new(1, vec![T::terminal('x'), fraction, T::terminal('y')]);
+    // the end of the numerator is a stop, like the end of a line.
+    assert_eq!(tree.apply_move(Motion::Word), None);
+    assert_cursors!(tree, 1, CombinedCursor::TOP, 2);
+    assert_eq!(tree.apply_move(Motion::Word), None);
+    assert_eq!(tree.cursor(), 2);
+
+    let fraction = T::fraction(FI::Top, seq(0, "ab"), TS::str("c"));
+    let mut tree = TS::new(1, vec![T::terminal('x'), fraction, T::terminal('y')]);
+    assert_eq!(tree.apply_move(Motion::Back), None);
+    assert_cursors!(tree, 1, CombinedCursor::LEFT);
+}
+
+#[test]
+fn counted_motion_overflows() {
+    let mut state = NormalState::default();
+    let mut tree = seq(0, "abc");
+    assert_eq!(run(&mut state, &mut tree, "2l"), NormalOutcome::Done);
+    assert_eq!(tree.cursor(), 2);
+    assert_eq!(
+        run(&mut state, &mut tree, "5l"),
+        NormalOutcome::Overflow(Motion::Right)
+    );
+    assert_eq!(
+        run(&mut state, &mut tree, "j"),
+        NormalOutcome::Overflow(Motion::Down)
+    );
+}
+
+#[test]
+fn delete_words() {
+    let mut state = NormalState::default();
+    let mut tree = seq(0, "ab+cd");
+    assert_eq!(run(&mut state, &mut tree, "2dw"), NormalOutcome::Done);
+    assert_eq!(tree, seq(0, "cd"));
+    assert_eq!(state.register, TS::str("ab+"));
+}
+
+#[test]
+fn yank_word_keeps_tree() {
+    let mut state = NormalState::default();
+    let mut tree = seq(0, "ab+c");
+    run(&mut state, &mut tree, "yw");
+    assert_eq!(tree, seq(0, "ab+c"));
+    assert_eq!(state.register, TS::str("ab"));
+}
+
+#[test]
+fn delete_char_and_paste() {
+    let mut state = NormalState::default();
+    let mut tree = seq(1, "a+b");
+    run(&mut state, &mut tree, "x");
+    assert_eq!(tree, seq(1, "ab"));
+    run(&mut state, &mut tree, "2p");
+    assert_eq!(tree, seq(3, "a++b"));
+}
+
+#[test]
+fn huge_counts_are_capped() {
+    assert_eq!(command("999999999p").count(), NormalCommand::MAX_COUNT);
+    assert_eq!(
+        command("99999999999999999999999p").count(),
+        NormalCommand::MAX_COUNT
+    );
+    assert_eq!(command("99999d99999w").count(), NormalCommand::MAX_COUNT);
+
+    let mut state = NormalState::default();
+    let mut tree = seq(1, "a+b");
+    run(&mut state, &mut tree, "x");
+    run(&mut state, &mut tree, "999999999p");
+    assert_eq!(tree.len(), 2 + NormalCommand::MAX_COUNT);
+    run(&mut state, &mut tree, "0");
+    run(&mut state, &mut tree, "999999999999x");
+    assert_eq!(tree.len(), 2);
+}
+
+#[test]
+fn yank_and_delete_lines() {
+    let mut state = NormalState::default();
+    let mut tree = seq(2, "ab");
+    run(&mut state, &mut tree, "yy");
+    assert_eq!(tree, seq(0, "ab"));
+    run(&mut state, &mut tree, "p");
+    assert_eq!(tree, seq(2, "abab"));
+    run(&mut state, &mut tree, "dd");
+    assert_eq!(tree, TS::empty());
+    assert_eq!(state.register, TS::str("abab"));
+}
+
+#[test]
+fn repeat_change() {
+    let mut state = NormalState::default();
+    let mut tree = seq(0, "abcd");
+    run(&mut state, &mut tree, "x");
+    run(&mut state, &mut tree, ".");
+    assert_eq!(tree, seq(0, "cd"));
+    run(&mut state, &mut tree, "2.");
+    assert_eq!(tree, TS::empty());
+}
+
+fn parens_tree(cursor: SI) -> TS {
+    let inside = seq(1, "b+c");
+    TS::new(
+        1,
+        vec![
+            T::terminal('a'),
+            T::complete_paren(cursor, inside),
+            T::terminal('d'),
+        ],
+    )
+}
+
+#[test]
+fn paren_objects() {
+    let mut state = NormalState::default();
+    let mut tree = parens_tree(SI::Inside);
+    run(&mut state, &mut tree, "di(");
+    let emptied = T::complete_paren(SI::Inside, TS::empty());
+    assert_eq!(
+        tree,
+        TS::new(1, vec![T::terminal('a'), emptied, T::terminal('d')])
+    );
+    assert_eq!(state.register, TS::str("b+c"));
+
+    let mut tree = parens_tree(SI::Inside);
+    run(&mut state, &mut tree, "da)");
+    assert_eq!(tree, seq(1, "ad"));
+
+    // right before the parentheses counts too.
+    let mut tree = parens_tree(SI::Left);
+    run(&mut state, &mut tree, "yab");
+    assert_eq!(
+        state.register,
+        TS::one(T::complete_paren(SI::Left, seq(1, "b+c")))
+    );
+}
+
+#[test]
+fn nested_objects() {
+    let inner = T::complete_abs(SI::Inside, seq(1, "x"));
+    let outer = T::complete_abs(SI::Inside, TS::new(1, vec![T::terminal('y'), inner]));
+    let mut tree = TS::new(0, vec![outer]);
+    let mut state = NormalState::default();
+    run(&mut state, &mut tree, "2di|");
+    assert_eq!(
+        tree,
+        TS::new(0, vec![T::complete_abs(SI::Inside, TS::empty())])
+    );
+}
+
+#[test]
+fn change_numerator_then_repeat() {
+    let fraction = || T::fraction(FI::Bottom, TS::str("1"), TS::str("x"));
+    let mut state = NormalState::default();
+    let mut tree = TS::new(0, vec![fraction()]);
+    assert_eq!(run(&mut state, &mut tree, "cif"), NormalOutcome::Insert);
+    for action in [TreeAction::Char('2'), TreeAction::Char('3')] {
+        tree.apply_action(action);
+        state.record_insert(action);
+    }
+    state.finish_insert();
+    let changed = T::fraction(FI::Top, seq(2, "23"), TS::str("x"));
+    assert_eq!(tree, TS::new(0, vec![changed.clone()]));
+
+    // typing after leaving insert mode isn't part of the change.
+    state.record_insert(TreeAction::Char('9'));
+    let mut other = TS::new(0, vec![fraction()]);
+    assert_eq!(run(&mut state, &mut other, "."), NormalOutcome::Done);
+    assert_eq!(other, TS::new(0, vec![changed]));
+}
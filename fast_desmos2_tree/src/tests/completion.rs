@@ -0,0 +1,160 @@
+use crate::{
+    tree::{
+        CombinedCursor, Completion, CompletionPrefix, EditorTree as T, EditorTreeSeq as TS, Motion,
+        SurroundIndex as SI, TreeAction, TreeMovable as _,
+    },
+    worksheet::{Cell, Worksheet},
+};
+
+fn typed(string: &str) -> TS {
+    let mut tree = TS::empty();
+    for ch in string.chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    tree
+}
+
+fn candidates() -> Vec<Completion> {
+    vec![
+        Completion::function("sinh"),
+        Completion::function("sin"),
+        Completion::function("sign"),
+        Completion::function("cos"),
+        Completion::variable("sin"),
+        Completion::variable("s").with_subscript("max"),
+    ]
+}
+
+#[test]
+fn prefix_before_caret() {
+    let tree = typed("2+si");
+    assert_eq!(
+        tree.completion_prefix(),
+        Some(CompletionPrefix {
+            text: "si".to_string(),
+            range: 2..4,
+        })
+    );
+
+    let mut tree = typed("cosx");
+    tree.apply_move(Motion::Left);
+    tree.apply_move(Motion::Left);
+    assert_eq!(
+        tree.completion_prefix(),
+        Some(CompletionPrefix {
+            text: "co".to_string(),
+            range: 0..4,
+        })
+    );
+
+    assert_eq!(typed("2+").completion_prefix(), None);
+    assert_eq!(typed("a_b").completion_prefix(), None);
+}
+
+#[test]
+fn matching_completions() {
+    let names: Vec<_> = typed("2si")
+        .completions(candidates())
+        .into_iter()
+        .map(|completion| completion.name)
+        .collect();
+    assert_eq!(names, ["sin", "sinh", "sign"]);
+
+    assert_eq!(
+        typed("s").completions(candidates())[0],
+        Completion::function("sin")
+    );
+    assert!(typed("1").completions(candidates()).is_empty());
+}
+
+#[test]
+fn accept_function() {
+    let mut tree = typed("2si");
+    assert!(tree.accept_completion(&Completion::function("sin")));
+    assert_cursors!(tree, 4, CombinedCursor::Paren(SI::Inside), 0);
+
+    tree.apply_action(TreeAction::Char('x'));
+    assert_eq!(tree.to_latex(), "2\\operatorname{sin}\\left(x\\right)");
+}
+
+#[test]
+fn accept_function_before_parens() {
+    let mut tree = TS::new(
+        2,
+        vec![
+            T::terminal('c'),
+            T::terminal('o'),
+            T::complete_paren(SI::Left, TS::str("x")),
+        ],
+    );
+    assert!(tree.accept_completion(&Completion::function("cos")));
+    assert_eq!(tree.len(), 4);
+    assert_cursors!(tree, 3, CombinedCursor::Paren(SI::Left));
+}
+
+#[test]
+fn accept_variable() {
+    let mut tree = typed("1+s");
+    assert!(tree.accept_completion(&Completion::variable("s").with_subscript("max")));
+    assert_eq!(
+        tree,
+        TS::new(
+            4,
+            vec![
+                T::terminal('1'),
+                T::terminal('+'),
+                T::terminal('s'),
+                T::subscript(TS::str("max")),
+            ]
+        )
+    );
+
+    let mut tree = typed("1+");
+    assert!(!tree.accept_completion(&Completion::variable("s")));
+    assert_eq!(tree, typed("1+"));
+}
+
+#[test]
+fn worksheet_names() {
+    let cells = [
+        TS::str("a=1"),
+        TS::first(vec![
+            T::terminal('f'),
+            T::complete_paren(SI::Left, TS::str("x")),
+            T::terminal('='),
+            T::terminal('x'),
+        ]),
+        TS::first(vec![
+            T::terminal('b'),
+            T::subscript(TS::str("0")),
+            T::terminal('='),
+            T::terminal('2'),
+        ]),
+        TS::str("y=2x"),
+        TS::first(vec![
+            T::terminal('x'),
+            T::subscript(TS::str("1")),
+            T::terminal('='),
+            T::terminal('2'),
+        ]),
+        TS::str("a=3"),
+        TS::str("1+1"),
+    ];
+    let worksheet = Worksheet {
+        cells: cells
+            .into_iter()
+            .enumerate()
+            .map(|(index, content)| Cell::new(index.to_string(), content))
+            .collect(),
+        ..Worksheet::default()
+    };
+    assert_eq!(
+        worksheet.defined_names(),
+        [
+            Completion::variable("a"),
+            Completion::function("f"),
+            Completion::variable("b").with_subscript("0"),
+            Completion::variable("x").with_subscript("1"),
+        ]
+    );
+}
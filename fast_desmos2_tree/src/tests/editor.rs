@@ -0,0 +1,234 @@
+use std::{env, fmt::Write as _, fs};
+
+use glam::UVec2;
+
+use crate::{
+    editor::{Editor, EditorEvent},
+    keymap::{EditorCommand, KeyPress, Keymap, KeymapMode},
+    tree::{pretty::Prettyable as _, Completion, EditorHistory, EditorTreeSeq as TS, Motion},
+};
+
+/// Cases of keys typed into an empty tree, with the trees they leave.
+const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/golden/editing.txt");
+
+/// Types `script` into the tree written `from`, starting in normal mode, giving the notation of
+/// the result.
+fn edited(from: &str, script: &str) -> String {
+    edited_with(&mut Editor::default(), from, script).0
+}
+
+fn edited_with(editor: &mut Editor, from: &str, script: &str) -> (String, Vec<EditorEvent>) {
+    let mut tree = TS::from_notation(from).unwrap();
+    let mut history = EditorHistory::default();
+    let events = editor.run_script(&mut tree, &mut history, script).unwrap();
+    let notation = tree.notation();
+    assert_eq!(TS::from_notation(&notation), Ok(tree), "{notation}");
+    (notation, events)
+}
+
+#[test]
+fn script_keys() {
+    assert_eq!(
+        KeyPress::parse_script(" i a/b <Right><Space> ").unwrap(),
+        [
+            KeyPress::Char('i'),
+            KeyPress::Char('a'),
+            KeyPress::Char('/'),
+            KeyPress::Char('b'),
+            KeyPress::Right,
+            KeyPress::Char(' '),
+        ]
+    );
+    assert_eq!(KeyPress::parse_script("").unwrap(), []);
+    assert!(KeyPress::parse_script("i <Nope>").is_err());
+}
+
+#[test]
+fn typing() {
+    assert_eq!(edited("|", "i 1 + x"), "1+x|");
+    assert_eq!(edited("|", "i a / b"), r"\frac{a}{b|}");
+    assert_eq!(
+        edited("|", "i a / b <Right> + c <Esc> h h"),
+        r"\frac{a}'{b'}|+c"
+    );
+    assert_eq!(edited("|", "i 2 * x"), "2×x|");
+}
+
+#[test]
+fn modes() {
+    let mut editor = Editor::default();
+    assert_eq!(editor.mode(), KeymapMode::Normal);
+    edited_with(&mut editor, "|", "i x");
+    assert_eq!(editor.mode(), KeymapMode::Insert);
+    edited_with(&mut editor, "|", "<Esc> v");
+    assert_eq!(editor.mode(), KeymapMode::Visual);
+    edited_with(&mut editor, "|", "<Esc>");
+    assert_eq!(editor.mode(), KeymapMode::Normal);
+}
+
+#[test]
+fn normal_commands() {
+    assert_eq!(edited("|", "i 1 2 3 <Esc> 0 x"), "|23");
+    assert_eq!(edited("|", "i a + b <Esc> 0 d w"), "|+b");
+    assert_eq!(edited("|", "i a b c <Esc> 0 x . ."), "|");
+    assert_eq!(edited("|", "i a b c <Esc> 0 x u"), "|abc");
+    assert_eq!(edited("|", "i a b c <Esc> 0 x u <C-r>"), "|bc");
+    assert_eq!(edited("|", "i x y <Esc> 0 c w 1 <Esc>"), "1|");
+}
+
+#[test]
+fn from_fixtures() {
+    assert_eq!(edited(r"\frac{a}'{b'}|", "i <Left>"), r"\frac{a|}{b'}");
+    assert_eq!(
+        edited(r"\frac{a}'{b'}|", "i <Left> <Down>"),
+        r"\frac{a'}{b|}"
+    );
+    assert_eq!(edited(r"x\pow{2|}", "<Esc> a"), r"x\pow{2|}");
+    assert_eq!(edited(r"2\paren{x+1|}", "d i ("), r"2\paren{|}");
+    assert_eq!(
+        edited(r"\cases{x<0}{|}{}", "i 1 <Right> 2"),
+        r"\cases{x<0}{1'}{2|}"
+    );
+}
+
+#[test]
+fn visual_mode() {
+    assert_eq!(edited("|", "i a + b <Esc> 0 v $ ("), r"\paren{a+b}|");
+    assert_eq!(edited("|", "i a + b <Esc> 0 v l d"), "|+b");
+    assert_eq!(edited("|", "i a + b <Esc> 0 v l y $ p"), "a+ba|");
+}
+
+#[test]
+fn events() {
+    let mut editor = Editor::default();
+    let (_, events) = edited_with(&mut editor, "|", "J i x <CR>");
+    assert_eq!(
+        events,
+        [
+            EditorEvent::Command(EditorCommand::NextCell),
+            EditorEvent::Edited,
+            EditorEvent::Command(EditorCommand::AddCellBelow),
+        ]
+    );
+    let (_, events) = edited_with(&mut editor, "|", "<Esc> j");
+    assert_eq!(events, [EditorEvent::Overflow(Motion::Down)]);
+}
+
+#[test]
+fn clicks() {
+    let mut editor = Editor::default();
+    let mut tree = TS::from_notation("|").unwrap();
+    let mut history = EditorHistory::default();
+    editor
+        .run_script(&mut tree, &mut history, "i a / x + 1 <Right> b")
+        .unwrap();
+    let path = tree.pretty(true).hit_test(UVec2::new(2, 0));
+    assert!(editor.place_cursor(&mut tree, &mut history, &path));
+    editor.run_script(&mut tree, &mut history, "c").unwrap();
+    assert_eq!(tree.notation(), r"\frac{c|a}{x+1'}b");
+    // the click ended what was typed before it, so undoing stops there.
+    editor
+        .run_script(&mut tree, &mut history, "<Esc> u")
+        .unwrap();
+    assert_eq!(tree.notation(), r"\frac{|a}{x+1'}b");
+}
+
+#[test]
+fn completes_while_typing() {
+    let mut editor = Editor::default();
+    editor.set_candidates([
+        Completion::function("sin"),
+        Completion::function("sinh"),
+        Completion::function("f"),
+        Completion::variable("sigma"),
+    ]);
+
+    let (tree, _) = edited_with(&mut editor, "|", "i 2 s i");
+    assert_eq!(tree, "2si|");
+    assert_eq!(
+        editor.completions(),
+        [
+            Completion::function("sin"),
+            Completion::function("sinh"),
+            Completion::variable("sigma"),
+        ]
+    );
+    let (tree, _) = edited_with(&mut editor, "|", "<Esc> i s i n h");
+    assert_eq!(tree, r"sinh\paren{|}");
+    assert!(editor.completions().is_empty());
+
+    // names only complete as they're typed, and single letters are left alone.
+    let (tree, _) = edited_with(&mut editor, "|", "<Esc> i f");
+    assert_eq!(tree, "f|");
+    assert_eq!(editor.completions(), [Completion::function("f")]);
+    let (tree, _) = edited_with(&mut editor, "sinh|", "<Esc> i x <Left> <BS> h");
+    assert_eq!(tree, "sinh|x");
+
+    // completing is its own step to undo.
+    let (tree, _) = edited_with(&mut editor, "|", "<Esc> i s i n h x <Esc> u");
+    assert_eq!(tree, r"sinh\paren{|}");
+    let (tree, _) = edited_with(&mut editor, "|", "<Esc> i s i n h <Esc> u");
+    assert_eq!(tree, "sinh|");
+}
+
+#[test]
+fn chords() {
+    let keymap = Keymap::from_json(r#"{"insert": {"jk": "normal_mode"}}"#).unwrap();
+    let mut editor = Editor::new(keymap);
+    let (tree, _) = edited_with(&mut editor, "|", "i a j b j k");
+    assert_eq!(tree, "ajb|");
+    assert_eq!(editor.mode(), KeymapMode::Normal);
+
+    let (tree, _) = edited_with(&mut editor, "|", "i j");
+    assert_eq!(tree, "|");
+    assert_eq!(editor.pending(), [KeyPress::Char('j')]);
+}
+
+#[test]
+fn unbound_structure_keys() {
+    let keymap = Keymap::from_json(r#"{"insert": {"/": null, "*": "char:·"}}"#).unwrap();
+    let mut editor = Editor::new(keymap);
+    let (tree, _) = edited_with(&mut editor, "|", "i a / b * c");
+    assert_eq!(tree, "a/b·c|");
+}
+
+/// Checks the cases in the snapshot file, or writes what they give now with `UPDATE_SNAPSHOTS`
+/// set, for new cases and deliberate changes.
+#[test]
+fn snapshots() {
+    let text = fs::read_to_string(SNAPSHOTS).unwrap();
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut updated = String::new();
+    let mut failures = Vec::new();
+    let mut lines = text.lines().peekable();
+    let mut from = "|";
+    while let Some(line) = lines.next() {
+        writeln!(updated, "{line}").unwrap();
+        if let Some(tree) = line.strip_prefix("from: ") {
+            from = tree;
+        }
+        let Some(script) = line.strip_prefix("keys: ") else {
+            continue;
+        };
+        let expected = lines.next_if(|line| line.starts_with("tree: "));
+        let tree = edited(from, script);
+        if expected.and_then(|line| line.strip_prefix("tree: ")) != Some(&tree) {
+            failures.push(format!(
+                "{from} {script}\n  expected {expected:?}\n  got      {tree}"
+            ));
+        }
+        writeln!(updated, "tree: {tree}").unwrap();
+        from = "|";
+    }
+
+    if update {
+        fs::write(SNAPSHOTS, updated).unwrap();
+    } else {
+        assert!(
+            failures.is_empty(),
+            "{} cases changed, rerun with UPDATE_SNAPSHOTS=1 to accept them:\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+}
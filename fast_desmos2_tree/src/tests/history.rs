@@ -0,0 +1,147 @@
+use crate::tree::{
+    CombinedCursor::Terminal as TM, EditorHistory, EditorTree as T, EditorTreeSeq as TS, Motion,
+    TreeAction, TreeMovable as _,
+};
+
+fn type_str(history: &mut EditorHistory, tree: &mut TS, string: &str) {
+    for ch in string.chars() {
+        history.apply_action(tree, TreeAction::from_char(ch));
+    }
+}
+
+#[test]
+fn char_inserts_group() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    type_str(&mut history, &mut tree, "ab");
+    assert_eq!(tree, TS::new(2, vec![T::terminal('a'), T::terminal('b')]));
+
+    assert_eq!(
+        history.undo(&mut tree),
+        Some(&[TreeAction::Char('a'), TreeAction::Char('b')][..])
+    );
+    assert_eq!(tree, TS::empty());
+    assert!(!history.can_undo());
+
+    history.redo(&mut tree);
+    assert_eq!(tree, TS::new(2, vec![T::terminal('a'), T::terminal('b')]));
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn structure_breaks_group() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    type_str(&mut history, &mut tree, "ab/c");
+    let typed = tree.clone();
+
+    history.undo(&mut tree);
+    assert_eq!(
+        history.undo(&mut tree),
+        Some(&[TreeAction::MakeFraction][..])
+    );
+    assert_eq!(tree, TS::new(2, vec![T::terminal('a'), T::terminal('b')]));
+    history.undo(&mut tree);
+    assert_eq!(tree, TS::empty());
+
+    while history.redo(&mut tree).is_some() {}
+    assert_eq!(tree, typed);
+}
+
+#[test]
+fn moving_breaks_group() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    type_str(&mut history, &mut tree, "ab");
+    tree.apply_move(Motion::Left);
+    type_str(&mut history, &mut tree, "c");
+
+    history.undo(&mut tree);
+    assert_eq!(tree, TS::new(1, vec![T::terminal('a'), T::terminal('b')]));
+    assert_cursors!(tree, 1, TM);
+    history.undo(&mut tree);
+    assert_eq!(tree, TS::empty());
+}
+
+#[test]
+fn undo_delete() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    type_str(&mut history, &mut tree, "a/b");
+    let typed = tree.clone();
+    history.break_group();
+    while history
+        .apply_action(&mut tree, TreeAction::Delete)
+        .is_none()
+        && tree != TS::empty()
+    {}
+
+    while tree != typed {
+        assert!(history.undo(&mut tree).is_some());
+    }
+    assert!(history.can_redo());
+}
+
+#[test]
+fn new_edit_clears_redo() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    type_str(&mut history, &mut tree, "a");
+    history.undo(&mut tree);
+    type_str(&mut history, &mut tree, "b");
+
+    assert!(!history.can_redo());
+    assert_eq!(history.redo(&mut tree), None);
+    assert_eq!(tree, TS::new(1, vec![T::terminal('b')]));
+}
+
+#[test]
+fn no_op_is_not_recorded() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    history.apply_action(&mut tree, TreeAction::Delete);
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn budget_forgets_oldest() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::new(100);
+
+    for _ in 0..10 {
+        type_str(&mut history, &mut tree, "a/");
+        tree.apply_move(Motion::Right);
+    }
+    assert!(history.used() <= 100);
+
+    while history.undo(&mut tree).is_some() {}
+    assert_ne!(tree, TS::empty());
+}
+
+#[test]
+fn steps_hold_only_what_changed() {
+    let mut tree = TS::empty();
+    let mut history = EditorHistory::default();
+
+    type_str(&mut history, &mut tree, "a/b");
+    for _ in 0..100 {
+        history.apply_action(&mut tree, TreeAction::from_char('c'));
+        history.break_group();
+    }
+    let (used, before) = (history.used(), tree.clone());
+    history.apply_action(&mut tree, TreeAction::from_char('d'));
+    assert_eq!(history.used(), used + 1);
+    let after = tree.clone();
+
+    history.undo(&mut tree);
+    assert_eq!(tree, before);
+    history.redo(&mut tree);
+    assert_eq!(tree, after);
+    assert_eq!(history.used(), used + 1);
+}
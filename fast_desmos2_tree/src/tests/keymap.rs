@@ -0,0 +1,159 @@
+use crate::{
+    keymap::{Binding, EditorCommand, KeyPress as K, Keymap, KeymapError, KeymapMode as M, Lookup},
+    tree::{Motion, TreeAction},
+};
+
+fn keys(keys: &str) -> Vec<K> {
+    K::parse_sequence(keys).unwrap()
+}
+
+#[test]
+fn key_notation() {
+    assert_eq!(keys("dd"), [K::Char('d'), K::Char('d')]);
+    assert_eq!(
+        keys("<C-r><a-J><M-k><esc><CR><BS>"),
+        [
+            K::Ctrl('r'),
+            K::Alt('J'),
+            K::Alt('k'),
+            K::Esc,
+            K::Enter,
+            K::Backspace
+        ]
+    );
+    assert_eq!(
+        keys("<lt><Space>>"),
+        [K::Char('<'), K::Char(' '), K::Char('>')]
+    );
+    assert_eq!(keys("<"), [K::Char('<')]);
+
+    assert!(matches!(K::parse_sequence(""), Err(KeymapError::Key(_))));
+    assert!(matches!(
+        K::parse_sequence("<Nope>"),
+        Err(KeymapError::Key(_))
+    ));
+    assert!(matches!(
+        K::parse_sequence("<C-ab>"),
+        Err(KeymapError::Key(_))
+    ));
+
+    for written in ["d<C-r><A-j><Esc><CR><Tab><Left>", "<lt>x<Space>"] {
+        let shown: String = keys(written).iter().map(ToString::to_string).collect();
+        assert_eq!(shown, written);
+    }
+}
+
+#[test]
+fn binding_names() {
+    assert_eq!(Binding::parse("word").unwrap(), Binding::Move(Motion::Word));
+    assert_eq!(
+        Binding::parse("fraction").unwrap(),
+        Binding::Action(TreeAction::MakeFraction)
+    );
+    assert_eq!(
+        Binding::parse("char:·").unwrap(),
+        Binding::Action(TreeAction::Char('·'))
+    );
+    assert_eq!(
+        Binding::parse("move_cell_up").unwrap(),
+        Binding::Command(EditorCommand::MoveCellUp)
+    );
+    assert_eq!(
+        Binding::parse("keys:2dw").unwrap(),
+        Binding::Keys("2dw".to_string())
+    );
+
+    for invalid in ["", "char:", "char:ab", "keys:", "keys:d", "nope"] {
+        assert!(
+            matches!(Binding::parse(invalid), Err(KeymapError::Binding(_))),
+            "{invalid:?}"
+        );
+    }
+}
+
+#[test]
+fn default_keymap() {
+    let keymap = Keymap::default();
+    for ch in ['/', '^', '_', '(', '|', '{', '*'] {
+        assert_eq!(
+            keymap.lookup(M::Insert, &[K::Char(ch)]),
+            Lookup::Bound(&Binding::Action(TreeAction::from_char(ch)))
+        );
+    }
+    assert_eq!(keymap.lookup(M::Insert, &[K::Char('x')]), Lookup::Unbound);
+    assert_eq!(
+        keymap.lookup(M::Normal, &keys("<C-r>")),
+        Lookup::Bound(&Binding::Command(EditorCommand::Redo))
+    );
+    // left to the command grammar.
+    assert_eq!(keymap.lookup(M::Normal, &keys("d")), Lookup::Unbound);
+    assert_eq!(
+        keymap.lookup(M::Visual, &keys("$")),
+        Lookup::Bound(&Binding::Move(Motion::Last))
+    );
+}
+
+#[test]
+fn keymap_file() {
+    let keymap = Keymap::from_json(
+        r#"{
+            "normal": { "gg": "first_cell", "<C-d>": "keys:dd", "D": null },
+            "insert": { "jk": "normal_mode", "*": "char:·" }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(keymap.lookup(M::Normal, &keys("g")), Lookup::Prefix);
+    assert_eq!(
+        keymap.lookup(M::Normal, &keys("gg")),
+        Lookup::Bound(&Binding::Command(EditorCommand::FirstCell))
+    );
+    assert_eq!(keymap.lookup(M::Normal, &keys("gx")), Lookup::Unbound);
+    assert_eq!(
+        keymap.lookup(M::Normal, &keys("<C-d>")),
+        Lookup::Bound(&Binding::Keys("dd".to_string()))
+    );
+    assert_eq!(keymap.lookup(M::Normal, &keys("D")), Lookup::Unbound);
+
+    assert_eq!(keymap.lookup(M::Insert, &keys("j")), Lookup::Prefix);
+    assert_eq!(
+        keymap.lookup(M::Insert, &keys("*")),
+        Lookup::Bound(&Binding::Action(TreeAction::Char('·')))
+    );
+    // the rest of the defaults stay.
+    assert_eq!(
+        keymap.lookup(M::Insert, &keys("/")),
+        Lookup::Bound(&Binding::Action(TreeAction::MakeFraction))
+    );
+    assert_eq!(Keymap::from_json("{}").unwrap(), Keymap::default());
+}
+
+#[test]
+fn keymap_file_errors() {
+    let error = |json| Keymap::from_json(json).unwrap_err();
+
+    assert!(matches!(
+        error(r#"{"normal": {"J": 1}}"#),
+        KeymapError::Json(_)
+    ));
+    assert!(matches!(error(r#"{"latex": {}}"#), KeymapError::Json(_)));
+    assert!(matches!(
+        error(r#"{"normal": {"<Nope>": "undo"}}"#),
+        KeymapError::Key(_)
+    ));
+    assert!(matches!(
+        error(r#"{"insert": {"x": "nope"}}"#),
+        KeymapError::Binding(_)
+    ));
+
+    let KeymapError::Shadowed { mode, keys, longer } = error(r#"{"normal": {"JJ": "undo"}}"#)
+    else {
+        panic!("the chord should be hidden by `J`");
+    };
+    assert_eq!(
+        (mode, keys.as_str(), longer.as_str()),
+        (M::Normal, "J", "JJ")
+    );
+    // unbinding the shorter keys makes room for the chord.
+    Keymap::from_json(r#"{"normal": {"J": null, "JJ": "undo"}}"#).unwrap();
+}
@@ -0,0 +1,371 @@
+use fast_desmos2_parser::{parse, AstNode, IdentStorer};
+
+use crate::tree::{
+    latex::LatexError, EditorTree as T, EditorTreeSeq as TS, FractionIndex, PiecewiseIndex,
+    PiecewiseRow, RootIndex, SumProdIndex, SurroundIndex,
+};
+
+fn seq(children: Vec<T>) -> TS {
+    TS::new(0, children)
+}
+
+fn str_then(string: &str, rest: Vec<T>) -> Vec<T> {
+    let mut children = TS::str(string).children().to_vec();
+    children.extend(rest);
+    children
+}
+
+fn parsed(idents: &IdentStorer, latex: &str, check: impl FnOnce(&AstNode)) {
+    let outcome = parse(idents, latex).unwrap_or_else(|err| panic!("{latex:?}: {err:?}"));
+    let Ok(ast) = outcome.borrow_dependent() else {
+        panic!("{latex:?} doesn't parse");
+    };
+    assert_eq!(
+        ast.span_as_str(latex),
+        latex.trim_end(),
+        "{latex:?} only partly parses"
+    );
+    check(ast);
+}
+
+/// Checks the exported LaTeX, and that it means the same to the old parser as `reference` does.
+fn assert_latex(tree: TS, expected: &str, reference: &str) {
+    let latex = tree.to_latex();
+    assert_eq!(latex, expected);
+
+    let idents = IdentStorer::default();
+    parsed(&idents, &latex, |exported| {
+        parsed(&idents, reference, |reference| {
+            assert_eq!(exported, reference)
+        });
+    });
+}
+
+#[test]
+fn plain() {
+    assert_latex(TS::str("2x+1"), "2x+1", "2x + 1");
+    assert_latex(
+        seq(vec![T::terminal('a'), T::terminal('×'), T::terminal('b')]),
+        "a\\cdot b",
+        "a\\times b",
+    );
+}
+
+#[test]
+fn fraction_and_power() {
+    let tree = seq(vec![
+        T::fraction(FractionIndex::Left, TS::str("1"), TS::str("x")),
+        T::terminal('+'),
+        T::terminal('x'),
+        T::power(TS::str("2")),
+    ]);
+    assert_latex(tree, "\\frac{1}{x}+x^{2}", "\\frac{1}{x}+x^{2}");
+}
+
+#[test]
+fn subscript() {
+    let tree = seq(vec![
+        T::terminal('a'),
+        T::subscript(TS::str("max")),
+        T::terminal('+'),
+        T::terminal('x'),
+        T::subscript(TS::str("1")),
+        T::power(TS::str("2")),
+    ]);
+    assert_latex(tree, "a_{max}+x_{1}^{2}", "a_{max} + x_{1}^{2}");
+}
+
+#[test]
+fn sqrt_and_surrounds() {
+    let tree = seq(vec![
+        T::sqrt(SurroundIndex::Left, TS::str("x")),
+        T::terminal('+'),
+        T::complete_abs(SurroundIndex::Left, TS::str("y")),
+        T::terminal('+'),
+        T::complete_paren(SurroundIndex::Left, TS::str("1,2")),
+        T::terminal('+'),
+        T::complete_brackets(SurroundIndex::Left, TS::str("1,2")),
+    ]);
+    assert_latex(
+        tree,
+        "\\sqrt{x}+\\left|y\\right|+\\left(1,2\\right)+\\left[1,2\\right]",
+        "\\sqrt{x}+|y|+(1,2)+[1,2]",
+    );
+}
+
+#[test]
+fn piecewise() {
+    let tree = seq(vec![T::complete_curly(
+        SurroundIndex::Left,
+        TS::str("x>0:1,2"),
+    )]);
+    assert_latex(tree, "\\left\\{x>0:1,2\\right\\}", "\\{x>0:1,2\\}");
+}
+
+#[test]
+fn piecewise_rows() {
+    let rows = vec![
+        PiecewiseRow::new(TS::str("x<0"), TS::str("-1")),
+        PiecewiseRow::new(TS::str("x>1"), TS::empty()),
+    ];
+    let tree = seq(vec![T::piecewise(PiecewiseIndex::Left, rows, TS::str("0"))]);
+    assert_latex(
+        tree,
+        "\\left\\{x<0:-1,x>1:1,0\\right\\}",
+        "\\{x<0:-1,x>1:1,0\\}",
+    );
+
+    let rows = vec![
+        PiecewiseRow::new(TS::str("x<0"), TS::str("x")),
+        PiecewiseRow::new(TS::str("x>1"), TS::empty()),
+    ];
+    let tree = seq(vec![T::piecewise(PiecewiseIndex::Left, rows, TS::empty())]);
+    assert_latex(tree, "\\left\\{x<0:x,x>1\\right\\}", "\\{x<0:x,x>1\\}");
+}
+
+#[test]
+fn nth_root() {
+    let tree = seq(vec![
+        T::root(RootIndex::Left, TS::str("3"), TS::str("x")),
+        T::terminal('+'),
+        T::root(RootIndex::Left, TS::str("n"), TS::str("2")),
+    ]);
+    assert_latex(
+        tree,
+        "\\sqrt[3]{x}+\\sqrt[n]{2}",
+        "\\sqrt[3]{x}+\\sqrt[n]{2}",
+    );
+}
+
+#[test]
+fn sum_and_prod() {
+    let tree = seq(vec![
+        T::sum(
+            SumProdIndex::Left,
+            TS::str("10"),
+            TS::str("1"),
+            TS::str("n"),
+        ),
+        T::terminal('n'),
+    ]);
+    assert_latex(tree, "\\sum_{n=1}^{10}n", "\\sum_{n=1}^{10}n");
+
+    let tree = seq(vec![
+        T::prod(SumProdIndex::Left, TS::str("k"), TS::str("2"), TS::str("i")),
+        T::terminal('i'),
+        T::power(TS::str("2")),
+    ]);
+    assert_latex(tree, "\\prod_{i=2}^{k}i^{2}", "\\prod_{i=2}^{k}i^{2}");
+}
+
+#[test]
+fn builtins() {
+    let tree = seq(str_then(
+        "sin",
+        vec![T::complete_paren(SurroundIndex::Left, TS::str("x"))],
+    ));
+    assert_latex(tree, "\\operatorname{sin}\\left(x\\right)", "\\sin(x)");
+
+    let tree = seq(str_then(
+        "f",
+        vec![T::complete_paren(
+            SurroundIndex::Left,
+            seq(str_then(
+                "floor",
+                vec![T::complete_paren(SurroundIndex::Left, TS::str("x"))],
+            )),
+        )],
+    ));
+    assert_latex(
+        tree,
+        "f\\left(\\operatorname{floor}\\left(x\\right)\\right)",
+        "f(\\operatorname{floor}(x))",
+    );
+}
+
+fn assert_import(latex: &str, expected: TS) {
+    let tree = TS::from_latex(latex).unwrap_or_else(|err| panic!("{latex:?}: {err}"));
+    assert_eq!(tree, expected);
+}
+
+#[test]
+fn import_structures() {
+    assert_import(
+        "\\frac{1}{x}+x^{2}",
+        seq(vec![
+            T::fraction(FractionIndex::Left, TS::str("1"), TS::str("x")),
+            T::terminal('+'),
+            T::terminal('x'),
+            T::power(TS::str("2")),
+        ]),
+    );
+    assert_import(
+        "\\sqrt{x}+|y|+\\left(1,2\\right)",
+        seq(vec![
+            T::sqrt(SurroundIndex::Left, TS::str("x")),
+            T::terminal('+'),
+            T::complete_abs(SurroundIndex::Left, TS::str("y")),
+            T::terminal('+'),
+            T::complete_paren(SurroundIndex::Left, TS::str("1,2")),
+        ]),
+    );
+    assert_import(
+        "\\sum_{n=1}^{10}n",
+        seq(vec![
+            T::sum(
+                SumProdIndex::Left,
+                TS::str("10"),
+                TS::str("1"),
+                TS::str("n"),
+            ),
+            T::terminal('n'),
+        ]),
+    );
+}
+
+#[test]
+fn import_nth_root() {
+    assert_import(
+        "\\sqrt[3]{x}",
+        seq(vec![T::root(RootIndex::Left, TS::str("3"), TS::str("x"))]),
+    );
+}
+
+#[test]
+fn import_subscripts() {
+    assert_import(
+        "x_{1}+a_{max}",
+        seq(vec![
+            T::terminal('x'),
+            T::subscript(TS::str("1")),
+            T::terminal('+'),
+            T::terminal('a'),
+            T::subscript(TS::str("max")),
+        ]),
+    );
+    assert_import(
+        "y_2",
+        seq(vec![T::terminal('y'), T::subscript(TS::str("2"))]),
+    );
+}
+
+#[test]
+fn import_builtins() {
+    assert_import(
+        "\\operatorname{floor}(x)\\cdot\\sin(y)",
+        seq(str_then(
+            "floor",
+            [
+                vec![
+                    T::complete_paren(SurroundIndex::Left, TS::str("x")),
+                    T::terminal('×'),
+                ],
+                TS::str("sin").children().to_vec(),
+                vec![T::complete_paren(SurroundIndex::Left, TS::str("y"))],
+            ]
+            .concat(),
+        )),
+    );
+}
+
+#[test]
+fn import_keeps_numbers_apart() {
+    assert_import("2\\cdot3", TS::str("2×3"));
+    assert_import("-x+1", TS::str("-x+1"));
+    assert_import("3.141592653589793", TS::str("3.141592653589793"));
+    assert_import("6.283185307179586", TS::str("6.283185307179586"));
+    assert_import("2\\pi+\\tau", TS::str("2π+τ"));
+}
+
+#[test]
+fn import_piecewise() {
+    assert_import(
+        "\\left\\{x<0:-1,x\\ge 1:1,0\\right\\}",
+        seq(vec![T::piecewise(
+            PiecewiseIndex::Left,
+            vec![
+                PiecewiseRow::new(TS::str("x<0"), TS::str("-1")),
+                PiecewiseRow::new(TS::str("x>=1"), TS::str("1")),
+            ],
+            TS::str("0"),
+        )]),
+    );
+}
+
+#[test]
+fn import_definitions() {
+    assert_import(
+        "f\\left(x\\right)=x^{2}",
+        seq(vec![
+            T::terminal('f'),
+            T::complete_paren(SurroundIndex::Left, TS::str("x")),
+            T::terminal('='),
+            T::terminal('x'),
+            T::power(TS::str("2")),
+        ]),
+    );
+    assert_import(
+        "(1)=a",
+        seq(vec![
+            T::complete_paren(SurroundIndex::Left, TS::str("1")),
+            T::terminal('='),
+            T::terminal('a'),
+        ]),
+    );
+    assert_import("a=1", TS::str("a=1"));
+}
+
+#[test]
+fn import_errors() {
+    assert!(matches!(
+        TS::from_latex("\\frac{1}"),
+        Err(LatexError::Parse)
+    ));
+    assert!(matches!(TS::from_latex("x)"), Err(LatexError::Partial(1))));
+    assert!(matches!(
+        TS::from_latex("f(x)=x)"),
+        Err(LatexError::Partial(6))
+    ));
+    assert!(TS::from_latex("f(x)=").is_err());
+    assert!(matches!(
+        TS::from_latex("\\alpha"),
+        Err(LatexError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn export_then_import() {
+    let tree = seq(vec![
+        T::fraction(
+            FractionIndex::Left,
+            seq(str_then(
+                "cos",
+                vec![T::complete_paren(SurroundIndex::Left, TS::str("x"))],
+            )),
+            TS::str("2"),
+        ),
+        T::terminal('-'),
+        T::complete_abs(
+            SurroundIndex::Left,
+            seq(vec![T::terminal('y'), T::power(TS::str("3"))]),
+        ),
+    ]);
+    assert_eq!(TS::from_latex(&tree.to_latex()).unwrap(), tree);
+}
+
+#[test]
+fn paste_latex() {
+    let mut tree = TS::new(1, TS::str("ab").children().to_vec());
+    tree.paste_latex("\\frac{1}{2}").unwrap();
+    assert_eq!(
+        tree,
+        TS::new(
+            2,
+            vec![
+                T::terminal('a'),
+                T::fraction(FractionIndex::Left, TS::str("1"), TS::str("2")),
+                T::terminal('b'),
+            ]
+        )
+    );
+}
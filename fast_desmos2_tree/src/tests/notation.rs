@@ -0,0 +1,196 @@
+use crate::tree::{
+    EditorTree as T, EditorTreeSeq as TS, FractionIndex as FI, NotationError as NE,
+    PiecewiseIndex as PI, PiecewiseRow, RootIndex as RI, SumProdIndex as SPI, SurroundIndex as SI,
+};
+
+/// The notation of `tree`, after checking it reads back as the same tree.
+fn written(tree: &TS) -> String {
+    let notation = tree.notation();
+    assert_eq!(
+        TS::from_notation(&notation).as_ref(),
+        Ok(tree),
+        "{notation}"
+    );
+    notation
+}
+
+fn error(notation: &str) -> NE {
+    TS::from_notation(notation).unwrap_err()
+}
+
+#[test]
+fn characters() {
+    assert_eq!(written(&TS::empty()), "|");
+    assert_eq!(written(&TS::str("1+x")), "|1+x");
+    assert_eq!(
+        written(&TS::new(3, TS::str("1+x").children().to_vec())),
+        "1+x|"
+    );
+    assert_eq!(written(&TS::str(r"\{}|'a")), r"|\\\{\}\|\'a");
+}
+
+#[test]
+fn caret_inside() {
+    let tree = TS::new(
+        1,
+        vec![
+            T::terminal('y'),
+            T::fraction(FI::Bottom, TS::str("1"), TS::new(1, vec![T::terminal('x')])),
+        ],
+    );
+    assert_eq!(written(&tree), r"y\frac{1}{x|}");
+
+    let tree = TS::new(1, vec![T::terminal('x'), T::power(TS::str("2"))]);
+    assert_eq!(written(&tree), r"x\pow{|2}");
+}
+
+#[test]
+fn caret_left_of_node() {
+    let tree = TS::new(
+        1,
+        vec![T::terminal('2'), T::complete_paren(SI::Left, TS::str("x"))],
+    );
+    assert_eq!(written(&tree), r"2|\paren{x}");
+}
+
+#[test]
+fn remembered_cursors() {
+    let fraction = T::fraction(FI::Bottom, TS::str("a"), TS::new(1, vec![T::terminal('b')]));
+    let tree = TS::new(1, vec![fraction]);
+    assert_eq!(written(&tree), r"\frac{a}'{b'}|");
+
+    let power = T::power(TS::new(2, TS::str("10").children().to_vec()));
+    let tree = TS::first(vec![T::terminal('x'), power]);
+    assert_eq!(written(&tree), r"|x\pow{10'}");
+}
+
+#[test]
+fn every_node() {
+    let tree = TS::first(vec![
+        T::subscript(TS::str("1")),
+        T::sqrt(SI::Left, TS::str("x")),
+        T::root(RI::Index, TS::str("3"), TS::str("y")),
+        T::incomplete_abs(SI::Left, TS::empty()),
+        T::complete_brackets(SI::Left, TS::str("1,2")),
+        T::incomplete_curly(SI::Left, TS::empty()),
+        T::sum(SPI::Left, TS::str("9"), TS::str("1"), TS::str("n")),
+        T::prod(SPI::Top, TS::str("9"), TS::str("1"), TS::str("n")),
+        T::piecewise(
+            PI::Value(1),
+            vec![
+                PiecewiseRow::new(TS::str("x<0"), TS::str("0")),
+                PiecewiseRow::new(TS::str("x<1"), TS::str("x")),
+            ],
+            TS::str("1"),
+        ),
+    ]);
+    assert_eq!(
+        written(&tree),
+        concat!(
+            r"\sub{|1}\sqrt{x}\root'{3}{y}\abs?{}\brack{1,2}\curly?{}",
+            r"\sum{n}{1}{9}\prod{n}{1}'{9}\cases{x<0}{0}{x<1}'{x}{1}",
+        )
+    );
+}
+
+#[test]
+fn stale_cursors_read_back() {
+    for notation in [
+        r"\frac'{a}{b}|",
+        r"\frac{a'}'{b}x|",
+        r"\cases'{a}{b}{c}\cases{a}'{b}{c}\cases{a}{b}'{c}|",
+        r"\sum'{n}{1}{9}\sum{n}'{1}{9}|",
+        r"\root{3'}'{y}\sqrt'{x}|",
+        r"\paren?'{x}\abs'{}\brack'{}\curly?'{}|",
+    ] {
+        assert_eq!(TS::from_notation(notation).unwrap().notation(), notation);
+    }
+    // a remembered cursor at the start of a sequence isn't written.
+    assert_eq!(
+        TS::from_notation(r"\frac{'a}{b}|").unwrap().notation(),
+        r"\frac{a}{b}|"
+    );
+}
+
+#[test]
+fn notation_errors() {
+    assert_eq!(error(""), NE::NoCaret);
+    assert_eq!(error("ab"), NE::NoCaret);
+    assert_eq!(error("a|b|"), NE::Cursors(0));
+    assert_eq!(error("|a'b"), NE::Cursors(0));
+    assert_eq!(error(r"\frac{|}{|}"), NE::SecondCaret(9));
+    assert_eq!(error(r"a'\frac{|}{}"), NE::Cursors(0));
+    assert_eq!(error(r"\frac'{a}'{b}|"), NE::Cursors(0));
+    assert_eq!(error(r"\frac'{a}{b|}"), NE::Cursors(0));
+    assert_eq!(error(r"\pow'{2}|"), NE::Cursors(0));
+    assert_eq!(error(r"x|\pow{2}"), NE::CaretOutside(1));
+    assert_eq!(error(r"|\frac'{a}{b}"), NE::CaretOutside(0));
+    assert_eq!(
+        error(r"\frac{a}|"),
+        NE::Groups {
+            index: 0,
+            name: "frac".to_string(),
+            groups: 1
+        }
+    );
+    assert_eq!(
+        error(r"\cases{a}{b}|"),
+        NE::Groups {
+            index: 0,
+            name: "cases".to_string(),
+            groups: 2
+        }
+    );
+    assert_eq!(
+        error(r"a\nope{}|"),
+        NE::UnknownNode {
+            index: 1,
+            name: "nope".to_string()
+        }
+    );
+    assert_eq!(
+        error(r"\frac?{a}{b}|"),
+        NE::Expected {
+            index: 5,
+            expected: "`{`"
+        }
+    );
+    assert_eq!(
+        error(r"\frac{a}{b|"),
+        NE::Expected {
+            index: 11,
+            expected: "`}`"
+        }
+    );
+    assert_eq!(
+        error("a}|"),
+        NE::Expected {
+            index: 1,
+            expected: "the end"
+        }
+    );
+    assert_eq!(
+        error("a{b}|"),
+        NE::Expected {
+            index: 1,
+            expected: "a character or node"
+        }
+    );
+    assert_eq!(
+        error(r"\1|"),
+        NE::Expected {
+            index: 1,
+            expected: "a node name or an escaped character"
+        }
+    );
+}
+
+#[test]
+fn debug_shows_notation() {
+    let tree = TS::from_notation(r"y=\frac{1}{x|}").unwrap();
+    assert_eq!(format!("{tree:?}"), r"EditorTreeSeq(y=\frac{1}{x|})");
+    assert_eq!(
+        format!("{:?}", tree.children()[2]),
+        r"EditorTree(\frac{1}'{x'})"
+    );
+}
@@ -0,0 +1,125 @@
+use crate::tree::{
+    CombinedCursor::{self, Terminal as TM},
+    EditorTree as T, EditorTreeKind, EditorTreeSeq as TS, Motion, PiecewiseIndex as PI,
+    PiecewiseRow, TreeAction, TreeMovable as _,
+};
+
+fn typed(string: &str) -> TS {
+    let mut tree = TS::empty();
+    for ch in string.chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    tree
+}
+
+fn row_count(tree: &TS) -> usize {
+    match tree.children()[0].kind() {
+        EditorTreeKind::Piecewise(piecewise) => piecewise.rows().len(),
+        kind => panic!("expected a piecewise node, found {kind:?}"),
+    }
+}
+
+#[test]
+fn type_piecewise() {
+    let mut tree = typed("{");
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(0)), 0);
+
+    for ch in "x<0:-1,".chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Default), 0);
+
+    // a condition typed into the default turns it into another row
+    for ch in "x>1:1,0".chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Default), 1);
+    assert_eq!(row_count(&tree), 2);
+    assert_eq!(tree.to_latex(), "\\left\\{x<0:-1,x>1:1,0\\right\\}");
+}
+
+#[test]
+fn insert_row() {
+    let mut tree = typed("{a:1,b:2");
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(1)), 1);
+
+    tree.apply_move(Motion::Up);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(0)), 1);
+    for ch in ",c:3".chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(1)), 1);
+    assert_eq!(tree.to_latex(), "\\left\\{a:1,c:3,b:2\\right\\}");
+}
+
+#[test]
+fn move_through_piecewise() {
+    let rows = vec![
+        PiecewiseRow::new(TS::str("a"), TS::str("1")),
+        PiecewiseRow::new(TS::str("b"), TS::str("2")),
+    ];
+    let mut tree = TS::new(1, vec![T::piecewise(PI::Left, rows, TS::str("3"))]);
+
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Default), 1);
+    // moving between rows keeps where the caret was in each of them
+    tree.apply_move(Motion::Up);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(1)), 0, TM);
+    tree.apply_move(Motion::Up);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(0)), 0, TM);
+    assert_eq!(tree.apply_move(Motion::Up), Some(Motion::Up));
+
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(0)), 1);
+    tree.apply_move(Motion::Left);
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Left));
+    tree.apply_move(Motion::Right);
+    tree.apply_move(Motion::Right);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(0)), 1);
+    tree.apply_move(Motion::Down);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(1)), 0, TM);
+    tree.apply_move(Motion::Right);
+    tree.apply_move(Motion::Right);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(1)), 0, TM);
+    tree.apply_move(Motion::Down);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Default), 1);
+
+    tree.apply_move(Motion::Right);
+    assert_cursors!(tree, 1);
+}
+
+#[test]
+fn delete_rows() {
+    let mut tree = typed("{a:1,b:2");
+
+    for _ in 0..3 {
+        tree.apply_action(TreeAction::Delete);
+    }
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(1)), 0);
+    // deleting at the start of an empty row removes it
+    tree.apply_action(TreeAction::Delete);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(0)), 1);
+    assert_eq!(row_count(&tree), 1);
+
+    for _ in 0..3 {
+        tree.apply_action(TreeAction::Delete);
+    }
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(0)), 0);
+    tree.apply_action(TreeAction::Delete);
+    assert_eq!(tree, TS::empty());
+}
+
+#[test]
+fn delete_keeps_filled_piecewise() {
+    let mut tree = typed("{");
+    tree.apply_move(Motion::Down);
+    tree.apply_action(TreeAction::Char('1'));
+    tree.apply_move(Motion::Up);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Value(0)), 0);
+
+    tree.apply_action(TreeAction::Delete);
+    tree.apply_action(TreeAction::Delete);
+    assert_cursors!(tree, 0, CombinedCursor::Piecewise(PI::Condition(0)), 0);
+    assert_eq!(tree.len(), 1);
+}
@@ -0,0 +1,280 @@
+use glam::UVec2;
+
+use crate::tree::{
+    pretty::{CellStyle, Prettyable as _},
+    CombinedCursor as CC, CursorPath, EditorTree as T, EditorTreeSeq as TS, FractionIndex as FI,
+    Motion, PathStep, PiecewiseIndex as PI, PiecewiseRow, RootIndex as RI, SurroundIndex as SI,
+    TreeAction, TreeMovable as _,
+};
+
+fn typed(string: &str) -> TS {
+    let mut tree = TS::empty();
+    for ch in string.chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    tree
+}
+
+fn render(tree: &TS) -> String {
+    tree.pretty(false).render().to_string()
+}
+
+#[test]
+fn caret_at_end() {
+    let tree = typed("1+x");
+    let screen = tree.pretty(true).render();
+    assert_eq!(screen.to_string(), "1+x\n");
+    assert_eq!(screen.size(), UVec2::new(4, 1));
+    assert_eq!(screen.caret(), Some(UVec2::new(3, 0)));
+}
+
+#[test]
+fn caret_on_character() {
+    let tree = TS::new(1, vec![T::terminal('a'), T::terminal('b')]);
+    let screen = tree.pretty(true).render();
+    assert_eq!(screen.to_string(), "ab\n");
+    assert_eq!(screen.caret(), Some(UVec2::new(1, 0)));
+    assert_eq!(screen.get(UVec2::new(0, 0)).style, CellStyle::Plain);
+
+    assert_eq!(tree.pretty(false).render().caret(), None);
+}
+
+#[test]
+fn times_is_a_dot() {
+    assert_eq!(render(&typed("2*3")), "2·3\n");
+}
+
+#[test]
+fn fraction() {
+    let tree = TS::first(vec![
+        T::terminal('y'),
+        T::terminal('='),
+        T::fraction(FI::Left, TS::str("1"), TS::str("x+1")),
+    ]);
+    assert_eq!(render(&tree), "    1\ny=─────\n   x+1\n");
+}
+
+#[test]
+fn powers_and_subscripts() {
+    let tree = TS::first(vec![
+        T::terminal('x'),
+        T::power(TS::str("2")),
+        T::terminal('+'),
+        T::terminal('a'),
+        T::subscript(TS::str("1")),
+    ]);
+    assert_eq!(render(&tree), " 2\nx +a\n    1\n");
+}
+
+#[test]
+fn roots() {
+    let tree = TS::first(vec![
+        T::sqrt(SI::Left, TS::str("x")),
+        T::terminal('+'),
+        T::root(RI::Left, TS::str("3"), TS::str("y")),
+    ]);
+    assert_eq!(render(&tree), "┌─ 3┌─\n√x+ √y\n");
+}
+
+#[test]
+fn tall_brackets() {
+    let tree = TS::first(vec![
+        T::terminal('2'),
+        T::complete_paren(
+            SI::Left,
+            TS::one(T::fraction(FI::Left, TS::str("1"), TS::str("x"))),
+        ),
+    ]);
+    assert_eq!(render(&tree), " ⎛ 1 ⎞\n2⎜───⎟\n ⎝ x ⎠\n");
+
+    let flat = TS::one(T::incomplete_paren(SI::Left, TS::str("x")));
+    let screen = flat.pretty(false).render();
+    assert_eq!(screen.to_string(), "(x)\n");
+    assert_eq!(screen.get(UVec2::new(2, 0)).style, CellStyle::Faint);
+}
+
+#[test]
+fn piecewise() {
+    let tree = TS::one(T::piecewise(
+        PI::Left,
+        vec![
+            PiecewiseRow::new(TS::str("x<0"), TS::str("-x")),
+            PiecewiseRow::new(TS::str("x>1"), TS::str("1")),
+        ],
+        TS::str("x"),
+    ));
+    assert_eq!(render(&tree), "⎧x<0: -x\n⎨x>1: 1\n⎩x\n");
+}
+
+#[test]
+fn caret_before_node() {
+    let tree = TS::one(T::fraction(FI::Left, TS::str("1"), TS::str("2")));
+    let screen = tree.pretty(true).render();
+    assert_eq!(screen.caret(), Some(UVec2::new(0, 0)));
+    assert!((0..3).all(|y| screen.get(UVec2::new(0, y)).style == CellStyle::Caret));
+}
+
+#[test]
+fn empty_seqs() {
+    let tree = typed("/");
+    let screen = tree.pretty(true).render();
+    assert_eq!(screen.to_string(), " □\n───\n □\n");
+    assert_eq!(screen.caret(), Some(UVec2::new(1, 0)));
+}
+
+#[test]
+fn value() {
+    let tree = typed("1+2");
+    let screen = tree.pretty(false).with_value("3").render();
+    assert_eq!(screen.to_string(), "1+2  = 3\n");
+    assert_eq!(screen.get(UVec2::new(7, 0)).style, CellStyle::Faint);
+}
+
+/// Clicks at `(x, y)` on the tree written `notation`, drawn with its caret, giving the notation with
+/// the caret where the click put it.
+fn clicked(notation: &str, x: u32, y: u32) -> String {
+    let mut tree = TS::from_notation(notation).unwrap();
+    let path = tree.pretty(true).hit_test(UVec2::new(x, y));
+    assert!(tree.place_cursor(&path), "{path:?}");
+    tree.notation()
+}
+
+#[test]
+fn hit_characters() {
+    assert_eq!(clicked("|1+x", 0, 0), "|1+x");
+    assert_eq!(clicked("|1+x", 2, 0), "1+|x");
+    // past the end, and after the caret at the end.
+    assert_eq!(clicked("|1+x", 9, 0), "1+x|");
+    assert_eq!(clicked("1+x|", 3, 0), "1+x|");
+    assert_eq!(clicked("1|+x", 2, 0), "1+|x");
+    assert_eq!(clicked("|", 0, 0), "|");
+}
+
+#[test]
+fn hit_fraction() {
+    //     1
+    // y=─────
+    //    x+1
+    let notation = r"|y=\frac{1}{x+1}";
+    assert_eq!(clicked(notation, 4, 0), r"y=\frac{|1}{x+1}");
+    assert_eq!(clicked(notation, 4, 2), r"y=\frac{1}{x|+1}");
+    assert_eq!(clicked(notation, 5, 2), r"y=\frac{1}{x+|1}");
+    // the bar, and the space around the sequences, are in front of the fraction.
+    assert_eq!(clicked(notation, 4, 1), r"y=|\frac{1}{x+1}");
+    assert_eq!(clicked(notation, 3, 0), r"y=|\frac{1}{x+1}");
+    // above the characters next to it.
+    assert_eq!(clicked(notation, 1, 0), r"y|=\frac{1}{x+1}");
+}
+
+#[test]
+fn hit_nested() {
+    //  2
+    // x +a
+    //     1
+    let notation = r"x\pow{2}+a\sub{1}|";
+    assert_eq!(clicked(notation, 1, 0), r"x\pow{|2}+a\sub{1}");
+    assert_eq!(clicked(notation, 4, 2), r"x\pow{2}+a\sub{|1}");
+    // below a power is in it, there being no place in front of it.
+    assert_eq!(clicked(notation, 1, 1), r"x\pow{|2}+a\sub{1}");
+
+    // ┌─ 3┌─
+    // √x+ √y
+    let notation = r"\sqrt{x}+\root{3}{y}|";
+    assert_eq!(clicked(notation, 1, 1), r"\sqrt{|x}+\root{3}{y}");
+    assert_eq!(clicked(notation, 0, 1), r"|\sqrt{x}+\root{3}{y}");
+    assert_eq!(clicked(notation, 3, 0), r"\sqrt{x}+\root{|3}{y}");
+    assert_eq!(clicked(notation, 5, 1), r"\sqrt{x}+\root{3}{|y}");
+
+    // ⎛ 1 ⎞
+    // ⎜───⎟⎧x<0: 1
+    // ⎝ x ⎠⎩2
+    let notation = r"\paren{\frac{1}{x}}\cases{x<0}{1}{2}|";
+    assert_eq!(
+        clicked(notation, 2, 2),
+        r"\paren{\frac{1}{|x}}\cases{x<0}{1}{2}"
+    );
+    assert_eq!(
+        clicked(notation, 0, 2),
+        r"|\paren{\frac{1}{x}}\cases{x<0}{1}{2}"
+    );
+    assert_eq!(
+        clicked(notation, 11, 1),
+        r"\paren{\frac{1}{x}}\cases{x<0}{|1}{2}"
+    );
+    assert_eq!(
+        clicked(notation, 10, 1),
+        r"\paren{\frac{1}{x}}|\cases{x<0}{1}{2}"
+    );
+    assert_eq!(
+        clicked(notation, 6, 2),
+        r"\paren{\frac{1}{x}}\cases{x<0}{1}{|2}"
+    );
+}
+
+#[test]
+fn hit_with_value() {
+    let mut tree = TS::from_notation("|1+2").unwrap();
+    let layout = tree.pretty(true).with_value("3");
+    let path = layout.hit_test(UVec2::new(6, 0));
+    assert_eq!(
+        path,
+        CursorPath {
+            steps: vec![],
+            index: 3
+        }
+    );
+    assert!(tree.place_cursor(&path));
+    assert_eq!(tree.notation(), "1+2|");
+}
+
+#[test]
+fn hit_paths() {
+    let tree = TS::from_notation(r"y=\frac{1}{x+1|}").unwrap();
+    assert_eq!(
+        tree.pretty(false).hit_test(UVec2::new(4, 2)),
+        CursorPath {
+            steps: vec![PathStep {
+                child: 2,
+                slot: CC::Fraction(FI::Bottom)
+            }],
+            index: 1
+        }
+    );
+    assert!(!TS::str("x").place_cursor(&CursorPath {
+        steps: vec![PathStep {
+            child: 0,
+            slot: CC::Power
+        }],
+        index: 0
+    }));
+    assert!(!TS::str("x").place_cursor(&CursorPath {
+        steps: vec![],
+        index: 2
+    }));
+}
+
+/// Clicking where the caret is drawn leaves it there, wherever it is in the tree.
+#[test]
+fn hit_caret() {
+    let mut tree = TS::from_notation(concat!(
+        r"|y=\frac{1}{x\pow{2}}+\sqrt{\abs{a_\sub{1}}}\root{3}{}",
+        r"\sum{n}{1}{9}\cases{x<0}{1}{}\brack?{1,2}\curly{}",
+    ))
+    .unwrap();
+    let mut count = 0;
+    loop {
+        let screen = tree.pretty(true).render();
+        let caret = screen.caret().unwrap();
+        let path = tree.cursor_path();
+        assert_eq!(tree.pretty(true).hit_test(caret), path, "{tree:?}");
+
+        let mut clicked = tree.clone();
+        assert!(clicked.place_cursor(&path));
+        assert_eq!(clicked.cursor_path(), path);
+        count += 1;
+        if tree.apply_move(Motion::Right).is_some() {
+            break;
+        }
+    }
+    assert!(count > 30, "{count}");
+}
@@ -0,0 +1,85 @@
+use crate::tree::{
+    CombinedCursor::{self, Terminal as TM},
+    EditorTree as T, EditorTreeSeq as TS, Motion, RootIndex as RI, SurroundIndex as SI, TreeAction,
+    TreeMovable as _,
+};
+
+fn typed(string: &str) -> TS {
+    let mut tree = TS::empty();
+    for ch in string.chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    tree
+}
+
+#[test]
+fn type_square_root() {
+    let tree = typed("sqrt2");
+    assert_eq!(
+        tree,
+        TS::new(
+            0,
+            vec![T::sqrt(SI::Inside, TS::new(1, vec![T::terminal('2')]))]
+        )
+    );
+}
+
+#[test]
+fn type_nth_root() {
+    let mut tree = typed("1+nthroot3");
+    assert_cursors!(tree, 2, CombinedCursor::Root(RI::Index), 1);
+
+    tree.apply_move(Motion::Right);
+    assert_cursors!(tree, 2, CombinedCursor::Root(RI::Inside), 0);
+    tree.apply_action(TreeAction::Char('x'));
+    tree.apply_move(Motion::Right);
+    assert_cursors!(tree, 3);
+    assert_eq!(tree.to_latex(), "1+\\sqrt[3]{x}");
+}
+
+#[test]
+fn move_through_root() {
+    let mut tree = TS::new(1, vec![T::root(RI::Left, TS::str("3"), TS::str("x"))]);
+
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Inside), 1);
+    tree.apply_move(Motion::Up);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Index), 1);
+    tree.apply_move(Motion::Down);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Inside), 0, TM);
+
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Index), 1);
+    tree.apply_move(Motion::Left);
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Left));
+    assert_eq!(tree.apply_move(Motion::Left), Some(Motion::Left));
+}
+
+#[test]
+fn delete_into_root() {
+    let mut tree = TS::new(1, vec![T::root(RI::Left, TS::str("3"), TS::str("x"))]);
+
+    tree.apply_action(TreeAction::Delete);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Inside), 1);
+    tree.apply_action(TreeAction::Delete);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Inside), 0);
+
+    // deleting at the start of the radicand goes to the index instead of removing the root
+    tree.apply_action(TreeAction::Delete);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Index), 1);
+    assert_eq!(tree.to_latex(), "\\sqrt[3]{}");
+}
+
+#[test]
+fn unwrap_root() {
+    let mut tree = TS::new(1, vec![T::root(RI::Left, TS::str("3"), TS::str("x"))]);
+    tree.apply_move(Motion::Left);
+    tree.apply_move(Motion::First);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Inside), 0, TM);
+    tree.apply_move(Motion::First);
+    assert_cursors!(tree, 0, CombinedCursor::Root(RI::Index), 0, TM);
+
+    tree.apply_action(TreeAction::Delete);
+    assert_eq!(tree, TS::new(0, TS::str("3x").children().to_vec()));
+}
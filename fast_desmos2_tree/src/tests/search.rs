@@ -0,0 +1,160 @@
+use crate::tree::{
+    search::{SearchError, TreePattern},
+    CombinedCursor as CC, EditorTreeSeq as TS, FractionIndex as FI, NotationError, PathStep,
+    SelectedRange,
+};
+
+fn pattern(notation: &str) -> TreePattern {
+    TreePattern::parse(notation).unwrap()
+}
+
+/// The starts and ends of the ranges of the top sequence of `tree` that `pattern` matches.
+fn found(tree: &str, pattern_notation: &str) -> Vec<(usize, usize)> {
+    TS::from_notation_without_caret(tree)
+        .unwrap()
+        .find_all(&pattern(pattern_notation))
+        .into_iter()
+        .filter(|found| found.steps.is_empty())
+        .map(|found| (found.range.start, found.range.end))
+        .collect()
+}
+
+/// `tree` with every match of `from` replaced by `to`, and how many there were.
+fn replaced(tree: &str, from: &str, to: &str) -> (String, usize) {
+    let mut tree = TS::from_notation(tree).unwrap();
+    let count = tree.replace_all(&pattern(from), &pattern(to)).unwrap();
+    let notation = tree.notation();
+    assert_eq!(TS::from_notation(&notation), Ok(tree), "{notation}");
+    (notation, count)
+}
+
+#[test]
+fn patterns() {
+    assert_eq!(pattern(r"\frac{?a}{?b}+?a").wildcards(), ['a', 'b']);
+    // a `?` not before a letter is a character.
+    assert_eq!(pattern("?1+?").wildcards(), []);
+    assert_eq!(TreePattern::parse(""), Err(SearchError::Empty));
+    assert_eq!(
+        TreePattern::parse(r"\frac{a}"),
+        Err(SearchError::Notation(NotationError::Groups {
+            index: 0,
+            name: "frac".to_string(),
+            groups: 1
+        }))
+    );
+
+    let mut tree = TS::str("x");
+    assert_eq!(
+        tree.replace_all(&pattern("?a"), &pattern("?b")),
+        Err(SearchError::Unbound('b'))
+    );
+}
+
+#[test]
+fn find_characters() {
+    assert_eq!(found("x+1=x", "x"), [(0, 1), (4, 5)]);
+    assert_eq!(found("xx+x", "xx"), [(0, 2)]);
+    assert_eq!(found("a+b", "c"), []);
+    // the caret doesn't matter.
+    assert_eq!(found(r"x\sub{1}+x\sub{2}", r"x\sub{|1}"), [(0, 2)]);
+}
+
+#[test]
+fn find_wildcards() {
+    // wildcards take as little as they can.
+    assert_eq!(found("1+2+3", "?a+?b"), [(0, 3)]);
+    // but sequences in nodes have to match whole.
+    assert_eq!(found(r"\frac{1+2}{3}y", r"\frac{?a}{?b}"), [(0, 1)]);
+    assert_eq!(found(r"\frac{1}{}", r"\frac{?a}{?b}"), []);
+    // the same wildcard has to match the same thing again.
+    assert_eq!(found(r"x+y+\frac{y}{y}", "?a+?a"), []);
+    assert_eq!(
+        found(r"\frac{x+1}{x+1}\frac{x}{1}", r"\frac{?a}{?a}"),
+        [(0, 1)]
+    );
+    // brackets match whether they're closed or not.
+    assert_eq!(
+        found(r"\paren?{x}\paren{x}", r"\paren{x}"),
+        [(0, 1), (1, 2)]
+    );
+}
+
+#[test]
+fn find_nested() {
+    let tree = TS::from_notation_without_caret(r"y=\frac{1}{x\pow{2}}").unwrap();
+    assert_eq!(
+        tree.find_all(&pattern(r"?a\pow{2}")),
+        [SelectedRange {
+            steps: vec![PathStep {
+                child: 2,
+                slot: CC::Fraction(FI::Bottom)
+            }],
+            range: 0..2
+        }]
+    );
+}
+
+#[test]
+fn find_backtracks_across_sequences() {
+    // `?b` first takes `yz` from the top, which the bottom doesn't match.
+    assert_eq!(found(r"\frac{xyz}{z}", r"\frac{?a?b}{?b}"), [(0, 1)]);
+    assert_eq!(
+        replaced(r"|\frac{xyz}{z}", r"\frac{?a?b}{?b}", "?a"),
+        ("|xy".to_string(), 1)
+    );
+    assert_eq!(found(r"\frac{xyz}{w}", r"\frac{?a?b}{?b}"), []);
+}
+
+#[test]
+fn find_many_wildcards() {
+    // every way to split the sequence between the wildcards fails the same way, so this has to
+    // finish without trying them all.
+    let tree = "x+".repeat(30);
+    assert_eq!(found(&tree, r"?a?b?c?d?e?f\sqrt{1}"), []);
+    let tree = format!(r"{tree}\sqrt{{1}}");
+    assert_eq!(found(&tree, r"?a?b?c?d?e?f\sqrt{1}"), [(0, 61)]);
+}
+
+#[test]
+fn replace() {
+    assert_eq!(
+        replaced(r"x\sub{1}+2x\sub{1}|", r"x\sub{1}", "p.x"),
+        ("p.x+2p.x|".to_string(), 2)
+    );
+    assert_eq!(
+        replaced(r"|\sqrt{x+1}", r"\sqrt{?a}", r"\paren{?a}\pow{0.5}"),
+        (r"|\paren{x+1}\pow{0.5}".to_string(), 1)
+    );
+    assert_eq!(replaced("|a+b", "c", "d"), ("|a+b".to_string(), 0));
+    // the template can use a wildcard more than once, or not at all.
+    assert_eq!(replaced("|x+y", "?a+?b", "?a?a"), ("|xx".to_string(), 1));
+}
+
+#[test]
+fn replace_inner_first() {
+    assert_eq!(
+        replaced(
+            r"|\frac{\frac{a}{b}}{c}",
+            r"\frac{?a}{?b}",
+            r"\paren{?a}÷\paren{?b}"
+        ),
+        (r"|\paren{\paren{a}÷\paren{b}}÷\paren{c}".to_string(), 2)
+    );
+}
+
+#[test]
+fn replace_keeps_caret() {
+    // after the match, the caret moves with what it's on.
+    assert_eq!(replaced("xx+y|", "xx", "z"), ("z+y|".to_string(), 1));
+    assert_eq!(replaced("xx+|y", "xx", "z"), ("z+|y".to_string(), 1));
+    // inside the match, it goes in front of what replaced it.
+    assert_eq!(replaced("x|x+y", "xx", "z"), ("|z+y".to_string(), 1));
+    assert_eq!(
+        replaced(r"\frac{a|}{b}+1", r"\frac{?a}{?b}", r"?a÷?b"),
+        ("|a÷b+1".to_string(), 1)
+    );
+    assert_eq!(
+        replaced(r"\sqrt{x|}", "x", r"\frac{1}{x}"),
+        (r"\sqrt{\frac{1}{x}|}".to_string(), 1)
+    );
+}
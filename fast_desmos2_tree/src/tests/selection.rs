@@ -0,0 +1,174 @@
+use crate::tree::{
+    CombinedCursor::{self, Terminal as TM},
+    CursorPath, EditorTree as T, EditorTreeSeq as TS, FractionIndex, Motion, PathStep,
+    SurroundIndex, TreeAction, TreeMovable as _,
+};
+
+fn moved(mut tree: TS, motions: &[Motion]) -> TS {
+    for &motion in motions {
+        tree.apply_move(motion);
+    }
+    tree
+}
+
+fn a_plus_b() -> TS {
+    TS::new(
+        3,
+        vec![T::terminal('a'), T::terminal('+'), T::terminal('b')],
+    )
+}
+
+#[test]
+fn cursor_path_round_trip() {
+    let tree = TS::new(
+        1,
+        vec![
+            T::terminal('x'),
+            T::fraction(
+                FractionIndex::Bottom,
+                TS::str("a"),
+                TS::new(1, vec![T::terminal('b')]),
+            ),
+        ],
+    );
+    let path = tree.cursor_path();
+    assert_eq!(
+        path,
+        CursorPath {
+            steps: vec![PathStep {
+                child: 1,
+                slot: CombinedCursor::BOTTOM,
+            }],
+            index: 1,
+        }
+    );
+
+    let mut other = moved(tree.clone(), &[Motion::First]);
+    assert_ne!(other, tree);
+    assert!(other.place_cursor(&path));
+    assert_eq!(other.cursor_path(), path);
+
+    let bad = CursorPath {
+        steps: vec![PathStep {
+            child: 0,
+            slot: CombinedCursor::TOP,
+        }],
+        index: 0,
+    };
+    assert!(!other.place_cursor(&bad));
+    assert_eq!(other.cursor_path(), path);
+}
+
+#[test]
+fn copy_and_paste() {
+    let tree = a_plus_b();
+    let anchor = tree.cursor_path();
+    let tree = moved(tree, &[Motion::Left, Motion::Left]);
+
+    let clipboard = tree.copy_selection(&anchor);
+    assert_eq!(clipboard, TS::str("+b"));
+
+    let mut tree = moved(tree, &[Motion::Last]);
+    tree.paste(&clipboard);
+    assert_eq!(tree, TS::new(5, TS::str("a+b+b").children().to_vec()));
+}
+
+#[test]
+fn cut_leaves_caret() {
+    let mut tree = a_plus_b();
+    tree.apply_move(Motion::Left);
+    let anchor = tree.cursor_path();
+    tree.apply_move(Motion::Left);
+
+    assert_eq!(tree.cut_selection(&anchor), TS::str("+"));
+    assert_eq!(tree, TS::new(1, vec![T::terminal('a'), T::terminal('b')]));
+    assert_cursors!(tree, 1, TM);
+}
+
+#[test]
+fn selection_grows_across_levels() {
+    let tree = TS::new(
+        2,
+        vec![
+            T::terminal('x'),
+            T::terminal('+'),
+            T::fraction(
+                FractionIndex::Top,
+                TS::new(1, TS::str("ab").children().to_vec()),
+                TS::str("c"),
+            ),
+        ],
+    );
+    let anchor = CursorPath {
+        steps: Vec::new(),
+        index: 1,
+    };
+
+    let selected = tree.selected_range(&anchor);
+    assert!(selected.steps.is_empty());
+    assert_eq!(selected.range, 1..3);
+}
+
+#[test]
+fn wrap_in_fraction() {
+    let mut tree = a_plus_b();
+    let anchor = tree.cursor_path();
+    tree.apply_move(Motion::First);
+
+    assert!(tree.apply_to_selection(&anchor, TreeAction::MakeFraction));
+    assert_eq!(
+        tree,
+        TS::new(
+            0,
+            vec![T::fraction(
+                FractionIndex::Bottom,
+                TS::str("a+b"),
+                TS::empty()
+            )]
+        )
+    );
+    assert_cursors!(tree, 0, CombinedCursor::BOTTOM, 0);
+}
+
+#[test]
+fn wrap_in_paren() {
+    let mut tree = a_plus_b();
+    tree.apply_move(Motion::Left);
+    let anchor = tree.cursor_path();
+    tree.apply_move(Motion::Left);
+
+    assert!(tree.apply_to_selection(&anchor, TreeAction::MakeParen));
+    assert_eq!(
+        tree,
+        TS::new(
+            2,
+            vec![
+                T::terminal('a'),
+                T::complete_paren(SurroundIndex::Left, TS::str("+")),
+                T::terminal('b'),
+            ]
+        )
+    );
+}
+
+#[test]
+fn empty_selection_does_nothing() {
+    let mut tree = a_plus_b();
+    let anchor = tree.cursor_path();
+
+    assert!(!tree.apply_to_selection(&anchor, TreeAction::MakeFraction));
+    assert_eq!(tree, a_plus_b());
+}
+
+#[test]
+fn stale_anchor_is_clamped() {
+    let anchor = a_plus_b().cursor_path();
+    let mut tree = TS::new(1, vec![T::terminal('a')]);
+
+    assert_eq!(tree.copy_selection(&anchor), TS::empty());
+    assert_eq!(tree.cut_selection(&anchor), TS::empty());
+    assert_eq!(tree, TS::new(1, vec![T::terminal('a')]));
+
+    tree.apply_move(Motion::Left);
+    assert_eq!(tree.copy_selection(&anchor), TS::str("a"));
+}
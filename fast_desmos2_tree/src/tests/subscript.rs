@@ -0,0 +1,86 @@
+use crate::tree::{
+    CombinedCursor::{self, Terminal as TM},
+    EditorTree as T, EditorTreeSeq as TS, Motion, TreeAction, TreeMovable as _,
+};
+
+fn typed(string: &str) -> TS {
+    let mut tree = TS::empty();
+    for ch in string.chars() {
+        tree.apply_action(TreeAction::from_char(ch));
+    }
+    tree
+}
+
+#[test]
+fn type_subscript() {
+    let mut tree = typed("x_12");
+    assert_eq!(
+        tree,
+        TS::new(
+            1,
+            vec![
+                T::terminal('x'),
+                T::subscript(TS::new(2, TS::str("12").children().to_vec()))
+            ]
+        )
+    );
+    assert_cursors!(tree, 1, CombinedCursor::Subscript, 2);
+
+    tree.apply_move(Motion::Right);
+    tree.apply_action(TreeAction::Char('='));
+    assert_eq!(tree.len(), 3);
+    assert_cursors!(tree, 3);
+}
+
+#[test]
+fn move_through_subscript() {
+    let mut tree = typed("x_1");
+    tree.apply_move(Motion::Right);
+    assert_cursors!(tree, 2);
+
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 1, CombinedCursor::Subscript, 1);
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 1, CombinedCursor::Subscript, 0, TM);
+    tree.apply_move(Motion::Left);
+    assert_cursors!(tree, 0, TM);
+}
+
+#[test]
+fn delete_into_subscript() {
+    let mut tree = typed("x_1");
+    tree.apply_move(Motion::Right);
+
+    tree.apply_action(TreeAction::Delete);
+    assert_cursors!(tree, 1, CombinedCursor::Subscript, 1);
+    tree.apply_action(TreeAction::Delete);
+    assert_eq!(
+        tree,
+        TS::new(1, vec![T::terminal('x'), T::subscript(TS::empty())])
+    );
+
+    // deleting at the start of the subscript takes it apart
+    tree.apply_action(TreeAction::Delete);
+    assert_eq!(tree, TS::new(1, vec![T::terminal('x')]));
+}
+
+#[test]
+fn unwrap_subscript() {
+    let mut tree = typed("a_bc");
+    tree.apply_move(Motion::First);
+    assert_cursors!(tree, 1, CombinedCursor::Subscript, 0, TM);
+
+    tree.apply_action(TreeAction::Delete);
+    assert_eq!(tree, TS::new(1, TS::str("abc").children().to_vec()));
+    assert_cursors!(tree, 1, TM);
+}
+
+#[test]
+fn delete_empty_at_end() {
+    // the node goes away without leaving anything after the caret to enter.
+    for string in ["x_", "x^"] {
+        let mut tree = typed(string);
+        tree.apply_action(TreeAction::Delete);
+        assert_eq!(tree, TS::new(1, vec![T::terminal('x')]), "{string:?}");
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{
+    tree::{
+        EditorTree as T, EditorTreeSeq as TS, FractionIndex as FI, PiecewiseIndex as PI,
+        PiecewiseRow, RootIndex as RI, SumProdIndex as SPI, SurroundIndex as SI,
+    },
+    worksheet::{
+        Cell, ChannelBinding, ExportWarningKind, SkipReason, SliderBounds, Viewport, Worksheet,
+        WorksheetError,
+    },
+};
+
+const V1: &str = include_str!("golden/worksheet_v1.json");
+const V2: &str = include_str!("golden/worksheet_v2.json");
+const V3: &str = include_str!("golden/worksheet_v3.json");
+const FUTURE: &str = include_str!("golden/worksheet_future.json");
+const DESMOS: &str = include_str!("golden/desmos_state.json");
+const DESMOS_EXPORT: &str = include_str!("golden/desmos_export.json");
+
+fn v1_worksheet() -> Worksheet {
+    let first = TS::first(vec![
+        T::terminal('y'),
+        T::terminal('='),
+        T::fraction(FI::Left, TS::str("1"), TS::str("x")),
+        T::terminal('+'),
+        T::terminal('x'),
+        T::power(TS::str("2")),
+    ]);
+    let second = TS::first(vec![
+        T::terminal('a'),
+        T::terminal('='),
+        T::sum(SPI::Left, TS::str("10"), TS::str("1"), TS::str("n")),
+        T::sqrt(SI::Left, TS::str("n")),
+        T::complete_paren(
+            SI::Left,
+            TS::first(vec![
+                T::terminal('1'),
+                T::terminal(','),
+                T::complete_abs(SI::Left, TS::str("b")),
+            ]),
+        ),
+        T::incomplete_brackets(SI::Left, TS::str("1")),
+    ]);
+
+    Worksheet {
+        viewport: Viewport {
+            xmin: -5.0,
+            xmax: 5.0,
+            ymin: -2.5,
+            ymax: 2.5,
+        },
+        cells: vec![
+            Cell {
+                color: Some("#c74440".to_string()),
+                ..Cell::new("1", first)
+            },
+            Cell {
+                hidden: true,
+                folder: Some("Data".to_string()),
+                ..Cell::new("2", second)
+            },
+            Cell {
+                folder: Some("Data".to_string()),
+                ..Cell::new("3", TS::str("b=0"))
+            },
+        ],
+        bindings: vec![ChannelBinding {
+            channel: "sensor".to_string(),
+            cell: "3".to_string(),
+        }],
+    }
+}
+
+fn v2_worksheet() -> Worksheet {
+    let mut worksheet = v1_worksheet();
+    worksheet.cells[2].content = TS::first(vec![
+        T::terminal('b'),
+        T::subscript(TS::str("0")),
+        T::terminal('='),
+        T::terminal('0'),
+    ]);
+    worksheet
+}
+
+fn v3_worksheet() -> Worksheet {
+    let mut worksheet = v2_worksheet();
+    let piecewise = T::piecewise(
+        PI::Left,
+        vec![
+            PiecewiseRow::new(TS::str("x<0"), TS::str("-x")),
+            PiecewiseRow::new(TS::str("x>1"), TS::empty()),
+        ],
+        TS::one(T::root(RI::Left, TS::str("3"), TS::str("x"))),
+    );
+    worksheet.cells.push(Cell::new(
+        "4",
+        TS::first(vec![T::terminal('c'), T::terminal('='), piecewise]),
+    ));
+    worksheet
+}
+
+#[test]
+fn load_golden() {
+    assert_eq!(Worksheet::from_json(V3).unwrap(), v3_worksheet());
+}
+
+#[test]
+fn load_older_golden() {
+    assert_eq!(Worksheet::from_json(V1).unwrap(), v1_worksheet());
+    assert_eq!(Worksheet::from_json(V2).unwrap(), v2_worksheet());
+}
+
+#[test]
+fn save_golden() {
+    assert_eq!(v3_worksheet().to_json(), V3);
+}
+
+#[test]
+fn min_reader_version_follows_nodes() {
+    assert_eq!(Worksheet::default().min_reader_version(), 1);
+    assert_eq!(v1_worksheet().min_reader_version(), 1);
+    assert_eq!(v2_worksheet().min_reader_version(), 2);
+    assert_eq!(v3_worksheet().min_reader_version(), 3);
+
+    // a version 1 reader can still open what's saved, as long as nothing newer is in it.
+    let json = V1.replace("\"version\": 1", "\"version\": 3");
+    assert_eq!(v1_worksheet().to_json(), json);
+
+    let mut nested = v1_worksheet();
+    nested.cells[0].content = TS::one(T::fraction(
+        FI::Left,
+        TS::one(T::subscript(TS::str("1"))),
+        TS::str("2"),
+    ));
+    assert_eq!(nested.min_reader_version(), 2);
+}
+
+#[test]
+fn save_then_load_file() {
+    let path = std::env::temp_dir().join(format!("worksheet-{}.json", std::process::id()));
+    let worksheet = v3_worksheet();
+    worksheet.save(&path).unwrap();
+    let loaded = Worksheet::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), worksheet);
+}
+
+#[test]
+fn next_id() {
+    assert_eq!(Worksheet::default().next_id(), "1");
+    let mut worksheet = v3_worksheet();
+    assert_eq!(worksheet.next_id(), "5");
+    worksheet.cells[0].id = "folder-a".to_string();
+    worksheet.cells[1].id = "12".to_string();
+    assert_eq!(worksheet.next_id(), "13");
+}
+
+#[test]
+fn newer_readable_version_ignores_unknown_fields() {
+    let worksheet = Worksheet::from_json(FUTURE).unwrap();
+    assert_eq!(worksheet.viewport, Viewport::default());
+    assert_eq!(worksheet.cells, [Cell::new("1", TS::str("x=1"))]);
+    assert!(worksheet.bindings.is_empty());
+}
+
+#[test]
+fn newer_unreadable_version() {
+    let json = FUTURE.replace("\"min_reader_version\": 1", "\"min_reader_version\": 4");
+    assert!(matches!(
+        Worksheet::from_json(&json),
+        Err(WorksheetError::TooNew {
+            min_reader_version: 4
+        })
+    ));
+}
+
+#[test]
+fn bad_files() {
+    assert!(matches!(
+        Worksheet::from_json("{\"cells\": []}"),
+        Err(WorksheetError::MissingVersion)
+    ));
+    assert!(matches!(
+        Worksheet::from_json("{\"version\": 1"),
+        Err(WorksheetError::Json(_))
+    ));
+    let unknown_node = r#"{"version": 1, "cells": [{"id": "1", "content": [{"integral": []}]}]}"#;
+    assert!(matches!(
+        Worksheet::from_json(unknown_node),
+        Err(WorksheetError::Json(_))
+    ));
+}
+
+#[test]
+fn import_desmos() {
+    let import = Worksheet::from_desmos_json(DESMOS).unwrap();
+    let worksheet = import.worksheet;
+    assert_eq!(
+        worksheet.viewport,
+        Viewport {
+            xmin: -5.0,
+            xmax: 5.0,
+            ymin: -3.0,
+            ymax: 3.0,
+        }
+    );
+    assert!(worksheet.bindings.is_empty());
+
+    let sine = TS::first(vec![
+        T::terminal('y'),
+        T::terminal('='),
+        T::terminal('s'),
+        T::terminal('i'),
+        T::terminal('n'),
+        T::complete_paren(SI::Left, TS::str("ax")),
+    ]);
+    let expected = [
+        Cell {
+            color: Some("#c74440".to_string()),
+            ..Cell::new("1", sine)
+        },
+        Cell {
+            hidden: true,
+            color: Some("#2d70b3".to_string()),
+            folder: Some("Parameters".to_string()),
+            slider: Some(SliderBounds {
+                min: "0".to_string(),
+                max: "5".to_string(),
+                step: "0.5".to_string(),
+            }),
+            ..Cell::new("3", TS::str("a=3"))
+        },
+        Cell {
+            color: Some("#6042a6".to_string()),
+            ..Cell::new("6", TS::empty())
+        },
+    ];
+    assert_eq!(worksheet.cells, expected);
+
+    let skipped: Vec<_> = import
+        .skipped
+        .iter()
+        .map(|item| (item.id.as_str(), item.latex.as_deref()))
+        .collect();
+    assert_eq!(skipped, [("4", Some("\\alpha=2")), ("5", None)]);
+    assert!(matches!(import.skipped[0].reason, SkipReason::Latex(_)));
+    assert!(matches!(&import.skipped[1].reason, SkipReason::Kind(kind) if kind == "text"));
+}
+
+#[test]
+fn import_bad_desmos() {
+    assert!(matches!(
+        Worksheet::from_desmos_json("{\"expressions\": {\"list\": 3}}"),
+        Err(WorksheetError::Json(_))
+    ));
+    let empty = Worksheet::from_desmos_json("{}").unwrap();
+    assert_eq!(empty.worksheet, Worksheet::default());
+    assert!(empty.skipped.is_empty());
+}
+
+fn export_worksheet() -> Worksheet {
+    let fraction = TS::first(vec![
+        T::terminal('y'),
+        T::terminal('='),
+        T::fraction(FI::Left, TS::str("1"), TS::str("x")),
+    ]);
+    let nested = TS::first(vec![
+        T::terminal('L'),
+        T::terminal('='),
+        T::complete_brackets(
+            SI::Left,
+            TS::first(vec![
+                T::complete_brackets(SI::Left, TS::str("1")),
+                T::terminal(','),
+                T::terminal('L'),
+                T::complete_brackets(SI::Left, TS::str("2")),
+            ]),
+        ),
+    ]);
+    let parameters = Some("Parameters".to_string());
+
+    Worksheet {
+        viewport: Viewport {
+            xmin: -5.0,
+            xmax: 5.0,
+            ymin: -3.0,
+            ymax: 3.0,
+        },
+        cells: vec![
+            Cell {
+                color: Some("#c74440".to_string()),
+                ..Cell::new("1", fraction)
+            },
+            Cell {
+                hidden: true,
+                folder: parameters.clone(),
+                slider: Some(SliderBounds {
+                    min: "0".to_string(),
+                    max: "5".to_string(),
+                    step: "0.5".to_string(),
+                }),
+                ..Cell::new("2", TS::str("a=3"))
+            },
+            Cell::new("3", nested),
+            Cell {
+                folder: parameters,
+                ..Cell::new("4", TS::str("b=0"))
+            },
+            Cell::new("5", TS::str("s=\"hi\"")),
+        ],
+        bindings: vec![ChannelBinding {
+            channel: "sensor".to_string(),
+            cell: "4".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn export_desmos() {
+    let export = export_worksheet().to_desmos_json();
+    let json: serde_json::Value = serde_json::from_str(&export.json).unwrap();
+    let golden: serde_json::Value = serde_json::from_str(DESMOS_EXPORT).unwrap();
+    assert_eq!(json, golden);
+
+    let warnings: Vec<_> = export
+        .warnings
+        .iter()
+        .map(|warning| (warning.id.as_str(), &warning.kind))
+        .collect();
+    assert!(matches!(
+        warnings[..],
+        [
+            ("3", ExportWarningKind::NestedList),
+            ("4", ExportWarningKind::Channel(ref channel)),
+            ("5", ExportWarningKind::String),
+        ] if channel == "sensor"
+    ));
+}
+
+#[test]
+fn export_then_import_desmos() {
+    let worksheet = export_worksheet();
+    let import = Worksheet::from_desmos_json(&worksheet.to_desmos_json().json).unwrap();
+    assert_eq!(import.worksheet.viewport, worksheet.viewport);
+    for cell in &worksheet.cells[..2] {
+        assert_eq!(import.worksheet.cell(&cell.id), Some(cell));
+    }
+    assert_eq!(
+        import.worksheet.cell("4").map(|cell| &cell.folder),
+        Some(&worksheet.cells[3].folder)
+    );
+}
+
+#[test]
+fn export_then_import_desmos_functions() {
+    let function = TS::first(vec![
+        T::terminal('f'),
+        T::complete_paren(SI::Left, TS::str("x,y")),
+        T::terminal('='),
+        T::terminal('x'),
+        T::power(TS::str("2")),
+        T::terminal('+'),
+        T::terminal('y'),
+    ]);
+    let worksheet = Worksheet {
+        cells: vec![
+            Cell::new("1", function),
+            Cell::new(
+                "2",
+                TS::first(vec![
+                    T::terminal('f'),
+                    T::complete_paren(SI::Left, TS::str("1,2")),
+                ]),
+            ),
+        ],
+        ..Worksheet::default()
+    };
+    let import = Worksheet::from_desmos_json(&worksheet.to_desmos_json().json).unwrap();
+    assert!(import.skipped.is_empty(), "{:?}", import.skipped);
+    assert_eq!(import.worksheet.cells, worksheet.cells);
+}
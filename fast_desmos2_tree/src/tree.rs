@@ -1,10 +1,25 @@
 pub use actions::{ActionOutcome, TreeAction};
+pub use command::{
+    parse_keys, KeysParse, NormalCommand, NormalKind, NormalOutcome, NormalState, ObjectKind,
+    Operator, Target, TextObject,
+};
+pub use completion::{Completion, CompletionKind, CompletionPrefix};
+pub use history::EditorHistory;
 pub use movement::{Direction, Motion, TreeMovable};
-use std::fmt::Debug;
+pub use notation::{NotationError, NotationResult};
+pub use selection::{CursorPath, PathStep, SelectedRange};
 
 mod actions;
+mod command;
+mod completion;
 pub mod debug;
+mod history;
+pub mod latex;
 mod movement;
+mod notation;
+pub mod pretty;
+pub mod search;
+mod selection;
 
 #[derive(Clone, PartialEq)]
 pub struct EditorTreeSeq {
@@ -12,35 +27,7 @@ pub struct EditorTreeSeq {
     children: Vec<EditorTree>,
 }
 
-impl Debug for EditorTreeSeq {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let all_str = self
-            .children()
-            .iter()
-            .all(|child| matches!(child.kind(), EditorTreeKind::Terminal(_)));
-        if all_str {
-            let mut string = String::with_capacity(self.children().len());
-
-            for child in self.children().iter() {
-                let EditorTreeKind::Terminal(term) = child.kind() else {
-                    unreachable!()
-                };
-                string.push(term.ch);
-            }
-
-            f.debug_struct("EditorTreeSeq")
-                .field("string", &string)
-                .finish()
-        } else {
-            f.debug_struct("EditorTreeSeq")
-                .field("cursor", &self.cursor)
-                .field("children", &self.children)
-                .finish()
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct EditorTree {
     kind: EditorTreeKind,
 }
@@ -119,6 +106,15 @@ impl EditorTreeSeq {
     pub fn move_left(&mut self, by: usize) {
         self.move_to(self.cursor - by, Direction::Right);
     }
+
+    /// The number of nodes in the whole tree, counting sequences as well.
+    pub fn node_count(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(|child| 1 + child.child_seqs().map(Self::node_count).sum::<usize>())
+            .sum::<usize>()
+    }
 }
 
 impl EditorTree {
@@ -134,6 +130,22 @@ impl EditorTree {
         Self::new(EditorTreeKind::Sqrt(EditorTreeSqrt { cursor, child }))
     }
 
+    pub fn root(cursor: RootIndex, index: EditorTreeSeq, child: EditorTreeSeq) -> Self {
+        Self::new(EditorTreeKind::Root(EditorTreeRoot::new(
+            cursor, index, child,
+        )))
+    }
+
+    pub fn piecewise(
+        cursor: PiecewiseIndex,
+        rows: Vec<PiecewiseRow>,
+        default: EditorTreeSeq,
+    ) -> Self {
+        Self::new(EditorTreeKind::Piecewise(EditorTreePiecewise::new(
+            cursor, rows, default,
+        )))
+    }
+
     pub fn complete_paren(cursor: SurroundIndex, child: EditorTreeSeq) -> Self {
         Self::new(EditorTreeKind::Paren(EditorTreeParen::complete(
             cursor, child,
@@ -214,6 +226,12 @@ impl EditorTree {
         Self::new(EditorTreeKind::Power(EditorTreePower::new(power)))
     }
 
+    pub fn subscript(subscript: EditorTreeSeq) -> Self {
+        Self::new(EditorTreeKind::Subscript(EditorTreeSubscript::new(
+            subscript,
+        )))
+    }
+
     pub fn fraction(cursor: FractionIndex, top: EditorTreeSeq, bottom: EditorTreeSeq) -> Self {
         Self::new(EditorTreeKind::Fraction(EditorTreeFraction::new(
             cursor, top, bottom,
@@ -231,9 +249,12 @@ impl EditorTree {
     pub fn cursor(&self) -> CombinedCursor {
         match &self.kind {
             EditorTreeKind::Power(_) => CombinedCursor::Power,
+            EditorTreeKind::Subscript(_) => CombinedCursor::Subscript,
             EditorTreeKind::Fraction(fraction) => CombinedCursor::Fraction(fraction.cursor()),
             EditorTreeKind::Terminal(_) => CombinedCursor::Terminal,
             EditorTreeKind::Sqrt(sqrt) => CombinedCursor::Sqrt(sqrt.cursor()),
+            EditorTreeKind::Root(root) => CombinedCursor::Root(root.cursor()),
+            EditorTreeKind::Piecewise(piecewise) => CombinedCursor::Piecewise(piecewise.cursor()),
             EditorTreeKind::SumProd(sum_prod) => CombinedCursor::SumProd(sum_prod.cursor()),
             EditorTreeKind::Paren(paren) => CombinedCursor::Paren(paren.cursor()),
             EditorTreeKind::Abs(abs) => CombinedCursor::Abs(abs.cursor()),
@@ -247,7 +268,10 @@ impl EditorTree {
             EditorTreeKind::Terminal(_) => None,
             EditorTreeKind::Fraction(fraction) => fraction.active_child(),
             EditorTreeKind::Power(power) => Some(power.power()),
+            EditorTreeKind::Subscript(subscript) => Some(subscript.subscript()),
             EditorTreeKind::Sqrt(sqrt) => sqrt.active_child(),
+            EditorTreeKind::Root(root) => root.active_child(),
+            EditorTreeKind::Piecewise(piecewise) => piecewise.active_child(),
             EditorTreeKind::SumProd(sum_prod) => sum_prod.active_child(),
             EditorTreeKind::Paren(paren) => paren.active_child(),
             EditorTreeKind::Abs(abs) => abs.active_child(),
@@ -256,6 +280,58 @@ impl EditorTree {
         }
     }
 
+    /// All sequences directly inside this node, whether the cursor is in them or not.
+    pub fn child_seqs(&self) -> impl Iterator<Item = &EditorTreeSeq> {
+        let seqs: Vec<&EditorTreeSeq> = match &self.kind {
+            EditorTreeKind::Terminal(_) => Vec::new(),
+            EditorTreeKind::Fraction(fraction) => vec![&fraction.top, &fraction.bottom],
+            EditorTreeKind::Power(power) => vec![&power.power],
+            EditorTreeKind::Subscript(subscript) => vec![&subscript.subscript],
+            EditorTreeKind::Sqrt(sqrt) => vec![&sqrt.child],
+            EditorTreeKind::Root(root) => vec![&root.index, &root.child],
+            EditorTreeKind::Paren(paren) => vec![&paren.child],
+            EditorTreeKind::Abs(abs) => vec![&abs.child],
+            EditorTreeKind::Bracket(bracket) => vec![&bracket.child],
+            EditorTreeKind::Curly(curly) => vec![&curly.child],
+            EditorTreeKind::Piecewise(piecewise) => piecewise
+                .rows
+                .iter()
+                .flat_map(|row| [&row.condition, &row.value])
+                .chain([&piecewise.default])
+                .collect(),
+            EditorTreeKind::SumProd(sum_prod) => {
+                vec![&sum_prod.top, &sum_prod.ident, &sum_prod.bottom]
+            }
+        };
+        seqs.into_iter()
+    }
+
+    /// [`EditorTree::child_seqs`], but mutable.
+    pub fn child_seqs_mut(&mut self) -> impl Iterator<Item = &mut EditorTreeSeq> {
+        let seqs: Vec<&mut EditorTreeSeq> = match &mut self.kind {
+            EditorTreeKind::Terminal(_) => Vec::new(),
+            EditorTreeKind::Fraction(fraction) => vec![&mut fraction.top, &mut fraction.bottom],
+            EditorTreeKind::Power(power) => vec![&mut power.power],
+            EditorTreeKind::Subscript(subscript) => vec![&mut subscript.subscript],
+            EditorTreeKind::Sqrt(sqrt) => vec![&mut sqrt.child],
+            EditorTreeKind::Root(root) => vec![&mut root.index, &mut root.child],
+            EditorTreeKind::Paren(paren) => vec![&mut paren.child],
+            EditorTreeKind::Abs(abs) => vec![&mut abs.child],
+            EditorTreeKind::Bracket(bracket) => vec![&mut bracket.child],
+            EditorTreeKind::Curly(curly) => vec![&mut curly.child],
+            EditorTreeKind::Piecewise(piecewise) => piecewise
+                .rows
+                .iter_mut()
+                .flat_map(|row| [&mut row.condition, &mut row.value])
+                .chain([&mut piecewise.default])
+                .collect(),
+            EditorTreeKind::SumProd(sum_prod) => {
+                vec![&mut sum_prod.top, &mut sum_prod.ident, &mut sum_prod.bottom]
+            }
+        };
+        seqs.into_iter()
+    }
+
     pub fn is_terminal_and_eq(&self, other: char) -> bool {
         self.is_terminal_and(|x| x.ch == other)
     }
@@ -290,12 +366,15 @@ pub enum EditorTreeKind {
     Terminal(EditorTreeTerminal),
     Fraction(EditorTreeFraction),
     Power(EditorTreePower),
+    Subscript(EditorTreeSubscript),
     Sqrt(EditorTreeSqrt),
+    Root(EditorTreeRoot),
     Paren(EditorTreeParen),
     SumProd(EditorTreeSumProd),
     Abs(EditorTreeAbs),
     Bracket(EditorTreeBracket),
     Curly(EditorTreeCurly),
+    Piecewise(EditorTreePiecewise),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -303,12 +382,15 @@ pub enum CombinedCursor {
     Terminal,
     Fraction(FractionIndex),
     Power,
+    Subscript,
     Sqrt(SurroundIndex),
     Paren(SurroundIndex),
     SumProd(SumProdIndex),
     Abs(SurroundIndex),
     Bracket(SurroundIndex),
     Curly(SurroundIndex),
+    Root(RootIndex),
+    Piecewise(PiecewiseIndex),
 }
 
 impl CombinedCursor {
@@ -550,6 +632,23 @@ impl EditorTreePower {
     }
 }
 
+/// A subscript of the identifier before it, like the `1` of `x_1`. It has no cursor of its own,
+/// in the same way as [`EditorTreePower`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditorTreeSubscript {
+    subscript: EditorTreeSeq,
+}
+
+impl EditorTreeSubscript {
+    pub const fn new(subscript: EditorTreeSeq) -> Self {
+        Self { subscript }
+    }
+
+    pub const fn subscript(&self) -> &EditorTreeSeq {
+        &self.subscript
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SumProdIndex {
     BottomExpr,
@@ -638,3 +737,189 @@ impl EditorTreeSumProd {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootIndex {
+    Left,
+    Index,
+    Inside,
+}
+
+/// A root with an index, like `\sqrt[3]{x}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditorTreeRoot {
+    cursor: RootIndex,
+    index: EditorTreeSeq,
+    child: EditorTreeSeq,
+}
+
+impl EditorTreeRoot {
+    pub const fn new(cursor: RootIndex, index: EditorTreeSeq, child: EditorTreeSeq) -> Self {
+        Self {
+            cursor,
+            index,
+            child,
+        }
+    }
+
+    pub const fn cursor(&self) -> RootIndex {
+        self.cursor
+    }
+
+    pub const fn index(&self) -> &EditorTreeSeq {
+        &self.index
+    }
+
+    pub const fn child(&self) -> &EditorTreeSeq {
+        &self.child
+    }
+
+    pub const fn active_child(&self) -> Option<&EditorTreeSeq> {
+        match self.cursor {
+            RootIndex::Left => None,
+            RootIndex::Index => Some(&self.index),
+            RootIndex::Inside => Some(&self.child),
+        }
+    }
+
+    pub fn active_child_mut(&mut self) -> Option<&mut EditorTreeSeq> {
+        match self.cursor {
+            RootIndex::Left => None,
+            RootIndex::Index => Some(&mut self.index),
+            RootIndex::Inside => Some(&mut self.child),
+        }
+    }
+
+    fn move_to(&mut self, to: RootIndex, from: Direction) {
+        self.cursor = to;
+        if let Some(child) = self.active_child_mut() {
+            child.enter_from(from);
+        }
+    }
+}
+
+/// Where the cursor is in a piecewise node, with rows counted from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiecewiseIndex {
+    Left,
+    Condition(usize),
+    Value(usize),
+    Default,
+}
+
+/// One case of a piecewise node. An empty `value` means the case is worth 1, like in Desmos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiecewiseRow {
+    condition: EditorTreeSeq,
+    value: EditorTreeSeq,
+}
+
+impl PiecewiseRow {
+    pub const fn new(condition: EditorTreeSeq, value: EditorTreeSeq) -> Self {
+        Self { condition, value }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(EditorTreeSeq::empty(), EditorTreeSeq::empty())
+    }
+
+    pub const fn condition(&self) -> &EditorTreeSeq {
+        &self.condition
+    }
+
+    pub const fn value(&self) -> &EditorTreeSeq {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.condition.is_empty() && self.value.is_empty()
+    }
+}
+
+/// Conditional values, the first row whose condition holds giving the value, and `default` being
+/// used when none do. An empty `default` makes the node undefined then. There's always a row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditorTreePiecewise {
+    cursor: PiecewiseIndex,
+    rows: Vec<PiecewiseRow>,
+    default: EditorTreeSeq,
+}
+
+impl EditorTreePiecewise {
+    pub fn new(cursor: PiecewiseIndex, rows: Vec<PiecewiseRow>, default: EditorTreeSeq) -> Self {
+        assert!(!rows.is_empty());
+        Self {
+            cursor,
+            rows,
+            default,
+        }
+    }
+
+    pub const fn cursor(&self) -> PiecewiseIndex {
+        self.cursor
+    }
+
+    pub fn rows(&self) -> &[PiecewiseRow] {
+        &self.rows
+    }
+
+    pub const fn default(&self) -> &EditorTreeSeq {
+        &self.default
+    }
+
+    pub fn seq(&self, index: PiecewiseIndex) -> Option<&EditorTreeSeq> {
+        match index {
+            PiecewiseIndex::Left => None,
+            PiecewiseIndex::Condition(row) => Some(&self.rows.get(row)?.condition),
+            PiecewiseIndex::Value(row) => Some(&self.rows.get(row)?.value),
+            PiecewiseIndex::Default => Some(&self.default),
+        }
+    }
+
+    fn seq_mut(&mut self, index: PiecewiseIndex) -> Option<&mut EditorTreeSeq> {
+        match index {
+            PiecewiseIndex::Left => None,
+            PiecewiseIndex::Condition(row) => Some(&mut self.rows.get_mut(row)?.condition),
+            PiecewiseIndex::Value(row) => Some(&mut self.rows.get_mut(row)?.value),
+            PiecewiseIndex::Default => Some(&mut self.default),
+        }
+    }
+
+    pub fn active_child(&self) -> Option<&EditorTreeSeq> {
+        self.seq(self.cursor)
+    }
+
+    pub fn active_child_mut(&mut self) -> Option<&mut EditorTreeSeq> {
+        self.seq_mut(self.cursor)
+    }
+
+    fn move_to(&mut self, to: PiecewiseIndex, from: Direction) {
+        self.cursor = to;
+        if let Some(child) = self.active_child_mut() {
+            child.enter_from(from);
+        }
+    }
+
+    /// The slots in reading order: each condition followed by its value, then the default.
+    fn next(&self, index: PiecewiseIndex) -> Option<PiecewiseIndex> {
+        match index {
+            PiecewiseIndex::Left => Some(PiecewiseIndex::Condition(0)),
+            PiecewiseIndex::Condition(row) => Some(PiecewiseIndex::Value(row)),
+            PiecewiseIndex::Value(row) if row + 1 < self.rows.len() => {
+                Some(PiecewiseIndex::Condition(row + 1))
+            }
+            PiecewiseIndex::Value(_) => Some(PiecewiseIndex::Default),
+            PiecewiseIndex::Default => None,
+        }
+    }
+
+    fn previous(&self, index: PiecewiseIndex) -> Option<PiecewiseIndex> {
+        match index {
+            PiecewiseIndex::Left => None,
+            PiecewiseIndex::Condition(0) => Some(PiecewiseIndex::Left),
+            PiecewiseIndex::Condition(row) => Some(PiecewiseIndex::Value(row - 1)),
+            PiecewiseIndex::Value(row) => Some(PiecewiseIndex::Condition(row)),
+            PiecewiseIndex::Default => Some(PiecewiseIndex::Value(self.rows.len() - 1)),
+        }
+    }
+}
@@ -3,7 +3,8 @@ use std::cmp::Ordering;
 use crate::tree::{CompletableSurrounds, EditorTreeFraction, EditorTreeKind, FractionIndex};
 
 use super::{
-    movement::Direction, EditorTree, EditorTreeSeq, SumProdIndex, SurroundIndex, TreeMovable,
+    movement::Direction, EditorTree, EditorTreeSeq, PiecewiseIndex, PiecewiseRow, RootIndex,
+    SumProdIndex, SurroundIndex, TreeMovable,
 };
 
 mod search_back;
@@ -13,8 +14,10 @@ pub enum TreeAction {
     Char(char),
     MakeFraction,
     MakePower,
+    MakeSubscript,
     MakeParen,
     MakeAbs,
+    MakePiecewise,
     Delete,
 }
 
@@ -30,6 +33,8 @@ pub enum NotLeftAction {
     MakeAbs,
     MakeFraction,
     MakePower,
+    MakeSubscript,
+    MakePiecewise,
 }
 
 impl From<LeftAction> for TreeAction {
@@ -47,21 +52,27 @@ impl TryFrom<TreeAction> for LeftAction {
         match value {
             TreeAction::MakeFraction => Err(Self::Error::MakeFraction),
             TreeAction::MakePower => Err(Self::Error::MakePower),
+            TreeAction::MakeSubscript => Err(Self::Error::MakeSubscript),
             TreeAction::Delete => Ok(Self::Delete),
             TreeAction::MakeParen => Err(Self::Error::MakeParen),
             TreeAction::MakeAbs => Err(Self::Error::MakeAbs),
+            TreeAction::MakePiecewise => Err(Self::Error::MakePiecewise),
             TreeAction::Char(ch) => Err(Self::Error::Char(ch)),
         }
     }
 }
 
 impl TreeAction {
+    /// What typing `char` does by default, which the default
+    /// [`Keymap`](crate::keymap::Keymap) binds in insert mode.
     pub const fn from_char(char: char) -> Self {
         match char {
             '/' => Self::MakeFraction,
             '^' => Self::MakePower,
+            '_' => Self::MakeSubscript,
             '(' => Self::MakeParen,
             '|' => Self::MakeAbs,
+            '{' => Self::MakePiecewise,
             '*' => Self::Char('×'),
             otherwise => Self::Char(otherwise),
         }
@@ -117,6 +128,15 @@ impl HereOrRight {
     }
 }
 
+/// A piecewise node with the cursor in its only, empty, condition.
+fn new_piecewise() -> EditorTree {
+    EditorTree::piecewise(
+        PiecewiseIndex::Condition(0),
+        vec![PiecewiseRow::empty()],
+        EditorTreeSeq::empty(),
+    )
+}
+
 impl EditorTreeSeq {
     pub fn apply_action(&mut self, action: TreeAction) -> Option<SeqActionOutcome> {
         if self.cursor < self.children.len() {
@@ -134,6 +154,12 @@ impl EditorTreeSeq {
                             NotLeftAction::Char(ch) => {
                                 self.children.push(EditorTree::terminal(ch));
                                 self.cursor += 1;
+                                if let Some((at, enter)) = self.expand_command(self.cursor - 1) {
+                                    match enter {
+                                        true => self.cursor = at,
+                                        false => self.move_to(at + 1, Direction::Left),
+                                    }
+                                }
                             }
                             NotLeftAction::MakeParen => {
                                 self.children.push(EditorTree::incomplete_paren(
@@ -164,6 +190,11 @@ impl EditorTreeSeq {
                                 self.children
                                     .push(EditorTree::power(EditorTreeSeq::empty()));
                             }
+                            NotLeftAction::MakeSubscript => {
+                                self.children
+                                    .push(EditorTree::subscript(EditorTreeSeq::empty()));
+                            }
+                            NotLeftAction::MakePiecewise => self.children.push(new_piecewise()),
                         }
                         None
                     }
@@ -190,6 +221,10 @@ impl EditorTreeSeq {
                     Err(NotLeftAction::MakePower) => self
                         .children
                         .push(EditorTree::power(EditorTreeSeq::empty())),
+                    Err(NotLeftAction::MakeSubscript) => self
+                        .children
+                        .push(EditorTree::subscript(EditorTreeSeq::empty())),
+                    Err(NotLeftAction::MakePiecewise) => self.children.push(new_piecewise()),
                 }
                 None
             }
@@ -247,8 +282,8 @@ impl EditorTreeSeq {
                 let len = children.len();
                 self.children.splice(index..=index, children);
                 match index.cmp(&self.cursor) {
-                    Ordering::Equal => self.children[index].enter_from(Direction::Left),
-                    Ordering::Less => self.cursor += len - 1,
+                    Ordering::Equal => self.move_to(index, Direction::Left),
+                    Ordering::Less => self.cursor = self.cursor + len - 1,
                     Ordering::Greater => {}
                 }
             }
@@ -266,72 +301,18 @@ impl EditorTreeSeq {
                     let new_node = EditorTree::terminal(ch);
                     self.children.insert(index, new_node);
 
-                    fn at_index(children: &[EditorTree], index: usize, string: &str) -> bool {
-                        string.chars().rev().enumerate().all(|(offset, ch)| {
-                            children
-                                .get(index - offset)
-                                .is_some_and(|tree| tree.is_terminal_and_eq(ch))
-                        })
-                    }
-
-                    if at_index(self.children(), index, "sqrt") {
-                        const OFFSET: usize = "sum".len() - 1;
-                        let min_index = index - OFFSET;
-                        self.children.splice(
-                            min_index..=index,
-                            std::iter::once(EditorTree::sqrt(
-                                SurroundIndex::Inside,
-                                EditorTreeSeq::empty(),
-                            )),
-                        );
-
-                        match index.cmp(&self.cursor) {
-                            Ordering::Equal => self.cursor = min_index,
-                            Ordering::Less => self.cursor -= OFFSET,
-                            Ordering::Greater => {}
-                        }
-                    } else if at_index(self.children(), index, "sum") {
-                        const OFFSET: usize = "sum".len() - 1;
-                        let min_index = index - OFFSET;
-                        self.children.splice(
-                            min_index..=index,
-                            std::iter::once(EditorTree::sum(
-                                SumProdIndex::Top,
-                                EditorTreeSeq::str("10"),
-                                EditorTreeSeq::str("1"),
-                                EditorTreeSeq::str("n"),
-                            )),
-                        );
-
-                        match index.cmp(&self.cursor) {
-                            Ordering::Equal => self.move_to(min_index + 1, Direction::Left),
-                            Ordering::Less => self.cursor -= OFFSET,
+                    match self.expand_command(index) {
+                        Some((at, enter)) => match index.cmp(&self.cursor) {
+                            Ordering::Equal if enter => self.cursor = at,
+                            Ordering::Equal => self.move_to(at + 1, Direction::Left),
+                            Ordering::Less => self.cursor -= index - at,
                             Ordering::Greater => {}
-                        }
-                    } else if at_index(self.children(), index, "prod") {
-                        const OFFSET: usize = "prod".len() - 1;
-                        let min_index = index - OFFSET;
-                        self.children.splice(
-                            min_index..=index,
-                            std::iter::once(EditorTree::prod(
-                                SumProdIndex::Top,
-                                EditorTreeSeq::str("10"),
-                                EditorTreeSeq::str("1"),
-                                EditorTreeSeq::str("n"),
-                            )),
-                        );
-
-                        match index.cmp(&self.cursor) {
-                            Ordering::Equal => self.move_to(min_index + 1, Direction::Left),
-                            Ordering::Less => self.cursor -= OFFSET,
-                            Ordering::Greater => {}
-                        }
-                    } else {
-                        match index.cmp(&self.cursor) {
+                        },
+                        None => match index.cmp(&self.cursor) {
                             Ordering::Equal => self.move_right(1),
                             Ordering::Less => self.cursor += 1,
                             Ordering::Greater => {}
-                        }
+                        },
                     }
                 }
                 TreeAction::MakeFraction => {
@@ -352,6 +333,12 @@ impl EditorTreeSeq {
                     new_node.enter_from(Direction::Left);
                     self.children.insert(index, new_node);
                 }
+                TreeAction::MakeSubscript => {
+                    let mut new_node = EditorTree::subscript(EditorTreeSeq::empty());
+                    new_node.enter_from(Direction::Left);
+                    self.children.insert(index, new_node);
+                }
+                TreeAction::MakePiecewise => self.children.insert(index, new_piecewise()),
                 TreeAction::MakeParen => {
                     let useful = self.children.drain(index..).collect::<Vec<_>>();
                     let new_child = EditorTree::incomplete_paren(
@@ -376,6 +363,56 @@ impl EditorTreeSeq {
         None
     }
 
+    /// Replaces a command name like `sqrt` whose last letter is at `index` with the node it
+    /// stands for. Returns the index of the node and whether the caret should go into it, rather
+    /// than after it.
+    fn expand_command(&mut self, index: usize) -> Option<(usize, bool)> {
+        let at_index = |string: &str| {
+            string.chars().rev().enumerate().all(|(offset, ch)| {
+                index.checked_sub(offset).is_some_and(|at| {
+                    self.children
+                        .get(at)
+                        .is_some_and(|tree| tree.is_terminal_and_eq(ch))
+                })
+            })
+        };
+
+        let (name, node, enter) = if at_index("sqrt") {
+            let node = EditorTree::sqrt(SurroundIndex::Inside, EditorTreeSeq::empty());
+            ("sqrt", node, true)
+        } else if at_index("nthroot") {
+            let node = EditorTree::root(
+                RootIndex::Index,
+                EditorTreeSeq::empty(),
+                EditorTreeSeq::empty(),
+            );
+            ("nthroot", node, true)
+        } else if at_index("sum") {
+            let node = EditorTree::sum(
+                SumProdIndex::Top,
+                EditorTreeSeq::str("10"),
+                EditorTreeSeq::str("1"),
+                EditorTreeSeq::str("n"),
+            );
+            ("sum", node, false)
+        } else if at_index("prod") {
+            let node = EditorTree::prod(
+                SumProdIndex::Top,
+                EditorTreeSeq::str("10"),
+                EditorTreeSeq::str("1"),
+                EditorTreeSeq::str("n"),
+            );
+            ("prod", node, false)
+        } else {
+            return None;
+        };
+
+        let min_index = index + 1 - name.len();
+        self.children
+            .splice(min_index..=index, std::iter::once(node));
+        Some((min_index, enter))
+    }
+
     pub fn apply_action_from_right(&mut self, action: LeftAction) -> Option<SeqActionOutcome> {
         if let Some(last_index) = self.children.len().checked_sub(1) {
             self.apply_action_internal(last_index, HereOrRight::Right(action))
@@ -430,9 +467,11 @@ impl EditorTree {
                 TreeAction::Delete => Some(ActionOutcome::LeftOverflow(LeftAction::Delete)),
                 TreeAction::Char(_)
                 | TreeAction::MakePower
+                | TreeAction::MakeSubscript
                 | TreeAction::MakeFraction
                 | TreeAction::MakeAbs
-                | TreeAction::MakeParen => Some(ActionOutcome::Delegated),
+                | TreeAction::MakeParen
+                | TreeAction::MakePiecewise => Some(ActionOutcome::Delegated),
             },
             EditorTreeKind::Fraction(fraction) => match fraction.cursor() {
                 FractionIndex::Top => {
@@ -483,8 +522,10 @@ impl EditorTree {
                         NotLeftAction::Char(_)
                         | NotLeftAction::MakeParen
                         | NotLeftAction::MakePower
+                        | NotLeftAction::MakeSubscript
                         | NotLeftAction::MakeAbs
-                        | NotLeftAction::MakeFraction => Some(ActionOutcome::Delegated),
+                        | NotLeftAction::MakeFraction
+                        | NotLeftAction::MakePiecewise => Some(ActionOutcome::Delegated),
                     },
                 },
             },
@@ -501,6 +542,19 @@ impl EditorTree {
                     }
                 }
             }
+            EditorTreeKind::Subscript(subscript) => {
+                let outcome = subscript.subscript.apply_action(action);
+                match outcome? {
+                    SeqActionOutcome::LeftDelete => {
+                        let old_self = std::mem::replace(self, EditorTree::terminal('X'));
+                        let EditorTreeKind::Subscript(subscript) = old_self.kind else {
+                            unreachable!()
+                        };
+                        let children = subscript.subscript.children;
+                        Some(ActionOutcome::Splice { children })
+                    }
+                }
+            }
             EditorTreeKind::SumProd(sum_prod) => {
                 match sum_prod.cursor {
                     SumProdIndex::BottomExpr => match sum_prod.bottom.apply_action(action)? {
@@ -533,15 +587,104 @@ impl EditorTree {
                     match outcome? {
                         SeqActionOutcome::LeftDelete => {
                             let old_self = std::mem::replace(self, EditorTree::terminal('X'));
-                            let EditorTreeKind::Paren(paren) = old_self.kind else {
+                            let EditorTreeKind::Sqrt(sqrt) = old_self.kind else {
                                 unreachable!()
                             };
-                            let children = paren.child.children;
+                            let children = sqrt.child.children;
                             Some(ActionOutcome::Splice { children })
                         }
                     }
                 }
             },
+            EditorTreeKind::Root(root) => match root.cursor {
+                RootIndex::Left => match LeftAction::try_from(action) {
+                    Ok(left_action) => Some(ActionOutcome::LeftOverflow(left_action)),
+                    Err(_) => Some(ActionOutcome::Delegated),
+                },
+                RootIndex::Index => match root.index.apply_action(action)? {
+                    SeqActionOutcome::LeftDelete => {
+                        let old_self = std::mem::replace(self, EditorTree::terminal('X'));
+                        let EditorTreeKind::Root(root) = old_self.kind else {
+                            unreachable!()
+                        };
+                        Some(ActionOutcome::Splice2 {
+                            first: root.index.children,
+                            second: root.child.children,
+                            put_cursor_first: true,
+                        })
+                    }
+                },
+                RootIndex::Inside => match root.child.apply_action(action)? {
+                    SeqActionOutcome::LeftDelete => {
+                        root.move_to(RootIndex::Index, Direction::Right);
+                        None
+                    }
+                },
+            },
+            EditorTreeKind::Piecewise(piecewise) => {
+                let cursor = piecewise.cursor;
+                let last_row = piecewise.rows.len() - 1;
+                let Some(child) = piecewise.active_child_mut() else {
+                    return match LeftAction::try_from(action) {
+                        Ok(left_action) => Some(ActionOutcome::LeftOverflow(left_action)),
+                        Err(_) => Some(ActionOutcome::Delegated),
+                    };
+                };
+
+                // `:` and `,` at the end of a slot move on like they would in the LaTeX, and the
+                // default turns into a new row when given a value.
+                let at_end = child.cursor() == child.len();
+                match (cursor, action) {
+                    (PiecewiseIndex::Condition(row), TreeAction::Char(':')) if at_end => {
+                        piecewise.move_to(PiecewiseIndex::Value(row), Direction::Left);
+                        return None;
+                    }
+                    (PiecewiseIndex::Value(row), TreeAction::Char(',')) if at_end => {
+                        match row == last_row {
+                            true => piecewise.move_to(PiecewiseIndex::Default, Direction::Right),
+                            false => {
+                                piecewise.rows.insert(row + 1, PiecewiseRow::empty());
+                                piecewise
+                                    .move_to(PiecewiseIndex::Condition(row + 1), Direction::Left);
+                            }
+                        }
+                        return None;
+                    }
+                    (PiecewiseIndex::Default, TreeAction::Char(':')) if at_end => {
+                        let condition = std::mem::replace(child, EditorTreeSeq::empty());
+                        piecewise
+                            .rows
+                            .push(PiecewiseRow::new(condition, EditorTreeSeq::empty()));
+                        piecewise.move_to(PiecewiseIndex::Value(last_row + 1), Direction::Left);
+                        return None;
+                    }
+                    _ => {}
+                }
+
+                match child.apply_action(action)? {
+                    SeqActionOutcome::LeftDelete => match cursor {
+                        PiecewiseIndex::Left => unreachable!(),
+                        PiecewiseIndex::Condition(0) => {
+                            let is_empty = piecewise.rows.iter().all(PiecewiseRow::is_empty)
+                                && piecewise.default.is_empty();
+                            return is_empty.then_some(ActionOutcome::Deleted);
+                        }
+                        PiecewiseIndex::Condition(row) => {
+                            if piecewise.rows[row].is_empty() {
+                                piecewise.rows.remove(row);
+                            }
+                            piecewise.move_to(PiecewiseIndex::Value(row - 1), Direction::Right);
+                        }
+                        PiecewiseIndex::Value(row) => {
+                            piecewise.move_to(PiecewiseIndex::Condition(row), Direction::Right)
+                        }
+                        PiecewiseIndex::Default => {
+                            piecewise.move_to(PiecewiseIndex::Value(last_row), Direction::Right)
+                        }
+                    },
+                }
+                None
+            }
             EditorTreeKind::Paren(paren) => completable_surrounds!(paren, EditorTreeKind::Paren),
             EditorTreeKind::Abs(abs) => completable_surrounds!(abs, EditorTreeKind::Abs),
             EditorTreeKind::Curly(curly) => completable_surrounds!(curly, EditorTreeKind::Curly),
@@ -579,12 +722,30 @@ impl EditorTree {
                     Some(ActionOutcome::CaptureCursor)
                 }
             },
+            EditorTreeKind::Subscript(subscript) => match action {
+                LeftAction::Delete => {
+                    subscript.enter_from(Direction::Right);
+                    Some(ActionOutcome::CaptureCursor)
+                }
+            },
             EditorTreeKind::Sqrt(sqrt) => match action {
                 LeftAction::Delete => {
                     sqrt.enter_from(Direction::Right);
                     Some(ActionOutcome::CaptureCursor)
                 }
             },
+            EditorTreeKind::Root(root) => match action {
+                LeftAction::Delete => {
+                    root.enter_from(Direction::Right);
+                    Some(ActionOutcome::CaptureCursor)
+                }
+            },
+            EditorTreeKind::Piecewise(piecewise) => match action {
+                LeftAction::Delete => {
+                    piecewise.enter_from(Direction::Right);
+                    Some(ActionOutcome::CaptureCursor)
+                }
+            },
             EditorTreeKind::Paren(paren) => completable_surrounds(paren, action),
             EditorTreeKind::Abs(abs) => completable_surrounds(abs, action),
             EditorTreeKind::Bracket(bracket) => completable_surrounds(bracket, action),
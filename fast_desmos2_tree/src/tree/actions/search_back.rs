@@ -123,16 +123,18 @@ impl SearchState<'_> {
                 '0'..='9' => self.advance_number().assert_ok(),
                 ch => Err(SearchError::UnknownChar(ch)),
             },
-            EditorTreeKind::Power(_) => {
+            EditorTreeKind::Power(_) | EditorTreeKind::Subscript(_) => {
                 self.advance();
                 self.advance_item()
             }
             EditorTreeKind::SumProd(_) => Err(SearchError::FoundSumProdFirst),
             EditorTreeKind::Fraction(_)
             | EditorTreeKind::Sqrt(_)
+            | EditorTreeKind::Root(_)
             | EditorTreeKind::Paren(_)
             | EditorTreeKind::Bracket(_)
             | EditorTreeKind::Curly(_)
+            | EditorTreeKind::Piecewise(_)
             | EditorTreeKind::Abs(_) => {
                 self.advance();
                 Ok(())
@@ -164,4 +166,16 @@ impl EditorTreeSeq {
 
         Ok(state.index)
     }
+
+    /// The start of the run of letters ending at `start`.
+    pub fn search_back_ident(&self, start: usize) -> SearchResult<usize> {
+        let mut state = SearchState {
+            seq: self,
+            index: start,
+        };
+
+        state.advance_ident()?;
+
+        Ok(state.index)
+    }
 }
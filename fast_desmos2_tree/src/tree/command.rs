@@ -0,0 +1,413 @@
+//! Normal mode commands like Vim's: counts, operators over motions or text objects, `x`, `p` and
+//! `.`. Keys are parsed into a [`NormalCommand`] first, so any front end can run them.
+
+use super::{
+    CombinedCursor, CursorPath, EditorTreeKind, EditorTreeSeq, Motion, PathStep, SelectedRange,
+    SurroundIndex, TreeAction, TreeMovable,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// `d`
+    Delete,
+    /// `c`, which deletes and then inserts.
+    Change,
+    /// `y`
+    Yank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    /// `f`: inside is the numerator.
+    Fraction,
+    /// `(`, `)` or `b`
+    Paren,
+    /// `|`
+    Abs,
+}
+
+/// A node around the caret, like `i(` for what's inside the parentheses or `a(` for them as a
+/// whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextObject {
+    pub around: bool,
+    pub kind: ObjectKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Motion(Motion),
+    Object(TextObject),
+    /// The operator twice, like `dd`: the whole tree.
+    Line,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalKind {
+    Move(Motion),
+    Operate(Operator, Target),
+    /// `x`
+    DeleteChar,
+    /// `p`
+    Paste,
+    /// `.`, doing the last change again.
+    Repeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalCommand {
+    /// How many times to do it, when it was given.
+    pub count: Option<usize>,
+    pub kind: NormalKind,
+}
+
+impl NormalCommand {
+    /// The most times a command is done, however large its count, so that a mistyped count can't
+    /// hang the editor.
+    pub const MAX_COUNT: usize = 1000;
+
+    pub fn new(kind: NormalKind) -> Self {
+        Self { count: None, kind }
+    }
+
+    pub fn counted(count: usize, kind: NormalKind) -> Self {
+        Self {
+            count: Some(count),
+            kind,
+        }
+    }
+
+    /// How many times to do it, at most [`MAX_COUNT`](Self::MAX_COUNT).
+    pub fn count(&self) -> usize {
+        self.count.unwrap_or(1).min(Self::MAX_COUNT)
+    }
+
+    /// Whether `.` can do it again.
+    fn is_change(&self) -> bool {
+        match self.kind {
+            NormalKind::Operate(operator, _) => operator != Operator::Yank,
+            NormalKind::DeleteChar | NormalKind::Paste => true,
+            NormalKind::Move(_) | NormalKind::Repeat => false,
+        }
+    }
+}
+
+/// How far the keys typed so far got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeysParse {
+    Complete(NormalCommand),
+    /// The keys are the start of a command.
+    Pending,
+    /// No command starts with the keys.
+    Invalid,
+}
+
+fn motion(ch: char) -> Option<Motion> {
+    match ch {
+        'h' => Some(Motion::Left),
+        'j' => Some(Motion::Down),
+        'k' => Some(Motion::Up),
+        'l' => Some(Motion::Right),
+        'w' => Some(Motion::Word),
+        'b' => Some(Motion::Back),
+        '^' | '0' => Some(Motion::First),
+        '$' => Some(Motion::Last),
+        _ => None,
+    }
+}
+
+fn operator(ch: char) -> Option<Operator> {
+    match ch {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        _ => None,
+    }
+}
+
+fn object_kind(ch: char) -> Option<ObjectKind> {
+    match ch {
+        'f' => Some(ObjectKind::Fraction),
+        '(' | ')' | 'b' => Some(ObjectKind::Paren),
+        '|' => Some(ObjectKind::Abs),
+        _ => None,
+    }
+}
+
+/// Takes the digits of a count. `0` can't start one, since it's a motion.
+fn take_count(keys: &mut &[char]) -> Option<usize> {
+    let digits = match keys.first() {
+        Some('1'..='9') => keys.iter().take_while(|ch| ch.is_ascii_digit()).count(),
+        _ => return None,
+    };
+    let count = keys[..digits].iter().fold(0usize, |count, ch| {
+        count
+            .saturating_mul(10)
+            .saturating_add(ch.to_digit(10).unwrap_or(0) as usize)
+    });
+    *keys = &keys[digits..];
+    Some(count)
+}
+
+/// Parses the keys typed in normal mode: `[count] motion`, `[count] operator [count] target`,
+/// `[count] x`, `[count] p` or `[count] .`.
+pub fn parse_keys(keys: &[char]) -> KeysParse {
+    let mut keys = keys;
+    let count = take_count(&mut keys);
+    let Some((&first, rest)) = keys.split_first() else {
+        return KeysParse::Pending;
+    };
+
+    let (kind, rest, inner_count) = if let Some(motion) = motion(first) {
+        (NormalKind::Move(motion), rest, None)
+    } else if let Some(operator) = operator(first) {
+        let mut rest = rest;
+        let inner_count = take_count(&mut rest);
+        let (target, rest) = match rest {
+            [] | ['i' | 'a'] => return KeysParse::Pending,
+            [ch, rest @ ..] if *ch == first => (Target::Line, rest),
+            [around @ ('i' | 'a'), kind, rest @ ..] => {
+                let Some(kind) = object_kind(*kind) else {
+                    return KeysParse::Invalid;
+                };
+                let around = *around == 'a';
+                (Target::Object(TextObject { around, kind }), rest)
+            }
+            [ch, rest @ ..] => match motion(*ch) {
+                Some(motion) => (Target::Motion(motion), rest),
+                None => return KeysParse::Invalid,
+            },
+        };
+        (NormalKind::Operate(operator, target), rest, inner_count)
+    } else {
+        let kind = match first {
+            'x' => NormalKind::DeleteChar,
+            'p' => NormalKind::Paste,
+            '.' => NormalKind::Repeat,
+            _ => return KeysParse::Invalid,
+        };
+        (kind, rest, None)
+    };
+
+    if !rest.is_empty() {
+        return KeysParse::Invalid;
+    }
+    let count = match (count, inner_count) {
+        (Some(outer), Some(inner)) => Some(outer.saturating_mul(inner)),
+        (count, None) | (None, count) => count,
+    };
+    KeysParse::Complete(NormalCommand { count, kind })
+}
+
+/// What the front end should do after a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalOutcome {
+    Done,
+    /// The command was a change: go on in insert mode.
+    Insert,
+    /// A motion went past the edge of the tree.
+    Overflow(Motion),
+}
+
+#[derive(Debug, Clone)]
+struct LastChange {
+    command: NormalCommand,
+    /// What was typed after a `c`.
+    inserted: Vec<TreeAction>,
+}
+
+/// What normal mode remembers between commands.
+#[derive(Debug, Clone)]
+pub struct NormalState {
+    /// What the last `d`, `c`, `y` or `x` took, for `p`.
+    pub register: EditorTreeSeq,
+    last_change: Option<LastChange>,
+    /// Whether what's inserted belongs to the last change.
+    recording: bool,
+}
+
+impl Default for NormalState {
+    fn default() -> Self {
+        Self {
+            register: EditorTreeSeq::empty(),
+            last_change: None,
+            recording: false,
+        }
+    }
+}
+
+impl NormalState {
+    /// Records `action`, typed in insert mode, as part of the last change if it was a `c`.
+    pub fn record_insert(&mut self, action: TreeAction) {
+        if let Some(change) = self.last_change.as_mut().filter(|_| self.recording) {
+            change.inserted.push(action);
+        }
+    }
+
+    /// Stops recording what's inserted, such as when leaving insert mode.
+    pub fn finish_insert(&mut self) {
+        self.recording = false;
+    }
+
+    pub fn run(&mut self, tree: &mut EditorTreeSeq, command: NormalCommand) -> NormalOutcome {
+        self.recording = false;
+        if command.kind == NormalKind::Repeat {
+            let Some(mut change) = self.last_change.clone() else {
+                return NormalOutcome::Done;
+            };
+            change.command.count = command.count.or(change.command.count);
+            self.execute(tree, change.command);
+            for action in &change.inserted {
+                tree.apply_action(*action);
+            }
+            self.last_change = Some(change);
+            return NormalOutcome::Done;
+        }
+
+        if command.is_change() {
+            self.last_change = Some(LastChange {
+                command,
+                inserted: Vec::new(),
+            });
+        }
+        let outcome = self.execute(tree, command);
+        self.recording = outcome == NormalOutcome::Insert;
+        outcome
+    }
+
+    fn execute(&mut self, tree: &mut EditorTreeSeq, command: NormalCommand) -> NormalOutcome {
+        let count = command.count();
+        match command.kind {
+            NormalKind::Move(motion) => {
+                for _ in 0..count {
+                    if let Some(rest) = tree.apply_move(motion) {
+                        return NormalOutcome::Overflow(rest);
+                    }
+                }
+            }
+            NormalKind::Operate(operator, target) => {
+                let Some(selected) = target_range(tree, target, count) else {
+                    return NormalOutcome::Done;
+                };
+                return self.operate(tree, operator, &selected);
+            }
+            NormalKind::DeleteChar => {
+                let path = tree.cursor_path();
+                let len = tree.seq_at(&path.steps).map_or(0, EditorTreeSeq::len);
+                let selected = SelectedRange {
+                    steps: path.steps,
+                    range: path.index..(path.index + count).min(len),
+                };
+                if !selected.is_empty() {
+                    self.register = tree.cut_range(&selected);
+                }
+            }
+            NormalKind::Paste => {
+                for _ in 0..count {
+                    tree.paste(&self.register);
+                }
+            }
+            NormalKind::Repeat => {}
+        }
+        NormalOutcome::Done
+    }
+
+    fn operate(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        operator: Operator,
+        selected: &SelectedRange,
+    ) -> NormalOutcome {
+        match operator {
+            Operator::Yank => {
+                self.register = tree.copy_range(selected);
+                tree.place_cursor(&CursorPath {
+                    steps: selected.steps.clone(),
+                    index: selected.range.start,
+                });
+                NormalOutcome::Done
+            }
+            Operator::Delete | Operator::Change => {
+                if !selected.is_empty() {
+                    self.register = tree.cut_range(selected);
+                }
+                match operator {
+                    Operator::Change => NormalOutcome::Insert,
+                    _ => NormalOutcome::Done,
+                }
+            }
+        }
+    }
+}
+
+/// The children an operator works on.
+fn target_range(tree: &mut EditorTreeSeq, target: Target, count: usize) -> Option<SelectedRange> {
+    match target {
+        Target::Motion(motion) => {
+            let anchor = tree.cursor_path();
+            for _ in 0..count {
+                if tree.apply_move(motion).is_some() {
+                    break;
+                }
+            }
+            let selected = tree.selected_range(&anchor);
+            match selected.is_empty() {
+                true => {
+                    tree.place_cursor(&anchor);
+                    None
+                }
+                false => Some(selected),
+            }
+        }
+        Target::Object(object) => object_range(tree, object, count),
+        Target::Line => Some(SelectedRange {
+            steps: Vec::new(),
+            range: 0..tree.len(),
+        }),
+    }
+}
+
+/// The `count`th node of the kind of `object` around the caret, innermost first. A node right
+/// after the caret counts as around it.
+fn object_range(tree: &EditorTreeSeq, object: TextObject, count: usize) -> Option<SelectedRange> {
+    let path = tree.cursor_path();
+    let is_kind = |steps: &[PathStep], child: usize| {
+        let node = tree.seq_at(steps).and_then(|seq| seq.children().get(child));
+        node.is_some_and(|node| {
+            matches!(
+                (node.kind(), object.kind),
+                (EditorTreeKind::Fraction(_), ObjectKind::Fraction)
+                    | (EditorTreeKind::Paren(_), ObjectKind::Paren)
+                    | (EditorTreeKind::Abs(_), ObjectKind::Abs)
+            )
+        })
+    };
+
+    let after = std::iter::once((path.steps.len(), path.index));
+    let around = (0..path.steps.len())
+        .rev()
+        .map(|depth| (depth, path.steps[depth].child));
+    let (depth, child) = after
+        .chain(around)
+        .filter(|&(depth, child)| is_kind(&path.steps[..depth], child))
+        .nth(count.saturating_sub(1))?;
+
+    let mut steps = path.steps[..depth].to_vec();
+    if object.around {
+        return Some(SelectedRange {
+            steps,
+            range: child..child + 1,
+        });
+    }
+    let slot = match object.kind {
+        ObjectKind::Fraction => CombinedCursor::TOP,
+        ObjectKind::Paren => CombinedCursor::Paren(SurroundIndex::Inside),
+        ObjectKind::Abs => CombinedCursor::Abs(SurroundIndex::Inside),
+    };
+    steps.push(PathStep { child, slot });
+    let len = tree.seq_at(&steps)?.len();
+    Some(SelectedRange {
+        steps,
+        range: 0..len,
+    })
+}
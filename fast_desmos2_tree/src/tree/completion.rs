@@ -0,0 +1,156 @@
+//! Completing the name in front of the caret, like Desmos does while typing `sin`.
+
+use std::ops::Range;
+
+use super::{
+    movement::Direction, CombinedCursor, EditorTree, EditorTreeKind, EditorTreeSeq, SurroundIndex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// Completes to a call, with the caret inside its parentheses.
+    Function,
+    /// Completes to the name alone.
+    Variable,
+}
+
+/// A name the identifier at the caret can be completed to, along with the subscript it's written
+/// with, if it has one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub name: String,
+    pub subscript: Option<String>,
+    pub kind: CompletionKind,
+}
+
+impl Completion {
+    pub fn function(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            subscript: None,
+            kind: CompletionKind::Function,
+        }
+    }
+
+    pub fn variable(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            subscript: None,
+            kind: CompletionKind::Variable,
+        }
+    }
+
+    pub fn with_subscript(self, subscript: impl Into<String>) -> Self {
+        Self {
+            subscript: Some(subscript.into()),
+            ..self
+        }
+    }
+
+    fn is_same_name(&self, other: &Self) -> bool {
+        self.name == other.name && self.subscript == other.subscript
+    }
+
+    fn len(&self) -> usize {
+        self.name.len() + self.subscript.as_ref().map_or(0, String::len)
+    }
+
+    /// The children the name is typed as.
+    fn nodes(&self) -> Vec<EditorTree> {
+        let mut nodes: Vec<_> = self.name.chars().map(EditorTree::terminal).collect();
+        if let Some(subscript) = &self.subscript {
+            nodes.push(EditorTree::subscript(EditorTreeSeq::str(subscript)));
+        }
+        nodes
+    }
+}
+
+/// The letters around the caret that a completion would replace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionPrefix {
+    /// The letters before the caret, which completions have to start with.
+    pub text: String,
+    /// The children of the sequence holding the caret that make up the identifier, including the
+    /// letters after the caret.
+    pub range: Range<usize>,
+}
+
+impl EditorTreeSeq {
+    /// The identifier the caret is at the end of, or in the middle of. Subscripts are part of a
+    /// name already, so they never have one.
+    pub fn completion_prefix(&self) -> Option<CompletionPrefix> {
+        let path = self.cursor_path();
+        if path
+            .steps
+            .last()
+            .is_some_and(|step| step.slot == CombinedCursor::Subscript)
+        {
+            return None;
+        }
+        let seq = self.seq_at(&path.steps)?;
+        let start = seq.search_back_ident(path.index).ok()?;
+        let end = path.index
+            + seq.children[path.index..]
+                .iter()
+                .take_while(|child| child.is_terminal_and(|term| term.ch().is_ascii_alphabetic()))
+                .count();
+        let text = seq.children[start..path.index]
+            .iter()
+            .filter_map(|child| child.is_terminal_then(|term| term.ch()))
+            .collect();
+        Some(CompletionPrefix {
+            text,
+            range: start..end,
+        })
+    }
+
+    /// The `candidates` that complete the identifier at the caret, shortest first and each name
+    /// once. The first candidate with a name wins over later ones.
+    pub fn completions(&self, candidates: impl IntoIterator<Item = Completion>) -> Vec<Completion> {
+        let Some(prefix) = self.completion_prefix() else {
+            return Vec::new();
+        };
+        let mut completions: Vec<Completion> = Vec::new();
+        for candidate in candidates {
+            let is_new = completions
+                .iter()
+                .all(|other| !other.is_same_name(&candidate));
+            if is_new && candidate.name.starts_with(&prefix.text) {
+                completions.push(candidate);
+            }
+        }
+        completions.sort_by_key(Completion::len);
+        completions
+    }
+
+    /// Replaces the identifier at the caret with `completion`. Functions get parentheses to put
+    /// the caret in, unless there already are some. Returns `false`, changing nothing, when
+    /// there's no identifier at the caret.
+    pub fn accept_completion(&mut self, completion: &Completion) -> bool {
+        let Some(prefix) = self.completion_prefix() else {
+            return false;
+        };
+        let path = self.cursor_path();
+        let seq = self.enter_steps(&path.steps);
+
+        let nodes = completion.nodes();
+        let after = prefix.range.start + nodes.len();
+        seq.children.splice(prefix.range, nodes);
+        let has_parens = seq
+            .children
+            .get(after)
+            .is_some_and(|next| matches!(next.kind(), EditorTreeKind::Paren(_)));
+        match completion.kind {
+            CompletionKind::Function if !has_parens => {
+                let parens =
+                    EditorTree::complete_paren(SurroundIndex::Inside, EditorTreeSeq::empty());
+                seq.children.insert(after, parens);
+                seq.cursor = after;
+            }
+            CompletionKind::Function | CompletionKind::Variable => {
+                seq.move_to(after, Direction::Left)
+            }
+        }
+        true
+    }
+}
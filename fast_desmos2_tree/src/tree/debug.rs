@@ -1,5 +1,5 @@
 use std::fmt::Display;
-#[cfg(feature = "binary")]
+#[cfg(feature = "termion")]
 use std::io::Write as _;
 
 use glam::UVec2;
@@ -7,9 +7,10 @@ use glam::UVec2;
 use crate::tree::SumProdIndex;
 
 use super::{
-    EditorTree, EditorTreeFraction, EditorTreeKind, EditorTreeParen, EditorTreePower,
-    EditorTreeSeq, EditorTreeSqrt, EditorTreeSumProd, EditorTreeTerminal, FractionIndex, SumOrProd,
-    SurroundIndex,
+    EditorTree, EditorTreeFraction, EditorTreeKind, EditorTreeParen, EditorTreePiecewise,
+    EditorTreePower, EditorTreeRoot, EditorTreeSeq, EditorTreeSqrt, EditorTreeSubscript,
+    EditorTreeSumProd, EditorTreeTerminal, FractionIndex, PiecewiseIndex, PiecewiseRow, RootIndex,
+    SumOrProd, SurroundIndex,
 };
 
 trait RectStyle {
@@ -125,7 +126,7 @@ impl CharScreen {
         }
     }
 
-    #[cfg(feature = "binary")]
+    #[cfg(feature = "termion")]
     pub fn display_raw(
         &self,
         to: &mut termion::raw::RawTerminal<std::io::Stdout>,
@@ -430,6 +431,12 @@ impl Debugable for EditorTreePower {
     }
 }
 
+impl Debugable for EditorTreeSubscript {
+    fn debug(&self, with_cursor: bool) -> DebugTree {
+        self.subscript.debug(with_cursor).boxed(RectStyles::Normal)
+    }
+}
+
 impl Debugable for EditorTreeFraction {
     fn debug(&self, with_cursor: bool) -> DebugTree {
         let top = self
@@ -486,6 +493,53 @@ impl Debugable for EditorTreeSqrt {
     }
 }
 
+impl Debugable for EditorTreeRoot {
+    fn debug(&self, with_cursor: bool) -> DebugTree {
+        let index = self
+            .index
+            .debug(with_cursor && self.cursor == RootIndex::Index)
+            .boxed(RectStyles::Weak);
+        let child = self
+            .child
+            .debug(with_cursor && self.cursor == RootIndex::Inside)
+            .sqrt(RectStyles::Normal);
+        let tree = DebugTree::horizontal(vec![index, child]);
+        if with_cursor && self.cursor == RootIndex::Left {
+            DebugTree::horizontal(vec![DebugTree::solid(UVec2::new(1, tree.size.y)), tree])
+        } else {
+            tree
+        }
+    }
+}
+
+impl Debugable for EditorTreePiecewise {
+    fn debug(&self, with_cursor: bool) -> DebugTree {
+        let has_cursor = |index| with_cursor && self.cursor == index;
+        let mut lines: Vec<_> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(row, PiecewiseRow { condition, value })| {
+                DebugTree::horizontal(vec![
+                    condition.debug(has_cursor(PiecewiseIndex::Condition(row))),
+                    DebugTree::char(':'),
+                    value.debug(has_cursor(PiecewiseIndex::Value(row))),
+                ])
+            })
+            .collect();
+        lines.push(self.default.debug(has_cursor(PiecewiseIndex::Default)));
+        let rows = DebugTree::vertical(lines);
+        let brace = DebugTree::char('{');
+
+        if has_cursor(PiecewiseIndex::Left) {
+            let cursor = DebugTree::solid(UVec2::new(1, rows.size.y));
+            DebugTree::horizontal(vec![cursor, brace, rows])
+        } else {
+            DebugTree::horizontal(vec![brace, rows])
+        }
+    }
+}
+
 impl Debugable for EditorTreeSumProd {
     fn debug(&self, with_cursor: bool) -> DebugTree {
         let bottom_row = DebugTree::horizontal(vec![
@@ -506,10 +560,9 @@ impl Debugable for EditorTreeSumProd {
         ]);
 
         match (self.cursor(), with_cursor) {
-            (SumProdIndex::Left, true) => DebugTree::horizontal(vec![
-                DebugTree::solid(UVec2::new(1, result.size.y)),
-                result,
-            ]),
+            (SumProdIndex::Left, true) => {
+                DebugTree::horizontal(vec![DebugTree::solid(UVec2::new(1, result.size.y)), result])
+            }
             _ => result,
         }
     }
@@ -520,12 +573,15 @@ impl Debugable for EditorTree {
         match &self.kind {
             EditorTreeKind::Terminal(term) => term.debug(with_cursor),
             EditorTreeKind::Power(power) => power.debug(with_cursor),
+            EditorTreeKind::Subscript(subscript) => subscript.debug(with_cursor),
             EditorTreeKind::Fraction(fraction) => fraction.debug(with_cursor),
             EditorTreeKind::Sqrt(sqrt) => sqrt.debug(with_cursor),
+            EditorTreeKind::Root(root) => root.debug(with_cursor),
             EditorTreeKind::Paren(paren) => paren.debug(with_cursor),
             EditorTreeKind::Abs(_) => todo!(),
             EditorTreeKind::Bracket(_) => todo!(),
             EditorTreeKind::Curly(_) => todo!(),
+            EditorTreeKind::Piecewise(piecewise) => piecewise.debug(with_cursor),
             EditorTreeKind::SumProd(sum_prod) => sum_prod.debug(with_cursor),
         }
     }
@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+
+use super::{
+    actions::SeqActionOutcome, CursorPath, EditorTree, EditorTreeSeq, PathStep, TreeAction,
+};
+
+/// The smallest part of a tree that changed in an edit: some children of one sequence, along with
+/// its cursor. Everything else, including the cursors outside that sequence, stayed the same.
+///
+/// It holds the other version of that part, so applying it swaps the two, undoing the edit or, once
+/// undone, redoing it.
+#[derive(Debug, Clone)]
+struct Change {
+    /// Leads to the sequence that changed.
+    steps: Vec<PathStep>,
+    /// Where the children that changed start.
+    start: usize,
+    /// How many children changed, in the version in the tree.
+    len: usize,
+    /// The children in the other version.
+    children: Vec<EditorTree>,
+    /// The cursor of the sequence in the other version.
+    cursor: usize,
+    /// The nodes of both versions.
+    size: usize,
+}
+
+impl Change {
+    /// What changed from `before` to `after`, if anything. `before` is taken apart for the
+    /// children that changed, and `after` is only changed in between.
+    fn between(before: &mut EditorTreeSeq, after: &mut EditorTreeSeq) -> Option<Self> {
+        Self::between_at(before, after, &mut Vec::new())
+    }
+
+    fn between_at(
+        before: &mut EditorTreeSeq,
+        after: &mut EditorTreeSeq,
+        steps: &mut Vec<PathStep>,
+    ) -> Option<Self> {
+        let prefix = before
+            .children
+            .iter()
+            .zip(&after.children)
+            .take_while(|(before, after)| before == after)
+            .count();
+        let (before_len, after_len) = (before.len(), after.len());
+        if prefix == before_len && prefix == after_len && before.cursor == after.cursor {
+            return None;
+        }
+        let suffix = before.children[prefix..]
+            .iter()
+            .rev()
+            .zip(after.children[prefix..].iter().rev())
+            .take_while(|(before, after)| before == after)
+            .count();
+        let (before_end, after_end) = (before_len - suffix, after_len - suffix);
+
+        // a single node that only changed inside one of its sequences is gone into.
+        if before.cursor == after.cursor && before_end == prefix + 1 && after_end == prefix + 1 {
+            let (before_node, after_node) =
+                (&mut before.children[prefix], &mut after.children[prefix]);
+            if let Some(index) = changed_seq(before_node, after_node) {
+                let slot = after_node.slots()[index];
+                let before_seq = nth_seq(before_node, index);
+                let after_seq = nth_seq(after_node, index);
+                steps.push(PathStep {
+                    child: prefix,
+                    slot,
+                });
+                return Self::between_at(before_seq, after_seq, steps);
+            }
+        }
+
+        let children: Vec<_> = before.children.drain(prefix..before_end).collect();
+        let size = node_count(&children) + node_count(&after.children[prefix..after_end]);
+        Some(Self {
+            steps: steps.clone(),
+            start: prefix,
+            len: after_end - prefix,
+            children,
+            cursor: before.cursor,
+            size,
+        })
+    }
+
+    /// Swaps the version in `tree` with the other one.
+    fn swap(&mut self, tree: &mut EditorTreeSeq) {
+        let seq = self.steps.iter().fold(tree, |seq, step| {
+            let node = &mut seq.children[step.child];
+            let index = node
+                .slots()
+                .iter()
+                .position(|slot| *slot == step.slot)
+                .unwrap_or_else(|| unreachable!("changes are swapped in order"));
+            nth_seq(node, index)
+        });
+        let children = std::mem::take(&mut self.children);
+        let len = std::mem::replace(&mut self.len, children.len());
+        self.children = seq
+            .children
+            .splice(self.start..self.start + len, children)
+            .collect();
+        std::mem::swap(&mut seq.cursor, &mut self.cursor);
+    }
+}
+
+/// Which sequence of the node differs, if only one does and nothing else about the node does.
+fn changed_seq(before: &mut EditorTree, after: &mut EditorTree) -> Option<usize> {
+    let changed: Vec<_> = before
+        .child_seqs()
+        .zip(after.child_seqs())
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(index, _)| index)
+        .collect();
+    let [index] = changed[..] else {
+        return None;
+    };
+    if before.slots() != after.slots() {
+        return None;
+    }
+    // the rest of the node, like its cursor, is compared with that sequence taken out.
+    let before_seq = std::mem::replace(nth_seq(before, index), EditorTreeSeq::empty());
+    let after_seq = std::mem::replace(nth_seq(after, index), EditorTreeSeq::empty());
+    let is_same = before == after;
+    *nth_seq(before, index) = before_seq;
+    *nth_seq(after, index) = after_seq;
+    is_same.then_some(index)
+}
+
+fn nth_seq(node: &mut EditorTree, index: usize) -> &mut EditorTreeSeq {
+    node.child_seqs_mut()
+        .nth(index)
+        .unwrap_or_else(|| unreachable!("the node has the sequence"))
+}
+
+fn node_count(children: &[EditorTree]) -> usize {
+    children
+        .iter()
+        .map(|child| {
+            1 + child
+                .child_seqs()
+                .map(EditorTreeSeq::node_count)
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+/// One undoable step: the actions making it up and what they changed.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    /// The actions making up the step, or nothing for edits done through
+    /// [`EditorHistory::edit`].
+    actions: Vec<TreeAction>,
+    /// What each action changed, in order.
+    changes: Vec<Change>,
+    /// Where the caret was after the step.
+    after: CursorPath,
+}
+
+impl HistoryEntry {
+    fn size(&self) -> usize {
+        self.changes.iter().map(|change| change.size).sum()
+    }
+
+    fn is_char_group(&self) -> bool {
+        !self.actions.is_empty()
+            && self
+                .actions
+                .iter()
+                .all(|action| matches!(action, TreeAction::Char(_)))
+    }
+}
+
+/// Undo and redo for edits of an [`EditorTreeSeq`].
+///
+/// Each step only keeps the part of the tree it changed. Consecutive character inserts are undone
+/// together, as long as nothing else (including moving the cursor) happened in between. The oldest
+/// steps are forgotten once all steps together hold more nodes than the budget.
+#[derive(Debug, Clone)]
+pub struct EditorHistory {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    budget: usize,
+    used: usize,
+    /// Whether the next character insert may join the last step.
+    can_group: bool,
+}
+
+impl Default for EditorHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_BUDGET)
+    }
+}
+
+impl EditorHistory {
+    pub const DEFAULT_BUDGET: usize = 1 << 20;
+
+    /// `budget` is the number of tree nodes the history may hold on to.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            budget,
+            used: 0,
+            can_group: false,
+        }
+    }
+
+    pub fn apply_action(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        action: TreeAction,
+    ) -> Option<SeqActionOutcome> {
+        let mut before = tree.clone();
+        let caret = tree.cursor_path();
+        let outcome = tree.apply_action(action);
+        let Some(change) = Change::between(&mut before, tree) else {
+            return outcome;
+        };
+
+        let joins_last = matches!(action, TreeAction::Char(_))
+            && self.can_group
+            && self
+                .undo
+                .back()
+                .is_some_and(|last| last.is_char_group() && last.after == caret);
+        if joins_last {
+            let mut last = self.undo.pop_back().unwrap_or_else(|| unreachable!());
+            self.used -= last.size();
+            last.actions.push(action);
+            last.changes.push(change);
+            last.after = tree.cursor_path();
+            self.push(last);
+        } else {
+            self.push(HistoryEntry {
+                actions: vec![action],
+                changes: vec![change],
+                after: tree.cursor_path(),
+            });
+        }
+        self.can_group = true;
+        outcome
+    }
+
+    /// Makes any edit of the tree a single undoable step.
+    pub fn edit<T>(
+        &mut self,
+        tree: &mut EditorTreeSeq,
+        func: impl FnOnce(&mut EditorTreeSeq) -> T,
+    ) -> T {
+        let mut before = tree.clone();
+        let result = func(tree);
+        if let Some(change) = Change::between(&mut before, tree) {
+            self.push(HistoryEntry {
+                actions: Vec::new(),
+                changes: vec![change],
+                after: tree.cursor_path(),
+            });
+        }
+        self.can_group = false;
+        result
+    }
+
+    /// Makes the next character insert start a new step, such as when leaving insert mode.
+    pub fn break_group(&mut self) {
+        self.can_group = false;
+    }
+
+    fn push(&mut self, entry: HistoryEntry) {
+        self.used -= self.redo.drain(..).map(|entry| entry.size()).sum::<usize>();
+        self.used += entry.size();
+        self.undo.push_back(entry);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        // the newest step stays, even if it is over budget by itself.
+        while self.used > self.budget && self.undo.len() > 1 {
+            let oldest = self.undo.pop_front().unwrap_or_else(|| unreachable!());
+            self.used -= oldest.size();
+        }
+    }
+
+    /// Goes back one step, returning the actions that got undone.
+    pub fn undo(&mut self, tree: &mut EditorTreeSeq) -> Option<&[TreeAction]> {
+        let mut entry = self.undo.pop_back()?;
+        for change in entry.changes.iter_mut().rev() {
+            change.swap(tree);
+        }
+        self.can_group = false;
+        self.redo.push(entry);
+        self.redo.last().map(|entry| &*entry.actions)
+    }
+
+    /// Goes forward one undone step, returning the actions that got redone.
+    pub fn redo(&mut self, tree: &mut EditorTreeSeq) -> Option<&[TreeAction]> {
+        let mut entry = self.redo.pop()?;
+        for change in &mut entry.changes {
+            change.swap(tree);
+        }
+        self.can_group = false;
+        self.undo.push_back(entry);
+        self.undo.back().map(|entry| &*entry.actions)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// The number of nodes held by all steps.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.used = 0;
+        self.can_group = false;
+    }
+}
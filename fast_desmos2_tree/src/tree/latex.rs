@@ -0,0 +1,145 @@
+//! Conversion between editor trees and Desmos-flavoured LaTeX.
+
+use fast_desmos2_parser::Builtins;
+
+use super::{EditorTree, EditorTreeKind, EditorTreeSeq, SumOrProd};
+
+pub use import::{LatexError, LatexResult};
+
+mod import;
+
+/// The LaTeX commands for terminals that aren't written as themselves. Commands are followed by a
+/// space so that a letter after them doesn't become part of the name.
+const COMMANDS: &[(char, &str)] = &[
+    ('×', "\\cdot "),
+    ('≤', "\\le "),
+    ('≥', "\\ge "),
+    ('→', "\\to "),
+    ('π', "\\pi "),
+    ('τ', "\\tau "),
+    (' ', "\\ "),
+];
+
+impl EditorTreeSeq {
+    pub fn to_latex(&self) -> String {
+        let mut latex = String::new();
+        self.write_latex(&mut latex);
+        latex
+    }
+
+    pub fn write_latex(&self, latex: &mut String) {
+        let mut children = self.children.iter().peekable();
+        while let Some(child) = children.next() {
+            let Some(ch) = child.is_terminal_then(|term| term.ch()) else {
+                child.write_latex(latex);
+                continue;
+            };
+            if !ch.is_ascii_alphabetic() {
+                let or_equal = matches!(ch, '<' | '>')
+                    && children
+                        .peek()
+                        .is_some_and(|next| next.is_terminal_and_eq('='));
+                if or_equal {
+                    children.next();
+                    latex.push_str(if ch == '<' { "\\le " } else { "\\ge " });
+                    continue;
+                }
+                match COMMANDS.iter().find(|(from, _)| *from == ch) {
+                    Some((_, command)) => latex.push_str(command),
+                    None => latex.push(ch),
+                }
+                continue;
+            }
+
+            // runs of letters are either builtins or single letter variables multiplied together.
+            let mut word = String::from(ch);
+            while let Some(ch) = children
+                .peek()
+                .and_then(|child| child.is_terminal_then(|term| term.ch()))
+                .filter(char::is_ascii_alphabetic)
+            {
+                word.push(ch);
+                children.next();
+            }
+            match Builtins::from_str(word.as_bytes()) {
+                Some(_) => {
+                    latex.push_str("\\operatorname{");
+                    latex.push_str(&word);
+                    latex.push('}');
+                }
+                None => latex.push_str(&word),
+            }
+        }
+    }
+}
+
+impl EditorTree {
+    pub fn write_latex(&self, latex: &mut String) {
+        let mut group = |before: &str, seq: &EditorTreeSeq, after: &str| {
+            latex.push_str(before);
+            seq.write_latex(latex);
+            latex.push_str(after);
+        };
+
+        match self.kind() {
+            EditorTreeKind::Terminal(_) => EditorTreeSeq::one(self.clone()).write_latex(latex),
+            EditorTreeKind::Fraction(fraction) => {
+                group("\\frac{", fraction.top(), "}");
+                group("{", fraction.bottom(), "}");
+            }
+            EditorTreeKind::Power(power) => group("^{", power.power(), "}"),
+            EditorTreeKind::Subscript(subscript) => {
+                // subscripts are part of a name, so their letters are never builtins.
+                latex.push_str("_{");
+                for child in subscript.subscript().children() {
+                    match child.is_terminal_then(|term| term.ch()) {
+                        Some(ch) => latex.push(ch),
+                        None => child.write_latex(latex),
+                    }
+                }
+                latex.push('}');
+            }
+            EditorTreeKind::Sqrt(sqrt) => group("\\sqrt{", sqrt.child(), "}"),
+            EditorTreeKind::Root(root) => {
+                group("\\sqrt[", root.index(), "]");
+                group("{", root.child(), "}");
+            }
+            EditorTreeKind::Paren(paren) => group("\\left(", paren.child(), "\\right)"),
+            EditorTreeKind::Abs(abs) => group("\\left|", abs.child(), "\\right|"),
+            EditorTreeKind::Bracket(bracket) => group("\\left[", bracket.child(), "\\right]"),
+            EditorTreeKind::Curly(curly) => group("\\left\\{", curly.child(), "\\right\\}"),
+            EditorTreeKind::Piecewise(piecewise) => {
+                // a condition without a value is only unambiguous at the very end.
+                let rows = piecewise.rows();
+                let last_row = rows.len() - 1;
+                latex.push_str("\\left\\{");
+                for (index, row) in rows.iter().enumerate() {
+                    if index > 0 {
+                        latex.push(',');
+                    }
+                    row.condition().write_latex(latex);
+                    if !row.value().is_empty() {
+                        latex.push(':');
+                        row.value().write_latex(latex);
+                    } else if index < last_row || !piecewise.default().is_empty() {
+                        latex.push_str(":1");
+                    }
+                }
+                if !piecewise.default().is_empty() {
+                    latex.push(',');
+                    piecewise.default().write_latex(latex);
+                }
+                latex.push_str("\\right\\}");
+            }
+            EditorTreeKind::SumProd(sum_prod) => {
+                let command = match sum_prod.sum_or_prod() {
+                    SumOrProd::Sum => "\\sum_{",
+                    SumOrProd::Prod => "\\prod_{",
+                };
+                group(command, sum_prod.ident(), "=");
+                group("", sum_prod.bottom(), "}");
+                group("^{", sum_prod.top(), "}");
+            }
+        }
+    }
+}
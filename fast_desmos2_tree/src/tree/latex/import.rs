@@ -0,0 +1,365 @@
+use fast_desmos2_parser::{
+    parse, AddOrSub, AstKind, AstNode, CompSet, Element, IdentStorer, SumOrProd,
+};
+use thiserror::Error;
+
+use crate::tree::{
+    EditorTree, EditorTreeSeq, FractionIndex, PiecewiseIndex, PiecewiseRow, RootIndex,
+    SumProdIndex, SurroundIndex,
+};
+
+#[derive(Debug, Error)]
+pub enum LatexError {
+    #[error("can't read the LaTeX at byte {index}: {message}")]
+    Lex { index: usize, message: String },
+    #[error("can't parse the LaTeX")]
+    Parse,
+    #[error("only the LaTeX up to byte {} could be parsed", .0)]
+    Partial(usize),
+    #[error("{} can't be edited yet", .0)]
+    Unsupported(&'static str),
+}
+
+pub type LatexResult<T> = Result<T, LatexError>;
+
+impl LatexError {
+    /// The error for LaTeX read from `offset` bytes in.
+    fn shifted(self, offset: usize) -> Self {
+        match self {
+            Self::Lex { index, message } => Self::Lex {
+                index: index + offset,
+                message,
+            },
+            Self::Partial(at) => Self::Partial(at + offset),
+            err => err,
+        }
+    }
+}
+
+impl EditorTreeSeq {
+    /// Reads LaTeX the way the old parser does, with the cursor ending up at the start.
+    ///
+    /// The old parser only takes a name on the left of `=`, so definitions like
+    /// `f\left(x\right)=x^{2}` are read as what's on either side of the `=`.
+    pub fn from_latex(latex: &str) -> LatexResult<Self> {
+        match Self::from_latex_cell(latex) {
+            Err(LatexError::Partial(at)) => {
+                let Some(rest) = latex[at..].trim_start().strip_prefix('=') else {
+                    return Err(LatexError::Partial(at));
+                };
+                let mut children = Self::from_latex_cell(&latex[..at])?.children;
+                children.push(EditorTree::terminal('='));
+                let offset = latex.len() - rest.len();
+                let right = Self::from_latex(rest).map_err(|err| err.shifted(offset))?;
+                children.extend(right.children);
+                Ok(Self::new(0, children))
+            }
+            result => result,
+        }
+    }
+
+    fn from_latex_cell(latex: &str) -> LatexResult<Self> {
+        let idents = IdentStorer::default();
+        let outcome = parse(&idents, latex).map_err(|err| LatexError::Lex {
+            index: err.index,
+            message: err.error.to_string(),
+        })?;
+        let ast = outcome
+            .borrow_dependent()
+            .as_ref()
+            .map_err(|_| LatexError::Parse)?;
+        if ast.span().to < latex.trim_end().len() {
+            return Err(LatexError::Partial(ast.span().to));
+        }
+        Self::from_ast(ast, latex)
+    }
+
+    /// Converts a node of the old parser, where `source` is what it was parsed from.
+    pub fn from_ast(ast: &AstNode, source: &str) -> LatexResult<Self> {
+        let mut children = Vec::new();
+        Importer { source }.push(ast, &mut children)?;
+        Ok(Self::new(0, children))
+    }
+
+    /// Inserts LaTeX in front of the caret as a tree, rather than as the characters it's made of.
+    pub fn paste_latex(&mut self, latex: &str) -> LatexResult<()> {
+        self.paste(&Self::from_latex(latex)?);
+        Ok(())
+    }
+}
+
+struct Importer<'a> {
+    source: &'a str,
+}
+
+fn push_str(string: &str, out: &mut Vec<EditorTree>) {
+    out.extend(string.chars().map(EditorTree::terminal));
+}
+
+/// Pushes an identifier, with what comes after `_` as a subscript node.
+fn push_ident(name: &str, out: &mut Vec<EditorTree>) {
+    let Some((base, subscript)) = name.split_once('_') else {
+        return push_str(name, out);
+    };
+    push_str(base, out);
+    let subscript = subscript
+        .strip_prefix('{')
+        .and_then(|inner| inner.strip_suffix('}'))
+        .unwrap_or(subscript);
+    out.push(EditorTree::subscript(EditorTreeSeq::str(subscript.trim())));
+}
+
+fn ends_with(out: &[EditorTree], func: impl Fn(char) -> bool) -> bool {
+    out.last()
+        .is_some_and(|last| last.is_terminal_and(|term| func(term.ch())))
+}
+
+impl Importer<'_> {
+    fn seq(&self, ast: &AstNode) -> LatexResult<EditorTreeSeq> {
+        let mut children = Vec::new();
+        self.push(ast, &mut children)?;
+        Ok(EditorTreeSeq::new(0, children))
+    }
+
+    fn separated<'b>(
+        &self,
+        items: impl IntoIterator<Item = &'b AstNode>,
+        separator: &str,
+        out: &mut Vec<EditorTree>,
+    ) -> LatexResult<()> {
+        for (index, item) in items.into_iter().enumerate() {
+            if index > 0 {
+                push_str(separator, out);
+            }
+            self.push(item, out)?;
+        }
+        Ok(())
+    }
+
+    fn separated_seq<'b>(
+        &self,
+        items: impl IntoIterator<Item = &'b AstNode>,
+        separator: &str,
+    ) -> LatexResult<EditorTreeSeq> {
+        let mut children = Vec::new();
+        self.separated(items, separator, &mut children)?;
+        Ok(EditorTreeSeq::new(0, children))
+    }
+
+    fn push(&self, ast: &AstNode, out: &mut Vec<EditorTree>) -> LatexResult<()> {
+        match ast.kind() {
+            AstKind::Identifier(_) => {
+                let name = ast.span_as_str(self.source);
+                if name.starts_with('\\') {
+                    return Err(LatexError::Unsupported("named symbols"));
+                }
+                push_ident(name, out);
+            }
+            AstKind::Builtins(builtins) => push_str(builtins.as_str(), out),
+            AstKind::Number(_) => match ast.span_as_str(self.source) {
+                "\\pi" => out.push(EditorTree::terminal('π')),
+                "\\tau" => out.push(EditorTree::terminal('τ')),
+                digits => push_str(digits, out),
+            },
+            AstKind::Group(inner) => out.push(EditorTree::complete_paren(
+                SurroundIndex::Left,
+                self.seq(inner)?,
+            )),
+            AstKind::LatexGroup(inner) => self.push(inner, out)?,
+            AstKind::Abs(inner) => out.push(EditorTree::complete_abs(
+                SurroundIndex::Left,
+                self.seq(inner)?,
+            )),
+            AstKind::Point(x, y) => out.push(EditorTree::complete_paren(
+                SurroundIndex::Left,
+                self.separated_seq([x, y], ",")?,
+            )),
+            AstKind::List(items) => out.push(EditorTree::complete_brackets(
+                SurroundIndex::Left,
+                self.separated_seq(items, ",")?,
+            )),
+            AstKind::VarDef { ident, expr } | AstKind::Definition { ident, expr } => {
+                self.separated([ident, expr], "=", out)?
+            }
+            AstKind::SumProd {
+                kind,
+                from,
+                to,
+                expr,
+            } => {
+                let AstKind::VarDef {
+                    ident,
+                    expr: bottom,
+                } = from.kind()
+                else {
+                    return Err(LatexError::Parse);
+                };
+                let (ident, bottom, top) = (self.seq(ident)?, self.seq(bottom)?, self.seq(to)?);
+                out.push(match kind {
+                    SumOrProd::Sum => EditorTree::sum(SumProdIndex::Left, top, bottom, ident),
+                    SumOrProd::Prod => EditorTree::prod(SumProdIndex::Left, top, bottom, ident),
+                });
+                self.push(expr, out)?;
+            }
+            AstKind::FunctionCall {
+                ident,
+                power,
+                params,
+            } => {
+                self.push(ident, out)?;
+                if let Some(power) = power {
+                    out.push(EditorTree::power(self.seq(power)?));
+                }
+                out.push(EditorTree::complete_paren(
+                    SurroundIndex::Left,
+                    self.separated_seq(params, ",")?,
+                ));
+            }
+            AstKind::Multiply(items) => {
+                let mut last_end = None;
+                for item in items {
+                    // keep explicit multiplication, and the one between numbers that would
+                    // otherwise become a single number.
+                    let explicit = last_end.is_some_and(|end| {
+                        let between = &self.source[end..item.span().from];
+                        between.contains("\\cdot") || between.contains("\\times")
+                    });
+                    let is_digit = |ch: char| ch.is_ascii_digit() || ch == '.';
+                    let digits = ends_with(out, is_digit)
+                        && matches!(item.kind(), AstKind::Number(_))
+                        && item.span_as_str(self.source).starts_with(is_digit);
+                    if explicit || (last_end.is_some() && digits) {
+                        out.push(EditorTree::terminal('×'));
+                    }
+                    self.push(item, out)?;
+                    last_end = Some(item.span().to);
+                }
+            }
+            AstKind::AddSub(items) => {
+                for (index, (sign, item)) in items.iter().enumerate() {
+                    match sign {
+                        AddOrSub::Add if index > 0 => out.push(EditorTree::terminal('+')),
+                        AddOrSub::Add => {}
+                        AddOrSub::Sub => out.push(EditorTree::terminal('-')),
+                    }
+                    self.push(item, out)?;
+                }
+            }
+            AstKind::Frac { above, below } => out.push(EditorTree::fraction(
+                FractionIndex::Left,
+                self.seq(above)?,
+                self.seq(below)?,
+            )),
+            AstKind::Root { root: None, expr } => {
+                out.push(EditorTree::sqrt(SurroundIndex::Left, self.seq(expr)?))
+            }
+            AstKind::Root {
+                root: Some(root),
+                expr,
+            } => out.push(EditorTree::root(
+                RootIndex::Left,
+                self.seq(root)?,
+                self.seq(expr)?,
+            )),
+            AstKind::Exp { expr, exp } => {
+                self.push(expr, out)?;
+                out.push(EditorTree::power(self.seq(exp)?));
+            }
+            AstKind::For { expr, defs } => {
+                self.push(expr, out)?;
+                push_str("for", out);
+                self.separated(defs, ",", out)?;
+            }
+            AstKind::ListComp { expr, defs } => {
+                let mut children = Vec::new();
+                self.push(expr, &mut children)?;
+                push_str("for", &mut children);
+                self.separated(defs, ",", &mut children)?;
+                out.push(EditorTree::complete_brackets(
+                    SurroundIndex::Left,
+                    EditorTreeSeq::new(0, children),
+                ));
+            }
+            AstKind::ListRange { from, next, to } => {
+                let mut children = Vec::new();
+                self.separated(
+                    [Some(from), next.as_ref()].into_iter().flatten(),
+                    ",",
+                    &mut children,
+                )?;
+                push_str("...", &mut children);
+                self.push(to, &mut children)?;
+                out.push(EditorTree::complete_brackets(
+                    SurroundIndex::Left,
+                    EditorTreeSeq::new(0, children),
+                ));
+            }
+            AstKind::Conditional { exprs, comps } => {
+                let mut exprs = exprs.iter();
+                if let Some(first) = exprs.next() {
+                    self.push(first, out)?;
+                }
+                for (expr, comp) in exprs.zip(comps) {
+                    push_str(comp_str(*comp), out);
+                    self.push(expr, out)?;
+                }
+            }
+            AstKind::IfElse { .. } => out.push(self.piecewise(ast)?),
+            AstKind::ElemAccess { expr, element } => {
+                self.push(expr, out)?;
+                push_str(
+                    match element {
+                        Element::X => ".x",
+                        Element::Y => ".y",
+                    },
+                    out,
+                );
+            }
+            AstKind::ListIndexing { expr, index } => {
+                self.push(expr, out)?;
+                out.push(EditorTree::complete_brackets(
+                    SurroundIndex::Left,
+                    self.seq(index)?,
+                ));
+            }
+            AstKind::With { def, expr } => {
+                self.push(expr, out)?;
+                push_str("with", out);
+                self.push(def, out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A piecewise definition, where each otherwise branch that is another one becomes a row.
+    fn piecewise(&self, mut ast: &AstNode) -> LatexResult<EditorTree> {
+        let mut rows = Vec::new();
+        let mut default = EditorTreeSeq::empty();
+        loop {
+            let AstKind::IfElse { conds, yes, no } = ast.kind() else {
+                default = self.seq(ast)?;
+                break;
+            };
+            let value = match yes {
+                Some(yes) => self.seq(yes)?,
+                None => EditorTreeSeq::empty(),
+            };
+            rows.push(PiecewiseRow::new(self.separated_seq(conds, ",")?, value));
+            match no {
+                Some(no) => ast = no,
+                None => break,
+            }
+        }
+        Ok(EditorTree::piecewise(PiecewiseIndex::Left, rows, default))
+    }
+}
+
+fn comp_str(comp: CompSet) -> &'static str {
+    match comp.reference_char() {
+        '>' => ">",
+        '<' => "<",
+        'G' => ">=",
+        'L' => "<=",
+        _ => "=",
+    }
+}
@@ -1,8 +1,9 @@
 use crate::tree::SumProdIndex;
 
 use super::{
-    EditorTree, EditorTreeFraction, EditorTreeKind, EditorTreePower, EditorTreeSeq,
-    EditorTreeSumProd, EditorTreeTerminal, FractionIndex, SurroundIndex, SurroundsTreeSeq,
+    EditorTree, EditorTreeFraction, EditorTreeKind, EditorTreePiecewise, EditorTreePower,
+    EditorTreeRoot, EditorTreeSeq, EditorTreeSubscript, EditorTreeSumProd, EditorTreeTerminal,
+    FractionIndex, PiecewiseIndex, RootIndex, SurroundIndex, SurroundsTreeSeq,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -46,8 +47,64 @@ pub trait TreeMovable {
     fn enter_from(&mut self, direction: Direction);
 }
 
+/// Whether a node is part of a word, like `sin` or `2.5`. Other nodes are words by themselves.
+fn is_word_char(child: &EditorTree) -> bool {
+    child.is_terminal_and(|term| term.ch().is_ascii_alphanumeric() || term.ch() == '.')
+}
+
+fn is_space(child: &EditorTree) -> bool {
+    child.is_terminal_and(|term| term.ch() == ' ')
+}
+
+impl EditorTreeSeq {
+    /// The start of the word after the one at `index`.
+    fn next_word(&self, index: usize) -> usize {
+        let children = &self.children[index..];
+        let word = match children.first().is_some_and(is_word_char) {
+            true => children
+                .iter()
+                .take_while(|child| is_word_char(child))
+                .count(),
+            false => 1,
+        };
+        let spaces = children[word..]
+            .iter()
+            .take_while(|child| is_space(child))
+            .count();
+        index + word + spaces
+    }
+
+    /// The start of the word before `index`.
+    fn previous_word(&self, index: usize) -> usize {
+        let children = &self.children[..index];
+        let spaces = children
+            .iter()
+            .rev()
+            .take_while(|child| is_space(child))
+            .count();
+        let children = &children[..index - spaces];
+        let word = match children.last().is_some_and(is_word_char) {
+            true => children
+                .iter()
+                .rev()
+                .take_while(|child| is_word_char(child))
+                .count(),
+            false => children.len().min(1),
+        };
+        index - spaces - word
+    }
+}
+
 impl TreeMovable for EditorTreeSeq {
     fn apply_move(&mut self, movement: Motion) -> Option<Motion> {
+        // leaving a node counts as being at the start of its word, for the nodes with a left.
+        let was_inside = self.active_child().is_some_and(|child| {
+            child.active_child().is_some()
+                && !matches!(
+                    child.kind(),
+                    EditorTreeKind::Power(_) | EditorTreeKind::Subscript(_)
+                )
+        });
         let movement = self
             .children
             .get_mut(self.cursor)
@@ -62,8 +119,15 @@ impl TreeMovable for EditorTreeSeq {
                 true => return Some(Motion::Right),
                 false => self.move_right(1),
             },
-            Some(Motion::Word) => todo!(),
-            Some(Motion::Back) => todo!(),
+            Some(Motion::Word) => match self.cursor == self.children.len() {
+                true => return Some(Motion::Word),
+                false => self.move_to(self.next_word(self.cursor), Direction::Left),
+            },
+            Some(Motion::Back) => match (self.cursor, was_inside) {
+                (0, false) => return Some(Motion::Back),
+                (cursor, true) => self.move_to(cursor, Direction::Left),
+                (cursor, false) => self.move_to(self.previous_word(cursor), Direction::Left),
+            },
             Some(Motion::First) => match self.cursor == 0 {
                 true => return movement,
                 false => self.move_to(0, Direction::Left),
@@ -108,6 +172,16 @@ impl TreeMovable for EditorTreePower {
     }
 }
 
+impl TreeMovable for EditorTreeSubscript {
+    fn apply_move(&mut self, movement: Motion) -> Option<Motion> {
+        self.subscript.apply_move(movement)
+    }
+
+    fn enter_from(&mut self, direction: Direction) {
+        self.subscript.enter_from(direction);
+    }
+}
+
 impl TreeMovable for EditorTreeFraction {
     fn apply_move(&mut self, movement: Motion) -> Option<Motion> {
         match self.cursor {
@@ -234,17 +308,118 @@ impl TreeMovable for EditorTreeSumProd {
     }
 }
 
+impl TreeMovable for EditorTreeRoot {
+    fn apply_move(&mut self, movement: Motion) -> Option<Motion> {
+        match self.cursor {
+            RootIndex::Left => match movement {
+                Motion::Right => self.move_to(RootIndex::Index, Direction::Left),
+                _ => return Some(movement),
+            },
+            RootIndex::Index => match self.index.apply_move(movement) {
+                Some(Motion::Left | Motion::Back) => self.cursor = RootIndex::Left,
+                Some(Motion::Right | Motion::Down | Motion::Word) => {
+                    self.move_to(RootIndex::Inside, Direction::Left)
+                }
+                Some(Motion::Last) => self.move_to(RootIndex::Inside, Direction::Right),
+                outcome @ (None | Some(Motion::Up | Motion::First)) => return outcome,
+            },
+            RootIndex::Inside => match self.child.apply_move(movement) {
+                Some(Motion::Left | Motion::Up | Motion::Back) => {
+                    self.move_to(RootIndex::Index, Direction::Right)
+                }
+                Some(Motion::First) => self.move_to(RootIndex::Index, Direction::Left),
+                outcome @ (None
+                | Some(Motion::Right | Motion::Down | Motion::Word | Motion::Last)) => {
+                    return outcome
+                }
+            },
+        }
+        None
+    }
+
+    fn enter_from(&mut self, direction: Direction) {
+        match direction {
+            Direction::Left => self.move_to(RootIndex::Left, direction),
+            Direction::Up => self.move_to(RootIndex::Index, direction),
+            Direction::Right | Direction::Down => self.move_to(RootIndex::Inside, direction),
+        }
+    }
+}
+
+impl TreeMovable for EditorTreePiecewise {
+    fn apply_move(&mut self, movement: Motion) -> Option<Motion> {
+        let last_row = self.rows.len() - 1;
+        let Some(child) = self.active_child_mut() else {
+            return match movement {
+                Motion::Right => {
+                    self.move_to(PiecewiseIndex::Condition(0), Direction::Left);
+                    None
+                }
+                _ => Some(movement), // the left of the piecewise is outside of it
+            };
+        };
+
+        match child.apply_move(movement) {
+            Some(Motion::Left | Motion::Back) => match self.previous(self.cursor) {
+                Some(PiecewiseIndex::Left) | None => self.cursor = PiecewiseIndex::Left,
+                Some(previous) => self.move_to(previous, Direction::Right),
+            },
+            Some(Motion::Right | Motion::Word) => match self.next(self.cursor) {
+                Some(next) => self.move_to(next, Direction::Left),
+                None => return Some(Motion::Right),
+            },
+            Some(Motion::Up) => match self.cursor {
+                PiecewiseIndex::Condition(row @ 1..) => {
+                    self.move_to(PiecewiseIndex::Condition(row - 1), Direction::Down)
+                }
+                PiecewiseIndex::Value(row @ 1..) => {
+                    self.move_to(PiecewiseIndex::Value(row - 1), Direction::Down)
+                }
+                PiecewiseIndex::Default => {
+                    self.move_to(PiecewiseIndex::Value(last_row), Direction::Down)
+                }
+                _ => return Some(Motion::Up),
+            },
+            Some(Motion::Down) => match self.cursor {
+                PiecewiseIndex::Condition(row) if row < last_row => {
+                    self.move_to(PiecewiseIndex::Condition(row + 1), Direction::Up)
+                }
+                PiecewiseIndex::Value(row) if row < last_row => {
+                    self.move_to(PiecewiseIndex::Value(row + 1), Direction::Up)
+                }
+                PiecewiseIndex::Condition(_) | PiecewiseIndex::Value(_) => {
+                    self.move_to(PiecewiseIndex::Default, Direction::Up)
+                }
+                _ => return Some(Motion::Down),
+            },
+            outcome @ (None | Some(Motion::First | Motion::Last)) => return outcome,
+        }
+        None
+    }
+
+    fn enter_from(&mut self, direction: Direction) {
+        match direction {
+            Direction::Left => self.move_to(PiecewiseIndex::Left, direction),
+            Direction::Up => self.move_to(PiecewiseIndex::Condition(0), direction),
+            Direction::Right | Direction::Down => self.move_to(PiecewiseIndex::Default, direction),
+        }
+    }
+}
+
 impl TreeMovable for EditorTree {
     fn enter_from(&mut self, direction: Direction) {
         match &mut self.kind {
             EditorTreeKind::Terminal(term) => term.enter_from(direction),
             EditorTreeKind::Power(power) => power.enter_from(direction),
+            EditorTreeKind::Subscript(subscript) => subscript.enter_from(direction),
             EditorTreeKind::Fraction(fraction) => fraction.enter_from(direction),
             EditorTreeKind::Sqrt(sqrt) => sqrt.enter_from(direction),
+            EditorTreeKind::Root(root) => root.enter_from(direction),
             EditorTreeKind::Paren(paren) => paren.enter_from(direction),
             EditorTreeKind::Abs(abs) => abs.enter_from(direction),
             EditorTreeKind::Bracket(bracket) => bracket.enter_from(direction),
             EditorTreeKind::Curly(curly) => curly.enter_from(direction),
+            EditorTreeKind::Piecewise(piecewise) => piecewise.enter_from(direction),
             EditorTreeKind::SumProd(sum_prod) => sum_prod.enter_from(direction),
         }
     }
@@ -253,12 +428,15 @@ impl TreeMovable for EditorTree {
         match &mut self.kind {
             EditorTreeKind::Terminal(term) => term.apply_move(movement),
             EditorTreeKind::Power(power) => power.apply_move(movement),
+            EditorTreeKind::Subscript(subscript) => subscript.apply_move(movement),
             EditorTreeKind::Fraction(fraction) => fraction.apply_move(movement),
             EditorTreeKind::Sqrt(sqrt) => sqrt.apply_move(movement),
+            EditorTreeKind::Root(root) => root.apply_move(movement),
             EditorTreeKind::Paren(paren) => paren.apply_move(movement),
             EditorTreeKind::Abs(abs) => abs.apply_move(movement),
             EditorTreeKind::Bracket(bracket) => bracket.apply_move(movement),
             EditorTreeKind::Curly(curly) => curly.apply_move(movement),
+            EditorTreeKind::Piecewise(piecewise) => piecewise.apply_move(movement),
             EditorTreeKind::SumProd(sum_prod) => sum_prod.apply_move(movement),
         }
     }
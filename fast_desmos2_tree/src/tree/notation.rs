@@ -0,0 +1,512 @@
+//! A compact text form of trees, caret included, for tests and bug reports.
+//!
+//! Characters are written as themselves, escaping `\`, `{`, `}`, `|` and `'` with a backslash.
+//! Other nodes are written like LaTeX commands with a braced group for each of their sequences:
+//!
+//! | node            | notation                               |
+//! |-----------------|----------------------------------------|
+//! | fraction        | `\frac{top}{bottom}`                   |
+//! | power           | `\pow{power}`                          |
+//! | subscript       | `\sub{subscript}`                      |
+//! | square root     | `\sqrt{child}`                         |
+//! | root            | `\root{index}{child}`                  |
+//! | brackets        | `\paren{}`, `\abs{}`, `\brack{}`, `\curly{}`, with a `?` after the name while incomplete |
+//! | sum and product | `\sum{ident}{bottom}{top}`, `\prod{…}` |
+//! | piecewise       | `\cases{condition}{value}…{default}`   |
+//!
+//! The caret is written `|`. Every other sequence and node remembers a cursor for when the caret
+//! comes back, written `'`: in a sequence where its cursor is unless it's at the start, and before
+//! the group a node's cursor is in unless it's left of the node. Typing `a/b`, then going right,
+//! gives `\frac{a}'{b'}|`.
+//!
+//! [`EditorTreeSeq::from_notation`] reads it back into exactly the tree it was written from.
+
+use std::fmt::{self, Debug, Formatter};
+
+use thiserror::Error;
+
+use super::{
+    EditorTree, EditorTreeKind, EditorTreeSeq, FractionIndex, PiecewiseIndex, PiecewiseRow,
+    RootIndex, SumOrProd, SumProdIndex, SurroundIndex,
+};
+
+/// The characters that have to be escaped.
+const SPECIAL: &[char] = &['\\', '{', '}', '|', '\''];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NotationError {
+    #[error("expected {expected} at byte {index}")]
+    Expected {
+        index: usize,
+        expected: &'static str,
+    },
+    #[error("`\\{name}` at byte {index} isn't a node")]
+    UnknownNode { index: usize, name: String },
+    #[error("`\\{name}` at byte {index} can't have {groups} groups")]
+    Groups {
+        index: usize,
+        name: String,
+        groups: usize,
+    },
+    #[error("the sequence or node at byte {0} has more than one cursor")]
+    Cursors(usize),
+    #[error("the caret at byte {0} is left of a node it should be in")]
+    CaretOutside(usize),
+    #[error("there's no caret")]
+    NoCaret,
+    #[error("there's a second caret at byte {0}")]
+    SecondCaret(usize),
+}
+
+pub type NotationResult<T> = Result<T, NotationError>;
+
+impl EditorTreeSeq {
+    /// Reads a tree from its notation, see [`EditorTreeSeq::notation`].
+    pub fn from_notation(notation: &str) -> NotationResult<Self> {
+        match Self::read_notation(notation)? {
+            (seq, true) => Ok(seq),
+            (_, false) => Err(NotationError::NoCaret),
+        }
+    }
+
+    /// Reads a tree that isn't being edited, such as a pattern to search for, so that the caret
+    /// can be left out. Without one, it's at the start.
+    pub fn from_notation_without_caret(notation: &str) -> NotationResult<Self> {
+        Self::read_notation(notation).map(|(seq, _)| seq)
+    }
+
+    fn read_notation(notation: &str) -> NotationResult<(Self, bool)> {
+        let mut reader = Reader {
+            source: notation,
+            index: 0,
+            caret: None,
+        };
+        let read = reader.seq()?;
+        if reader.index < notation.len() {
+            return Err(reader.expected("the end"));
+        }
+        Ok(read)
+    }
+
+    pub fn notation(&self) -> String {
+        let mut out = String::new();
+        self.write_notation(&mut out, true);
+        out
+    }
+
+    /// Writes the sequence, `on_path` telling whether the caret is in it or further in.
+    fn write_notation(&self, out: &mut String, on_path: bool) {
+        let entered = self
+            .active_child()
+            .is_some_and(|child| child.active_child().is_some());
+        let mark = |out: &mut String| match on_path {
+            true if !entered => out.push('|'),
+            true => {}
+            false if self.cursor != 0 => out.push('\''),
+            false => {}
+        };
+        for (index, child) in self.children.iter().enumerate() {
+            if index == self.cursor {
+                mark(out);
+            }
+            child.write_notation(out, on_path && index == self.cursor);
+        }
+        if self.cursor == self.children.len() {
+            mark(out);
+        }
+    }
+}
+
+/// A node other than a character, taken apart into what the notation writes.
+struct Groups<'a> {
+    name: &'static str,
+    incomplete: bool,
+    seqs: Vec<&'a EditorTreeSeq>,
+    /// The group the node's cursor is in, if it has a cursor and it isn't left of the node.
+    cursor: Option<usize>,
+    has_cursor: bool,
+}
+
+impl<'a> Groups<'a> {
+    fn new(name: &'static str, seqs: Vec<&'a EditorTreeSeq>, cursor: Option<usize>) -> Self {
+        Self {
+            name,
+            incomplete: false,
+            seqs,
+            cursor,
+            has_cursor: true,
+        }
+    }
+
+    fn surround(
+        name: &'static str,
+        is_complete: bool,
+        cursor: SurroundIndex,
+        child: &'a EditorTreeSeq,
+    ) -> Self {
+        let cursor = (cursor == SurroundIndex::Inside).then_some(0);
+        Self {
+            incomplete: !is_complete,
+            ..Self::new(name, vec![child], cursor)
+        }
+    }
+
+    /// Powers and subscripts, which are always entered.
+    fn without_cursor(name: &'static str, child: &'a EditorTreeSeq) -> Self {
+        Self {
+            has_cursor: false,
+            ..Self::new(name, vec![child], Some(0))
+        }
+    }
+}
+
+impl EditorTree {
+    fn write_notation(&self, out: &mut String, on_path: bool) {
+        let groups = match &self.kind {
+            EditorTreeKind::Terminal(term) => {
+                if SPECIAL.contains(&term.ch) {
+                    out.push('\\');
+                }
+                out.push(term.ch);
+                return;
+            }
+            EditorTreeKind::Fraction(fraction) => {
+                let cursor = match fraction.cursor {
+                    FractionIndex::Left => None,
+                    FractionIndex::Top => Some(0),
+                    FractionIndex::Bottom => Some(1),
+                };
+                Groups::new("frac", vec![&fraction.top, &fraction.bottom], cursor)
+            }
+            EditorTreeKind::Power(power) => Groups::without_cursor("pow", &power.power),
+            EditorTreeKind::Subscript(subscript) => {
+                Groups::without_cursor("sub", &subscript.subscript)
+            }
+            EditorTreeKind::Sqrt(sqrt) => Groups::surround("sqrt", true, sqrt.cursor, &sqrt.child),
+            EditorTreeKind::Root(root) => {
+                let cursor = match root.cursor {
+                    RootIndex::Left => None,
+                    RootIndex::Index => Some(0),
+                    RootIndex::Inside => Some(1),
+                };
+                Groups::new("root", vec![&root.index, &root.child], cursor)
+            }
+            EditorTreeKind::Paren(paren) => {
+                Groups::surround("paren", paren.is_complete, paren.cursor, &paren.child)
+            }
+            EditorTreeKind::Abs(abs) => {
+                Groups::surround("abs", abs.is_complete, abs.cursor, &abs.child)
+            }
+            EditorTreeKind::Bracket(bracket) => {
+                Groups::surround("brack", bracket.is_complete, bracket.cursor, &bracket.child)
+            }
+            EditorTreeKind::Curly(curly) => {
+                Groups::surround("curly", curly.is_complete, curly.cursor, &curly.child)
+            }
+            EditorTreeKind::SumProd(sum_prod) => {
+                let name = match sum_prod.sum_or_prod {
+                    SumOrProd::Sum => "sum",
+                    SumOrProd::Prod => "prod",
+                };
+                let cursor = match sum_prod.cursor {
+                    SumProdIndex::Left => None,
+                    SumProdIndex::BottomIdent => Some(0),
+                    SumProdIndex::BottomExpr => Some(1),
+                    SumProdIndex::Top => Some(2),
+                };
+                let seqs = vec![&sum_prod.ident, &sum_prod.bottom, &sum_prod.top];
+                Groups::new(name, seqs, cursor)
+            }
+            EditorTreeKind::Piecewise(piecewise) => {
+                let cursor = match piecewise.cursor {
+                    PiecewiseIndex::Left => None,
+                    PiecewiseIndex::Condition(row) => Some(2 * row),
+                    PiecewiseIndex::Value(row) => Some(2 * row + 1),
+                    PiecewiseIndex::Default => Some(2 * piecewise.rows.len()),
+                };
+                let seqs = piecewise
+                    .rows
+                    .iter()
+                    .flat_map(|row| [&row.condition, &row.value])
+                    .chain([&piecewise.default])
+                    .collect();
+                Groups::new("cases", seqs, cursor)
+            }
+        };
+
+        out.push('\\');
+        out.push_str(groups.name);
+        if groups.incomplete {
+            out.push('?');
+        }
+        for (index, seq) in groups.seqs.into_iter().enumerate() {
+            let is_cursor = groups.cursor == Some(index);
+            if is_cursor && groups.has_cursor && !on_path {
+                out.push('\'');
+            }
+            out.push('{');
+            seq.write_notation(out, on_path && is_cursor);
+            out.push('}');
+        }
+    }
+}
+
+/// Shows the notation, which is much shorter than the fields.
+impl Debug for EditorTreeSeq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "EditorTreeSeq({})", self.notation())
+    }
+}
+
+/// Shows the notation of the node on its own, as if the caret were elsewhere.
+impl Debug for EditorTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut notation = String::new();
+        self.write_notation(&mut notation, false);
+        write!(f, "EditorTree({notation})")
+    }
+}
+
+struct Reader<'a> {
+    source: &'a str,
+    /// The byte read next.
+    index: usize,
+    /// Where the caret was found.
+    caret: Option<usize>,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.index..].chars().next()
+    }
+
+    /// Whether the rest starts with `prefix`, going past it if so.
+    fn eat(&mut self, prefix: &str) -> bool {
+        let found = self.source[self.index..].starts_with(prefix);
+        if found {
+            self.index += prefix.len();
+        }
+        found
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.index += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn expected(&self, expected: &'static str) -> NotationError {
+        NotationError::Expected {
+            index: self.index,
+            expected,
+        }
+    }
+
+    /// Reads a sequence up to the end or a `}`, along with whether the caret is in it.
+    fn seq(&mut self) -> NotationResult<(EditorTreeSeq, bool)> {
+        let start = self.index;
+        let mut children = Vec::new();
+        let mut mark = None;
+        let mut caret = None;
+        let mut entered = None;
+        while let Some(ch) = self.peek() {
+            let at = self.index;
+            match ch {
+                '}' => break,
+                // groups only come after a node's name.
+                '{' => return Err(self.expected("a character or node")),
+                _ if self.source[at..].starts_with("'{") => {
+                    return Err(self.expected("a character or node"))
+                }
+                '|' | '\'' => {
+                    self.next();
+                    if mark.replace(children.len()).is_some() {
+                        return Err(NotationError::Cursors(start));
+                    }
+                    if ch == '|' {
+                        if self.caret.replace(at).is_some() {
+                            return Err(NotationError::SecondCaret(at));
+                        }
+                        caret = Some(at);
+                    }
+                }
+                '\\' => {
+                    self.next();
+                    match self.peek() {
+                        Some(ch) if ch.is_ascii_alphabetic() => {
+                            let (node, has_caret) = self.node(at)?;
+                            if has_caret {
+                                entered = Some(children.len());
+                            }
+                            children.push(node);
+                        }
+                        Some(ch) if SPECIAL.contains(&ch) => {
+                            self.next();
+                            children.push(EditorTree::terminal(ch));
+                        }
+                        _ => return Err(self.expected("a node name or an escaped character")),
+                    }
+                }
+                _ => {
+                    self.next();
+                    children.push(EditorTree::terminal(ch));
+                }
+            }
+        }
+
+        let cursor = match (entered, caret) {
+            (Some(_), Some(_)) => return Err(NotationError::Cursors(start)),
+            (Some(_), None) if mark.is_some() => return Err(NotationError::Cursors(start)),
+            (Some(entered), None) => entered,
+            (None, _) => mark.unwrap_or(0),
+        };
+        let seq = EditorTreeSeq::new(cursor, children);
+        if let Some(caret) = caret {
+            // the caret would be inside a node that's entered.
+            if seq
+                .active_child()
+                .is_some_and(|child| child.active_child().is_some())
+            {
+                return Err(NotationError::CaretOutside(caret));
+            }
+        }
+        Ok((seq, entered.is_some() || caret.is_some()))
+    }
+
+    /// Reads a node after its `\\`, which is at `start`, along with whether the caret is in it.
+    fn node(&mut self, start: usize) -> NotationResult<(EditorTree, bool)> {
+        let name_start = self.index;
+        while self.peek().is_some_and(|ch| ch.is_ascii_alphabetic()) {
+            self.next();
+        }
+        let name = &self.source[name_start..self.index];
+        let incomplete_at = self.index;
+        let incomplete = self.eat("?");
+
+        let mut seqs = Vec::new();
+        let mut cursor = None;
+        let mut caret = None;
+        loop {
+            let marked = self.eat("'{");
+            if !marked && !self.eat("{") {
+                break;
+            }
+            if marked && cursor.replace(seqs.len()).is_some() {
+                return Err(NotationError::Cursors(start));
+            }
+            let (seq, has_caret) = self.seq()?;
+            if has_caret {
+                caret = Some(seqs.len());
+            }
+            seqs.push(seq);
+            if !self.eat("}") {
+                return Err(self.expected("`}`"));
+            }
+        }
+        if caret.is_some() && cursor.is_some() {
+            return Err(NotationError::Cursors(start));
+        }
+        let cursor = caret.or(cursor);
+
+        let groups_error = |groups| NotationError::Groups {
+            index: start,
+            name: name.to_string(),
+            groups,
+        };
+        let count = seqs.len();
+        let mut seqs = seqs.into_iter();
+        let mut seq = || seqs.next().unwrap_or_else(EditorTreeSeq::empty);
+        let surround = |cursor: Option<usize>| match cursor {
+            Some(_) => SurroundIndex::Inside,
+            None => SurroundIndex::Left,
+        };
+        let completable = matches!(name, "paren" | "abs" | "brack" | "curly");
+        if incomplete && !completable {
+            return Err(NotationError::Expected {
+                index: incomplete_at,
+                expected: "`{`",
+            });
+        }
+        if matches!(name, "pow" | "sub") && cursor.is_some() && caret.is_none() {
+            // they have no cursor of their own to remember.
+            return Err(NotationError::Cursors(start));
+        }
+
+        let expected_count = match name {
+            "frac" | "root" => 2,
+            "pow" | "sub" | "sqrt" | "paren" | "abs" | "brack" | "curly" => 1,
+            "sum" | "prod" => 3,
+            "cases" if count >= 3 && count % 2 == 1 => count,
+            "cases" => return Err(groups_error(count)),
+            _ => {
+                return Err(NotationError::UnknownNode {
+                    index: start,
+                    name: name.to_string(),
+                })
+            }
+        };
+        if count != expected_count {
+            return Err(groups_error(count));
+        }
+
+        let node = match name {
+            "frac" => {
+                let cursor = match cursor {
+                    None => FractionIndex::Left,
+                    Some(0) => FractionIndex::Top,
+                    Some(_) => FractionIndex::Bottom,
+                };
+                EditorTree::fraction(cursor, seq(), seq())
+            }
+            "pow" => EditorTree::power(seq()),
+            "sub" => EditorTree::subscript(seq()),
+            "sqrt" => EditorTree::sqrt(surround(cursor), seq()),
+            "root" => {
+                let cursor = match cursor {
+                    None => RootIndex::Left,
+                    Some(0) => RootIndex::Index,
+                    Some(_) => RootIndex::Inside,
+                };
+                EditorTree::root(cursor, seq(), seq())
+            }
+            "paren" | "abs" | "brack" | "curly" => {
+                let (cursor, child) = (surround(cursor), seq());
+                match (name, incomplete) {
+                    ("paren", false) => EditorTree::complete_paren(cursor, child),
+                    ("paren", true) => EditorTree::incomplete_paren(cursor, child),
+                    ("abs", false) => EditorTree::complete_abs(cursor, child),
+                    ("abs", true) => EditorTree::incomplete_abs(cursor, child),
+                    ("brack", false) => EditorTree::complete_brackets(cursor, child),
+                    ("brack", true) => EditorTree::incomplete_brackets(cursor, child),
+                    (_, false) => EditorTree::complete_curly(cursor, child),
+                    (_, true) => EditorTree::incomplete_curly(cursor, child),
+                }
+            }
+            "sum" | "prod" => {
+                let cursor = match cursor {
+                    None => SumProdIndex::Left,
+                    Some(0) => SumProdIndex::BottomIdent,
+                    Some(1) => SumProdIndex::BottomExpr,
+                    Some(_) => SumProdIndex::Top,
+                };
+                let (ident, bottom, top) = (seq(), seq(), seq());
+                match name {
+                    "sum" => EditorTree::sum(cursor, top, bottom, ident),
+                    _ => EditorTree::prod(cursor, top, bottom, ident),
+                }
+            }
+            _ => {
+                let rows = (count - 1) / 2;
+                let cursor = match cursor {
+                    None => PiecewiseIndex::Left,
+                    Some(slot) if slot == 2 * rows => PiecewiseIndex::Default,
+                    Some(slot) if slot % 2 == 0 => PiecewiseIndex::Condition(slot / 2),
+                    Some(slot) => PiecewiseIndex::Value(slot / 2),
+                };
+                let rows = (0..rows).map(|_| PiecewiseRow::new(seq(), seq())).collect();
+                EditorTree::piecewise(cursor, rows, seq())
+            }
+        };
+        Ok((node, caret.is_some()))
+    }
+}
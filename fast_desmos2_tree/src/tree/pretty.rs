@@ -0,0 +1,758 @@
+//! Drawing trees the way they're typeset, for using the editor in a terminal. Unlike
+//! [`debug`](super::debug), nothing is boxed: fractions get a bar, powers are raised, and
+//! brackets grow with what they hold. The caret is shown in inverse video.
+//!
+//! The boxes remember which sequences, children and slots of the tree they were laid out from, so
+//! that [`PrettyTree::hit_test`] can take a point on the screen back to a [`CursorPath`].
+
+use std::fmt::Display;
+#[cfg(feature = "termion")]
+use std::io::Write as _;
+
+use glam::UVec2;
+
+use super::{
+    CombinedCursor, CursorPath, EditorTree, EditorTreeFraction, EditorTreeKind,
+    EditorTreePiecewise, EditorTreePower, EditorTreeRoot, EditorTreeSeq, EditorTreeSqrt,
+    EditorTreeSubscript, EditorTreeSumProd, FractionIndex, PathStep, PiecewiseIndex, PiecewiseRow,
+    RootIndex, SumOrProd, SumProdIndex, SurroundIndex,
+};
+
+/// Drawn in place of an empty sequence.
+const PLACEHOLDER: char = '□';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellStyle {
+    #[default]
+    Plain,
+    /// Where the caret is, in inverse video.
+    Caret,
+    /// Things that aren't typed, like the value of the cell or the missing side of brackets.
+    Faint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrettyCell {
+    pub ch: char,
+    pub style: CellStyle,
+}
+
+#[derive(Debug)]
+pub struct PrettyScreen {
+    screen: Vec<PrettyCell>,
+    width: usize,
+    height: usize,
+}
+
+impl PrettyScreen {
+    fn new(width: usize, height: usize) -> Self {
+        let blank = PrettyCell {
+            ch: ' ',
+            style: CellStyle::Plain,
+        };
+        Self {
+            screen: vec![blank; width * height],
+            width,
+            height,
+        }
+    }
+
+    fn calc_index(&self, pos: UVec2) -> usize {
+        pos.x as usize + pos.y as usize * self.width
+    }
+
+    fn write(&mut self, pos: UVec2, ch: char, style: CellStyle) {
+        let index = self.calc_index(pos);
+        self.screen[index] = PrettyCell { ch, style };
+    }
+
+    pub fn size(&self) -> UVec2 {
+        UVec2::new(self.width as u32, self.height as u32)
+    }
+
+    pub fn get(&self, pos: UVec2) -> PrettyCell {
+        self.screen[self.calc_index(pos)]
+    }
+
+    /// The top left of the caret, if it was drawn.
+    pub fn caret(&self) -> Option<UVec2> {
+        let index = self
+            .screen
+            .iter()
+            .position(|cell| cell.style == CellStyle::Caret)?;
+        Some(UVec2::new(
+            (index % self.width) as u32,
+            (index / self.width) as u32,
+        ))
+    }
+
+    #[cfg(feature = "termion")]
+    pub fn display_raw(
+        &self,
+        to: &mut termion::raw::RawTerminal<std::io::Stdout>,
+        offset: UVec2,
+    ) -> std::io::Result<()> {
+        use termion::{cursor, style};
+        for y in 0..self.height {
+            write!(
+                to,
+                "{}",
+                cursor::Goto(offset.x as u16 + 1, offset.y as u16 + y as u16 + 1)
+            )?;
+            for x in 0..self.width {
+                let cell = self.get(UVec2::new(x as u32, y as u32));
+                match cell.style {
+                    CellStyle::Plain => write!(to, "{}", cell.ch)?,
+                    CellStyle::Caret => {
+                        write!(to, "{}{}{}", style::Invert, cell.ch, style::NoInvert)?
+                    }
+                    CellStyle::Faint => {
+                        write!(to, "{}{}{}", style::Faint, cell.ch, style::NoFaint)?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shows the characters alone, the caret included, with trailing spaces trimmed.
+impl Display for PrettyScreen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            let row = &self.screen[y * self.width..(y + 1) * self.width];
+            let line: String = row.iter().map(|cell| cell.ch).collect();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A laid out node. The baseline is the row that lines up with the characters around it.
+#[derive(Debug)]
+pub struct PrettyTree {
+    offset: UVec2,
+    size: UVec2,
+    baseline: u32,
+    kind: PrettyTreeKind,
+    target: PrettyTarget,
+}
+
+/// What part of the tree a box was laid out from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrettyTarget {
+    None,
+    /// A sequence with this many children.
+    Seq(usize),
+    /// The `n`th child of the sequence around it, or the caret or placeholder after the last one.
+    Child(usize),
+    /// The sequence of the node around it in this slot.
+    Slot(CombinedCursor),
+}
+
+impl PrettyTarget {
+    const fn seq(self) -> Option<usize> {
+        match self {
+            Self::Seq(len) => Some(len),
+            _ => None,
+        }
+    }
+
+    const fn child(self) -> Option<usize> {
+        match self {
+            Self::Child(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    const fn slot(self) -> Option<CombinedCursor> {
+        match self {
+            Self::Slot(slot) => Some(slot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PrettyTreeKind {
+    Empty,
+    Fill(char, CellStyle),
+    Text(String, CellStyle),
+    HorizontalBar,
+    /// A bracket as tall as the node, drawn with the pieces of tall Unicode brackets.
+    Bracket(BracketShape, CellStyle),
+    /// The root sign, as tall as the node, with the bar over the child.
+    RootSign(Box<PrettyTree>),
+    Children(Vec<PrettyTree>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BracketShape {
+    ParenLeft,
+    ParenRight,
+    BracketLeft,
+    BracketRight,
+    CurlyLeft,
+    CurlyRight,
+    Bar,
+}
+
+impl BracketShape {
+    /// The character when it's one line high, then the top, middle, bottom and the extension.
+    const fn pieces(self) -> [char; 5] {
+        match self {
+            Self::ParenLeft => ['(', '⎛', '⎜', '⎝', '⎜'],
+            Self::ParenRight => [')', '⎞', '⎟', '⎠', '⎟'],
+            Self::BracketLeft => ['[', '⎡', '⎢', '⎣', '⎢'],
+            Self::BracketRight => [']', '⎤', '⎥', '⎦', '⎥'],
+            Self::CurlyLeft => ['{', '⎧', '⎨', '⎩', '⎪'],
+            Self::CurlyRight => ['}', '⎫', '⎬', '⎭', '⎪'],
+            Self::Bar => ['|', '│', '│', '│', '│'],
+        }
+    }
+}
+
+impl PrettyTree {
+    pub fn render(&self) -> PrettyScreen {
+        let mut screen = PrettyScreen::new(self.size.x as usize, self.size.y as usize);
+        self.render_to(&mut screen, UVec2::ZERO);
+        screen
+    }
+
+    fn render_to(&self, screen: &mut PrettyScreen, offset: UVec2) {
+        match self.kind {
+            PrettyTreeKind::Empty => {}
+            PrettyTreeKind::Fill(ch, style) => {
+                for y in 0..self.size.y {
+                    for x in 0..self.size.x {
+                        screen.write(offset + UVec2::new(x, y), ch, style);
+                    }
+                }
+            }
+            PrettyTreeKind::Text(ref string, style) => {
+                for (x, ch) in string.chars().enumerate() {
+                    screen.write(offset + UVec2::new(x as u32, 0), ch, style);
+                }
+            }
+            PrettyTreeKind::HorizontalBar => {
+                for x in 0..self.size.x {
+                    screen.write(offset + UVec2::new(x, 0), '─', CellStyle::Plain);
+                }
+            }
+            PrettyTreeKind::Bracket(shape, style) => {
+                let [single, top, middle, bottom, extension] = shape.pieces();
+                let height = self.size.y;
+                if height == 1 {
+                    screen.write(offset, single, style);
+                    return;
+                }
+                for y in 0..height {
+                    let ch = match y {
+                        0 => top,
+                        y if y == height - 1 => bottom,
+                        y if y == (height - 1) / 2 => middle,
+                        _ => extension,
+                    };
+                    screen.write(offset.with_y(offset.y + y), ch, style);
+                }
+            }
+            PrettyTreeKind::RootSign(ref child) => {
+                child.render_to(screen, offset + child.offset);
+                let outer = offset + self.size - 1;
+                screen.write(offset, '┌', CellStyle::Plain);
+                for x in (offset.x + 1)..=outer.x {
+                    screen.write(offset.with_x(x), '─', CellStyle::Plain);
+                }
+                for y in (offset.y + 1)..outer.y {
+                    screen.write(offset.with_y(y), '│', CellStyle::Plain);
+                }
+                screen.write(offset.with_y(outer.y), '√', CellStyle::Plain);
+            }
+            PrettyTreeKind::Children(ref children) => children
+                .iter()
+                .for_each(|child| child.render_to(screen, offset + child.offset)),
+        }
+    }
+}
+
+/// Finding where in the tree a point on the screen is.
+impl PrettyTree {
+    /// Where the caret goes when `point`, relative to the top left of the node, is clicked. The
+    /// node has to be laid out from a whole [`EditorTreeSeq`], the way it's drawn.
+    ///
+    /// The caret goes in front of the child the point is over, or after the last one when the
+    /// point is past it. Points over the slots of a node go into them, the rest of the node puts
+    /// the caret in front of it.
+    pub fn hit_test(&self, point: UVec2) -> CursorPath {
+        let mut path = CursorPath::default();
+        let seqs = self.targets(UVec2::ZERO, &PrettyTarget::seq);
+        let Some(mut seq) = seqs.into_iter().next() else {
+            return path;
+        };
+        loop {
+            let (len, tree, offset) = seq;
+            let over = tree
+                .targets(offset, &PrettyTarget::child)
+                .into_iter()
+                .find(|(_, child, offset)| (offset.x..offset.x + child.size.x).contains(&point.x));
+            let Some((index, child, offset)) = over else {
+                path.index = len;
+                return path;
+            };
+            let slot = child
+                .targets(offset, &PrettyTarget::slot)
+                .into_iter()
+                .find(|(_, slot, offset)| slot.contains(*offset, point));
+            let Some((slot, tree, offset)) = slot else {
+                path.index = index;
+                return path;
+            };
+            path.steps.push(PathStep { child: index, slot });
+            seq = tree
+                .targets(offset, &PrettyTarget::seq)
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| unreachable!());
+        }
+    }
+
+    fn contains(&self, offset: UVec2, point: UVec2) -> bool {
+        point.cmpge(offset).all() && point.cmplt(offset + self.size).all()
+    }
+
+    /// The outermost nodes under this one, this one included, that `pick` takes something out of
+    /// the target of, with where they are. Nodes inside them aren't looked at.
+    fn targets<T>(
+        &self,
+        offset: UVec2,
+        pick: &impl Fn(PrettyTarget) -> Option<T>,
+    ) -> Vec<(T, &PrettyTree, UVec2)> {
+        if let Some(picked) = pick(self.target) {
+            return vec![(picked, self, offset)];
+        }
+        match &self.kind {
+            PrettyTreeKind::Children(children) => children
+                .iter()
+                .flat_map(|child| child.targets(offset + child.offset, pick))
+                .collect(),
+            PrettyTreeKind::RootSign(child) => child.targets(offset + child.offset, pick),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl PrettyTree {
+    pub const fn new(size: UVec2, baseline: u32, kind: PrettyTreeKind) -> Self {
+        Self {
+            offset: UVec2::ZERO,
+            size,
+            baseline,
+            kind,
+            target: PrettyTarget::None,
+        }
+    }
+
+    const fn with_target(mut self, target: PrettyTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Marks the node as the sequence of `slot` in the node around it.
+    fn in_slot(self, slot: CombinedCursor) -> Self {
+        Self::new(self.size, self.baseline, PrettyTreeKind::Empty)
+            .with_target(PrettyTarget::Slot(slot))
+            .containing(self)
+    }
+
+    /// Puts `child` in an otherwise empty node of the same size.
+    fn containing(mut self, child: Self) -> Self {
+        self.kind = PrettyTreeKind::Children(vec![child]);
+        self
+    }
+
+    pub const fn char(ch: char, style: CellStyle) -> Self {
+        Self::new(UVec2::ONE, 0, PrettyTreeKind::Fill(ch, style))
+    }
+
+    pub fn text(string: impl Into<String>, style: CellStyle) -> Self {
+        let string = string.into();
+        Self::new(
+            UVec2::new(string.chars().count() as u32, 1),
+            0,
+            PrettyTreeKind::Text(string, style),
+        )
+    }
+
+    pub const fn empty(size: UVec2, baseline: u32) -> Self {
+        Self::new(size, baseline, PrettyTreeKind::Empty)
+    }
+
+    /// The caret in front of something `height` high, with its baseline at `baseline`.
+    pub const fn caret(height: u32, baseline: u32) -> Self {
+        Self::new(
+            UVec2::new(1, height),
+            baseline,
+            PrettyTreeKind::Fill(' ', CellStyle::Caret),
+        )
+    }
+
+    pub const fn bracket(
+        shape: BracketShape,
+        height: u32,
+        baseline: u32,
+        style: CellStyle,
+    ) -> Self {
+        Self::new(
+            UVec2::new(1, height),
+            baseline,
+            PrettyTreeKind::Bracket(shape, style),
+        )
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.size.y
+    }
+
+    pub const fn baseline(&self) -> u32 {
+        self.baseline
+    }
+
+    /// Puts the nodes side by side with their baselines lined up.
+    pub fn horizontal(mut vec: Vec<PrettyTree>) -> Self {
+        let baseline = vec.iter().map(|item| item.baseline).max().unwrap_or(0);
+        let below = vec
+            .iter()
+            .map(|item| item.size.y - item.baseline)
+            .max()
+            .unwrap_or(1);
+        let mut current_x = 0;
+        for item in vec.iter_mut() {
+            assert!(item.offset == UVec2::ZERO);
+            item.offset = UVec2::new(current_x, baseline - item.baseline);
+            current_x += item.size.x;
+        }
+
+        Self::new(
+            UVec2::new(current_x, baseline + below),
+            baseline,
+            PrettyTreeKind::Children(vec),
+        )
+    }
+
+    /// Stacks the nodes, each centred, with the baseline of the `baseline_of`th one as the
+    /// baseline.
+    pub fn vertical(mut vec: Vec<PrettyTree>, baseline_of: usize) -> Self {
+        let width = vec.iter().map(|item| item.size.x).max().unwrap_or(0);
+        let mut current_y = 0;
+        let mut baseline = 0;
+        for (index, item) in vec.iter_mut().enumerate() {
+            assert!(item.offset == UVec2::ZERO);
+            item.offset = UVec2::new((width - item.size.x) / 2, current_y);
+            if index == baseline_of {
+                baseline = current_y + item.baseline;
+            }
+            current_y += item.size.y;
+        }
+
+        Self::new(
+            UVec2::new(width, current_y),
+            baseline,
+            PrettyTreeKind::Children(vec),
+        )
+    }
+
+    /// Stacks the nodes aligned to the left, with the baseline in the middle.
+    fn rows(mut vec: Vec<PrettyTree>) -> Self {
+        let width = vec.iter().map(|item| item.size.x).max().unwrap_or(0);
+        let mut current_y = 0;
+        for item in vec.iter_mut() {
+            assert!(item.offset == UVec2::ZERO);
+            item.offset = UVec2::new(0, current_y);
+            current_y += item.size.y;
+        }
+
+        Self::new(
+            UVec2::new(width, current_y),
+            current_y.saturating_sub(1) / 2,
+            PrettyTreeKind::Children(vec),
+        )
+    }
+
+    /// Raises the node so that its bottom is just above the baseline, like a power.
+    fn raised(self) -> Self {
+        let size = self.size + UVec2::Y;
+        let baseline = self.size.y;
+        Self::new(size, baseline, PrettyTreeKind::Children(vec![self]))
+    }
+
+    /// Lowers the node so that its top is just below the baseline, like a subscript.
+    fn lowered(mut self) -> Self {
+        self.offset = UVec2::Y;
+        Self::new(
+            self.size + UVec2::Y,
+            0,
+            PrettyTreeKind::Children(vec![self]),
+        )
+    }
+
+    fn under_root_sign(mut self) -> Self {
+        self.offset = UVec2::ONE;
+        let baseline = self.baseline + 1;
+        Self::new(
+            self.size + UVec2::ONE,
+            baseline,
+            PrettyTreeKind::RootSign(Box::new(self)),
+        )
+    }
+
+    /// Puts the caret in front of the node when `with_cursor` is set.
+    fn caret_before(self, with_cursor: bool) -> Self {
+        match with_cursor {
+            true => Self::horizontal(vec![Self::caret(self.size.y, self.baseline), self]),
+            false => self,
+        }
+    }
+
+    /// Shows `value` faintly after the node, as what it evaluates to.
+    pub fn with_value(self, value: &str) -> Self {
+        let value = Self::text(format!("  = {value}"), CellStyle::Faint);
+        Self::horizontal(vec![self, value])
+    }
+}
+
+pub trait Prettyable {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree;
+}
+
+impl Prettyable for EditorTreeSeq {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        let mut nodes = Vec::with_capacity(self.children.len() + 1);
+        for (index, child) in self.children.iter().enumerate() {
+            let node = child.pretty(with_cursor && index == self.cursor);
+            nodes.push(node.with_target(PrettyTarget::Child(index)));
+        }
+
+        let is_cursor_last = with_cursor && self.cursor == self.children.len();
+        let last = match (self.children.is_empty(), is_cursor_last) {
+            (true, true) => Some(PrettyTree::char(PLACEHOLDER, CellStyle::Caret)),
+            (true, false) => Some(PrettyTree::char(PLACEHOLDER, CellStyle::Faint)),
+            (false, true) => Some(PrettyTree::caret(1, 0)),
+            (false, false) => None,
+        };
+        if let Some(last) = last {
+            nodes.push(last.with_target(PrettyTarget::Child(self.children.len())));
+        }
+
+        PrettyTree::horizontal(nodes).with_target(PrettyTarget::Seq(self.children.len()))
+    }
+}
+
+impl Prettyable for EditorTreeFraction {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        let top = self
+            .top
+            .pretty(with_cursor && self.cursor == FractionIndex::Top)
+            .in_slot(CombinedCursor::TOP);
+        let bottom = self
+            .bottom
+            .pretty(with_cursor && self.cursor == FractionIndex::Bottom)
+            .in_slot(CombinedCursor::Fraction(FractionIndex::Bottom));
+        let width = top.size.x.max(bottom.size.x) + 2;
+        let bar = PrettyTree::new(UVec2::new(width, 1), 0, PrettyTreeKind::HorizontalBar);
+
+        PrettyTree::vertical(vec![top, bar, bottom], 1)
+            .caret_before(with_cursor && self.cursor == FractionIndex::Left)
+    }
+}
+
+impl Prettyable for EditorTreePower {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        self.power
+            .pretty(with_cursor)
+            .in_slot(CombinedCursor::Power)
+            .raised()
+    }
+}
+
+impl Prettyable for EditorTreeSubscript {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        self.subscript
+            .pretty(with_cursor)
+            .in_slot(CombinedCursor::Subscript)
+            .lowered()
+    }
+}
+
+impl Prettyable for EditorTreeSqrt {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        self.child()
+            .pretty(with_cursor && self.cursor == SurroundIndex::Inside)
+            .in_slot(CombinedCursor::Sqrt(SurroundIndex::Inside))
+            .under_root_sign()
+            .caret_before(with_cursor && self.cursor == SurroundIndex::Left)
+    }
+}
+
+impl Prettyable for EditorTreeRoot {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        let index = self
+            .index
+            .pretty(with_cursor && self.cursor == RootIndex::Index)
+            .in_slot(CombinedCursor::Root(RootIndex::Index))
+            .raised();
+        let child = self
+            .child
+            .pretty(with_cursor && self.cursor == RootIndex::Inside)
+            .in_slot(CombinedCursor::Root(RootIndex::Inside))
+            .under_root_sign();
+        PrettyTree::horizontal(vec![index, child])
+            .caret_before(with_cursor && self.cursor == RootIndex::Left)
+    }
+}
+
+/// Draws `child` between a `left` and a `right` bracket, the right one faint when it hasn't been
+/// typed. `slot` is the inside of the node.
+fn surround(
+    child: &EditorTreeSeq,
+    cursor: SurroundIndex,
+    is_complete: bool,
+    with_cursor: bool,
+    slot: CombinedCursor,
+    [left, right]: [BracketShape; 2],
+) -> PrettyTree {
+    let child = child
+        .pretty(with_cursor && cursor == SurroundIndex::Inside)
+        .in_slot(slot);
+    let (height, baseline) = (child.height(), child.baseline());
+    let right_style = match is_complete {
+        true => CellStyle::Plain,
+        false => CellStyle::Faint,
+    };
+    PrettyTree::horizontal(vec![
+        PrettyTree::bracket(left, height, baseline, CellStyle::Plain),
+        child,
+        PrettyTree::bracket(right, height, baseline, right_style),
+    ])
+    .caret_before(with_cursor && cursor == SurroundIndex::Left)
+}
+
+impl Prettyable for EditorTreePiecewise {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        let has_cursor = |index| with_cursor && self.cursor == index;
+        let mut lines: Vec<_> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(row, PiecewiseRow { condition, value })| {
+                let condition_index = PiecewiseIndex::Condition(row);
+                let value_index = PiecewiseIndex::Value(row);
+                PrettyTree::horizontal(vec![
+                    condition
+                        .pretty(has_cursor(condition_index))
+                        .in_slot(CombinedCursor::Piecewise(condition_index)),
+                    PrettyTree::text(": ", CellStyle::Plain),
+                    value
+                        .pretty(has_cursor(value_index))
+                        .in_slot(CombinedCursor::Piecewise(value_index)),
+                ])
+            })
+            .collect();
+        if !self.default.is_empty() || has_cursor(PiecewiseIndex::Default) {
+            let default = self.default.pretty(has_cursor(PiecewiseIndex::Default));
+            lines.push(default.in_slot(CombinedCursor::Piecewise(PiecewiseIndex::Default)));
+        }
+        let rows = PrettyTree::rows(lines);
+        let (height, baseline) = (rows.height(), rows.baseline());
+
+        PrettyTree::horizontal(vec![
+            PrettyTree::bracket(BracketShape::CurlyLeft, height, baseline, CellStyle::Plain),
+            rows,
+        ])
+        .caret_before(has_cursor(PiecewiseIndex::Left))
+    }
+}
+
+impl Prettyable for EditorTreeSumProd {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        let bottom_row = PrettyTree::horizontal(vec![
+            self.ident
+                .pretty(with_cursor && self.cursor == SumProdIndex::BottomIdent)
+                .in_slot(CombinedCursor::SumProd(SumProdIndex::BottomIdent)),
+            PrettyTree::char('=', CellStyle::Plain),
+            self.bottom
+                .pretty(with_cursor && self.cursor == SumProdIndex::BottomExpr)
+                .in_slot(CombinedCursor::SumProd(SumProdIndex::BottomExpr)),
+        ]);
+        let symbol = match self.sum_or_prod {
+            SumOrProd::Sum => '∑',
+            SumOrProd::Prod => '∏',
+        };
+        PrettyTree::vertical(
+            vec![
+                self.top
+                    .pretty(with_cursor && self.cursor == SumProdIndex::Top)
+                    .in_slot(CombinedCursor::SumProd(SumProdIndex::Top)),
+                PrettyTree::char(symbol, CellStyle::Plain),
+                bottom_row,
+            ],
+            1,
+        )
+        .caret_before(with_cursor && self.cursor == SumProdIndex::Left)
+    }
+}
+
+impl Prettyable for EditorTree {
+    fn pretty(&self, with_cursor: bool) -> PrettyTree {
+        match &self.kind {
+            EditorTreeKind::Terminal(term) => {
+                let ch = match term.ch {
+                    '×' => '·',
+                    ch => ch,
+                };
+                match with_cursor {
+                    true => PrettyTree::char(ch, CellStyle::Caret),
+                    false => PrettyTree::char(ch, CellStyle::Plain),
+                }
+            }
+            EditorTreeKind::Power(power) => power.pretty(with_cursor),
+            EditorTreeKind::Subscript(subscript) => subscript.pretty(with_cursor),
+            EditorTreeKind::Fraction(fraction) => fraction.pretty(with_cursor),
+            EditorTreeKind::Sqrt(sqrt) => sqrt.pretty(with_cursor),
+            EditorTreeKind::Root(root) => root.pretty(with_cursor),
+            EditorTreeKind::Paren(paren) => surround(
+                paren.child(),
+                paren.cursor(),
+                paren.is_complete(),
+                with_cursor,
+                CombinedCursor::Paren(SurroundIndex::Inside),
+                [BracketShape::ParenLeft, BracketShape::ParenRight],
+            ),
+            EditorTreeKind::Abs(abs) => surround(
+                abs.child(),
+                abs.cursor(),
+                abs.is_complete(),
+                with_cursor,
+                CombinedCursor::Abs(SurroundIndex::Inside),
+                [BracketShape::Bar, BracketShape::Bar],
+            ),
+            EditorTreeKind::Bracket(bracket) => surround(
+                bracket.child(),
+                bracket.cursor(),
+                bracket.is_complete(),
+                with_cursor,
+                CombinedCursor::Bracket(SurroundIndex::Inside),
+                [BracketShape::BracketLeft, BracketShape::BracketRight],
+            ),
+            EditorTreeKind::Curly(curly) => surround(
+                curly.child(),
+                curly.cursor(),
+                curly.is_complete(),
+                with_cursor,
+                CombinedCursor::Curly(SurroundIndex::Inside),
+                [BracketShape::CurlyLeft, BracketShape::CurlyRight],
+            ),
+            EditorTreeKind::Piecewise(piecewise) => piecewise.pretty(with_cursor),
+            EditorTreeKind::SumProd(sum_prod) => sum_prod.pretty(with_cursor),
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Finding trees by their structure, and replacing them.
+//!
+//! A [`TreePattern`] is a tree in which `?` followed by a letter is a wildcard standing for one
+//! or more nodes, so that `\frac{?a}{?b}` finds every fraction and `?a\pow{2}` every square.
+//! A wildcard used twice has to stand for the same nodes both times. Sequences inside the nodes of
+//! a pattern have to match whole, while the pattern itself can match any run of children of any
+//! sequence. Cursors don't matter, and neither does whether brackets were closed.
+//!
+//! Replacing puts a copy of a template, itself a pattern, in place of every match, with the
+//! wildcards standing for what they matched.
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use super::{
+    Direction, EditorTree, EditorTreeKind, EditorTreeSeq, NotationError, PathStep, SelectedRange,
+};
+
+/// Comes before the letter naming a wildcard.
+const WILDCARD: char = '?';
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SearchError {
+    #[error(transparent)]
+    Notation(#[from] NotationError),
+    #[error("the pattern is empty")]
+    Empty,
+    #[error("`?{0}` is in the template but not in the pattern")]
+    Unbound(char),
+}
+
+pub type SearchResult<T> = Result<T, SearchError>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreePattern {
+    seq: EditorTreeSeq,
+}
+
+/// A piece of a sequence of a pattern, with the pieces of the sequences in a node.
+#[derive(Debug, Clone)]
+enum Item<'a> {
+    Node(&'a EditorTree, Vec<Vec<Item<'a>>>),
+    Wildcard(char),
+}
+
+/// What the wildcards matched so far, by name.
+type Bindings<'a> = Vec<(char, &'a [EditorTree])>;
+
+impl TreePattern {
+    pub fn new(seq: EditorTreeSeq) -> SearchResult<Self> {
+        match seq.is_empty() {
+            true => Err(SearchError::Empty),
+            false => Ok(Self { seq }),
+        }
+    }
+
+    /// Reads a pattern written in tree notation, where the caret can be left out.
+    pub fn parse(notation: &str) -> SearchResult<Self> {
+        Self::new(EditorTreeSeq::from_notation_without_caret(notation)?)
+    }
+
+    /// The names of the wildcards, in the order they first appear.
+    pub fn wildcards(&self) -> Vec<char> {
+        let mut names = Vec::new();
+        collect_wildcards(&self.seq, &mut names);
+        names
+    }
+
+    /// Checks that `template` only uses the wildcards of this pattern.
+    fn check_template(&self, template: &TreePattern) -> SearchResult<()> {
+        let names = self.wildcards();
+        match template
+            .wildcards()
+            .into_iter()
+            .find(|name| !names.contains(name))
+        {
+            Some(name) => Err(SearchError::Unbound(name)),
+            None => Ok(()),
+        }
+    }
+
+    /// How many children at the start of `children` this matches, if it does.
+    fn match_start<'t>(&self, children: &'t [EditorTree]) -> Option<(usize, Bindings<'t>)> {
+        let items = items(&self.seq);
+        let mut search = Search {
+            bindings: Vec::new(),
+            failed: HashSet::new(),
+        };
+        let goal = Goal {
+            items: &items,
+            children,
+            whole: false,
+        };
+        let left = search.run(vec![goal])?;
+        Some((children.len() - left, search.bindings))
+    }
+}
+
+fn collect_wildcards(seq: &EditorTreeSeq, names: &mut Vec<char>) {
+    collect_item_wildcards(&items(seq), names);
+}
+
+fn collect_item_wildcards(items: &[Item], names: &mut Vec<char>) {
+    for item in items {
+        match item {
+            Item::Wildcard(name) if !names.contains(name) => names.push(*name),
+            Item::Wildcard(_) => {}
+            Item::Node(_, seqs) => seqs
+                .iter()
+                .for_each(|items| collect_item_wildcards(items, names)),
+        }
+    }
+}
+
+fn items(seq: &EditorTreeSeq) -> Vec<Item<'_>> {
+    let mut found = Vec::with_capacity(seq.len());
+    let mut children = seq.children.iter().peekable();
+    while let Some(child) = children.next() {
+        let name = children
+            .peek()
+            .and_then(|next| next.is_terminal_then(|term| term.ch()))
+            .filter(|name| child.is_terminal_and_eq(WILDCARD) && name.is_ascii_alphabetic());
+        match name {
+            Some(name) => {
+                children.next();
+                found.push(Item::Wildcard(name));
+            }
+            None => found.push(Item::Node(child, child.child_seqs().map(items).collect())),
+        }
+    }
+    found
+}
+
+/// A sequence of the pattern still to be matched: `items` against the start of `children`, or
+/// all of them when `whole` is set.
+#[derive(Debug, Clone, Copy)]
+struct Goal<'i, 't> {
+    items: &'i [Item<'i>],
+    children: &'t [EditorTree],
+    whole: bool,
+}
+
+/// Where a search is: the goals left, by where their items and children start, and what the
+/// wildcards still in them are bound to.
+type SearchState = (Vec<(usize, usize, usize, bool)>, Vec<(char, usize, usize)>);
+
+/// One backtracking search through every sequence of a pattern, so that what a wildcard takes
+/// in one sequence can still change when a later one doesn't match.
+struct Search<'t> {
+    bindings: Bindings<'t>,
+    /// The states known not to lead to a match, so that wildcards don't try the same splits of a
+    /// sequence over and over.
+    failed: HashSet<SearchState>,
+}
+
+impl<'t> Search<'t> {
+    /// Matches the goals, the last one first, giving how many children the first one left over.
+    /// Wildcards take as few children as they can. The bindings are only kept on a match.
+    fn run<'i>(&mut self, mut goals: Vec<Goal<'i, 't>>) -> Option<usize> {
+        let goal = *goals.last()?;
+        let Some((first, rest)) = goal.items.split_first() else {
+            if goal.whole && !goal.children.is_empty() {
+                return None;
+            }
+            goals.pop();
+            return match goals.is_empty() {
+                true => Some(goal.children.len()),
+                false => self.run(goals),
+            };
+        };
+
+        let state = self.state(&goals);
+        if self.failed.contains(&state) {
+            return None;
+        }
+        goals.pop();
+        let rest_of = |children: &'t [EditorTree]| Goal {
+            items: rest,
+            children,
+            whole: goal.whole,
+        };
+        let found = match first {
+            Item::Node(node, seqs) => match goal.children.split_first() {
+                Some((child, after)) if same_node(node, child) => {
+                    goals.push(rest_of(after));
+                    let child_seqs: Vec<_> = child.child_seqs().collect();
+                    for (items, seq) in seqs.iter().zip(child_seqs).rev() {
+                        goals.push(Goal {
+                            items,
+                            children: &seq.children,
+                            whole: true,
+                        });
+                    }
+                    self.run(goals)
+                }
+                _ => None,
+            },
+            Item::Wildcard(name) => match self.bound(*name) {
+                Some(nodes) => match goal.children.get(..nodes.len()) {
+                    Some(matched) if same_children(nodes, matched) => {
+                        goals.push(rest_of(&goal.children[nodes.len()..]));
+                        self.run(goals)
+                    }
+                    _ => None,
+                },
+                None => (1..=goal.children.len()).find_map(|taken| {
+                    let mut goals = goals.clone();
+                    goals.push(rest_of(&goal.children[taken..]));
+                    self.bindings.push((*name, &goal.children[..taken]));
+                    let found = self.run(goals);
+                    if found.is_none() {
+                        self.bindings.pop();
+                    }
+                    found
+                }),
+            },
+        };
+        if found.is_none() {
+            self.failed.insert(state);
+        }
+        found
+    }
+
+    fn bound(&self, name: char) -> Option<&'t [EditorTree]> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == name)
+            .map(|(_, nodes)| *nodes)
+    }
+
+    /// The goals are told apart by the addresses of their slices, which stay put during a search.
+    fn state(&self, goals: &[Goal]) -> SearchState {
+        let mut names = Vec::new();
+        let goals = goals
+            .iter()
+            .map(|goal| {
+                collect_item_wildcards(goal.items, &mut names);
+                let items = goal.items.as_ptr() as usize;
+                let children = goal.children.as_ptr() as usize;
+                (items, children, goal.children.len(), goal.whole)
+            })
+            .collect();
+        names.sort_unstable();
+        let bindings = names
+            .into_iter()
+            .filter_map(|name| {
+                let nodes = self.bound(name)?;
+                Some((name, nodes.as_ptr() as usize, nodes.len()))
+            })
+            .collect();
+        (goals, bindings)
+    }
+}
+
+/// Whether the nodes are the same but for the sequences in them and their cursors.
+fn same_node(a: &EditorTree, b: &EditorTree) -> bool {
+    use EditorTreeKind as K;
+    match (&a.kind, &b.kind) {
+        (K::Terminal(a), K::Terminal(b)) => a.ch() == b.ch(),
+        (K::Piecewise(a), K::Piecewise(b)) => a.rows().len() == b.rows().len(),
+        (K::SumProd(a), K::SumProd(b)) => a.sum_or_prod() == b.sum_or_prod(),
+        (K::Fraction(_), K::Fraction(_))
+        | (K::Power(_), K::Power(_))
+        | (K::Subscript(_), K::Subscript(_))
+        | (K::Sqrt(_), K::Sqrt(_))
+        | (K::Root(_), K::Root(_))
+        | (K::Paren(_), K::Paren(_))
+        | (K::Abs(_), K::Abs(_))
+        | (K::Bracket(_), K::Bracket(_))
+        | (K::Curly(_), K::Curly(_)) => true,
+        _ => false,
+    }
+}
+
+fn same_children(a: &[EditorTree], b: &[EditorTree]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            same_node(a, b)
+                && a.child_seqs()
+                    .zip(b.child_seqs())
+                    .all(|(a, b)| same_children(&a.children, &b.children))
+        })
+}
+
+/// The children `template` stands for, with its wildcards replaced by what they matched.
+fn instantiate(template: &EditorTreeSeq, bindings: &Bindings) -> Vec<EditorTree> {
+    let mut children = Vec::with_capacity(template.len());
+    for item in items(template) {
+        match item {
+            Item::Node(template, _) => {
+                let mut node = template.clone();
+                for (seq, template) in node.child_seqs_mut().zip(template.child_seqs()) {
+                    *seq = EditorTreeSeq::first(instantiate(template, bindings));
+                }
+                children.push(node);
+            }
+            Item::Wildcard(name) => {
+                let (_, nodes) = bindings
+                    .iter()
+                    .find(|(bound, _)| *bound == name)
+                    .unwrap_or_else(|| unreachable!("templates are checked first"));
+                children.extend_from_slice(nodes);
+            }
+        }
+    }
+    children
+}
+
+impl EditorTreeSeq {
+    /// Every match of `pattern`, outer sequences first. The matches in one sequence don't
+    /// overlap, but there can be more inside the nodes of a match.
+    pub fn find_all(&self, pattern: &TreePattern) -> Vec<SelectedRange> {
+        let mut found = Vec::new();
+        self.find_in(pattern, &mut Vec::new(), &mut found);
+        found
+    }
+
+    fn find_in(
+        &self,
+        pattern: &TreePattern,
+        steps: &mut Vec<PathStep>,
+        found: &mut Vec<SelectedRange>,
+    ) {
+        let mut index = 0;
+        while index < self.len() {
+            match pattern.match_start(&self.children[index..]) {
+                Some((taken, _)) => {
+                    found.push(SelectedRange {
+                        steps: steps.clone(),
+                        range: index..index + taken,
+                    });
+                    index += taken;
+                }
+                None => index += 1,
+            }
+        }
+        for (child, node) in self.children.iter().enumerate() {
+            for (slot, seq) in node.slots().into_iter().zip(node.child_seqs()) {
+                steps.push(PathStep { child, slot });
+                seq.find_in(pattern, steps, found);
+                steps.pop();
+            }
+        }
+    }
+
+    /// Puts `template` in place of every match of `pattern`, giving how many there were. The
+    /// sequences inside nodes are done first, so what a wildcard stands for has had its own
+    /// matches replaced already.
+    pub fn replace_all(
+        &mut self,
+        pattern: &TreePattern,
+        template: &TreePattern,
+    ) -> SearchResult<usize> {
+        pattern.check_template(template)?;
+        Ok(self.replace_in(pattern, template))
+    }
+
+    fn replace_in(&mut self, pattern: &TreePattern, template: &TreePattern) -> usize {
+        let mut count: usize = self
+            .children
+            .iter_mut()
+            .flat_map(EditorTree::child_seqs_mut)
+            .map(|seq| seq.replace_in(pattern, template))
+            .sum();
+
+        let mut children = Vec::with_capacity(self.len());
+        let mut cursor = None;
+        let mut index = 0;
+        while index < self.len() {
+            let Some((taken, bindings)) = pattern.match_start(&self.children[index..]) else {
+                if index == self.cursor {
+                    cursor = Some((children.len(), false));
+                }
+                children.push(self.children[index].clone());
+                index += 1;
+                continue;
+            };
+            if (index..index + taken).contains(&self.cursor) {
+                cursor = Some((children.len(), true));
+            }
+            children.extend(instantiate(&template.seq, &bindings));
+            count += 1;
+            index += taken;
+        }
+        if count == 0 {
+            return 0;
+        }
+
+        let (cursor, moved) = cursor.unwrap_or((children.len(), false));
+        self.children = children;
+        match moved {
+            // the caret was in what was replaced, and goes in front of what replaced it.
+            true => self.move_to(cursor, Direction::Left),
+            false => self.cursor = cursor,
+        }
+        count
+    }
+}
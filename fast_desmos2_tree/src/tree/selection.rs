@@ -0,0 +1,326 @@
+use std::ops::Range;
+
+use super::{
+    movement::Direction, CombinedCursor, EditorTree, EditorTreeKind, EditorTreeSeq, FractionIndex,
+    PiecewiseIndex, PiecewiseRow, RootIndex, SumProdIndex, SurroundIndex, SurroundsTreeSeq,
+    TreeAction,
+};
+
+/// One level of a [`CursorPath`]: the child of a sequence and which of its sequences to go into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStep {
+    pub child: usize,
+    pub slot: CombinedCursor,
+}
+
+/// Where the caret is, independent of the cursors stored in the tree.
+///
+/// `steps` lead from the root to the sequence holding the caret, and `index` is the child of that
+/// sequence the caret is in front of.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CursorPath {
+    pub steps: Vec<PathStep>,
+    pub index: usize,
+}
+
+/// The children `range` of the sequence reached by following `steps`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectedRange {
+    pub steps: Vec<PathStep>,
+    pub range: Range<usize>,
+}
+
+impl SelectedRange {
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+impl EditorTree {
+    /// The sequence `slot` refers to, if this kind of node has it.
+    pub fn slot(&self, slot: CombinedCursor) -> Option<&EditorTreeSeq> {
+        match (&self.kind, slot) {
+            (EditorTreeKind::Fraction(fraction), CombinedCursor::Fraction(FractionIndex::Top)) => {
+                Some(&fraction.top)
+            }
+            (
+                EditorTreeKind::Fraction(fraction),
+                CombinedCursor::Fraction(FractionIndex::Bottom),
+            ) => Some(&fraction.bottom),
+            (EditorTreeKind::Power(power), CombinedCursor::Power) => Some(&power.power),
+            (EditorTreeKind::Subscript(subscript), CombinedCursor::Subscript) => {
+                Some(&subscript.subscript)
+            }
+            (EditorTreeKind::Sqrt(sqrt), CombinedCursor::Sqrt(SurroundIndex::Inside)) => {
+                Some(&sqrt.child)
+            }
+            (EditorTreeKind::Root(root), CombinedCursor::Root(index)) => match index {
+                RootIndex::Index => Some(&root.index),
+                RootIndex::Inside => Some(&root.child),
+                RootIndex::Left => None,
+            },
+            (EditorTreeKind::Piecewise(piecewise), CombinedCursor::Piecewise(index)) => {
+                piecewise.seq(index)
+            }
+            (EditorTreeKind::Paren(paren), CombinedCursor::Paren(SurroundIndex::Inside)) => {
+                Some(&paren.child)
+            }
+            (EditorTreeKind::Abs(abs), CombinedCursor::Abs(SurroundIndex::Inside)) => {
+                Some(&abs.child)
+            }
+            (EditorTreeKind::Bracket(bracket), CombinedCursor::Bracket(SurroundIndex::Inside)) => {
+                Some(&bracket.child)
+            }
+            (EditorTreeKind::Curly(curly), CombinedCursor::Curly(SurroundIndex::Inside)) => {
+                Some(&curly.child)
+            }
+            (EditorTreeKind::SumProd(sum_prod), CombinedCursor::SumProd(index)) => match index {
+                SumProdIndex::BottomExpr => Some(&sum_prod.bottom),
+                SumProdIndex::BottomIdent => Some(&sum_prod.ident),
+                SumProdIndex::Top => Some(&sum_prod.top),
+                SumProdIndex::Left => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The slots of the node, in the same order as [`EditorTree::child_seqs`].
+    pub fn slots(&self) -> Vec<CombinedCursor> {
+        use EditorTreeKind as K;
+        match &self.kind {
+            K::Terminal(_) => Vec::new(),
+            K::Fraction(_) => vec![
+                CombinedCursor::TOP,
+                CombinedCursor::Fraction(FractionIndex::Bottom),
+            ],
+            K::Power(_) => vec![CombinedCursor::Power],
+            K::Subscript(_) => vec![CombinedCursor::Subscript],
+            K::Sqrt(_) => vec![CombinedCursor::Sqrt(SurroundIndex::Inside)],
+            K::Root(_) => vec![
+                CombinedCursor::Root(RootIndex::Index),
+                CombinedCursor::Root(RootIndex::Inside),
+            ],
+            K::Paren(_) => vec![CombinedCursor::Paren(SurroundIndex::Inside)],
+            K::Abs(_) => vec![CombinedCursor::Abs(SurroundIndex::Inside)],
+            K::Bracket(_) => vec![CombinedCursor::Bracket(SurroundIndex::Inside)],
+            K::Curly(_) => vec![CombinedCursor::Curly(SurroundIndex::Inside)],
+            K::Piecewise(piecewise) => (0..piecewise.rows().len())
+                .flat_map(|row| [PiecewiseIndex::Condition(row), PiecewiseIndex::Value(row)])
+                .chain([PiecewiseIndex::Default])
+                .map(CombinedCursor::Piecewise)
+                .collect(),
+            K::SumProd(_) => [
+                SumProdIndex::Top,
+                SumProdIndex::BottomIdent,
+                SumProdIndex::BottomExpr,
+            ]
+            .map(CombinedCursor::SumProd)
+            .to_vec(),
+        }
+    }
+
+    /// Puts the cursor of this node into `slot`, returning the sequence it refers to.
+    fn enter_slot(&mut self, slot: CombinedCursor) -> Option<&mut EditorTreeSeq> {
+        self.slot(slot)?;
+        match (&mut self.kind, slot) {
+            (EditorTreeKind::Fraction(fraction), CombinedCursor::Fraction(index)) => {
+                fraction.cursor = index;
+                fraction.active_child_mut()
+            }
+            (EditorTreeKind::Power(power), _) => Some(&mut power.power),
+            (EditorTreeKind::Subscript(subscript), _) => Some(&mut subscript.subscript),
+            (EditorTreeKind::Sqrt(sqrt), _) => Some(enter_surrounds(sqrt)),
+            (EditorTreeKind::Root(root), CombinedCursor::Root(index)) => {
+                root.cursor = index;
+                root.active_child_mut()
+            }
+            (EditorTreeKind::Piecewise(piecewise), CombinedCursor::Piecewise(index)) => {
+                piecewise.cursor = index;
+                piecewise.active_child_mut()
+            }
+            (EditorTreeKind::Paren(paren), _) => Some(enter_surrounds(paren)),
+            (EditorTreeKind::Abs(abs), _) => Some(enter_surrounds(abs)),
+            (EditorTreeKind::Bracket(bracket), _) => Some(enter_surrounds(bracket)),
+            (EditorTreeKind::Curly(curly), _) => Some(enter_surrounds(curly)),
+            (EditorTreeKind::SumProd(sum_prod), CombinedCursor::SumProd(index)) => {
+                sum_prod.cursor = index;
+                sum_prod.active_child_mut()
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn enter_surrounds(surrounds: &mut impl SurroundsTreeSeq) -> &mut EditorTreeSeq {
+    surrounds.set_cursor(SurroundIndex::Inside);
+    surrounds.child_mut()
+}
+
+impl EditorTreeSeq {
+    pub fn cursor_path(&self) -> CursorPath {
+        let mut steps = Vec::new();
+        let mut seq = self;
+        loop {
+            let Some((child, inner)) = seq
+                .active_child()
+                .and_then(|child| Some((child, child.active_child()?)))
+            else {
+                return CursorPath {
+                    steps,
+                    index: seq.cursor,
+                };
+            };
+            steps.push(PathStep {
+                child: seq.cursor,
+                slot: child.cursor(),
+            });
+            seq = inner;
+        }
+    }
+
+    pub(super) fn seq_at(&self, steps: &[PathStep]) -> Option<&Self> {
+        steps.iter().try_fold(self, |seq, step| {
+            seq.children.get(step.child)?.slot(step.slot)
+        })
+    }
+
+    /// Sets the cursors along `steps`, returning the sequence they lead to. The steps have to
+    /// exist.
+    pub(super) fn enter_steps(&mut self, steps: &[PathStep]) -> &mut Self {
+        steps.iter().fold(self, |seq, step| {
+            seq.cursor = step.child;
+            seq.children[step.child]
+                .enter_slot(step.slot)
+                .unwrap_or_else(|| unreachable!())
+        })
+    }
+
+    /// Moves the caret to `path`, returning whether it exists in this tree.
+    pub fn place_cursor(&mut self, path: &CursorPath) -> bool {
+        match self.seq_at(&path.steps) {
+            Some(seq) if path.index <= seq.len() => {
+                self.enter_steps(&path.steps)
+                    .move_to(path.index, Direction::Left);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The children between `anchor` and the caret. When the two are in different sequences, the
+    /// selection grows to the whole nodes containing the deeper one, like Desmos does. Whatever of
+    /// it is past the end of the sequence is left out.
+    pub fn selected_range(&self, anchor: &CursorPath) -> SelectedRange {
+        let caret = self.cursor_path();
+        let common = anchor
+            .steps
+            .iter()
+            .zip(&caret.steps)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let bounds = |path: &CursorPath| match path.steps.get(common) {
+            Some(step) => step.child..step.child + 1,
+            None => path.index..path.index,
+        };
+        let (from, to) = (bounds(anchor), bounds(&caret));
+        let mut steps = caret.steps;
+        steps.truncate(common);
+        // the anchor may be left over from a tree that got changed since, like by undoing.
+        let len = self.seq_at(&steps).map_or(0, Self::len);
+        SelectedRange {
+            steps,
+            range: from.start.min(to.start).min(len)..from.end.max(to.end).min(len),
+        }
+    }
+
+    /// A copy of the selected children, to be pasted later.
+    pub fn copy_selection(&self, anchor: &CursorPath) -> Self {
+        self.copy_range(&self.selected_range(anchor))
+    }
+
+    /// Removes the selected children and returns them, leaving the caret where they were.
+    pub fn cut_selection(&mut self, anchor: &CursorPath) -> Self {
+        self.cut_range(&self.selected_range(anchor))
+    }
+
+    /// A copy of the children in `selected`.
+    pub fn copy_range(&self, selected: &SelectedRange) -> Self {
+        let children = self
+            .seq_at(&selected.steps)
+            .map(|seq| seq.children[selected.range.clone()].to_vec())
+            .unwrap_or_default();
+        Self::new(0, children)
+    }
+
+    /// Removes the children in `selected` and returns them, leaving the caret where they were.
+    pub fn cut_range(&mut self, selected: &SelectedRange) -> Self {
+        let seq = self.enter_steps(&selected.steps);
+        let children = seq.children.drain(selected.range.clone()).collect();
+        seq.move_to(selected.range.start, Direction::Left);
+        Self::new(0, children)
+    }
+
+    /// Inserts a copy of `clipboard` in front of the caret, leaving the caret after it.
+    pub fn paste(&mut self, clipboard: &Self) {
+        let path = self.cursor_path();
+        let seq = self.enter_steps(&path.steps);
+        seq.children
+            .splice(path.index..path.index, clipboard.children.iter().cloned());
+        seq.move_to(path.index + clipboard.len(), Direction::Left);
+    }
+
+    /// Applies `action` to the selection as a whole: structures get built around it, characters
+    /// replace it and deleting removes it. Returns `false`, changing nothing, when there's no
+    /// selection.
+    pub fn apply_to_selection(&mut self, anchor: &CursorPath, action: TreeAction) -> bool {
+        let selected = self.selected_range(anchor);
+        if selected.is_empty() {
+            return false;
+        }
+
+        let seq = self.enter_steps(&selected.steps);
+        let start = selected.range.start;
+        let inner: Vec<_> = seq.children.drain(selected.range).collect();
+        let (node, cursor) = match action {
+            TreeAction::Delete => {
+                seq.move_to(start, Direction::Left);
+                return true;
+            }
+            TreeAction::Char(ch) => {
+                seq.children.insert(start, EditorTree::terminal(ch));
+                seq.move_to(start + 1, Direction::Left);
+                return true;
+            }
+            TreeAction::MakeFraction => {
+                let node =
+                    EditorTree::fraction(FractionIndex::Bottom, Self::new(0, inner), Self::empty());
+                (node, start)
+            }
+            TreeAction::MakePower => (EditorTree::power(Self::new(inner.len(), inner)), start),
+            TreeAction::MakeSubscript => {
+                (EditorTree::subscript(Self::new(inner.len(), inner)), start)
+            }
+            TreeAction::MakeParen => {
+                let node = EditorTree::complete_paren(SurroundIndex::Left, Self::new(0, inner));
+                (node, start + 1)
+            }
+            TreeAction::MakeAbs => {
+                let node = EditorTree::complete_abs(SurroundIndex::Left, Self::new(0, inner));
+                (node, start + 1)
+            }
+            TreeAction::MakePiecewise => {
+                let row = PiecewiseRow::new(Self::new(inner.len(), inner), Self::empty());
+                let node =
+                    EditorTree::piecewise(PiecewiseIndex::Condition(0), vec![row], Self::empty());
+                (node, start)
+            }
+        };
+        seq.children.insert(start, node);
+        if cursor == start {
+            seq.cursor = start;
+        } else {
+            seq.move_to(cursor, Direction::Left);
+        }
+        true
+    }
+}
@@ -0,0 +1,277 @@
+//! Worksheets, the ordered cells of a graph together with how they're shown, and the versioned
+//! JSON files they're saved in.
+//!
+//! Every file records the `version` it was written with and the `min_reader_version`, the oldest
+//! version able to read it, which only depends on the kinds of node the file has. Each version
+//! only added to the format, so files from older versions read as they are, and files from newer
+//! versions are read as long as they declare themselves readable, ignoring what this version
+//! doesn't know about.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::tree::{Completion, EditorTreeKind, EditorTreeSeq};
+
+pub use desmos::{
+    DesmosExport, DesmosImport, ExportWarning, ExportWarningKind, SkipReason, SkippedItem,
+};
+
+mod content;
+mod desmos;
+
+/// The version of the files written by [`Worksheet::save`].
+pub const VERSION: u64 = 3;
+
+#[derive(Debug, Error)]
+pub enum WorksheetError {
+    #[error("can't access the worksheet file: {0}")]
+    Io(#[from] io::Error),
+    #[error("the worksheet file is malformed: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the worksheet file has no version")]
+    MissingVersion,
+    #[error("the worksheet file needs version {min_reader_version} to be read, this is {VERSION}")]
+    TooNew { min_reader_version: u64 },
+}
+
+pub type WorksheetResult<T> = Result<T, WorksheetError>;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Worksheet {
+    #[serde(default)]
+    pub viewport: Viewport,
+    #[serde(default)]
+    pub cells: Vec<Cell>,
+    #[serde(default)]
+    pub bindings: Vec<ChannelBinding>,
+}
+
+/// One expression of a worksheet and how it's shown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    pub id: String,
+    #[serde(with = "content")]
+    pub content: EditorTreeSeq,
+    #[serde(default)]
+    pub hidden: bool,
+    /// A CSS colour like `#c74440`, or `None` to pick one automatically.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// The title of the folder the cell is in.
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// The range of the slider for a cell defining a number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slider: Option<SliderBounds>,
+}
+
+/// Slider limits, each written in LaTeX. An empty `step` lets the slider move freely.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SliderBounds {
+    pub min: String,
+    pub max: String,
+    pub step: String,
+}
+
+impl Default for SliderBounds {
+    fn default() -> Self {
+        Self {
+            min: "-10".to_string(),
+            max: "10".to_string(),
+            step: String::new(),
+        }
+    }
+}
+
+/// The part of the plane shown by the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub xmin: f64,
+    pub xmax: f64,
+    pub ymin: f64,
+    pub ymax: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            xmin: -10.0,
+            xmax: 10.0,
+            ymin: -10.0,
+            ymax: 10.0,
+        }
+    }
+}
+
+/// Feeds the values received on a comms `channel` into the cell with the id `cell`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelBinding {
+    pub channel: String,
+    pub cell: String,
+}
+
+#[derive(Serialize)]
+struct VersionedWorksheet<'a> {
+    version: u64,
+    min_reader_version: u64,
+    #[serde(flatten)]
+    worksheet: &'a Worksheet,
+}
+
+impl Cell {
+    pub fn new(id: impl Into<String>, content: EditorTreeSeq) -> Self {
+        Self {
+            id: id.into(),
+            content,
+            hidden: false,
+            color: None,
+            folder: None,
+            slider: None,
+        }
+    }
+}
+
+impl Worksheet {
+    pub fn cell(&self, id: &str) -> Option<&Cell> {
+        self.cells.iter().find(|cell| cell.id == id)
+    }
+
+    pub fn cell_mut(&mut self, id: &str) -> Option<&mut Cell> {
+        self.cells.iter_mut().find(|cell| cell.id == id)
+    }
+
+    /// An id no cell has yet: one more than the largest numeric id.
+    pub fn next_id(&self) -> String {
+        let largest = self
+            .cells
+            .iter()
+            .filter_map(|cell| cell.id.parse::<u64>().ok())
+            .max()
+            .unwrap_or(0);
+        (largest + 1).to_string()
+    }
+
+    /// The names the cells define, like `a` in `a=1` or `f` in `f(x)=x^2`, for completing them
+    /// in other cells.
+    pub fn defined_names(&self) -> Vec<Completion> {
+        let mut names: Vec<Completion> = Vec::new();
+        for name in self
+            .cells
+            .iter()
+            .filter_map(|cell| defined_name(&cell.content))
+        {
+            if names
+                .iter()
+                .all(|other| (&other.name, &other.subscript) != (&name.name, &name.subscript))
+            {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> WorksheetResult<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> WorksheetResult<()> {
+        fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    /// Reads a worksheet file of any version this one can read.
+    pub fn from_json(json: &str) -> WorksheetResult<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        check_version(&value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// The oldest version able to read the worksheet's file.
+    pub fn min_reader_version(&self) -> u64 {
+        self.cells
+            .iter()
+            .map(|cell| content::min_reader_version(&cell.content))
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Writes the worksheet as a file of the current [`VERSION`].
+    pub fn to_json(&self) -> String {
+        let versioned = VersionedWorksheet {
+            version: VERSION,
+            min_reader_version: self.min_reader_version(),
+            worksheet: self,
+        };
+        let mut json = serde_json::to_string_pretty(&versioned)
+            .unwrap_or_else(|_| unreachable!("worksheets always serialize"));
+        json.push('\n');
+        json
+    }
+}
+
+/// The name on the left of a definition. `x` and `y` are the axes, so `y=2x` is a graph instead.
+fn defined_name(content: &EditorTreeSeq) -> Option<Completion> {
+    let children = content.children();
+    let letters = children
+        .iter()
+        .take_while(|child| child.is_terminal_and(|term| term.ch().is_ascii_alphabetic()))
+        .count();
+    let name: String = children[..letters]
+        .iter()
+        .filter_map(|child| child.is_terminal_then(|term| term.ch()))
+        .collect();
+    let mut rest = &children[letters..];
+    let mut subscript = None;
+    if let Some((EditorTreeKind::Subscript(inner), after)) = rest
+        .split_first()
+        .map(|(first, after)| (first.kind(), after))
+    {
+        subscript = inner
+            .subscript()
+            .children()
+            .iter()
+            .map(|child| child.is_terminal_then(|term| term.ch()))
+            .collect::<Option<String>>();
+        subscript.as_ref()?;
+        rest = after;
+    }
+    if name.is_empty() || (subscript.is_none() && (name == "x" || name == "y")) {
+        return None;
+    }
+
+    let completion = match rest {
+        [equals, ..] if equals.is_terminal_and_eq('=') => Completion::variable(name),
+        [parens, equals, ..]
+            if matches!(parens.kind(), EditorTreeKind::Paren(_))
+                && equals.is_terminal_and_eq('=') =>
+        {
+            Completion::function(name)
+        }
+        _ => return None,
+    };
+    Some(match subscript {
+        Some(subscript) => completion.with_subscript(subscript),
+        None => completion,
+    })
+}
+
+/// Checks that this version can read the file.
+fn check_version(value: &Value) -> WorksheetResult<()> {
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .filter(|&version| version > 0)
+        .ok_or(WorksheetError::MissingVersion)?;
+    let min_reader_version = value
+        .get("min_reader_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(version);
+    if min_reader_version > VERSION {
+        return Err(WorksheetError::TooNew { min_reader_version });
+    }
+    Ok(())
+}
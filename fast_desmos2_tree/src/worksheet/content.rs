@@ -0,0 +1,213 @@
+//! How the content of a cell is stored: a sequence is an array where runs of terminals are
+//! strings and every other node is an object named after its kind, like
+//! `["1+", {"frac": [["x"], ["2"]]}]`. Cursors aren't stored, loaded trees have them at the start.
+//! Older versions can't read new kinds of node, so adding one needs a new `VERSION`, which
+//! [`min_reader_version`] gives for files containing it.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+use crate::tree::{
+    EditorTree, EditorTreeKind, EditorTreeSeq, FractionIndex, PiecewiseIndex, PiecewiseRow,
+    RootIndex, SumOrProd, SumProdIndex, SurroundIndex,
+};
+
+pub fn serialize<S: Serializer>(seq: &EditorTreeSeq, serializer: S) -> Result<S::Ok, S::Error> {
+    seq_to_json(seq).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<EditorTreeSeq, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    seq_from_json(&value).map_err(de::Error::custom)
+}
+
+/// The oldest version knowing every kind of node in `seq`.
+pub fn min_reader_version(seq: &EditorTreeSeq) -> u64 {
+    seq.children()
+        .iter()
+        .map(|node| {
+            let version = match node.kind() {
+                EditorTreeKind::Subscript(_) => 2,
+                EditorTreeKind::Root(_) | EditorTreeKind::Piecewise(_) => 3,
+                _ => 1,
+            };
+            node.child_seqs()
+                .map(min_reader_version)
+                .fold(version, u64::max)
+        })
+        .max()
+        .unwrap_or(1)
+}
+
+fn seq_to_json(seq: &EditorTreeSeq) -> Value {
+    let mut items = Vec::new();
+    let mut run = String::new();
+    for child in seq.children() {
+        if let Some(ch) = child.is_terminal_then(|term| term.ch()) {
+            run.push(ch);
+            continue;
+        }
+        if !run.is_empty() {
+            items.push(Value::String(std::mem::take(&mut run)));
+        }
+        items.push(node_to_json(child));
+    }
+    if !run.is_empty() {
+        items.push(Value::String(run));
+    }
+    Value::Array(items)
+}
+
+fn node_to_json(node: &EditorTree) -> Value {
+    let surrounds = |name: &str, child: &EditorTreeSeq, is_complete: bool| {
+        let mut object = Map::new();
+        object.insert(name.to_string(), seq_to_json(child));
+        if !is_complete {
+            object.insert("incomplete".to_string(), Value::Bool(true));
+        }
+        Value::Object(object)
+    };
+    match node.kind() {
+        EditorTreeKind::Terminal(_) => unreachable!("terminals are stored as strings"),
+        EditorTreeKind::Fraction(fraction) => {
+            json!({ "frac": [seq_to_json(fraction.top()), seq_to_json(fraction.bottom())] })
+        }
+        EditorTreeKind::Power(power) => json!({ "pow": seq_to_json(power.power()) }),
+        EditorTreeKind::Subscript(subscript) => {
+            json!({ "sub": seq_to_json(subscript.subscript()) })
+        }
+        EditorTreeKind::Sqrt(sqrt) => json!({ "sqrt": seq_to_json(sqrt.child()) }),
+        EditorTreeKind::Root(root) => {
+            json!({ "root": [seq_to_json(root.index()), seq_to_json(root.child())] })
+        }
+        EditorTreeKind::Paren(paren) => surrounds("paren", paren.child(), paren.is_complete()),
+        EditorTreeKind::Abs(abs) => surrounds("abs", abs.child(), abs.is_complete()),
+        EditorTreeKind::Bracket(bracket) => {
+            surrounds("bracket", bracket.child(), bracket.is_complete())
+        }
+        EditorTreeKind::Curly(curly) => surrounds("curly", curly.child(), curly.is_complete()),
+        EditorTreeKind::Piecewise(piecewise) => {
+            let rows: Vec<_> = piecewise
+                .rows()
+                .iter()
+                .map(|row| json!([seq_to_json(row.condition()), seq_to_json(row.value())]))
+                .collect();
+            json!({ "piecewise": {
+                "rows": rows,
+                "default": seq_to_json(piecewise.default()),
+            } })
+        }
+        EditorTreeKind::SumProd(sum_prod) => {
+            let name = match sum_prod.sum_or_prod() {
+                SumOrProd::Sum => "sum",
+                SumOrProd::Prod => "prod",
+            };
+            json!({ name: {
+                "ident": seq_to_json(sum_prod.ident()),
+                "from": seq_to_json(sum_prod.bottom()),
+                "to": seq_to_json(sum_prod.top()),
+            } })
+        }
+    }
+}
+
+fn seq_from_json(value: &Value) -> Result<EditorTreeSeq, String> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| format!("expected a sequence, found {value}"))?;
+    let mut children = Vec::new();
+    for item in items {
+        match item {
+            Value::String(run) => children.extend(run.chars().map(EditorTree::terminal)),
+            Value::Object(object) => children.push(node_from_json(object)?),
+            _ => return Err(format!("expected characters or a node, found {item}")),
+        }
+    }
+    Ok(EditorTreeSeq::first(children))
+}
+
+fn node_from_json(object: &Map<String, Value>) -> Result<EditorTree, String> {
+    let is_complete = !object
+        .get("incomplete")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let (name, value) = object
+        .iter()
+        .find(|(name, _)| *name != "incomplete")
+        .ok_or("expected a node, found an empty object")?;
+    let surrounds = |complete: fn(SurroundIndex, EditorTreeSeq) -> EditorTree,
+                     incomplete: fn(SurroundIndex, EditorTreeSeq) -> EditorTree|
+     -> Result<EditorTree, String> {
+        let child = seq_from_json(value)?;
+        Ok(match is_complete {
+            true => complete(SurroundIndex::Left, child),
+            false => incomplete(SurroundIndex::Left, child),
+        })
+    };
+    let field = |name: &str| -> Result<EditorTreeSeq, String> {
+        seq_from_json(value.get(name).unwrap_or(&Value::Null))
+    };
+
+    match name.as_str() {
+        "frac" => match value.as_array().map(Vec::as_slice) {
+            Some([top, bottom]) => Ok(EditorTree::fraction(
+                FractionIndex::Left,
+                seq_from_json(top)?,
+                seq_from_json(bottom)?,
+            )),
+            _ => Err(format!("expected a top and a bottom, found {value}")),
+        },
+        "pow" => Ok(EditorTree::power(seq_from_json(value)?)),
+        "sub" => Ok(EditorTree::subscript(seq_from_json(value)?)),
+        "sqrt" => Ok(EditorTree::sqrt(SurroundIndex::Left, seq_from_json(value)?)),
+        "root" => match value.as_array().map(Vec::as_slice) {
+            Some([index, child]) => Ok(EditorTree::root(
+                RootIndex::Left,
+                seq_from_json(index)?,
+                seq_from_json(child)?,
+            )),
+            _ => Err(format!("expected an index and a radicand, found {value}")),
+        },
+        "paren" => surrounds(EditorTree::complete_paren, EditorTree::incomplete_paren),
+        "abs" => surrounds(EditorTree::complete_abs, EditorTree::incomplete_abs),
+        "bracket" => surrounds(
+            EditorTree::complete_brackets,
+            EditorTree::incomplete_brackets,
+        ),
+        "curly" => surrounds(EditorTree::complete_curly, EditorTree::incomplete_curly),
+        "piecewise" => {
+            let rows = match value.get("rows").and_then(Value::as_array) {
+                Some(rows) if !rows.is_empty() => rows,
+                _ => return Err(format!("expected at least one row, found {value}")),
+            };
+            let rows = rows
+                .iter()
+                .map(|row| match row.as_array().map(Vec::as_slice) {
+                    Some([condition, value]) => Ok(PiecewiseRow::new(
+                        seq_from_json(condition)?,
+                        seq_from_json(value)?,
+                    )),
+                    _ => Err(format!("expected a condition and a value, found {row}")),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(EditorTree::piecewise(
+                PiecewiseIndex::Left,
+                rows,
+                field("default")?,
+            ))
+        }
+        "sum" => Ok(EditorTree::sum(
+            SumProdIndex::Left,
+            field("to")?,
+            field("from")?,
+            field("ident")?,
+        )),
+        "prod" => Ok(EditorTree::prod(
+            SumProdIndex::Left,
+            field("to")?,
+            field("from")?,
+            field("ident")?,
+        )),
+        _ => Err(format!("unknown node `{name}`")),
+    }
+}
@@ -0,0 +1,300 @@
+//! Reading and writing the graph state desmos.com saves with `Calc.getState()`.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Cell, SliderBounds, Viewport, Worksheet, WorksheetResult};
+use crate::tree::{latex::LatexError, EditorTree, EditorTreeKind, EditorTreeSeq};
+
+/// The version of the graph state written by [`Worksheet::to_desmos_json`].
+const STATE_VERSION: u64 = 11;
+
+/// A worksheet read from a Desmos graph, along with everything in it that couldn't be kept.
+#[derive(Debug)]
+pub struct DesmosImport {
+    pub worksheet: Worksheet,
+    pub skipped: Vec<SkippedItem>,
+}
+
+/// An item of the expression list that has no cell in the imported worksheet.
+#[derive(Debug, Error)]
+#[error("item {id} was skipped: {reason}")]
+pub struct SkippedItem {
+    pub id: String,
+    pub latex: Option<String>,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Error)]
+pub enum SkipReason {
+    #[error(transparent)]
+    Latex(#[from] LatexError),
+    #[error("{0} items can't be represented")]
+    Kind(String),
+}
+
+/// A worksheet written as a Desmos graph, along with what Desmos won't understand in it.
+#[derive(Debug)]
+pub struct DesmosExport {
+    pub json: String,
+    pub warnings: Vec<ExportWarning>,
+}
+
+/// Something in a cell that is specific to this project.
+#[derive(Debug, Error)]
+#[error("cell {id} {kind}")]
+pub struct ExportWarning {
+    pub id: String,
+    pub kind: ExportWarningKind,
+}
+
+#[derive(Debug, Error)]
+pub enum ExportWarningKind {
+    #[error("has a list inside a list, which Desmos doesn't allow")]
+    NestedList,
+    #[error("has a string, which Desmos doesn't have")]
+    String,
+    #[error("is bound to the channel `{0}`, which Desmos won't update")]
+    Channel(String),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct State {
+    version: u64,
+    graph: Graph,
+    expressions: Expressions,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Graph {
+    viewport: Viewport,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Expressions {
+    list: Vec<Item>,
+}
+
+/// Any item of the expression list. Which fields are set depends on `kind`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    hidden: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slider_bounds: Option<SliderBounds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+impl Worksheet {
+    pub fn import_desmos(path: impl AsRef<Path>) -> WorksheetResult<DesmosImport> {
+        Self::from_desmos_json(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the JSON of a Desmos graph state. Expressions become cells through
+    /// [`EditorTreeSeq::from_latex`], and folders become the `folder` of the cells in them.
+    pub fn from_desmos_json(json: &str) -> WorksheetResult<DesmosImport> {
+        let state: State = serde_json::from_str(json)?;
+        let folders: HashMap<_, _> = state
+            .expressions
+            .list
+            .iter()
+            .filter(|item| item.kind == "folder")
+            .map(|item| (item.id.as_str(), item.title.clone().unwrap_or_default()))
+            .collect();
+
+        let mut worksheet = Worksheet {
+            viewport: state.graph.viewport,
+            ..Worksheet::default()
+        };
+        let mut skipped = Vec::new();
+        for item in &state.expressions.list {
+            match item.kind.as_str() {
+                "folder" => continue,
+                "expression" => {}
+                kind => {
+                    skipped.push(SkippedItem {
+                        id: item.id.clone(),
+                        latex: item.latex.clone(),
+                        reason: SkipReason::Kind(kind.to_string()),
+                    });
+                    continue;
+                }
+            }
+
+            let latex = item.latex.as_deref().unwrap_or_default();
+            let content = match latex.trim().is_empty() {
+                true => Ok(EditorTreeSeq::empty()),
+                false => EditorTreeSeq::from_latex(latex),
+            };
+            match content {
+                Ok(content) => worksheet.cells.push(Cell {
+                    hidden: item.hidden,
+                    color: item.color.clone(),
+                    folder: item
+                        .folder_id
+                        .as_deref()
+                        .and_then(|id| folders.get(id).cloned()),
+                    slider: item.slider_bounds.clone(),
+                    ..Cell::new(item.id.clone(), content)
+                }),
+                Err(err) => skipped.push(SkippedItem {
+                    id: item.id.clone(),
+                    latex: item.latex.clone(),
+                    reason: err.into(),
+                }),
+            }
+        }
+
+        Ok(DesmosImport { worksheet, skipped })
+    }
+
+    pub fn export_desmos(&self, path: impl AsRef<Path>) -> WorksheetResult<Vec<ExportWarning>> {
+        let export = self.to_desmos_json();
+        fs::write(path, export.json)?;
+        Ok(export.warnings)
+    }
+
+    /// Writes the worksheet as the JSON of a Desmos graph state, with each cell as the LaTeX of
+    /// its content. Desmos keeps the contents of a folder right after it, so the cells of a
+    /// folder are moved up to the first of them.
+    pub fn to_desmos_json(&self) -> DesmosExport {
+        let mut list = Vec::new();
+        let mut folder_ids: HashMap<&str, String> = HashMap::new();
+        for cell in &self.cells {
+            let folder_id = match cell.folder.as_deref() {
+                Some(title) if !folder_ids.contains_key(title) => {
+                    let id = self.unused_id(&format!("folder{}", folder_ids.len() + 1));
+                    list.push(Item {
+                        kind: "folder".to_string(),
+                        id: id.clone(),
+                        title: Some(title.to_string()),
+                        ..Item::default()
+                    });
+                    list.extend(
+                        self.cells
+                            .iter()
+                            .filter(|other| other.folder.as_deref() == Some(title))
+                            .map(|other| Item::expression(other, Some(id.clone()))),
+                    );
+                    folder_ids.insert(title, id);
+                    continue;
+                }
+                Some(_) => continue,
+                None => None,
+            };
+            list.push(Item::expression(cell, folder_id));
+        }
+
+        let state = State {
+            version: STATE_VERSION,
+            graph: Graph {
+                viewport: self.viewport,
+            },
+            expressions: Expressions { list },
+        };
+        let json = serde_json::to_string(&state)
+            .unwrap_or_else(|_| unreachable!("graph states always serialize"));
+        DesmosExport {
+            json,
+            warnings: self.export_warnings(),
+        }
+    }
+
+    /// `id` or, if a cell already has it, `id` with a number after it.
+    fn unused_id(&self, id: &str) -> String {
+        (0..)
+            .map(|n| match n {
+                0 => id.to_string(),
+                n => format!("{id}-{n}"),
+            })
+            .find(|id| self.cell(id).is_none())
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    fn export_warnings(&self) -> Vec<ExportWarning> {
+        let mut warnings = Vec::new();
+        for cell in &self.cells {
+            let mut warn = |kind| {
+                warnings.push(ExportWarning {
+                    id: cell.id.clone(),
+                    kind,
+                })
+            };
+            if has_nested_list(&cell.content, false) {
+                warn(ExportWarningKind::NestedList);
+            }
+            if has_string(&cell.content) {
+                warn(ExportWarningKind::String);
+            }
+            for binding in self
+                .bindings
+                .iter()
+                .filter(|binding| binding.cell == cell.id)
+            {
+                warn(ExportWarningKind::Channel(binding.channel.clone()));
+            }
+        }
+        warnings
+    }
+}
+
+impl Item {
+    fn expression(cell: &Cell, folder_id: Option<String>) -> Self {
+        Self {
+            kind: "expression".to_string(),
+            id: cell.id.clone(),
+            latex: Some(cell.content.to_latex()),
+            color: cell.color.clone(),
+            hidden: cell.hidden,
+            folder_id,
+            slider_bounds: cell.slider.clone(),
+            title: None,
+        }
+    }
+}
+
+/// Whether a list literal is inside another one, counting brackets right after something as
+/// indexing rather than lists.
+fn has_nested_list(seq: &EditorTreeSeq, in_list: bool) -> bool {
+    let mut previous: Option<&EditorTree> = None;
+    for child in seq.children() {
+        let is_list = matches!(child.kind(), EditorTreeKind::Bracket(_))
+            && previous.is_none_or(|previous| {
+                !previous.is_terminal_and(|term| term.ch().is_ascii_alphanumeric())
+                    && matches!(previous.kind(), EditorTreeKind::Terminal(_))
+            });
+        if is_list && in_list {
+            return true;
+        }
+        if child
+            .child_seqs()
+            .any(|inner| has_nested_list(inner, in_list || is_list))
+        {
+            return true;
+        }
+        previous = Some(child);
+    }
+    false
+}
+
+fn has_string(seq: &EditorTreeSeq) -> bool {
+    seq.children()
+        .iter()
+        .any(|child| child.is_terminal_and_eq('"') || child.child_seqs().any(has_string))
+}
@@ -0,0 +1,796 @@
+//! The worksheet editor: a scrolling list of cells, each shown with what it evaluates to, above a
+//! status line.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    io::{self, ErrorKind, Stdout, Write},
+    ops::Range,
+    path::PathBuf,
+};
+
+use fast_desmos2_comms::{Server, Value};
+use fast_desmos2_tree::{
+    editor::{Editor, EditorEvent},
+    keymap::{EditorCommand, KeyPress, Keymap, KeymapMode},
+    tree::{
+        debug::Debugable as _,
+        pretty::{PrettyScreen, Prettyable as _},
+        search::TreePattern,
+        CursorPath, EditorHistory, EditorTreeSeq as TS, Motion,
+    },
+    worksheet::{Cell, ChannelBinding, Worksheet},
+};
+use fast_desmos2_tree_parser::{
+    completion,
+    derivative::derivative,
+    eval::{evaluate_worksheet, worksheet_env, CellResult},
+    parse_statement,
+    refactor::Rename,
+    simplify::{Rewriter, Rules},
+    tree::{EvalNode, IdentStorer, Statement},
+    unparse,
+};
+use glam::UVec2;
+use termion::{clear, color, cursor, event::Key, raw::RawTerminal};
+
+/// The columns left of every cell, holding its number.
+const GUTTER: u16 = 5;
+
+/// A line of text being typed below the status line, which takes the keys until it's done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Latex,
+    Command,
+}
+
+#[derive(Debug, Default)]
+struct Message {
+    text: String,
+    is_error: bool,
+}
+
+pub struct App {
+    worksheet: Worksheet,
+    /// The undo history of each cell, in the same order.
+    histories: Vec<EditorHistory>,
+    results: Vec<CellResult>,
+    current: usize,
+    /// The first cell on screen.
+    scroll: usize,
+    /// The cells on screen when last drawn, with the rows they took.
+    drawn: Vec<(usize, Range<u32>)>,
+    editor: Editor,
+    prompt: Option<Prompt>,
+    /// What's typed in the prompt.
+    input: String,
+    message: Message,
+    path: Option<PathBuf>,
+    modified: bool,
+    server: Option<Server>,
+    /// The last value received for each bound cell, by cell id.
+    received: HashMap<String, Value>,
+    show_debug: bool,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(
+        worksheet: Worksheet,
+        keymap: Keymap,
+        path: Option<PathBuf>,
+        server: Option<Server>,
+    ) -> Self {
+        let mut app = Self {
+            worksheet: Worksheet::default(),
+            histories: Vec::new(),
+            results: Vec::new(),
+            current: 0,
+            scroll: 0,
+            drawn: Vec::new(),
+            editor: Editor::new(keymap),
+            prompt: None,
+            input: String::new(),
+            message: Message::default(),
+            path,
+            modified: false,
+            server,
+            received: HashMap::new(),
+            show_debug: false,
+            quit: false,
+        };
+        app.replace_worksheet(worksheet);
+        app
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Quits like `:q`, so not with unsaved changes.
+    pub fn try_quit(&mut self) {
+        match self.modified {
+            true => self.error("the worksheet has unsaved changes, use :q! to quit anyway"),
+            false => self.quit = true,
+        }
+    }
+
+    fn replace_worksheet(&mut self, worksheet: Worksheet) {
+        self.worksheet = worksheet;
+        if self.worksheet.cells.is_empty() {
+            let id = self.worksheet.next_id();
+            self.worksheet.cells.push(Cell::new(id, TS::empty()));
+        }
+        self.histories = vec![EditorHistory::default(); self.worksheet.cells.len()];
+        self.current = 0;
+        self.scroll = 0;
+        self.received.clear();
+        self.evaluate();
+    }
+
+    fn evaluate(&mut self) {
+        self.results = evaluate_worksheet(&self.worksheet, &self.received);
+    }
+
+    fn info(&mut self, text: impl Into<String>) {
+        self.message = Message {
+            text: text.into(),
+            is_error: false,
+        };
+    }
+
+    fn error(&mut self, text: impl Into<String>) {
+        self.message = Message {
+            text: text.into(),
+            is_error: true,
+        };
+    }
+
+    fn edit<T>(&mut self, func: impl FnOnce(&mut TS) -> T) -> T {
+        self.modified = true;
+        let tree = &mut self.worksheet.cells[self.current].content;
+        self.histories[self.current].edit(tree, func)
+    }
+
+    /// Goes on to the cells above and below when the caret can't go further.
+    fn overflow(&mut self, motion: Motion) {
+        match motion {
+            Motion::Down if self.current + 1 < self.worksheet.cells.len() => {
+                self.select(self.current + 1)
+            }
+            Motion::Up if self.current > 0 => self.select(self.current - 1),
+            _ => {}
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.histories[self.current].break_group();
+        self.current = index.min(self.worksheet.cells.len() - 1);
+    }
+
+    /// Adds an empty cell at `index` and makes it the current one.
+    fn add_cell(&mut self, index: usize) {
+        let id = self.worksheet.next_id();
+        self.worksheet
+            .cells
+            .insert(index, Cell::new(id, TS::empty()));
+        self.histories.insert(index, EditorHistory::default());
+        self.current = index;
+        self.modified = true;
+    }
+
+    fn delete_cell(&mut self) {
+        let cell = self.worksheet.cells.remove(self.current);
+        self.histories.remove(self.current);
+        self.worksheet
+            .bindings
+            .retain(|binding| binding.cell != cell.id);
+        if self.worksheet.cells.is_empty() {
+            self.add_cell(0);
+        }
+        self.current = self.current.min(self.worksheet.cells.len() - 1);
+        self.modified = true;
+    }
+
+    /// Swaps the current cell with the one `offset` away, if there is one.
+    fn move_cell(&mut self, offset: isize) {
+        let Some(other) = self
+            .current
+            .checked_add_signed(offset)
+            .filter(|&other| other < self.worksheet.cells.len())
+        else {
+            return;
+        };
+        self.worksheet.cells.swap(self.current, other);
+        self.histories.swap(self.current, other);
+        self.current = other;
+        self.modified = true;
+    }
+
+    fn complete(&mut self) {
+        let completions =
+            completion::completions(&self.worksheet.cells[self.current].content, &self.worksheet);
+        match completions.first() {
+            Some(first) => {
+                let first = first.clone();
+                self.edit(|tree| tree.accept_completion(&first));
+            }
+            None => self.error("nothing to complete"),
+        }
+    }
+
+    /// Handles a key press, then evaluates the worksheet again.
+    pub fn handle_key(&mut self, key: Key) {
+        match self.prompt {
+            Some(Prompt::Latex) => self.latex_key(key),
+            Some(Prompt::Command) => self.command_key(key),
+            None => {
+                if let Some(key) = key_press(key) {
+                    self.editor_key(key);
+                }
+            }
+        }
+        self.evaluate();
+    }
+
+    /// Puts the caret where the cell under `pos`, one-based like termion's, was clicked.
+    pub fn handle_click(&mut self, pos: UVec2) {
+        let origin = UVec2::new(GUTTER as u32 + 1, 1);
+        if pos.cmplt(origin).any() {
+            return;
+        }
+        let pos = pos - origin;
+        let Some((index, rows)) = self.drawn.iter().find(|(_, rows)| rows.contains(&pos.y)) else {
+            return;
+        };
+        let index = *index;
+        // the debug tree is laid out differently.
+        if self.prompt.is_some() || (self.show_debug && index == self.current) {
+            return;
+        }
+        let tree = &self.worksheet.cells[index].content;
+        let layout = tree.pretty(index == self.current);
+        let path = layout.hit_test(pos.with_y(pos.y - rows.start));
+        if index != self.current {
+            self.select(index);
+        }
+        let tree = &mut self.worksheet.cells[index].content;
+        let history = &mut self.histories[index];
+        self.editor.place_cursor(tree, history, &path);
+    }
+
+    fn editor_key(&mut self, key: KeyPress) {
+        self.editor.set_candidates(
+            completion::builtin_completions().chain(self.worksheet.defined_names()),
+        );
+        let tree = &mut self.worksheet.cells[self.current].content;
+        let history = &mut self.histories[self.current];
+        for event in self.editor.feed_key(tree, history, key) {
+            match event {
+                EditorEvent::Edited => self.modified = true,
+                EditorEvent::Overflow(motion) => self.overflow(motion),
+                EditorEvent::Command(command) => self.run_editor_command(command),
+            }
+        }
+    }
+
+    /// Runs the commands the editor leaves to the worksheet.
+    fn run_editor_command(&mut self, command: EditorCommand) {
+        match command {
+            EditorCommand::LatexMode => {
+                self.input.clear();
+                self.prompt = Some(Prompt::Latex);
+            }
+            EditorCommand::CommandMode => {
+                self.input.clear();
+                self.prompt = Some(Prompt::Command);
+            }
+            EditorCommand::NextCell => self.select(self.current + 1),
+            EditorCommand::PreviousCell => self.select(self.current.saturating_sub(1)),
+            EditorCommand::FirstCell => self.select(0),
+            EditorCommand::LastCell => self.select(self.worksheet.cells.len() - 1),
+            EditorCommand::MoveCellDown => self.move_cell(1),
+            EditorCommand::MoveCellUp => self.move_cell(-1),
+            EditorCommand::AddCellBelow | EditorCommand::AddCellAbove => {
+                let below = command == EditorCommand::AddCellBelow;
+                self.add_cell(self.current + usize::from(below));
+                let tree = &self.worksheet.cells[self.current].content;
+                self.editor.set_mode(KeymapMode::Insert, tree);
+            }
+            EditorCommand::Complete => self.complete(),
+            EditorCommand::ToggleDebug => self.show_debug = !self.show_debug,
+            EditorCommand::NormalMode
+            | EditorCommand::InsertMode
+            | EditorCommand::VisualMode
+            | EditorCommand::Undo
+            | EditorCommand::Redo
+            | EditorCommand::Yank
+            | EditorCommand::Cut => {}
+        }
+    }
+
+    fn latex_key(&mut self, key: Key) {
+        match key {
+            Key::Esc => self.prompt = None,
+            Key::Char('\n') => {
+                let latex = std::mem::take(&mut self.input);
+                if let Err(err) = self.edit(|tree| tree.paste_latex(&latex)) {
+                    self.error(err.to_string());
+                }
+                self.prompt = None;
+            }
+            Key::Backspace => _ = self.input.pop(),
+            Key::Char(c) => self.input.push(c),
+            _ => {}
+        }
+    }
+
+    fn command_key(&mut self, key: Key) {
+        match key {
+            Key::Esc => self.prompt = None,
+            Key::Char('\n') => {
+                self.prompt = None;
+                let command = std::mem::take(&mut self.input);
+                self.run_command(&command);
+            }
+            // backspacing past the start leaves command mode, like in Vim.
+            Key::Backspace if self.input.pop().is_none() => self.prompt = None,
+            Key::Char(c) => self.input.push(c),
+            _ => {}
+        }
+    }
+
+    fn run_command(&mut self, command: &str) {
+        let (name, arg) = match command.trim().split_once(' ') {
+            Some((name, arg)) => (name, Some(arg.trim())),
+            None => (command.trim(), None),
+        };
+        match (name, arg) {
+            ("w", arg) => _ = self.save(arg),
+            ("wq" | "x", arg) => self.quit = self.save(arg),
+            ("q", None) => self.try_quit(),
+            ("q!", None) => self.quit = true,
+            ("e", Some(_)) if self.modified => {
+                self.error("the worksheet has unsaved changes, use :e! to open anyway")
+            }
+            ("e" | "e!", Some(path)) => self.open(path),
+            ("d", None) => self.delete_cell(),
+            // for pasting into bug reports and tests.
+            ("notation", None) => {
+                let notation = self.worksheet.cells[self.current].content.notation();
+                self.info(notation);
+            }
+            ("import", Some(path)) => self.import(path),
+            ("export", Some(path)) => self.export(path),
+            ("bind", Some(channel)) => self.bind(Some(channel)),
+            ("unbind", None) => self.bind(None),
+            ("find", Some(pattern)) => self.find(pattern),
+            ("replace", Some(arg)) => match arg.split_once(" => ") {
+                Some((pattern, template)) => self.replace(pattern.trim(), template.trim()),
+                None => self.error("use :replace <pattern> => <template>"),
+            },
+            ("rename", Some(arg)) => match arg.split_once(' ') {
+                Some((from, to)) => self.rename(from, to.trim()),
+                None => self.error("use :rename <old> <new>"),
+            },
+            ("simplify", None) => self.rewrite(Rules::SIMPLIFY),
+            ("expand", None) => self.rewrite(Rules::SIMPLIFY | Rules::EXPAND),
+            ("derive", var) => self.derive(var.unwrap_or("x")),
+            _ => self.error(format!("unknown command `{command}`")),
+        }
+    }
+
+    /// Saves to `path`, which becomes the file of the worksheet, or to the file it was opened
+    /// from. Returns whether it was saved.
+    fn save(&mut self, path: Option<&str>) -> bool {
+        let Some(path) = path.map(PathBuf::from).or_else(|| self.path.clone()) else {
+            self.error("the worksheet has no file yet, use :w <path>");
+            return false;
+        };
+        match self.worksheet.save(&path) {
+            Ok(()) => {
+                self.info(format!("wrote {}", path.display()));
+                self.path = Some(path);
+                self.modified = false;
+                true
+            }
+            Err(err) => {
+                self.error(err.to_string());
+                false
+            }
+        }
+    }
+
+    fn open(&mut self, path: &str) {
+        match Worksheet::load(path) {
+            Ok(worksheet) => {
+                self.replace_worksheet(worksheet);
+                self.path = Some(PathBuf::from(path));
+                self.modified = false;
+                self.info(format!("opened {path}"));
+            }
+            Err(err) => self.error(err.to_string()),
+        }
+    }
+
+    fn import(&mut self, path: &str) {
+        let import = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|json| Worksheet::from_desmos_json(&json).map_err(|err| err.to_string()));
+        match import {
+            Ok(import) => {
+                self.replace_worksheet(import.worksheet);
+                self.modified = true;
+                match import.skipped.len() {
+                    0 => self.info(format!("imported {path}")),
+                    skipped => self.info(format!("imported {path}, skipping {skipped} items")),
+                }
+            }
+            Err(err) => self.error(err),
+        }
+    }
+
+    fn export(&mut self, path: &str) {
+        let export = self.worksheet.to_desmos_json();
+        match fs::write(path, export.json) {
+            Ok(()) => match export.warnings.len() {
+                0 => self.info(format!("exported {path}")),
+                warnings => self.info(format!("exported {path}, with {warnings} warnings")),
+            },
+            Err(err) => self.error(err.to_string()),
+        }
+    }
+
+    /// Binds the current cell to `channel`, or unbinds it.
+    fn bind(&mut self, channel: Option<&str>) {
+        let id = self.worksheet.cells[self.current].id.clone();
+        self.worksheet.bindings.retain(|binding| binding.cell != id);
+        self.received.remove(&id);
+        if let Some(channel) = channel {
+            self.worksheet.bindings.push(ChannelBinding {
+                channel: channel.to_string(),
+                cell: id,
+            });
+        }
+        self.modified = true;
+    }
+
+    /// Goes to the next match of `pattern`, from the cell after the current one around to the
+    /// current one again.
+    fn find(&mut self, pattern: &str) {
+        let pattern = match TreePattern::parse(pattern) {
+            Ok(pattern) => pattern,
+            Err(err) => return self.error(err.to_string()),
+        };
+        let count = self.worksheet.cells.len();
+        let found = (1..=count)
+            .map(|offset| (self.current + offset) % count)
+            .find_map(|index| {
+                let matches = self.worksheet.cells[index].content.find_all(&pattern);
+                let first = matches.first()?.clone();
+                Some((index, first, matches.len()))
+            });
+        let Some((index, first, matches)) = found else {
+            return self.error("no matches");
+        };
+        if index != self.current {
+            self.select(index);
+        }
+        let path = CursorPath {
+            steps: first.steps,
+            index: first.range.start,
+        };
+        let tree = &mut self.worksheet.cells[index].content;
+        self.editor
+            .place_cursor(tree, &mut self.histories[index], &path);
+        self.info(format!("{matches} matches in cell {}", index + 1));
+    }
+
+    /// Replaces every match of `pattern` in the worksheet with `template`.
+    fn replace(&mut self, pattern: &str, template: &str) {
+        let parsed = TreePattern::parse(pattern).and_then(|pattern| {
+            let template = TreePattern::parse(template)?;
+            Ok((pattern, template))
+        });
+        let (pattern, template) = match parsed {
+            Ok(parsed) => parsed,
+            Err(err) => return self.error(err.to_string()),
+        };
+        let mut count = 0;
+        for (cell, history) in self.worksheet.cells.iter_mut().zip(&mut self.histories) {
+            match history.edit(&mut cell.content, |tree| {
+                tree.replace_all(&pattern, &template)
+            }) {
+                Ok(replaced) => count += replaced,
+                Err(err) => return self.error(err.to_string()),
+            }
+        }
+        if count > 0 {
+            self.modified = true;
+        }
+        self.info(format!("replaced {count} matches"));
+    }
+
+    /// Renames a variable or function in every cell.
+    fn rename(&mut self, from: &str, to: &str) {
+        let rename = Rename::new(from, to).and_then(|rename| {
+            rename.check(self.worksheet.cells.iter().map(|cell| &cell.content))?;
+            Ok(rename)
+        });
+        let rename = match rename {
+            Ok(rename) => rename,
+            Err(err) => return self.error(err.to_string()),
+        };
+        let mut count = 0;
+        for (cell, history) in self.worksheet.cells.iter_mut().zip(&mut self.histories) {
+            count += history.edit(&mut cell.content, |tree| rename.apply(tree));
+        }
+        if count > 0 {
+            self.modified = true;
+        }
+        self.info(format!("renamed {count} uses of {from}"));
+    }
+
+    /// Rewrites the expression of the current cell, or what a definition in it is defined as,
+    /// into a new cell below it.
+    fn rewrite(&mut self, rules: Rules) {
+        let idents = IdentStorer::default();
+        let expr = match parse_statement(&self.worksheet.cells[self.current].content, &idents) {
+            Ok(Statement::Expression(expr)) => expr,
+            Ok(Statement::Variable(def)) => def.expr().clone(),
+            Ok(Statement::Function(def)) => def.expr().clone(),
+            Err(_) => return self.error("the cell isn't an expression"),
+        };
+        self.add_rewritten(&expr, &idents, rules);
+    }
+
+    /// Differentiates the current cell by `var` into a new cell below it, with what the other
+    /// cells define in scope.
+    fn derive(&mut self, var: &str) {
+        let idents = IdentStorer::default();
+        let (env, statements) = worksheet_env(&self.worksheet, &self.received, &idents);
+        let Some(statement) = &statements[self.current] else {
+            return self.error("the cell isn't an expression");
+        };
+        match derivative(&env, statement, idents.convert_id(var)) {
+            Ok(derived) => self.add_rewritten(&derived, &idents, Rules::SIMPLIFY),
+            Err(err) => self.error(err.to_string()),
+        }
+    }
+
+    /// Adds `expr` after rewriting it as a new cell below the current one.
+    fn add_rewritten(&mut self, expr: &EvalNode, idents: &IdentStorer, rules: Rules) {
+        let rewritten = Rewriter::new(idents, rules).rewrite(expr);
+        self.add_cell(self.current + 1);
+        self.edit(|tree| *tree = unparse(&rewritten.expr, idents));
+        match rewritten.nonzero.is_empty() {
+            true => self.info(format!("wrote cell {}", self.current + 1)),
+            false => {
+                let nonzero: Vec<_> = rewritten
+                    .nonzero
+                    .iter()
+                    .map(|node| format!("{}≠0", unparse(node, idents).to_latex()))
+                    .collect();
+                self.info(format!(
+                    "wrote cell {}, assuming {}",
+                    self.current + 1,
+                    nonzero.join(", ")
+                ));
+            }
+        }
+    }
+
+    /// Takes in the updates the server received, returning whether there were any.
+    pub fn poll_comms(&mut self) -> bool {
+        let Some(server) = &mut self.server else {
+            return false;
+        };
+        let mut any = false;
+        loop {
+            match server.try_accept_update() {
+                Ok(update) => {
+                    for binding in &self.worksheet.bindings {
+                        if binding.channel == update.channel {
+                            self.received
+                                .insert(binding.cell.clone(), update.value.clone());
+                        }
+                    }
+                    any = true;
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    self.server = None;
+                    self.error(format!("the comms server stopped: {err}"));
+                    any = true;
+                    break;
+                }
+            }
+        }
+        if any {
+            self.evaluate();
+        }
+        any
+    }
+
+    fn comms_status(&self) -> String {
+        let Some(server) = &self.server else {
+            return "comms off".to_string();
+        };
+        let addrs: Vec<_> = server.listen_addrs().map(ToString::to_string).collect();
+        let stats = server.stats();
+        let last = match stats.since_last_receive() {
+            Some(since) => format!("{}s ago", since.as_secs()),
+            None => "never".to_string(),
+        };
+        format!(
+            "comms {} | {} received, last {last}",
+            addrs.join(", "),
+            stats.received
+        )
+    }
+
+    pub fn draw(&mut self, out: &mut RawTerminal<Stdout>) -> io::Result<()> {
+        let (width, height) = termion::terminal_size()?;
+        let rows = height.saturating_sub(2) as usize;
+        write!(out, "{}{}", clear::All, cursor::Hide)?;
+
+        let blocks: Vec<_> = (0..self.worksheet.cells.len())
+            .map(|index| self.render_cell(index))
+            .collect();
+        let block_height = |(screen, error): &(PrettyScreen, Option<String>)| {
+            screen.size().y as usize + error.is_some() as usize
+        };
+        self.scroll = self.scroll.min(self.current);
+        while self.scroll < self.current
+            && blocks[self.scroll..=self.current]
+                .iter()
+                .map(block_height)
+                .sum::<usize>()
+                > rows
+        {
+            self.scroll += 1;
+        }
+
+        let mut y = 0;
+        self.drawn.clear();
+        for (index, block) in blocks.iter().enumerate().skip(self.scroll) {
+            if y >= rows {
+                break;
+            }
+            let (screen, error) = block;
+            let number_color: &dyn Display = match index == self.current {
+                true => &color::Fg(color::Green),
+                false => &color::Fg(color::Reset),
+            };
+            write!(
+                out,
+                "{}{number_color}{:>3}{}",
+                cursor::Goto(1, y as u16 + 1),
+                index + 1,
+                color::Fg(color::Reset)
+            )?;
+            match self.show_debug && index == self.current {
+                true => self.worksheet.cells[index]
+                    .content
+                    .debug(true)
+                    .render()
+                    .display_raw(out, UVec2::new(GUTTER as u32, y as u32))?,
+                false => screen.display_raw(out, UVec2::new(GUTTER as u32, y as u32))?,
+            }
+            let height = screen.size().y as usize;
+            self.drawn.push((index, y as u32..(y + height) as u32));
+            y += height;
+            if let Some(error) = error {
+                write!(
+                    out,
+                    "{}{}{error}{}",
+                    cursor::Goto(GUTTER + 1, y as u16 + 1),
+                    color::Fg(color::Red),
+                    color::Fg(color::Reset)
+                )?;
+                y += 1;
+            }
+        }
+
+        self.draw_status(out, width, height)?;
+        out.flush()
+    }
+
+    /// The cell drawn with its value after it, and the error under it if it has one.
+    fn render_cell(&self, index: usize) -> (PrettyScreen, Option<String>) {
+        let content = &self.worksheet.cells[index].content;
+        let with_cursor = index == self.current && self.prompt != Some(Prompt::Command);
+        let tree = content.pretty(with_cursor);
+        match &self.results[index] {
+            Ok(Some(value)) => (tree.with_value(&value.to_string()).render(), None),
+            Ok(None) => (tree.render(), None),
+            Err(err) => (tree.render(), Some(err.to_string())),
+        }
+    }
+
+    fn draw_status(
+        &mut self,
+        out: &mut RawTerminal<Stdout>,
+        width: u16,
+        height: u16,
+    ) -> io::Result<()> {
+        let file = match &self.path {
+            Some(path) => path.display().to_string(),
+            None => "[no file]".to_string(),
+        };
+        let modified = if self.modified { " [+]" } else { "" };
+        let comms = self.comms_status();
+        let pending: String = self
+            .editor
+            .pending()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mode = match self.prompt {
+            Some(Prompt::Latex) => "LATEX".to_string(),
+            Some(Prompt::Command) => "COMMAND".to_string(),
+            None => self.editor.mode().to_string().to_uppercase(),
+        };
+        let completions: Vec<_> = self
+            .editor
+            .completions()
+            .iter()
+            .map(|completion| match &completion.subscript {
+                Some(subscript) => format!("{}_{subscript}", completion.name),
+                None => completion.name.clone(),
+            })
+            .collect();
+        let left = format!(
+            "-- {mode} -- {file}{modified} {pending} {}",
+            completions.join(" ")
+        );
+        let padding = (width as usize).saturating_sub(left.chars().count() + comms.chars().count());
+        write!(
+            out,
+            "{}{}{left}{}{comms}{}",
+            cursor::Goto(1, height.saturating_sub(1)),
+            color::Fg(color::Green),
+            " ".repeat(padding),
+            color::Fg(color::Reset)
+        )?;
+
+        write!(out, "{}", cursor::Goto(1, height))?;
+        match self.prompt {
+            Some(Prompt::Command) => write!(out, ":{}", self.input)?,
+            Some(Prompt::Latex) => write!(out, "latex: {}", self.input)?,
+            _ if self.message.is_error => write!(
+                out,
+                "{}{}{}",
+                color::Fg(color::Red),
+                self.message.text,
+                color::Fg(color::Reset)
+            )?,
+            _ => write!(out, "{}", self.message.text)?,
+        }
+        Ok(())
+    }
+
+    /// Forgets the message once a key was pressed after it was shown.
+    pub fn clear_message(&mut self) {
+        self.message = Message::default();
+    }
+}
+
+/// The key termion read, as the keymap writes it.
+fn key_press(key: Key) -> Option<KeyPress> {
+    Some(match key {
+        Key::Char('\n') => KeyPress::Enter,
+        Key::Char('\t') => KeyPress::Tab,
+        Key::Char(ch) => KeyPress::Char(ch),
+        Key::Ctrl(ch) => KeyPress::Ctrl(ch),
+        Key::Alt(ch) => KeyPress::Alt(ch),
+        Key::Esc => KeyPress::Esc,
+        Key::Backspace => KeyPress::Backspace,
+        Key::Left => KeyPress::Left,
+        Key::Right => KeyPress::Right,
+        Key::Up => KeyPress::Up,
+        Key::Down => KeyPress::Down,
+        _ => return None,
+    })
+}
@@ -31,6 +31,28 @@ macro_rules! try_options {
 }
 
 impl Builtins {
+    /// Every builtin, each once even when it has several names.
+    pub fn all() -> impl Iterator<Item = Self> {
+        let monadic = MonadicPervasive::ALL
+            .iter()
+            .copied()
+            .map(Self::MonadicPervasive);
+        let dyadic = DyadicPervasive::ALL
+            .iter()
+            .copied()
+            .map(Self::DyadicPervasive);
+        let non_pervasive = MonadicNonPervasive::ALL
+            .iter()
+            .copied()
+            .map(Self::MonadicNonPervasive);
+        let list_stat = ListStat::ALL.iter().copied().map(Self::ListStat);
+        monadic
+            .chain(dyadic)
+            .chain(non_pervasive)
+            .chain(list_stat)
+            .chain([Self::Join, Self::Sort, Self::Random])
+    }
+
     pub const fn as_str(&self) -> &'static str {
         match self {
             Self::MonadicPervasive(x) => x.as_str(),
@@ -1,7 +1,3 @@
-// use fast_desmos2_comms::value::{OneRef, Value, ValueKind};
-
-// use crate::{executor::evaluator::EvalErrorKind, lexing::Span, math, utils::OptExt};
-
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum DyadicPervasive {
     Mod,
@@ -11,6 +7,9 @@ pub enum DyadicPervasive {
 }
 
 impl DyadicPervasive {
+    /// Every variant, in declaration order.
+    pub const ALL: &'static [Self] = &[Self::Mod, Self::Choose, Self::Permutation, Self::Distance];
+
     pub const fn from_str(from: &[u8]) -> Option<Self> {
         Some(match from {
             b"mod" => Self::Mod,
@@ -30,51 +29,22 @@ impl DyadicPervasive {
         }
     }
 
-    // pub fn type_check(
-    //     &self,
-    //     a: (Span, ValueKind),
-    //     b: (Span, ValueKind),
-    // ) -> Result<(), EvalErrorKind> {
-    //     match self {
-    //         Self::Mod | Self::Choose | Self::Permutation => {
-    //             a.1.try_number().map_err(EvalErrorKind::wrong_type)?;
-    //             b.1.try_number().map_err(EvalErrorKind::wrong_type)?;
-    //         }
-    //         Self::Distance => match (a.1, b.1) {
-    //             (ValueKind::Number, ValueKind::Number) => {}
-    //             (ValueKind::Point, ValueKind::Point) => {}
-    //             _ => {
-    //                 return Err(EvalErrorKind::TypeMismatch {
-    //                     op_name: self.as_str(),
-    //                     left: a,
-    //                     right: b,
-    //                 })
-    //             }
-    //         },
-    //     }
-    //     Ok(())
-    // }
-    //
-    // pub fn apply_one(&self, a: OneRef, b: OneRef) -> Result<Value, EvalErrorKind> {
-    //     match self {
-    //         Self::Mod | Self::Choose | Self::Permutation => {
-    //             let &a = a.try_number().unwrap_unreach();
-    //             let &b = b.try_number().unwrap_unreach();
-    //
-    //             let result = match self {
-    //                 Self::Mod => a % b,
-    //                 Self::Choose => math::ncr(a, b),
-    //                 Self::Permutation => math::npr(a, b),
-    //                 _ => unreachable!(),
-    //             };
-    //
-    //             Ok(Value::one_number(result))
-    //         }
-    //         Self::Distance => match (a, b) {
-    //             (OneRef::Number(&a), OneRef::Number(&b)) => Ok(Value::one_number((a - b).abs())),
-    //             (OneRef::Point(&a), OneRef::Point(&b)) => Ok(Value::one_number((a - b).length())),
-    //             _ => unreachable!(),
-    //         },
-    //     }
-    // }
+    /// Applies the builtin to two numbers. The distance between points is left to the caller.
+    pub fn apply_numbers(&self, a: f64, b: f64) -> f64 {
+        match self {
+            // the sign follows the divisor, as in desmos.
+            Self::Mod => a - b * (a / b).floor(),
+            Self::Choose => permutations(a, b) / permutations(b, b),
+            Self::Permutation => permutations(a, b),
+            Self::Distance => (a - b).abs(),
+        }
+    }
+}
+
+/// The ways to pick `k` out of `n` things in order, for whole numbers.
+fn permutations(n: f64, k: f64) -> f64 {
+    if n.fract() != 0.0 || k.fract() != 0.0 || k < 0.0 || n < k {
+        return f64::NAN;
+    }
+    (0..k as u64).map(|i| n - i as f64).product()
 }
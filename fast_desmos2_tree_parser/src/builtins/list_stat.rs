@@ -1,4 +1,6 @@
-// use fast_desmos2_comms::{value::ops::iter_full, List};
+use std::ops::Add;
+
+use fast_desmos2_comms::List;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum ListStat {
@@ -9,6 +11,9 @@ pub enum ListStat {
 }
 
 impl ListStat {
+    /// Every variant, in declaration order.
+    pub const ALL: &'static [Self] = &[Self::Mean, Self::Min, Self::Max, Self::Total];
+
     pub const fn from_str(from: &[u8]) -> Option<Self> {
         Some(match from {
             b"mean" => Self::Mean,
@@ -28,15 +33,15 @@ impl ListStat {
         }
     }
 
-    // pub fn apply_numbers(&self, val: List<f64>) -> List<f64> {
-    //     match self {
-    //         ListStat::Total => val.fold(List::Term(0.0), &List::add),
-    //         ListStat::Mean => {
-    //             let len = val.len().map_or(1.0, |x| x as f64);
-    //             val.fold(List::Term(0.0), &List::add).map(&|x| x / len)
-    //         }
-    //         ListStat::Min => val.fold_iter(List::Term(f64::INFINITY), &f64::min),
-    //         ListStat::Max => val.fold_iter(List::Term(f64::NEG_INFINITY), &f64::max),
-    //     }
-    // }
+    pub fn apply_numbers(&self, val: List<f64>) -> List<f64> {
+        match self {
+            ListStat::Total => val.fold(List::Term(0.0), &List::add),
+            ListStat::Mean => {
+                let len = val.len().map_or(1.0, |x| x as f64);
+                val.fold(List::Term(0.0), &List::add).map(&|x| x / len)
+            }
+            ListStat::Min => val.fold_iter(List::Term(f64::INFINITY), &f64::min),
+            ListStat::Max => val.fold_iter(List::Term(f64::NEG_INFINITY), &f64::max),
+        }
+    }
 }
@@ -1,4 +1,4 @@
-// use fast_desmos2_comms::Value;
+use fast_desmos2_comms::Value;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum MonadicNonPervasive {
@@ -7,6 +7,9 @@ pub enum MonadicNonPervasive {
 }
 
 impl MonadicNonPervasive {
+    /// Every variant, in declaration order.
+    pub const ALL: &'static [Self] = &[Self::Length, Self::Unique];
+
     pub const fn from_str(from: &[u8]) -> Option<Self> {
         Some(match from {
             b"length" => Self::Length,
@@ -22,10 +25,10 @@ impl MonadicNonPervasive {
         }
     }
 
-    // pub fn apply(&self, x: Value) -> Value {
-    //     match self {
-    //         Self::Length => Value::one_number(x.len().map(|x| x as f64).unwrap_or(1.0)),
-    //         Self::Unique => x.unique(),
-    //     }
-    // }
+    pub fn apply(&self, x: Value) -> Value {
+        match self {
+            Self::Length => Value::one_number(x.len().map(|x| x as f64).unwrap_or(1.0)),
+            Self::Unique => x.unique(),
+        }
+    }
 }
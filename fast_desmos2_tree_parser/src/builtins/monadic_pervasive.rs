@@ -30,6 +30,8 @@ pub enum MonadicPervasive {
     ArcCsch,
     ArcCoth,
 
+    Ln,
+
     Sign,
     Floor,
     Ceil,
@@ -45,6 +47,39 @@ impl FromStr for MonadicPervasive {
 }
 
 impl MonadicPervasive {
+    /// Every variant, in declaration order.
+    pub const ALL: &'static [Self] = &[
+        Self::Sin,
+        Self::Cos,
+        Self::Tan,
+        Self::Sec,
+        Self::Csc,
+        Self::Cot,
+        Self::Sinh,
+        Self::Cosh,
+        Self::Tanh,
+        Self::Sech,
+        Self::Csch,
+        Self::Coth,
+        Self::ArcSin,
+        Self::ArcCos,
+        Self::ArcTan,
+        Self::ArcSec,
+        Self::ArcCsc,
+        Self::ArcCot,
+        Self::ArcSinh,
+        Self::ArcCosh,
+        Self::ArcTanh,
+        Self::ArcSech,
+        Self::ArcCsch,
+        Self::ArcCoth,
+        Self::Ln,
+        Self::Sign,
+        Self::Floor,
+        Self::Ceil,
+        Self::Round,
+    ];
+
     pub const fn from_str(from: &[u8]) -> Option<Self> {
         Some(match from {
             b"sin" => Self::Sin,
@@ -82,6 +117,8 @@ impl MonadicPervasive {
             b"arcsch" => Self::ArcCsch,
             b"arcoth" => Self::ArcCoth,
 
+            b"ln" => Self::Ln,
+
             b"sign" => Self::Sign,
             b"floor" => Self::Floor,
             b"ceil" => Self::Ceil,
@@ -121,6 +158,8 @@ impl MonadicPervasive {
             Self::ArcCsch => "arccsch",
             Self::ArcCoth => "arccoth",
 
+            Self::Ln => "ln",
+
             Self::Sign => "sign",
             Self::Floor => "floor",
             Self::Ceil => "ceil",
@@ -158,6 +197,7 @@ impl MonadicPervasive {
             Self::ArcCsch => Self::Csch,
             Self::ArcCoth => Self::Coth,
 
+            Self::Ln => return None,
             Self::Sign => return None,
             Self::Ceil => return None,
             Self::Floor => return None,
@@ -165,77 +205,44 @@ impl MonadicPervasive {
         })
     }
 
-    // pub fn apply_one(&self, target: f64) -> f64 {
-    //     match self {
-    //         Self::Sin => f64::sin(target),
-    //         Self::Cos => f64::cos(target),
-    //         Self::Tan => f64::tan(target),
-    //         Self::Sec => target.cos().recip(),
-    //         Self::Csc => target.sin().recip(),
-    //         Self::Cot => target.tan().recip(),
-    //
-    //         Self::Sinh => f64::sinh(target),
-    //         Self::Cosh => f64::cosh(target),
-    //         Self::Tanh => f64::tanh(target),
-    //         Self::Sech => target.cosh().recip(),
-    //         Self::Csch => target.sinh().recip(),
-    //         Self::Coth => target.tanh().recip(),
-    //
-    //         Self::ArcSin => f64::asin(target),
-    //         Self::ArcCos => f64::acos(target),
-    //         Self::ArcTan => f64::atan(target),
-    //         Self::ArcSec => target.recip().acos(),
-    //         Self::ArcCsc => target.recip().asin(),
-    //         Self::ArcCot => target.recip().atan(),
-    //
-    //         Self::ArcSinh => f64::asinh(target),
-    //         Self::ArcCosh => f64::acosh(target),
-    //         Self::ArcTanh => f64::atanh(target),
-    //         Self::ArcSech => target.recip().acosh(),
-    //         Self::ArcCsch => target.recip().asinh(),
-    //         Self::ArcCoth => target.recip().atanh(),
-    //
-    //         Self::Sign => f64::signum(target),
-    //         Self::Floor => f64::floor(target),
-    //         Self::Ceil => f64::ceil(target),
-    //         Self::Round => f64::round(target),
-    //     }
-    // }
-    //
-    // pub fn apply_number(&self, numbers: ValueList<f64>) -> ValueList<f64> {
-    //     match self {
-    //         Self::Sin => numbers.map(&f64::sin),
-    //         Self::Cos => numbers.map(&f64::cos),
-    //         Self::Tan => numbers.map(&f64::tan),
-    //         Self::Sec => numbers.map(&|x| x.cos().recip()),
-    //         Self::Csc => numbers.map(&|x| x.sin().recip()),
-    //         Self::Cot => numbers.map(&|x| x.tan().recip()),
-    //
-    //         Self::Sinh => numbers.map(&f64::sinh),
-    //         Self::Cosh => numbers.map(&f64::cosh),
-    //         Self::Tanh => numbers.map(&f64::tanh),
-    //         Self::Sech => numbers.map(&|x| x.cosh().recip()),
-    //         Self::Csch => numbers.map(&|x| x.sinh().recip()),
-    //         Self::Coth => numbers.map(&|x| x.tanh().recip()),
-    //
-    //         Self::ArcSin => numbers.map(&f64::asin),
-    //         Self::ArcCos => numbers.map(&f64::acos),
-    //         Self::ArcTan => numbers.map(&f64::atan),
-    //         Self::ArcSec => numbers.map(&|x| x.recip().acos()),
-    //         Self::ArcCsc => numbers.map(&|x| x.recip().asin()),
-    //         Self::ArcCot => numbers.map(&|x| x.recip().atan()),
-    //
-    //         Self::ArcSinh => numbers.map(&f64::asinh),
-    //         Self::ArcCosh => numbers.map(&f64::acosh),
-    //         Self::ArcTanh => numbers.map(&f64::atanh),
-    //         Self::ArcSech => numbers.map(&|x| x.recip().acosh()),
-    //         Self::ArcCsch => numbers.map(&|x| x.recip().asinh()),
-    //         Self::ArcCoth => numbers.map(&|x| x.recip().atanh()),
-    //
-    //         Self::Sign => numbers.map(&f64::signum),
-    //         Self::Floor => numbers.map(&f64::floor),
-    //         Self::Ceil => numbers.map(&f64::ceil),
-    //         Self::Round => numbers.map(&f64::round),
-    //     }
-    // }
+    pub fn apply(self, target: f64) -> f64 {
+        match self {
+            Self::Sin => f64::sin(target),
+            Self::Cos => f64::cos(target),
+            Self::Tan => f64::tan(target),
+            Self::Sec => target.cos().recip(),
+            Self::Csc => target.sin().recip(),
+            Self::Cot => target.tan().recip(),
+
+            Self::Sinh => f64::sinh(target),
+            Self::Cosh => f64::cosh(target),
+            Self::Tanh => f64::tanh(target),
+            Self::Sech => target.cosh().recip(),
+            Self::Csch => target.sinh().recip(),
+            Self::Coth => target.tanh().recip(),
+
+            Self::ArcSin => f64::asin(target),
+            Self::ArcCos => f64::acos(target),
+            Self::ArcTan => f64::atan(target),
+            Self::ArcSec => target.recip().acos(),
+            Self::ArcCsc => target.recip().asin(),
+            Self::ArcCot => target.recip().atan(),
+
+            Self::ArcSinh => f64::asinh(target),
+            Self::ArcCosh => f64::acosh(target),
+            Self::ArcTanh => f64::atanh(target),
+            Self::ArcSech => target.recip().acosh(),
+            Self::ArcCsch => target.recip().asinh(),
+            Self::ArcCoth => target.recip().atanh(),
+
+            Self::Ln => f64::ln(target),
+
+            // `signum` has no zero.
+            Self::Sign if target == 0.0 => 0.0,
+            Self::Sign => f64::signum(target),
+            Self::Floor => f64::floor(target),
+            Self::Ceil => f64::ceil(target),
+            Self::Round => f64::round(target),
+        }
+    }
 }
@@ -0,0 +1,17 @@
+//! The candidates for completing names while typing, which the editor can't know about itself.
+
+use fast_desmos2_tree::tree::{Completion, EditorTreeSeq};
+use fast_desmos2_tree::worksheet::Worksheet;
+
+use crate::builtins::Builtins;
+
+/// Every builtin, as a function to call.
+pub fn builtin_completions() -> impl Iterator<Item = Completion> {
+    Builtins::all().map(|builtins| Completion::function(builtins.as_str()))
+}
+
+/// The completions of the identifier at the caret of `seq`, out of the builtins and the names
+/// defined in `worksheet`. A builtin wins over a cell redefining its name.
+pub fn completions(seq: &EditorTreeSeq, worksheet: &Worksheet) -> Vec<Completion> {
+    seq.completions(builtin_completions().chain(worksheet.defined_names()))
+}
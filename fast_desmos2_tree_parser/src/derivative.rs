@@ -0,0 +1,684 @@
+//! Symbolic derivatives, for showing how a derivative is worked out rather than just its value.
+//!
+//! Names defined by other cells are differentiated through their definitions, and calls of
+//! functions other cells define are inlined, so the derivative of `f(x^2)` with `f(t)=sin(t)` is
+//! that of `sin(x^2)`. Names that aren't defined anywhere are constants. The derivative that comes
+//! out isn't simplified at all; it's meant to go through [`simplify`](crate::simplify) first.
+
+use crate::{
+    builtins::{Builtins, MonadicPervasive},
+    eval::{Definition, Env, EvalError, EvalResult},
+    tree::{AddOrSub, Conditional, EvalKind, EvalNode, IdentId, Statement, VarDef},
+};
+use fast_desmos2_tree::tree::SumOrProd;
+
+/// The derivative of what `statement` is, or is defined as, by `var`. The parameters of a
+/// function are independent of `var`, unless one of them is `var`.
+pub fn derivative(env: &Env, statement: &Statement, var: IdentId) -> EvalResult<EvalNode> {
+    let mut differentiator = Differentiator::new(env, var);
+    match statement {
+        Statement::Expression(expr) => differentiator.derive(expr),
+        Statement::Variable(def) => differentiator.derive(def.expr()),
+        Statement::Function(def) => {
+            let params = def.params().iter().filter(|param| **param != var);
+            differentiator
+                .locals
+                .extend(params.map(|param| (*param, Local::Bound)));
+            differentiator.derive(def.expr())
+        }
+    }
+}
+
+/// What a name means where it's bound.
+#[derive(Debug, Clone)]
+enum Local {
+    /// The index of a sum or a parameter that's independent of the variable.
+    Bound,
+    /// A parameter of a function being inlined, with the argument it was called with and its
+    /// derivative.
+    Arg {
+        value: EvalNode,
+        derivative: EvalNode,
+    },
+}
+
+struct Differentiator<'e, 'a> {
+    env: &'e Env<'a>,
+    var: IdentId,
+    locals: Vec<(IdentId, Local)>,
+    /// The definitions being inlined, to catch the ones that depend on themselves.
+    inlining: Vec<IdentId>,
+}
+
+impl<'e, 'a> Differentiator<'e, 'a> {
+    fn new(env: &'e Env<'a>, var: IdentId) -> Self {
+        Self {
+            env,
+            var,
+            locals: Vec::new(),
+            inlining: Vec::new(),
+        }
+    }
+
+    fn name(&self, ident: IdentId) -> String {
+        self.env.idents().name(ident).to_string()
+    }
+
+    fn local(&self, ident: IdentId) -> Option<&Local> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| *local == ident)
+            .map(|(_, local)| local)
+    }
+
+    /// Differentiates the definition of `ident` with only `locals` in scope.
+    fn enter(
+        &mut self,
+        ident: IdentId,
+        locals: Vec<(IdentId, Local)>,
+        expr: &EvalNode,
+    ) -> EvalResult<EvalNode> {
+        if self.inlining.contains(&ident) {
+            return Err(EvalError::Cycle(self.name(ident)));
+        }
+        self.inlining.push(ident);
+        let outer = std::mem::replace(&mut self.locals, locals);
+        let result = self.derive(expr);
+        self.locals = outer;
+        self.inlining.pop();
+        result
+    }
+
+    /// `node` as it is where it's being differentiated, with the arguments of the functions
+    /// being inlined in place of their parameters.
+    fn value(&self, node: &EvalNode) -> EvalNode {
+        let args: Vec<_> = self
+            .locals
+            .iter()
+            .rev()
+            .filter_map(|(ident, local)| match local {
+                Local::Bound => None,
+                Local::Arg { value, .. } => Some((*ident, value.clone())),
+            })
+            .collect();
+        match args.is_empty() {
+            true => node.clone(),
+            false => substitute(node, &args),
+        }
+    }
+
+    fn derive(&mut self, node: &EvalNode) -> EvalResult<EvalNode> {
+        Ok(match node.kind() {
+            EvalKind::Number(_) => zero(),
+            EvalKind::Identifier(ident) => self.identifier(*ident)?,
+            EvalKind::AddSub(pairs) => {
+                let mut derived = Vec::with_capacity(pairs.len());
+                for (sign, node) in pairs {
+                    derived.push((*sign, self.derive(node)?));
+                }
+                sum(derived)
+            }
+            EvalKind::Multiply(factors) => {
+                // the product rule, one term for each factor being differentiated.
+                let mut terms = Vec::with_capacity(factors.len());
+                for (index, factor) in factors.iter().enumerate() {
+                    let derived = self.derive(factor)?;
+                    let term = factors
+                        .iter()
+                        .enumerate()
+                        .map(|(other, factor)| match other == index {
+                            true => derived.clone(),
+                            false => self.value(factor),
+                        })
+                        .collect();
+                    terms.push((AddOrSub::Add, product(term)));
+                }
+                sum(terms)
+            }
+            EvalKind::Frac { top, bottom } => {
+                let (d_top, d_bottom) = (self.derive(top)?, self.derive(bottom)?);
+                let (top, bottom) = (self.value(top), self.value(bottom));
+                match is_zero(&d_bottom) {
+                    true => frac(d_top, bottom),
+                    false => frac(
+                        sum(vec![
+                            (AddOrSub::Add, product(vec![d_top, bottom.clone()])),
+                            (AddOrSub::Sub, product(vec![top, d_bottom])),
+                        ]),
+                        EvalNode::power(bottom, EvalNode::number(2.0)),
+                    ),
+                }
+            }
+            EvalKind::Power { base, power } => {
+                let base_derived = self.derive(base)?;
+                self.power(self.value(base), base_derived, power)?
+            }
+            EvalKind::Sqrt(inner) => {
+                let derived = self.derive(inner)?;
+                let root = EvalNode::sqrt(self.value(inner));
+                frac(derived, product(vec![EvalNode::number(2.0), root]))
+            }
+            EvalKind::Root { index, expr } => {
+                if !is_zero(&self.derive(index)?) {
+                    return Err(EvalError::Unsupported(
+                        "differentiating roots with the variable in the index",
+                    ));
+                }
+                let derived = self.derive(expr)?;
+                let index = self.value(index);
+                let root = EvalNode::root(index.clone(), self.value(expr));
+                let one_less = sum(vec![
+                    (AddOrSub::Add, index.clone()),
+                    (AddOrSub::Sub, EvalNode::number(1.0)),
+                ]);
+                frac(
+                    derived,
+                    product(vec![index, EvalNode::power(root, one_less)]),
+                )
+            }
+            EvalKind::Abs(inner) => {
+                let derived = self.derive(inner)?;
+                let sign = call(MonadicPervasive::Sign, self.value(inner));
+                product(vec![sign, derived])
+            }
+            EvalKind::BuiltinsCall {
+                builtins,
+                power,
+                params,
+            } => self.builtins_call(*builtins, power.as_ref(), params)?,
+            EvalKind::FunctionCall {
+                ident,
+                power,
+                params,
+            } => {
+                let derived = self.function_call(*ident, params)?;
+                match power {
+                    Some(power) => {
+                        let call = EvalNode::function_call(*ident, None, params.clone());
+                        self.power(self.value(&call), derived, power)?
+                    }
+                    None => derived,
+                }
+            }
+            EvalKind::Point(x, y) => {
+                EvalNode::new(EvalKind::Point(self.derive(x)?, self.derive(y)?))
+            }
+            EvalKind::List(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.derive(item))
+                    .collect::<EvalResult<_>>()?;
+                EvalNode::list_literal(items)
+            }
+            EvalKind::IfElse { conds, yes, no } => {
+                let conds = conds
+                    .iter()
+                    .map(|cond| {
+                        let comps = cond
+                            .comps()
+                            .iter()
+                            .map(|(set, right)| (*set, self.value(right)))
+                            .collect();
+                        Conditional::new(self.value(cond.expr()), comps)
+                    })
+                    .collect();
+                // without a branch, a piecewise is one where its conditions hold.
+                let yes = match yes {
+                    Some(yes) => self.derive(yes)?,
+                    None => zero(),
+                };
+                let no = no.as_ref().map(|no| self.derive(no)).transpose()?;
+                EvalNode::if_else(conds, Some(yes), no)
+            }
+            EvalKind::SumProd {
+                kind: SumOrProd::Sum,
+                ident,
+                from,
+                to,
+                expr,
+            } => {
+                if !is_zero(&self.derive(from)?) || !is_zero(&self.derive(to)?) {
+                    return Err(EvalError::Unsupported(
+                        "differentiating sums with the variable in their bounds",
+                    ));
+                }
+                self.locals.push((*ident, Local::Bound));
+                let derived = self.derive(expr);
+                self.locals.pop();
+                EvalNode::new(EvalKind::SumProd {
+                    kind: SumOrProd::Sum,
+                    ident: *ident,
+                    from: self.value(from),
+                    to: self.value(to),
+                    expr: derived?,
+                })
+            }
+            EvalKind::SumProd {
+                kind: SumOrProd::Prod,
+                ..
+            } => return Err(EvalError::Unsupported("differentiating products")),
+            EvalKind::ElemAccess { expr, element } => EvalNode::new(EvalKind::ElemAccess {
+                expr: self.derive(expr)?,
+                element: *element,
+            }),
+            EvalKind::ListIndexing { expr, index } => {
+                EvalNode::index(self.derive(expr)?, self.value(index))
+            }
+            EvalKind::ListRange { .. } => {
+                return Err(EvalError::Unsupported("differentiating ranges"))
+            }
+            EvalKind::For { .. } => return Err(EvalError::Unsupported("`for`")),
+            EvalKind::ListComp { .. } => return Err(EvalError::Unsupported("list comprehensions")),
+            EvalKind::With { .. } => return Err(EvalError::Unsupported("`with`")),
+        })
+    }
+
+    fn identifier(&mut self, ident: IdentId) -> EvalResult<EvalNode> {
+        match self.local(ident) {
+            Some(Local::Bound) => return Ok(zero()),
+            Some(Local::Arg { derivative, .. }) => return Ok(derivative.clone()),
+            None if ident == self.var => return Ok(EvalNode::number(1.0)),
+            None => {}
+        }
+        match self.env.definition(ident) {
+            Some(Definition::Expr(expr)) => self.enter(ident, Vec::new(), &expr.clone()),
+            Some(Definition::Function(_)) => Err(EvalError::NotAValue(self.name(ident))),
+            Some(Definition::Redefined) => Err(EvalError::Redefined(self.name(ident))),
+            // values from outside and names that aren't defined are constants.
+            Some(Definition::Value(_)) | None => Ok(zero()),
+        }
+    }
+
+    /// The derivative of `base` to the `power`, where `base` has the derivative `derived`.
+    fn power(
+        &mut self,
+        base: EvalNode,
+        derived: EvalNode,
+        power: &EvalNode,
+    ) -> EvalResult<EvalNode> {
+        let power_derived = self.derive(power)?;
+        let power = self.value(power);
+        if !is_zero(&power_derived) {
+            // `u^v` is `e^(v ln(u))`, so its derivative is `u^v (v' ln(u) + v u'/u)`, which is
+            // `a^v ln(a) v'` for a constant `a`.
+            let log = call(MonadicPervasive::Ln, base.clone());
+            let factor = sum(vec![
+                (AddOrSub::Add, product(vec![power_derived, log])),
+                (
+                    AddOrSub::Add,
+                    frac(product(vec![power.clone(), derived]), base.clone()),
+                ),
+            ]);
+            return Ok(product(vec![EvalNode::power(base, power), factor]));
+        }
+        let one_less = match power.kind() {
+            EvalKind::Number(power) => EvalNode::number(power - 1.0),
+            _ => sum(vec![
+                (AddOrSub::Add, power.clone()),
+                (AddOrSub::Sub, EvalNode::number(1.0)),
+            ]),
+        };
+        Ok(product(vec![
+            power,
+            EvalNode::power(base, one_less),
+            derived,
+        ]))
+    }
+
+    fn builtins_call(
+        &mut self,
+        builtins: Builtins,
+        power: Option<&EvalNode>,
+        params: &[EvalNode],
+    ) -> EvalResult<EvalNode> {
+        let Builtins::MonadicPervasive(func) = builtins else {
+            return Err(EvalError::Unsupported(
+                "differentiating builtins that take more than a number",
+            ));
+        };
+        let [param] = params else {
+            return Err(EvalError::Arity {
+                name: builtins.as_str().to_string(),
+                expected: 1,
+                found: params.len(),
+            });
+        };
+        // `sin^-1` is the inverse, not one over `sin`.
+        let (func, power) = match (power.and_then(as_number), func.invert()) {
+            (Some(-1.0), Some(inverse)) => (inverse, None),
+            _ => (func, power),
+        };
+        let param_derived = self.derive(param)?;
+        let param = self.value(param);
+        let derived = product(vec![monadic(func, param.clone()), param_derived]);
+        match power {
+            Some(power) => self.power(call(func, param), derived, power),
+            None => Ok(derived),
+        }
+    }
+
+    /// The derivative of a call of a function another cell defines, which is inlined.
+    fn function_call(&mut self, ident: IdentId, params: &[EvalNode]) -> EvalResult<EvalNode> {
+        // calling a variable multiplies by it, like `a(x+1)`.
+        let is_value = self.local(ident).is_some()
+            || ident == self.var
+            || !matches!(self.env.definition(ident), Some(Definition::Function(_)));
+        if is_value {
+            let [param] = params else {
+                return Err(EvalError::Unsupported("multiplying by a point"));
+            };
+            return self.derive(&EvalNode::multiply(vec![
+                EvalNode::ident(ident),
+                param.clone(),
+            ]));
+        }
+
+        let Some(Definition::Function(def)) = self.env.definition(ident) else {
+            unreachable!("checked to be a function above");
+        };
+        if def.params().len() != params.len() {
+            return Err(EvalError::Arity {
+                name: self.name(ident),
+                expected: def.params().len(),
+                found: params.len(),
+            });
+        }
+        let def = def.clone();
+        let mut locals = Vec::with_capacity(params.len());
+        for (param, arg) in def.params().iter().zip(params) {
+            let local = Local::Arg {
+                value: self.value(arg),
+                derivative: self.derive(arg)?,
+            };
+            locals.push((*param, local));
+        }
+        self.enter(ident, locals, def.expr())
+    }
+}
+
+/// The derivative of `func` at `x`.
+fn monadic(func: MonadicPervasive, x: EvalNode) -> EvalNode {
+    use MonadicPervasive as M;
+    let number = EvalNode::number;
+    let square = |x: &EvalNode| EvalNode::power(x.clone(), number(2.0));
+    let one_plus_square = || {
+        sum(vec![
+            (AddOrSub::Add, number(1.0)),
+            (AddOrSub::Add, square(&x)),
+        ])
+    };
+    let one_minus_square = || {
+        sum(vec![
+            (AddOrSub::Add, number(1.0)),
+            (AddOrSub::Sub, square(&x)),
+        ])
+    };
+    let square_minus_one = || {
+        sum(vec![
+            (AddOrSub::Add, square(&x)),
+            (AddOrSub::Sub, number(1.0)),
+        ])
+    };
+    let times = |a: M, b: M| product(vec![call(a, x.clone()), call(b, x.clone())]);
+    let call_squared = |func: M| EvalNode::power(call(func, x.clone()), number(2.0));
+    let one_over = |bottom: EvalNode| frac(number(1.0), bottom);
+    match func {
+        M::Sin => call(M::Cos, x),
+        M::Cos => negate(call(M::Sin, x)),
+        M::Tan => call_squared(M::Sec),
+        M::Sec => times(M::Sec, M::Tan),
+        M::Csc => negate(times(M::Csc, M::Cot)),
+        M::Cot => negate(call_squared(M::Csc)),
+
+        M::Sinh => call(M::Cosh, x),
+        M::Cosh => call(M::Sinh, x),
+        M::Tanh => call_squared(M::Sech),
+        M::Sech => negate(times(M::Sech, M::Tanh)),
+        M::Csch => negate(times(M::Csch, M::Coth)),
+        M::Coth => negate(call_squared(M::Csch)),
+
+        M::ArcSin => one_over(EvalNode::sqrt(one_minus_square())),
+        M::ArcCos => negate(one_over(EvalNode::sqrt(one_minus_square()))),
+        M::ArcTan => one_over(one_plus_square()),
+        M::ArcSec => one_over(product(vec![
+            EvalNode::abs(x.clone()),
+            EvalNode::sqrt(square_minus_one()),
+        ])),
+        M::ArcCsc => negate(one_over(product(vec![
+            EvalNode::abs(x.clone()),
+            EvalNode::sqrt(square_minus_one()),
+        ]))),
+        M::ArcCot => negate(one_over(one_plus_square())),
+
+        M::ArcSinh => one_over(EvalNode::sqrt(one_plus_square())),
+        M::ArcCosh => one_over(EvalNode::sqrt(square_minus_one())),
+        M::ArcTanh | M::ArcCoth => one_over(one_minus_square()),
+        M::ArcSech => negate(one_over(product(vec![
+            x.clone(),
+            EvalNode::sqrt(one_minus_square()),
+        ]))),
+        M::ArcCsch => negate(one_over(product(vec![
+            EvalNode::abs(x.clone()),
+            EvalNode::sqrt(one_plus_square()),
+        ]))),
+
+        M::Ln => one_over(x),
+
+        // these are flat wherever they have a derivative.
+        M::Sign | M::Floor | M::Ceil | M::Round => zero(),
+    }
+}
+
+/// `node` with the names in `args` replaced by what they stand for, where they aren't bound to
+/// something else.
+fn substitute(node: &EvalNode, args: &[(IdentId, EvalNode)]) -> EvalNode {
+    let nodes = |nodes: &[EvalNode]| -> Vec<EvalNode> {
+        nodes.iter().map(|node| substitute(node, args)).collect()
+    };
+    let without = |idents: &[IdentId]| -> Vec<(IdentId, EvalNode)> {
+        args.iter()
+            .filter(|(ident, _)| !idents.contains(ident))
+            .cloned()
+            .collect()
+    };
+    // the names defined are bound in the expression, but not in the definitions.
+    let defs = |defs: &[VarDef]| -> (Vec<VarDef>, Vec<(IdentId, EvalNode)>) {
+        let bound: Vec<_> = defs.iter().map(VarDef::ident).collect();
+        let defs = defs
+            .iter()
+            .map(|def| VarDef::new(def.ident(), substitute(def.expr(), args)))
+            .collect();
+        (defs, without(&bound))
+    };
+    let kind = match node.kind() {
+        EvalKind::Identifier(ident) => {
+            return match args.iter().find(|(arg, _)| arg == ident) {
+                Some((_, value)) => value.clone(),
+                None => node.clone(),
+            }
+        }
+        EvalKind::Number(_) => return node.clone(),
+        EvalKind::BuiltinsCall {
+            builtins,
+            power,
+            params,
+        } => EvalKind::BuiltinsCall {
+            builtins: *builtins,
+            power: power.as_ref().map(|power| substitute(power, args)),
+            params: nodes(params),
+        },
+        EvalKind::FunctionCall {
+            ident,
+            power,
+            params,
+        } => {
+            let power = power.as_ref().map(|power| substitute(power, args));
+            let params = nodes(params);
+            // a parameter that's called multiplies by what it stands for.
+            if let Some((_, value)) = args.iter().find(|(arg, _)| arg == ident) {
+                let product = EvalNode::multiply([vec![value.clone()], params].concat());
+                return match power {
+                    Some(power) => EvalNode::power(product, power),
+                    None => product,
+                };
+            }
+            EvalKind::FunctionCall {
+                ident: *ident,
+                power,
+                params,
+            }
+        }
+        EvalKind::Abs(inner) => EvalKind::Abs(substitute(inner, args)),
+        EvalKind::Sqrt(inner) => EvalKind::Sqrt(substitute(inner, args)),
+        EvalKind::Root { index, expr } => EvalKind::Root {
+            index: substitute(index, args),
+            expr: substitute(expr, args),
+        },
+        EvalKind::Point(x, y) => EvalKind::Point(substitute(x, args), substitute(y, args)),
+        EvalKind::List(items) => EvalKind::List(nodes(items)),
+        EvalKind::Multiply(factors) => EvalKind::Multiply(nodes(factors)),
+        EvalKind::AddSub(pairs) => EvalKind::AddSub(
+            pairs
+                .iter()
+                .map(|(sign, node)| (*sign, substitute(node, args)))
+                .collect(),
+        ),
+        EvalKind::Frac { top, bottom } => EvalKind::Frac {
+            top: substitute(top, args),
+            bottom: substitute(bottom, args),
+        },
+        EvalKind::Power { base, power } => EvalKind::Power {
+            base: substitute(base, args),
+            power: substitute(power, args),
+        },
+        EvalKind::SumProd {
+            kind,
+            ident,
+            from,
+            to,
+            expr,
+        } => EvalKind::SumProd {
+            kind: *kind,
+            ident: *ident,
+            from: substitute(from, args),
+            to: substitute(to, args),
+            expr: substitute(expr, &without(&[*ident])),
+        },
+        EvalKind::ListRange { from, next, to } => EvalKind::ListRange {
+            from: substitute(from, args),
+            next: next.as_ref().map(|next| substitute(next, args)),
+            to: substitute(to, args),
+        },
+        EvalKind::IfElse { conds, yes, no } => EvalKind::IfElse {
+            conds: conds
+                .iter()
+                .map(|cond| {
+                    let comps = cond
+                        .comps()
+                        .iter()
+                        .map(|(set, right)| (*set, substitute(right, args)))
+                        .collect();
+                    Conditional::new(substitute(cond.expr(), args), comps)
+                })
+                .collect(),
+            yes: yes.as_ref().map(|yes| substitute(yes, args)),
+            no: no.as_ref().map(|no| substitute(no, args)),
+        },
+        EvalKind::ElemAccess { expr, element } => EvalKind::ElemAccess {
+            expr: substitute(expr, args),
+            element: *element,
+        },
+        EvalKind::ListIndexing { expr, index } => EvalKind::ListIndexing {
+            expr: substitute(expr, args),
+            index: substitute(index, args),
+        },
+        EvalKind::For { expr, defs: list } => {
+            let (defs, args) = defs(list);
+            EvalKind::For {
+                expr: substitute(expr, &args),
+                defs,
+            }
+        }
+        EvalKind::With { expr, defs: list } => {
+            let (defs, args) = defs(list);
+            EvalKind::With {
+                expr: substitute(expr, &args),
+                defs,
+            }
+        }
+        EvalKind::ListComp { expr, defs: list } => {
+            let (defs, args) = defs(list);
+            EvalKind::ListComp {
+                expr: substitute(expr, &args),
+                defs,
+            }
+        }
+    };
+    EvalNode::new(kind)
+}
+
+fn zero() -> EvalNode {
+    EvalNode::number(0.0)
+}
+
+fn is_zero(node: &EvalNode) -> bool {
+    as_number(node) == Some(0.0)
+}
+
+/// The number `node` is, which can be written with a minus in front.
+fn as_number(node: &EvalNode) -> Option<f64> {
+    match node.kind() {
+        EvalKind::Number(x) => Some(*x),
+        EvalKind::AddSub(pairs) => match pairs.as_slice() {
+            [(AddOrSub::Sub, node)] => as_number(node).map(|x| -x),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn call(func: MonadicPervasive, x: EvalNode) -> EvalNode {
+    EvalNode::builtins_call(Builtins::MonadicPervasive(func), None, vec![x])
+}
+
+// these leave out what adding zero and multiplying by one or zero would, so the derivatives of
+// what doesn't depend on the variable come out as zero.
+
+fn sum(pairs: Vec<(AddOrSub, EvalNode)>) -> EvalNode {
+    let mut pairs: Vec<_> = pairs
+        .into_iter()
+        .filter(|(_, node)| !is_zero(node))
+        .collect();
+    match pairs.as_slice() {
+        [] => zero(),
+        [(AddOrSub::Add, _)] => pairs.remove(0).1,
+        _ => EvalNode::add_sub(pairs),
+    }
+}
+
+fn negate(node: EvalNode) -> EvalNode {
+    sum(vec![(AddOrSub::Sub, node)])
+}
+
+fn product(factors: Vec<EvalNode>) -> EvalNode {
+    if factors.iter().any(is_zero) {
+        return zero();
+    }
+    let mut factors: Vec<_> = factors
+        .into_iter()
+        .filter(|node| as_number(node) != Some(1.0))
+        .collect();
+    match factors.len() {
+        0 => EvalNode::number(1.0),
+        1 => factors.remove(0),
+        _ => EvalNode::multiply(factors),
+    }
+}
+
+fn frac(top: EvalNode, bottom: EvalNode) -> EvalNode {
+    match is_zero(&top) {
+        true => zero(),
+        false => EvalNode::frac(top, bottom),
+    }
+}
@@ -0,0 +1,612 @@
+//! Evaluating parsed cells into values, the way desmos shows them under each expression.
+
+use std::collections::HashMap;
+
+use fast_desmos2_comms::{value::ops::iter_full, List, TypeMismatch, Value};
+use fast_desmos2_tree::{tree::SumOrProd, worksheet::Worksheet};
+use glam::DVec2;
+use thiserror::Error;
+
+use crate::{
+    builtins::{Builtins, DyadicPervasive, ListStat},
+    parsing::parse_statement,
+    tree::{
+        AddOrSub, CompSet, Conditional, Element, EvalKind, EvalNode, FunctionDef, IdentId,
+        IdentStorer, Statement, VarDef,
+    },
+};
+
+/// The longest list a range can make, as in desmos, and the most terms a sum or product can have.
+pub const MAX_LIST_LEN: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum EvalError {
+    #[error("`{0}` isn't defined")]
+    Undefined(String),
+    #[error("`{0}` is defined more than once")]
+    Redefined(String),
+    #[error("`{0}` is defined in terms of itself")]
+    Cycle(String),
+    #[error("`{0}` is a function and needs arguments")]
+    NotAValue(String),
+    #[error("`{name}` takes {expected} arguments, not {found}")]
+    Arity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("expected a {}, found a {}", .0.expect, .0.got)]
+    TypeMismatch(TypeMismatch),
+    #[error("expected a single number, found a list")]
+    NotANumber,
+    #[error("only lists can be indexed")]
+    NotAList,
+    #[error("lists and sums can't have more than {MAX_LIST_LEN} elements")]
+    TooLong,
+    #[error("{0} isn't supported yet")]
+    Unsupported(&'static str),
+}
+
+pub type EvalResult<T> = Result<T, EvalError>;
+
+impl From<TypeMismatch> for EvalError {
+    fn from(value: TypeMismatch) -> Self {
+        Self::TypeMismatch(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Definition {
+    Value(Value),
+    Expr(EvalNode),
+    Function(FunctionDef),
+    Redefined,
+}
+
+/// The names in scope, each defined by a cell or given its value from outside.
+#[derive(Debug)]
+pub struct Env<'a> {
+    idents: &'a IdentStorer,
+    definitions: HashMap<IdentId, Definition>,
+}
+
+impl<'a> Env<'a> {
+    pub fn new(idents: &'a IdentStorer) -> Self {
+        Self {
+            idents,
+            definitions: HashMap::new(),
+        }
+    }
+
+    /// Adds a definition. Names defined twice can't be used at all.
+    fn insert(&mut self, ident: IdentId, definition: Definition) {
+        self.definitions
+            .entry(ident)
+            .and_modify(|old| *old = Definition::Redefined)
+            .or_insert(definition);
+    }
+
+    pub fn define(&mut self, def: &VarDef) {
+        self.insert(def.ident(), Definition::Expr(def.expr().clone()));
+    }
+
+    pub fn define_function(&mut self, def: &FunctionDef) {
+        self.insert(def.ident(), Definition::Function(def.clone()));
+    }
+
+    /// Defines `ident` as a value that doesn't have to be evaluated.
+    pub fn set(&mut self, ident: IdentId, value: Value) {
+        self.insert(ident, Definition::Value(value));
+    }
+
+    pub fn evaluate(&self, node: &EvalNode) -> EvalResult<Value> {
+        Evaluator::new(self).eval(node)
+    }
+
+    /// Evaluates `node` with `locals` in scope, shadowing what the environment defines.
+    pub fn evaluate_with(
+        &self,
+        node: &EvalNode,
+        locals: impl IntoIterator<Item = (IdentId, Value)>,
+    ) -> EvalResult<Value> {
+        let mut evaluator = Evaluator::new(self);
+        evaluator.locals.extend(locals);
+        evaluator.eval(node)
+    }
+
+    pub fn value_of(&self, ident: IdentId) -> EvalResult<Value> {
+        Evaluator::new(self).lookup(ident)
+    }
+
+    pub(crate) fn idents(&self) -> &'a IdentStorer {
+        self.idents
+    }
+
+    pub(crate) fn definition(&self, ident: IdentId) -> Option<&Definition> {
+        self.definitions.get(&ident)
+    }
+}
+
+struct Evaluator<'e, 'a> {
+    env: &'e Env<'a>,
+    locals: Vec<(IdentId, Value)>,
+    /// The definitions being evaluated, to catch the ones that depend on themselves.
+    evaluating: Vec<IdentId>,
+}
+
+impl<'e, 'a> Evaluator<'e, 'a> {
+    fn new(env: &'e Env<'a>) -> Self {
+        Self {
+            env,
+            locals: Vec::new(),
+            evaluating: Vec::new(),
+        }
+    }
+
+    fn name(&self, ident: IdentId) -> String {
+        self.env.idents.name(ident).to_string()
+    }
+
+    fn local(&self, ident: IdentId) -> Option<&Value> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| *local == ident)
+            .map(|(_, value)| value)
+    }
+
+    /// Evaluates the definition of `ident` with only `locals` in scope.
+    fn enter(
+        &mut self,
+        ident: IdentId,
+        locals: Vec<(IdentId, Value)>,
+        expr: &EvalNode,
+    ) -> EvalResult<Value> {
+        if self.evaluating.contains(&ident) {
+            return Err(EvalError::Cycle(self.name(ident)));
+        }
+        self.evaluating.push(ident);
+        let outer = std::mem::replace(&mut self.locals, locals);
+        let result = self.eval(expr);
+        self.locals = outer;
+        self.evaluating.pop();
+        result
+    }
+
+    fn lookup(&mut self, ident: IdentId) -> EvalResult<Value> {
+        if let Some(value) = self.local(ident) {
+            return Ok(value.clone());
+        }
+        match self.env.definitions.get(&ident) {
+            Some(Definition::Value(value)) => Ok(value.clone()),
+            Some(Definition::Expr(expr)) => self.enter(ident, Vec::new(), expr),
+            Some(Definition::Function(_)) => Err(EvalError::NotAValue(self.name(ident))),
+            Some(Definition::Redefined) => Err(EvalError::Redefined(self.name(ident))),
+            None => Err(EvalError::Undefined(self.name(ident))),
+        }
+    }
+
+    fn eval(&mut self, node: &EvalNode) -> EvalResult<Value> {
+        match node.kind() {
+            EvalKind::Identifier(ident) => self.lookup(*ident),
+            EvalKind::Number(x) => Ok(Value::one_number(*x)),
+            EvalKind::BuiltinsCall {
+                builtins,
+                power,
+                params,
+            } => self.call_builtins(*builtins, power.as_ref(), params),
+            EvalKind::Abs(x) => map_numbers(self.eval(x)?, f64::abs),
+            EvalKind::Point(x, y) => {
+                let x = self.eval(x)?.try_number()?;
+                let y = self.eval(y)?.try_number()?;
+                Ok(Value::Point(iter_full(x, y, &DVec2::new)))
+            }
+            EvalKind::List(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.eval(item))
+                    .collect::<EvalResult<_>>()?;
+                Ok(Value::list(items)?)
+            }
+            EvalKind::SumProd {
+                kind,
+                ident,
+                from,
+                to,
+                expr,
+            } => self.sum_prod(*kind, *ident, from, to, expr),
+            EvalKind::FunctionCall {
+                ident,
+                power,
+                params,
+            } => {
+                let value = self.call_function(*ident, params)?;
+                match power {
+                    Some(power) => powers(value, self.eval(power)?),
+                    None => Ok(value),
+                }
+            }
+            EvalKind::Multiply(nodes) => {
+                let mut product = Value::one_number(1.0);
+                for node in nodes {
+                    product = (product * self.eval(node)?)?;
+                }
+                Ok(product)
+            }
+            EvalKind::AddSub(pairs) => {
+                let mut sum = Value::one_number(0.0);
+                for (sign, node) in pairs {
+                    let value = self.eval(node)?;
+                    sum = match sign {
+                        AddOrSub::Add => (sum + value)?,
+                        AddOrSub::Sub => (sum - value)?,
+                    };
+                }
+                Ok(sum)
+            }
+            EvalKind::Frac { top, bottom } => Ok((self.eval(top)? / self.eval(bottom)?)?),
+            EvalKind::Sqrt(x) => map_numbers(self.eval(x)?, f64::sqrt),
+            EvalKind::Root { index, expr } => {
+                zip_numbers(self.eval(expr)?, self.eval(index)?, nth_root)
+            }
+            EvalKind::Power { base, power } => powers(self.eval(base)?, self.eval(power)?),
+            EvalKind::ListRange { from, next, to } => {
+                let from = self.scalar(from)?;
+                let to = self.scalar(to)?;
+                let step = match next {
+                    Some(next) => self.scalar(next)? - from,
+                    None if to < from => -1.0,
+                    None => 1.0,
+                };
+                list_range(from, step, to)
+            }
+            EvalKind::IfElse { conds, yes, no } => {
+                // without branches, a piecewise is one where its conditions hold.
+                match (self.holds(conds)?, yes, no) {
+                    (true, Some(yes), _) => self.eval(yes),
+                    (true, None, _) => Ok(Value::one_number(1.0)),
+                    (false, _, Some(no)) => self.eval(no),
+                    (false, _, None) => Ok(Value::one_number(f64::NAN)),
+                }
+            }
+            EvalKind::ElemAccess { expr, element } => {
+                let points = self.eval(expr)?.try_point()?;
+                Ok(Value::Number(match element {
+                    Element::X => points.map(&|point| point.x),
+                    Element::Y => points.map(&|point| point.y),
+                }))
+            }
+            EvalKind::ListIndexing { expr, index } => {
+                let index = self.scalar(index)?;
+                match self.eval(expr)? {
+                    Value::Number(xs) => Ok(Value::Number(index_list(xs, index, f64::NAN)?)),
+                    Value::Point(xs) => {
+                        Ok(Value::Point(index_list(xs, index, DVec2::splat(f64::NAN))?))
+                    }
+                    _ => Err(EvalError::Unsupported("indexing this kind of value")),
+                }
+            }
+            EvalKind::For { .. } => Err(EvalError::Unsupported("`for`")),
+            EvalKind::ListComp { .. } => Err(EvalError::Unsupported("list comprehensions")),
+            EvalKind::With { .. } => Err(EvalError::Unsupported("`with`")),
+        }
+    }
+
+    fn scalar(&mut self, node: &EvalNode) -> EvalResult<f64> {
+        self.eval(node)?
+            .try_number()?
+            .try_term()
+            .ok_or(EvalError::NotANumber)
+    }
+
+    /// Whether every condition holds, comparing each side to the next one.
+    fn holds(&mut self, conds: &[Conditional]) -> EvalResult<bool> {
+        for cond in conds {
+            let mut left = self.scalar(cond.expr())?;
+            for (set, right) in cond.comps() {
+                let right = self.scalar(right)?;
+                let holds = left
+                    .partial_cmp(&right)
+                    .is_some_and(|ordering| set.contains(CompSet::from(ordering)));
+                if !holds {
+                    return Ok(false);
+                }
+                left = right;
+            }
+        }
+        Ok(true)
+    }
+
+    fn sum_prod(
+        &mut self,
+        kind: SumOrProd,
+        ident: IdentId,
+        from: &EvalNode,
+        to: &EvalNode,
+        expr: &EvalNode,
+    ) -> EvalResult<Value> {
+        let from = self.scalar(from)?.round();
+        let to = self.scalar(to)?.round();
+        let mut total = match kind {
+            SumOrProd::Sum => Value::one_number(0.0),
+            SumOrProd::Prod => Value::one_number(1.0),
+        };
+        // counted rather than stepped to `to`, which adding one stops reaching past 2^53.
+        let count = to - from + 1.0;
+        if count > MAX_LIST_LEN as f64 {
+            return Err(EvalError::TooLong);
+        }
+        for step in 0..count.max(0.0) as usize {
+            let index = from + step as f64;
+            self.locals.push((ident, Value::one_number(index)));
+            let value = self.eval(expr);
+            self.locals.pop();
+            total = match kind {
+                SumOrProd::Sum => (total + value?)?,
+                SumOrProd::Prod => (total * value?)?,
+            };
+        }
+        Ok(total)
+    }
+
+    /// Calls a function a cell defined. Calling a variable multiplies by it, like `a(x+1)`.
+    fn call_function(&mut self, ident: IdentId, params: &[EvalNode]) -> EvalResult<Value> {
+        let is_value = self.local(ident).is_some()
+            || matches!(
+                self.env.definitions.get(&ident),
+                Some(Definition::Value(_) | Definition::Expr(_))
+            );
+        if is_value {
+            let [param] = params else {
+                return Err(EvalError::Unsupported("multiplying by a point"));
+            };
+            return Ok((self.lookup(ident)? * self.eval(param)?)?);
+        }
+
+        let def = match self.env.definitions.get(&ident) {
+            Some(Definition::Function(def)) => def,
+            _ => return self.lookup(ident),
+        };
+        if def.params().len() != params.len() {
+            return Err(EvalError::Arity {
+                name: self.name(ident),
+                expected: def.params().len(),
+                found: params.len(),
+            });
+        }
+        let args = params
+            .iter()
+            .map(|param| self.eval(param))
+            .collect::<EvalResult<Vec<_>>>()?;
+        let locals = def.params().iter().copied().zip(args).collect();
+        self.enter(ident, locals, def.expr())
+    }
+
+    fn call_builtins(
+        &mut self,
+        builtins: Builtins,
+        power: Option<&EvalNode>,
+        params: &[EvalNode],
+    ) -> EvalResult<Value> {
+        let power = power.map(|power| self.eval(power)).transpose()?;
+        let mut args = params
+            .iter()
+            .map(|param| self.eval(param))
+            .collect::<EvalResult<Vec<_>>>()?;
+        let arity = |expected: usize| match args.len() == expected {
+            true => Ok(()),
+            false => Err(EvalError::Arity {
+                name: builtins.as_str().to_string(),
+                expected,
+                found: args.len(),
+            }),
+        };
+
+        let value = match builtins {
+            Builtins::MonadicPervasive(func) => {
+                arity(1)?;
+                // `sin^-1` is the inverse, not one over `sin`.
+                let is_inverse = power
+                    .as_ref()
+                    .is_some_and(|power| *power == Value::one_number(-1.0));
+                match (is_inverse, func.invert()) {
+                    (true, Some(inverse)) => {
+                        return map_numbers(args.remove(0), |x| inverse.apply(x))
+                    }
+                    _ => map_numbers(args.remove(0), |x| func.apply(x))?,
+                }
+            }
+            Builtins::DyadicPervasive(func) => {
+                arity(2)?;
+                let right = args.remove(1);
+                let left = args.remove(0);
+                match (func, left, right) {
+                    (DyadicPervasive::Distance, Value::Point(a), Value::Point(b)) => {
+                        Value::Number(iter_full(a, b, &|a: DVec2, b| a.distance(b)))
+                    }
+                    (func, left, right) => {
+                        zip_numbers(left, right, |a, b| func.apply_numbers(a, b))?
+                    }
+                }
+            }
+            Builtins::MonadicNonPervasive(func) => {
+                arity(1)?;
+                func.apply(args.remove(0))
+            }
+            Builtins::ListStat(stat) => {
+                let numbers = match args.len() {
+                    1 => args.remove(0).try_number()?,
+                    _ => Value::list(args)?.try_number()?,
+                };
+                Value::Number(list_stat(stat, numbers))
+            }
+            Builtins::Join => match args.first() {
+                Some(Value::Point(_)) => {
+                    let mut points = Vec::new();
+                    for arg in args {
+                        points.extend(flatten(arg.try_point()?)?);
+                    }
+                    Value::Point(List::Flat(points))
+                }
+                _ => {
+                    let mut numbers = Vec::new();
+                    for arg in args {
+                        numbers.extend(flatten(arg.try_number()?)?);
+                    }
+                    Value::Number(List::Flat(numbers))
+                }
+            },
+            Builtins::Sort => {
+                arity(1)?;
+                let mut numbers = flatten(args.remove(0).try_number()?)?;
+                numbers.sort_by(f64::total_cmp);
+                Value::Number(List::Flat(numbers))
+            }
+            Builtins::Random => return Err(EvalError::Unsupported("`random`")),
+        };
+
+        match power {
+            Some(power) => powers(value, power),
+            None => Ok(value),
+        }
+    }
+}
+
+fn list_stat(stat: ListStat, numbers: List<f64>) -> List<f64> {
+    match numbers {
+        // a single number is a list of one.
+        List::Term(x) => stat.apply_numbers(List::Flat(vec![x])),
+        numbers => stat.apply_numbers(numbers),
+    }
+}
+
+fn map_numbers(value: Value, func: impl Fn(f64) -> f64) -> EvalResult<Value> {
+    Ok(Value::Number(value.try_number()?.map(&func)))
+}
+
+fn zip_numbers(left: Value, right: Value, func: impl Fn(f64, f64) -> f64) -> EvalResult<Value> {
+    Ok(Value::Number(iter_full(
+        left.try_number()?,
+        right.try_number()?,
+        &func,
+    )))
+}
+
+fn powers(base: Value, power: Value) -> EvalResult<Value> {
+    zip_numbers(base, power, f64::powf)
+}
+
+/// The `index`th root, which is real for negative numbers when the index is odd.
+pub(crate) fn nth_root(x: f64, index: f64) -> f64 {
+    let is_odd = index.fract() == 0.0 && index % 2.0 != 0.0;
+    match x < 0.0 && is_odd {
+        true => -(-x).powf(index.recip()),
+        false => x.powf(index.recip()),
+    }
+}
+
+fn list_range(from: f64, step: f64, to: f64) -> EvalResult<Value> {
+    let count = ((to - from) / step).floor() + 1.0;
+    if !count.is_finite() || count > MAX_LIST_LEN as f64 {
+        return Err(EvalError::TooLong);
+    }
+    let numbers = (0..count.max(0.0) as usize)
+        .map(|index| from + step * index as f64)
+        .collect();
+    Ok(Value::Number(List::Flat(numbers)))
+}
+
+/// The element at the one-based `index`, or `missing` past the ends.
+fn index_list<T: Clone>(list: List<T>, index: f64, missing: T) -> EvalResult<List<T>> {
+    let at = index.floor() as usize;
+    let element = match list {
+        List::Term(_) => return Err(EvalError::NotAList),
+        _ if index.is_nan() || index < 1.0 => None,
+        List::Flat(xs) => xs.get(at - 1).cloned().map(List::Term),
+        List::Staggered(xs) => xs.get(at - 1).cloned(),
+    };
+    Ok(element.unwrap_or(List::Term(missing)))
+}
+
+fn flatten<T>(list: List<T>) -> EvalResult<Vec<T>> {
+    match list {
+        List::Term(x) => Ok(vec![x]),
+        List::Flat(xs) => Ok(xs),
+        List::Staggered(_) => Err(EvalError::Unsupported("lists of lists")),
+    }
+}
+
+/// What a cell shows under it: its value, or nothing for graphs, functions and empty cells.
+pub type CellResult = Result<Option<Value>, CellError>;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CellError {
+    #[error("the cell can't be parsed")]
+    Parse,
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+}
+
+/// Whether a name is an axis, which cells graph against instead of defining.
+fn is_axis(name: &str) -> bool {
+    name == "x" || name == "y"
+}
+
+/// Parses every cell of `worksheet`, giving what each says and an environment with what they
+/// define. The values in `received`, by cell id, replace what those cells define, like the values
+/// arriving on the channels bound to them.
+pub fn worksheet_env<'a>(
+    worksheet: &Worksheet,
+    received: &HashMap<String, Value>,
+    idents: &'a IdentStorer,
+) -> (Env<'a>, Vec<Option<Statement>>) {
+    let statements: Vec<_> = worksheet
+        .cells
+        .iter()
+        .map(|cell| parse_statement(&cell.content, idents).ok())
+        .collect();
+
+    let mut env = Env::new(idents);
+    for (cell, statement) in worksheet.cells.iter().zip(&statements) {
+        match statement {
+            Some(Statement::Variable(def)) if !is_axis(&idents.name(def.ident()).to_string()) => {
+                match received.get(&cell.id) {
+                    Some(value) => env.set(def.ident(), value.clone()),
+                    None => env.define(def),
+                }
+            }
+            Some(Statement::Function(def)) => env.define_function(def),
+            _ => {}
+        }
+    }
+    (env, statements)
+}
+
+/// Evaluates every cell of `worksheet`, with the values in `received` as in [`worksheet_env`].
+pub fn evaluate_worksheet(
+    worksheet: &Worksheet,
+    received: &HashMap<String, Value>,
+) -> Vec<CellResult> {
+    let idents = IdentStorer::default();
+    let (env, statements) = worksheet_env(worksheet, received, &idents);
+    worksheet
+        .cells
+        .iter()
+        .zip(&statements)
+        .map(|(cell, statement)| {
+            let value = match statement {
+                None if cell.content.is_empty() => return Ok(None),
+                None => return Err(CellError::Parse),
+                Some(Statement::Function(_)) => return Ok(None),
+                Some(Statement::Variable(def)) => env.value_of(def.ident()),
+                Some(Statement::Expression(expr)) => env.evaluate(expr),
+            };
+            match value {
+                Ok(value) => Ok(Some(value)),
+                // graphs have no single value.
+                Err(EvalError::Undefined(name)) if is_axis(&name) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        })
+        .collect()
+}
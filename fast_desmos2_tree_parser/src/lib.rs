@@ -1,8 +1,15 @@
 pub mod builtins;
+pub mod completion;
+pub mod derivative;
+pub mod eval;
 mod parsing;
+pub mod refactor;
+pub mod simplify;
 pub mod tree;
+mod unparsing;
 
-pub use parsing::parse;
+pub use parsing::{parse, parse_statement};
+pub use unparsing::unparse;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,169 @@
+//! The worksheet editor. It lives here rather than in `fast_desmos2_tree`, next to the editing it
+//! drives, because showing what cells evaluate to needs this crate, which already depends on that
+//! one.
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+    thread,
+    time::{Duration, Instant},
+};
+
+use app::App;
+use fast_desmos2_comms::Server;
+use fast_desmos2_tree::{keymap::Keymap, worksheet::Worksheet};
+use glam::UVec2;
+use termion::{
+    clear, cursor,
+    event::{Event, Key, MouseButton, MouseEvent},
+    input::{MouseTerminal, TermRead},
+    raw::IntoRawMode,
+};
+
+mod app;
+
+const USAGE: &str = "usage: fast_desmos2_worksheet [FILE] [--listen ADDR] [--keymap FILE]";
+/// How long to wait for keys and updates before looking again.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How often to redraw without any input, to keep the comms status current.
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Args {
+    path: Option<PathBuf>,
+    listen: Option<String>,
+    keymap: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        path: None,
+        listen: None,
+        keymap: None,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--listen" => {
+                let addr = iter.next().ok_or("--listen needs an address")?;
+                args.listen = Some(addr);
+            }
+            "--keymap" => {
+                let path = iter.next().ok_or("--keymap needs a file")?;
+                args.keymap = Some(PathBuf::from(path));
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{flag}`")),
+            _ if args.path.is_some() => return Err("only one file can be opened".to_string()),
+            _ => args.path = Some(PathBuf::from(arg)),
+        }
+    }
+    Ok(args)
+}
+
+/// The keymap in the user's config directory, if they wrote one.
+fn default_keymap_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("fast_desmos2").join("keymap.json")).filter(|path| path.exists())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    // a file that doesn't exist yet is created by the first save.
+    let worksheet = match &args.path {
+        Some(path) if path.exists() => match Worksheet::load(path) {
+            Ok(worksheet) => worksheet,
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => Worksheet::default(),
+    };
+    let keymap_path = args.keymap.or_else(default_keymap_path);
+    let keymap = match keymap_path {
+        Some(path) => match Keymap::load(&path) {
+            Ok(keymap) => keymap,
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Keymap::default(),
+    };
+    let server = match args.listen.as_deref().map(Server::new).transpose() {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("can't start the comms server: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut app = App::new(worksheet, keymap, args.path, server);
+    match run(&mut app) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(app: &mut App) -> io::Result<()> {
+    let mut stdout = MouseTerminal::from(io::stdout().into_raw_mode()?);
+    let mut events = termion::async_stdin().events();
+    app.draw(&mut stdout)?;
+    let mut last_draw = Instant::now();
+
+    while !app.should_quit() {
+        let mut changed = false;
+        for event in events.by_ref() {
+            match event? {
+                Event::Key(Key::Ctrl('c')) => {
+                    app.clear_message();
+                    app.try_quit();
+                }
+                Event::Key(key) => {
+                    app.clear_message();
+                    app.handle_key(key);
+                }
+                Event::Mouse(MouseEvent::Press(MouseButton::Left, x, y)) => {
+                    app.handle_click(UVec2::new(x.into(), y.into()))
+                }
+                _ => continue,
+            }
+            changed = true;
+            if app.should_quit() {
+                break;
+            }
+        }
+        changed |= app.poll_comms();
+
+        if changed || last_draw.elapsed() >= REDRAW_INTERVAL {
+            app.draw(&mut stdout)?;
+            last_draw = Instant::now();
+        } else {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    finish(&mut stdout)
+}
+
+fn finish(stdout: &mut impl Write) -> io::Result<()> {
+    write!(
+        stdout,
+        "{}{}{}",
+        clear::All,
+        cursor::Goto(1, 1),
+        cursor::Show
+    )?;
+    stdout.flush()
+}
@@ -1,7 +1,7 @@
 use fast_desmos2_tree::tree::EditorTreeSeq;
 use winnow::Stateful;
 
-use crate::tree::{EvalNode, IdentStorer};
+use crate::tree::{EvalNode, IdentStorer, Statement};
 use stream::ParseStream;
 
 mod parser;
@@ -24,3 +24,16 @@ pub fn parse<'a>(
     // let parsed = parser::parse_seq(input).parse(SliceStream(tree.children()));
     parser::parse_seq(&mut input)
 }
+
+/// Parses a whole cell, which unlike [`parse`] has to be used up entirely.
+pub fn parse_statement<'a>(
+    tree: &'a EditorTreeSeq,
+    idents: &'a IdentStorer,
+) -> parser::ParseResult<'a, Statement> {
+    let state = ParseExtra { idents };
+    let mut input = Stateful {
+        input: ParseStream::new(tree.children()),
+        state,
+    };
+    parser::parse_statement(&mut input)
+}
@@ -13,7 +13,7 @@ use winnow::{
 
 use crate::{
     builtins::Builtins,
-    tree::{AddOrSub, CompSet, Conditional, EvalNode, IdentId, VarDef},
+    tree::{AddOrSub, CompSet, Conditional, EvalNode, FunctionDef, IdentId, Statement, VarDef},
 };
 
 use super::{ParseExtra, ParseStream};
@@ -35,6 +35,27 @@ pub fn parse_var_def<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, VarDef>
         .parse_next(input)
 }
 
+pub fn parse_function_def<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, FunctionDef> {
+    (
+        parse_raw_ident,
+        parse_parens_chained(separated(1.., parse_raw_ident, parse_char(','))),
+        parse_char('='),
+        parse_whole_seq,
+    )
+        .map(|(ident, params, _, expr)| FunctionDef::new(ident, params, expr))
+        .parse_next(input)
+}
+
+/// A whole cell: a definition when it can be one, an expression otherwise.
+pub fn parse_statement<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, Statement> {
+    alt((
+        parse_function_def.map(Statement::Function),
+        parse_var_def.map(Statement::Variable),
+        parse_whole_seq.map(Statement::Expression),
+    ))
+    .parse_next(input)
+}
+
 pub fn parse_whole_seq<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
     terminated(parse_seq, eof).parse_next(input)
 }
@@ -77,9 +98,11 @@ fn parse_add_sub<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
 }
 
 fn parse_multiply<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
+    // the editor turns a typed `*` into `×`, which is also what `\cdot` and `\times` import as.
+    let parse_times = surround_whitespace(opt(alt((parse_char('×'), parse_char('*')))));
     (
         parse_postfix,
-        repeat(.., preceded(parse_whitespace, parse_postfix)),
+        repeat(.., preceded(parse_times, parse_postfix)),
     )
         .map(|(first, remaining): (_, Vec<_>)| {
             if remaining.is_empty() {
@@ -125,11 +148,14 @@ fn parse_everything_else<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, Eval
         parse_identifier,
         parse_point_literal,
         parse_parens,
+        parse_fraction,
         parse_sqrt,
+        parse_root,
         parse_abs,
         parse_list_literal,
         parse_list_range,
         parse_if_else,
+        parse_piecewise,
         parse_sum_prod,
     ))
     .parse_next(input)
@@ -185,11 +211,11 @@ fn parse_sum_prod<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
     ))
 }
 
-fn parse_if_else<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
-    fn parse_conditionals<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, Vec<Conditional>> {
-        separated(1.., parse_conditional, parse_char(',')).parse_next(input)
-    }
+fn parse_conditionals<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, Vec<Conditional>> {
+    separated(1.., parse_conditional, parse_char(',')).parse_next(input)
+}
 
+fn parse_if_else<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
     fn parse_if_else_content<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
         (
             parse_conditionals,
@@ -214,6 +240,32 @@ fn parse_if_else<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
     parse_curly_chained(parse_if_else_content).parse_next(input)
 }
 
+/// A piecewise node, as if each row was the `yes` branch of an `if_else` whose `no` branch holds
+/// the rows below it.
+fn parse_piecewise<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
+    let piecewise = any
+        .verify_map(|tree: &EditorTree| match tree.kind() {
+            EditorTreeKind::Piecewise(piecewise) => Some(piecewise),
+            _ => None,
+        })
+        .context(expect_description("a piecewise node"))
+        .parse_next(input)?;
+
+    let parse_optional = |input: &mut ParseInput<'a>, seq: &'a EditorTreeSeq| match seq.is_empty() {
+        true => Ok(None),
+        false => parse_whole_seq(&mut derived_input(input, seq)).map(Some),
+    };
+
+    let mut no = parse_optional(input, piecewise.default())?;
+    for row in piecewise.rows().iter().rev() {
+        let conds = terminated(parse_conditionals, eof)
+            .parse_next(&mut derived_input(input, row.condition()))?;
+        let yes = parse_optional(input, row.value())?;
+        no = Some(EvalNode::if_else(conds, yes, no));
+    }
+    Ok(no.unwrap_or_else(|| unreachable!("piecewise nodes always have a row")))
+}
+
 fn parse_conditional<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, Conditional> {
     (
         parse_seq,
@@ -307,31 +359,54 @@ fn parse_function_call<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNo
         parse_parens_chained(separated(.., parse_seq, parse_char(',')))
             .context(expect_description("function parameters")),
     )
-        .map(
-            |(ident, power, params): (_, _, Vec<_>)| match Builtins::from_str(ident.as_bytes()) {
-                Some(builtins) => EvalNode::builtins_call(builtins, power, params),
-                None => {
-                    let ident = input.state.idents.convert_id(&ident);
+        .map(|((base, subscript), power, params): (_, _, Vec<_>)| {
+            match (Builtins::from_str(base.as_bytes()), &subscript) {
+                (Some(builtins), None) => EvalNode::builtins_call(builtins, power, params),
+                _ => {
+                    let ident = input
+                        .state
+                        .idents
+                        .convert_parts(&base, subscript.as_deref());
                     EvalNode::function_call(ident, power, params)
                 }
-            },
-        )
+            }
+        })
         .context(expect_description("a function call"))
         .parse_next(input)
 }
 
-fn parse_raw_raw_ident<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, String> {
-    surround_whitespace(repeat(
-        1..,
-        parse_map_char(|ch| ch.is_ascii_alphabetic().then_some(ch)),
+/// The letters of an identifier and the subscript after them, if there is one.
+fn parse_raw_raw_ident<'a>(
+    input: &mut ParseInput<'a>,
+) -> ParseResult<'a, (String, Option<String>)> {
+    surround_whitespace((
+        repeat(
+            1..,
+            parse_map_char(|ch| ch.is_ascii_alphabetic().then_some(ch)),
+        ),
+        opt(parse_subscript),
     ))
     .context(expect_description("an identifier"))
     .parse_next(input)
 }
 
+fn parse_subscript<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, String> {
+    parse_subscript_chained(repeat(
+        1..,
+        parse_map_char(|ch| ch.is_ascii_alphanumeric().then_some(ch)),
+    ))
+    .context(expect_description("a subscript"))
+    .parse_next(input)
+}
+
 fn parse_raw_ident<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, IdentId> {
     parse_raw_raw_ident
-        .map(|ident_str| input.state.idents.convert_id(&ident_str))
+        .map(|(base, subscript)| {
+            input
+                .state
+                .idents
+                .convert_parts(&base, subscript.as_deref())
+        })
         .parse_next(input)
 }
 
@@ -400,6 +475,10 @@ parser_chain! {
         EditorTreeKind::Power(power) => Some(power.power())
     }
 
+    fn parse_subscript_chained() {
+        EditorTreeKind::Subscript(subscript) => Some(subscript.subscript())
+    }
+
     fn parse_abs_chained() {
         EditorTreeKind::Abs(abs) => Some(abs.child())
     }
@@ -444,6 +523,34 @@ fn parse_sqrt<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
         .parse_next(input)
 }
 
+fn parse_fraction<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
+    let fraction = any
+        .verify_map(|tree: &EditorTree| match tree.kind() {
+            EditorTreeKind::Fraction(fraction) => Some(fraction),
+            _ => None,
+        })
+        .context(expect_description("a fraction"))
+        .parse_next(input)?;
+
+    let top = parse_whole_seq(&mut derived_input(input, fraction.top()))?;
+    let bottom = parse_whole_seq(&mut derived_input(input, fraction.bottom()))?;
+    Ok(EvalNode::frac(top, bottom))
+}
+
+fn parse_root<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
+    let root = any
+        .verify_map(|tree: &EditorTree| match tree.kind() {
+            EditorTreeKind::Root(root) => Some(root),
+            _ => None,
+        })
+        .context(expect_description("a root"))
+        .parse_next(input)?;
+
+    let index = parse_whole_seq(&mut derived_input(input, root.index()))?;
+    let expr = parse_whole_seq(&mut derived_input(input, root.child()))?;
+    Ok(EvalNode::root(index, expr))
+}
+
 fn parse_list_literal<'a>(input: &mut ParseInput<'a>) -> ParseResult<'a, EvalNode> {
     parse_brackets_chained(separated(.., parse_seq, parse_char(',')))
         .map(EvalNode::list_literal)
@@ -0,0 +1,303 @@
+//! Changes to the meaning of a worksheet rather than to its text, like renaming a variable.
+//!
+//! Names are found the way the parser finds them: a run of letters with maybe a subscript after
+//! it, so renaming `a` leaves `ab`, `a_1` and `max` alone. Function calls of builtins aren't
+//! names, and neither are the letters after a `.`, as in `p.x`. A name that's a parameter of a
+//! function, or the index of a sum or product, is left alone where it's bound.
+
+use std::ops::Range;
+
+use fast_desmos2_tree::tree::{
+    CombinedCursor, Direction, EditorTree, EditorTreeKind, EditorTreeSeq, SumProdIndex,
+};
+use thiserror::Error;
+
+use crate::{
+    builtins::Builtins,
+    parse_statement,
+    tree::{IdentId, IdentStorer, Statement},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RenameError {
+    #[error("`{0}` isn't a name, which is letters with maybe a subscript like `x_1`")]
+    NotAName(String),
+    #[error("`{0}` is a builtin function")]
+    Builtin(String),
+    #[error("`{0}` is already used")]
+    Taken(String),
+}
+
+pub type RenameResult<T> = Result<T, RenameError>;
+
+/// A name as it's typed, the letters and then what's in its subscript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Name {
+    base: String,
+    subscript: Option<String>,
+}
+
+impl Name {
+    /// Reads a name written like `x`, `x_1` or `x_{1}`.
+    fn parse(name: &str) -> RenameResult<Self> {
+        let not_a_name = || RenameError::NotAName(name.to_string());
+        let (base, subscript) = match name.split_once('_') {
+            Some((base, subscript)) => {
+                let subscript = subscript
+                    .strip_prefix('{')
+                    .and_then(|inner| inner.strip_suffix('}'))
+                    .unwrap_or(subscript);
+                (base, Some(subscript))
+            }
+            None => (name, None),
+        };
+        let is_base = !base.is_empty() && base.chars().all(|ch| ch.is_ascii_alphabetic());
+        let is_subscript = subscript.is_none_or(|subscript| {
+            !subscript.is_empty() && subscript.chars().all(|ch| ch.is_ascii_alphanumeric())
+        });
+        if !is_base || !is_subscript {
+            return Err(not_a_name());
+        }
+        if subscript.is_none() && Builtins::from_str(base.as_bytes()).is_some() {
+            return Err(RenameError::Builtin(name.to_string()));
+        }
+        Ok(Self {
+            base: base.to_string(),
+            subscript: subscript.map(str::to_string),
+        })
+    }
+
+    fn id(&self, idents: &IdentStorer) -> IdentId {
+        idents.convert_parts(&self.base, self.subscript.as_deref())
+    }
+
+    fn nodes(&self) -> Vec<EditorTree> {
+        let mut nodes: Vec<_> = self.base.chars().map(EditorTree::terminal).collect();
+        if let Some(subscript) = &self.subscript {
+            nodes.push(EditorTree::subscript(EditorTreeSeq::str(subscript)));
+        }
+        nodes
+    }
+}
+
+/// A name in a sequence.
+#[derive(Debug, Clone)]
+struct Token {
+    range: Range<usize>,
+    ident: IdentId,
+}
+
+/// Renaming a variable or function everywhere it's used.
+#[derive(Debug)]
+pub struct Rename {
+    idents: IdentStorer,
+    from: IdentId,
+    to: IdentId,
+    to_name: Name,
+}
+
+impl Rename {
+    /// Renames `from` to `to`, both written like `x`, `ab` or `x_1`.
+    pub fn new(from: &str, to: &str) -> RenameResult<Self> {
+        let idents = IdentStorer::default();
+        let from = Name::parse(from)?.id(&idents);
+        let to_name = Name::parse(to)?;
+        Ok(Self {
+            from,
+            to: to_name.id(&idents),
+            to_name,
+            idents,
+        })
+    }
+
+    /// Checks that the new name isn't used in any of `trees` already, where renaming would mix
+    /// the two up.
+    pub fn check<'a>(
+        &self,
+        trees: impl IntoIterator<Item = &'a EditorTreeSeq>,
+    ) -> RenameResult<()> {
+        if self.from == self.to {
+            return Ok(());
+        }
+        match trees.into_iter().any(|tree| self.uses(tree, self.to)) {
+            true => Err(RenameError::Taken(self.idents.name(self.to).to_string())),
+            false => Ok(()),
+        }
+    }
+
+    fn uses(&self, seq: &EditorTreeSeq, ident: IdentId) -> bool {
+        self.tokens(seq.children())
+            .iter()
+            .any(|token| token.ident == ident)
+            || seq
+                .children()
+                .iter()
+                .flat_map(EditorTree::child_seqs)
+                .any(|seq| self.uses(seq, ident))
+    }
+
+    /// Renames the uses of the name in a cell, giving how many there were.
+    pub fn apply(&self, tree: &mut EditorTreeSeq) -> usize {
+        if self.from == self.to {
+            return 0;
+        }
+        // the whole cell is the scope of the parameters.
+        if let Ok(Statement::Function(def)) = parse_statement(tree, &self.idents) {
+            if def.params().contains(&self.from) {
+                return 0;
+            }
+        }
+        self.rename_in(tree, false)
+    }
+
+    /// Renames the name in `seq`, unless it's `bound` to something else there.
+    fn rename_in(&self, seq: &mut EditorTreeSeq, bound: bool) -> usize {
+        let children = seq.children();
+        let hidden: Vec<Range<usize>> = children
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| self.is_index_of(child))
+            .map(|(index, _)| index + 1..term_end(children, index + 1))
+            .collect();
+        let is_bound = |index: usize| bound || hidden.iter().any(|range| range.contains(&index));
+        let tokens: Vec<_> = self
+            .tokens(children)
+            .into_iter()
+            .filter(|token| token.ident == self.from && !is_bound(token.range.start))
+            .collect();
+
+        let mut count = 0;
+        let mut children = seq.children().to_vec();
+        for (index, child) in children.iter_mut().enumerate() {
+            let is_sum_prod = self.is_index_of(child);
+            for (slot, inner) in child.slots().into_iter().zip(child.child_seqs_mut()) {
+                let is_index = slot == CombinedCursor::SumProd(SumProdIndex::BottomIdent);
+                count += self.rename_in(inner, is_bound(index) || (is_sum_prod && is_index));
+            }
+        }
+        if count == 0 && tokens.is_empty() {
+            return 0;
+        }
+
+        let name = self.to_name.nodes();
+        let cursor = seq.cursor();
+        let mut renamed = Vec::with_capacity(children.len());
+        let mut new_cursor = None;
+        let mut rest = children.into_iter().enumerate().peekable();
+        for token in &tokens {
+            while let Some((index, child)) = rest.next_if(|(index, _)| *index < token.range.start) {
+                if index == cursor {
+                    new_cursor = Some((renamed.len(), false));
+                }
+                renamed.push(child);
+            }
+            rest.by_ref().take(token.range.len()).for_each(drop);
+            if token.range.start == cursor {
+                new_cursor = Some((renamed.len(), false));
+            }
+            renamed.extend(name.iter().cloned());
+            if token.range.start < cursor && cursor < token.range.end {
+                new_cursor = Some((renamed.len(), true));
+            }
+        }
+        for (index, child) in rest {
+            if index == cursor {
+                new_cursor = Some((renamed.len(), false));
+            }
+            renamed.push(child);
+        }
+
+        let (new_cursor, moved) = new_cursor.unwrap_or((renamed.len(), false));
+        *seq = EditorTreeSeq::new(new_cursor, renamed);
+        if moved {
+            // the caret was in the old name, and goes after the new one.
+            seq.move_to(new_cursor, Direction::Left);
+        }
+        count + tokens.len()
+    }
+
+    /// Whether `node` is a sum or product with the name as its index.
+    fn is_index_of(&self, node: &EditorTree) -> bool {
+        let EditorTreeKind::SumProd(sum_prod) = node.kind() else {
+            return false;
+        };
+        let tokens = self.tokens(sum_prod.ident().children());
+        matches!(tokens.as_slice(), [token] if token.ident == self.from)
+    }
+
+    /// The names in `children`, leaving out the names of builtins being called.
+    fn tokens(&self, children: &[EditorTree]) -> Vec<Token> {
+        let is_letter = |index: usize| {
+            children
+                .get(index)
+                .is_some_and(|child| child.is_terminal_and(|term| term.ch().is_ascii_alphabetic()))
+        };
+        let mut tokens = Vec::new();
+        let mut index = 0;
+        while index < children.len() {
+            let start = index;
+            if !is_letter(start) {
+                index += 1;
+                continue;
+            }
+            while is_letter(index) {
+                index += 1;
+            }
+            let base: String = children[start..index]
+                .iter()
+                .filter_map(|child| child.is_terminal_then(|term| term.ch()))
+                .collect();
+            let subscript = children.get(index).and_then(subscript_text);
+            if subscript.is_some() {
+                index += 1;
+            }
+            let after_dot = start > 0 && children[start - 1].is_terminal_and_eq('.');
+            let is_call = subscript.is_none()
+                && Builtins::from_str(base.as_bytes()).is_some()
+                && is_called(&children[index..]);
+            if !after_dot && !is_call {
+                tokens.push(Token {
+                    range: start..index,
+                    ident: self.idents.convert_parts(&base, subscript.as_deref()),
+                });
+            }
+        }
+        tokens
+    }
+}
+
+/// The text of a subscript that can be part of a name.
+fn subscript_text(node: &EditorTree) -> Option<String> {
+    let EditorTreeKind::Subscript(subscript) = node.kind() else {
+        return None;
+    };
+    let seq = subscript.subscript();
+    let text: Option<String> = seq
+        .children()
+        .iter()
+        .map(|child| {
+            child.is_terminal_and_then(|term| Some(term.ch()).filter(char::is_ascii_alphanumeric))
+        })
+        .collect();
+    text.filter(|text| !text.is_empty())
+}
+
+/// Whether what comes after a name makes it a call, maybe with a power before the brackets.
+fn is_called(after: &[EditorTree]) -> bool {
+    let after = match after.first().map(EditorTree::kind) {
+        Some(EditorTreeKind::Power(_)) => &after[1..],
+        _ => after,
+    };
+    matches!(
+        after.first().map(EditorTree::kind),
+        Some(EditorTreeKind::Paren(_))
+    )
+}
+
+/// Where the product starting at `start` ends, like the body of a sum does.
+fn term_end(children: &[EditorTree], start: usize) -> usize {
+    children[start..]
+        .iter()
+        .position(|child| child.is_terminal_and(|term| "+-,=<>≤≥:".contains(term.ch())))
+        .map_or(children.len(), |end| start + end)
+}
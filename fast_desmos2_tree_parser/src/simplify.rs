@@ -0,0 +1,776 @@
+//! Rewriting expressions into simpler ones with the same value.
+//!
+//! Expressions are brought into a normal form: a sum of terms, each a number times powers of
+//! things that aren't numbers, sums or products themselves, with the terms and factors in a fixed
+//! order. So `x·2+1` and `1+2x` come out the same, as `2x+1`, and powers of one or zero and terms
+//! multiplied by zero go away. The [`Rules`] decide what else is done on the way.
+//!
+//! Lists are left where they are, since a list times zero or to the zero is still a list, like
+//! `[0,0]` for `[1,2]-[1,2]`.
+//!
+//! Some rewrites only hold where something isn't zero, like `x/x` being `1`. They're only done
+//! with [`Rules::CANCEL`], and what was assumed not to be zero is kept with the result.
+
+use std::cmp::Ordering;
+
+use bitflags::bitflags;
+
+use crate::{
+    builtins::Builtins,
+    eval::nth_root,
+    tree::{AddOrSub, Conditional, EvalKind, EvalNode, IdentStorer, VarDef},
+};
+
+bitflags! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct Rules: u8 {
+        /// Works out roots, powers and builtins of numbers, like `2^3` or `sin(0)`, when the
+        /// answer is short enough to write exactly. Sums and products of numbers are always
+        /// worked out.
+        const FOLD = 0b0001;
+        /// Adds up terms that only differ by a number, like `2x+3x`, and multiplies powers of the
+        /// same thing, like `x·x^2`.
+        const COLLECT = 0b0010;
+        /// Lets collecting cancel out what's divided by, like in `x/x` or `x^2/x`.
+        const CANCEL = 0b0100;
+        /// Multiplies out products and whole powers of sums, like `(x+1)^2`.
+        const EXPAND = 0b1000;
+        const SIMPLIFY = 0b0111;
+    }
+}
+
+/// An expression after rewriting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rewritten {
+    pub expr: EvalNode,
+    /// What was assumed not to be zero. Where one of these is, the original has no value, but
+    /// `expr` can have one.
+    pub nonzero: Vec<EvalNode>,
+}
+
+pub fn simplify(node: &EvalNode, idents: &IdentStorer) -> Rewritten {
+    Rewriter::new(idents, Rules::SIMPLIFY).rewrite(node)
+}
+
+/// Simplifies `node`, multiplying out what it can.
+pub fn expand(node: &EvalNode, idents: &IdentStorer) -> Rewritten {
+    Rewriter::new(idents, Rules::SIMPLIFY | Rules::EXPAND).rewrite(node)
+}
+
+/// A sum of terms, in order. Zero is the sum of nothing.
+#[derive(Debug, Clone, PartialEq)]
+struct Sum {
+    terms: Vec<Term>,
+}
+
+/// A number times powers of other things, in order.
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    coeff: f64,
+    factors: Vec<Factor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Factor {
+    base: Base,
+    exp: Sum,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Base {
+    /// Something that isn't rewritten any further, though what's in it is.
+    Atom(EvalNode),
+    /// A sum that wasn't multiplied out.
+    Group(Sum),
+}
+
+impl Sum {
+    fn number(x: f64) -> Self {
+        let terms = match x == 0.0 {
+            true => Vec::new(),
+            false => vec![Term {
+                coeff: x,
+                factors: Vec::new(),
+            }],
+        };
+        Self { terms }
+    }
+
+    fn atom(node: EvalNode) -> Self {
+        Self::factor(Factor {
+            base: Base::Atom(node),
+            exp: Self::number(1.0),
+        })
+    }
+
+    fn factor(factor: Factor) -> Self {
+        Self {
+            terms: vec![Term {
+                coeff: 1.0,
+                factors: vec![factor],
+            }],
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self.terms.as_slice() {
+            [] => Some(0.0),
+            [term] if term.factors.is_empty() => Some(term.coeff),
+            _ => None,
+        }
+    }
+
+    fn has_list(&self) -> bool {
+        self.terms.iter().any(Term::has_list)
+    }
+
+    fn divides_by_zero(&self) -> bool {
+        let is_zero = |factor: &Factor| match &factor.base {
+            Base::Atom(node) => as_number(node) == Some(0.0),
+            Base::Group(_) => false,
+        };
+        self.terms
+            .iter()
+            .any(|term| term.factors.iter().any(is_zero))
+    }
+
+    fn scale(mut self, by: f64) -> Self {
+        for term in &mut self.terms {
+            term.coeff *= by;
+        }
+        self
+    }
+}
+
+impl Term {
+    /// The sum of the numbers the factors are raised to, which orders the terms of a sum.
+    fn degree(&self) -> f64 {
+        self.factors
+            .iter()
+            .map(|factor| factor.exp.as_number().unwrap_or(1.0))
+            .sum()
+    }
+
+    fn has_list(&self) -> bool {
+        self.factors.iter().any(Factor::is_list)
+    }
+}
+
+impl Factor {
+    fn is_list(&self) -> bool {
+        self.base.is_list() || self.exp.has_list()
+    }
+}
+
+impl Base {
+    fn is_list(&self) -> bool {
+        match self {
+            Self::Atom(node) => is_list(node),
+            Self::Group(sum) => sum.has_list(),
+        }
+    }
+}
+
+/// Whether `node` can be a list, as far as can be told without evaluating it.
+fn is_list(node: &EvalNode) -> bool {
+    match node.kind() {
+        EvalKind::List(_)
+        | EvalKind::ListRange { .. }
+        | EvalKind::ListComp { .. }
+        | EvalKind::For { .. } => true,
+        EvalKind::Abs(inner) | EvalKind::Sqrt(inner) => is_list(inner),
+        EvalKind::Root { index, expr } => is_list(index) || is_list(expr),
+        EvalKind::Power { base, power } => is_list(base) || is_list(power),
+        EvalKind::Frac { top, bottom } => is_list(top) || is_list(bottom),
+        EvalKind::Multiply(nodes) | EvalKind::BuiltinsCall { params: nodes, .. } => {
+            nodes.iter().any(is_list)
+        }
+        EvalKind::AddSub(pairs) => pairs.iter().any(|(_, node)| is_list(node)),
+        EvalKind::ListIndexing { index, .. } => is_list(index),
+        EvalKind::IfElse { yes, no, .. } => yes.iter().chain(no).any(is_list),
+        _ => false,
+    }
+}
+
+/// Whether `x` can be written in a few digits, so working it out loses nothing.
+fn is_short(x: f64) -> bool {
+    x.is_finite() && (x.fract() == 0.0 || format!("{x}").len() <= 8)
+}
+
+/// `x`, or a short number that's as good as equal to it, like `0.3` for `0.1+0.2`.
+fn tidy(x: f64) -> f64 {
+    let short: f64 = format!("{x:.12}").parse().unwrap_or(x);
+    let is_close = (x - short).abs() <= 4.0 * f64::EPSILON * x.abs();
+    match is_short(short) && is_close {
+        true => short,
+        false => x,
+    }
+}
+
+fn is_whole(x: f64) -> bool {
+    x.fract() == 0.0
+}
+
+/// `x` as a fraction with a small bottom, if it's one exactly.
+fn ratio(x: f64) -> (f64, f64) {
+    if is_whole(x) {
+        return (x, 1.0);
+    }
+    (2..=100)
+        .map(f64::from)
+        .map(|bottom| ((x * bottom).round(), bottom))
+        .find(|(top, bottom)| top / bottom == x)
+        .unwrap_or((x, 1.0))
+}
+
+/// The number `node` is, which can be written with a minus in front.
+fn as_number(node: &EvalNode) -> Option<f64> {
+    match node.kind() {
+        EvalKind::Number(x) => Some(*x),
+        EvalKind::AddSub(pairs) => match pairs.as_slice() {
+            [(AddOrSub::Sub, node)] => as_number(node).map(|x| -x),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn product(nodes: Vec<EvalNode>) -> EvalNode {
+    // a product in a product is written without brackets, so it's read back as part of it.
+    let mut nodes: Vec<_> = nodes
+        .into_iter()
+        .flat_map(|node| match node.kind() {
+            EvalKind::Multiply(inner) => inner.clone(),
+            _ => vec![node],
+        })
+        .collect();
+    match nodes.len() {
+        1 => nodes.remove(0),
+        _ => EvalNode::multiply(nodes),
+    }
+}
+
+pub struct Rewriter<'a> {
+    idents: &'a IdentStorer,
+    rules: Rules,
+    nonzero: Vec<EvalNode>,
+}
+
+impl<'a> Rewriter<'a> {
+    pub fn new(idents: &'a IdentStorer, rules: Rules) -> Self {
+        Self {
+            idents,
+            rules,
+            nonzero: Vec::new(),
+        }
+    }
+
+    pub fn rewrite(mut self, node: &EvalNode) -> Rewritten {
+        let expr = self.node(node);
+        Rewritten {
+            expr,
+            nonzero: self.nonzero,
+        }
+    }
+
+    fn node(&mut self, node: &EvalNode) -> EvalNode {
+        let sum = self.sum(node);
+        self.sum_node(&sum)
+    }
+
+    fn nodes(&mut self, nodes: &[EvalNode]) -> Vec<EvalNode> {
+        nodes.iter().map(|node| self.node(node)).collect()
+    }
+
+    /// The normal form of `node`.
+    fn sum(&mut self, node: &EvalNode) -> Sum {
+        match node.kind() {
+            EvalKind::Number(x) => Sum::number(*x),
+            EvalKind::Identifier(_) => Sum::atom(node.clone()),
+            EvalKind::AddSub(pairs) => {
+                let mut terms = Vec::new();
+                for (sign, node) in pairs {
+                    let sum = self.sum(node);
+                    terms.extend(match sign {
+                        AddOrSub::Add => sum.terms,
+                        AddOrSub::Sub => sum.scale(-1.0).terms,
+                    });
+                }
+                self.collect(terms)
+            }
+            EvalKind::Multiply(factors) => {
+                let mut product = Sum::number(1.0);
+                for factor in factors {
+                    let factor = self.sum(factor);
+                    product = self.multiply(product, factor);
+                }
+                product
+            }
+            EvalKind::Frac { top, bottom } => {
+                let top = self.sum(top);
+                let bottom = self.sum(bottom);
+                let inverse = self.power(bottom, Sum::number(-1.0));
+                self.multiply(top, inverse)
+            }
+            EvalKind::Power { base, power } => {
+                let base = self.sum(base);
+                let power = self.sum(power);
+                self.power(base, power)
+            }
+            EvalKind::Abs(inner) => {
+                let inner = self.node(inner);
+                self.fold(EvalNode::abs(inner.clone()), [&inner], |[x]| x.abs())
+            }
+            EvalKind::Sqrt(inner) => {
+                let inner = self.node(inner);
+                self.fold(EvalNode::sqrt(inner.clone()), [&inner], |[x]| x.sqrt())
+            }
+            EvalKind::Root { index, expr } => {
+                let (index, expr) = (self.node(index), self.node(expr));
+                let root = EvalNode::root(index.clone(), expr.clone());
+                self.fold(root, [&index, &expr], |[index, x]| nth_root(x, index))
+            }
+            EvalKind::BuiltinsCall {
+                builtins,
+                power,
+                params,
+            } => self.builtins_call(*builtins, power.as_ref(), params),
+            EvalKind::FunctionCall {
+                ident,
+                power,
+                params,
+            } => {
+                let call = EvalNode::function_call(*ident, None, self.nodes(params));
+                match power {
+                    Some(power) => {
+                        let power = self.sum(power);
+                        self.power(Sum::atom(call), power)
+                    }
+                    None => Sum::atom(call),
+                }
+            }
+            _ => Sum::atom(self.children(node)),
+        }
+    }
+
+    /// `node` with everything in it rewritten, for what isn't rewritten itself.
+    fn children(&mut self, node: &EvalNode) -> EvalNode {
+        let defs = |rewriter: &mut Self, defs: &[VarDef]| -> Vec<VarDef> {
+            defs.iter()
+                .map(|def| VarDef::new(def.ident(), rewriter.node(def.expr())))
+                .collect()
+        };
+        let kind = match node.kind() {
+            EvalKind::Point(x, y) => EvalKind::Point(self.node(x), self.node(y)),
+            EvalKind::List(items) => EvalKind::List(self.nodes(items)),
+            EvalKind::SumProd {
+                kind,
+                ident,
+                from,
+                to,
+                expr,
+            } => EvalKind::SumProd {
+                kind: *kind,
+                ident: *ident,
+                from: self.node(from),
+                to: self.node(to),
+                expr: self.node(expr),
+            },
+            EvalKind::For { expr, defs: list } => EvalKind::For {
+                expr: self.node(expr),
+                defs: defs(self, list),
+            },
+            EvalKind::ListComp { expr, defs: list } => EvalKind::ListComp {
+                expr: self.node(expr),
+                defs: defs(self, list),
+            },
+            EvalKind::With { expr, defs: list } => EvalKind::With {
+                expr: self.node(expr),
+                defs: defs(self, list),
+            },
+            EvalKind::ListRange { from, next, to } => EvalKind::ListRange {
+                from: self.node(from),
+                next: next.as_ref().map(|next| self.node(next)),
+                to: self.node(to),
+            },
+            EvalKind::IfElse { conds, yes, no } => EvalKind::IfElse {
+                conds: conds
+                    .iter()
+                    .map(|cond| {
+                        let comps = cond
+                            .comps()
+                            .iter()
+                            .map(|(set, right)| (*set, self.node(right)))
+                            .collect();
+                        Conditional::new(self.node(cond.expr()), comps)
+                    })
+                    .collect(),
+                yes: yes.as_ref().map(|yes| self.node(yes)),
+                no: no.as_ref().map(|no| self.node(no)),
+            },
+            EvalKind::ElemAccess { expr, element } => EvalKind::ElemAccess {
+                expr: self.node(expr),
+                element: *element,
+            },
+            EvalKind::ListIndexing { expr, index } => EvalKind::ListIndexing {
+                expr: self.node(expr),
+                index: self.node(index),
+            },
+            kind => kind.clone(),
+        };
+        EvalNode::new(kind)
+    }
+
+    /// `node`, or its value when `args` are numbers and `func` of them is short.
+    fn fold<const N: usize>(
+        &self,
+        node: EvalNode,
+        args: [&EvalNode; N],
+        func: impl FnOnce([f64; N]) -> f64,
+    ) -> Sum {
+        let numbers = args.map(as_number);
+        if self.rules.contains(Rules::FOLD) && numbers.iter().all(Option::is_some) {
+            let value = func(numbers.map(|x| x.unwrap_or_else(|| unreachable!())));
+            if is_short(value) {
+                return Sum::number(value);
+            }
+        }
+        Sum::atom(node)
+    }
+
+    fn builtins_call(
+        &mut self,
+        builtins: Builtins,
+        power: Option<&EvalNode>,
+        params: &[EvalNode],
+    ) -> Sum {
+        let params = self.nodes(params);
+        let mut power = power.map(|power| self.sum(power));
+        // `sin^-1` is `arcsin`, which is how it stays.
+        let builtins = match (builtins, &power) {
+            (Builtins::MonadicPervasive(func), Some(inverse))
+                if inverse.as_number() == Some(-1.0) && func.invert().is_some() =>
+            {
+                power = None;
+                Builtins::MonadicPervasive(func.invert().unwrap_or_else(|| unreachable!()))
+            }
+            _ => builtins,
+        };
+        let call = EvalNode::builtins_call(builtins, None, params.clone());
+        let value = match (builtins, params.as_slice()) {
+            (Builtins::MonadicPervasive(func), [x]) => self.fold(call, [x], |[x]| func.apply(x)),
+            (Builtins::DyadicPervasive(func), [a, b]) => {
+                self.fold(call, [a, b], |[a, b]| func.apply_numbers(a, b))
+            }
+            _ => Sum::atom(call),
+        };
+        match power {
+            Some(power) => self.power(value, power),
+            None => value,
+        }
+    }
+
+    /// The sum of `terms`, flattening sums in it and collecting what can be.
+    fn collect(&mut self, terms: Vec<Term>) -> Sum {
+        let mut flat = Vec::with_capacity(terms.len());
+        for term in terms {
+            match term.factors.as_slice() {
+                [Factor {
+                    base: Base::Group(group),
+                    exp,
+                }] if exp.as_number() == Some(1.0) => {
+                    flat.extend(group.clone().scale(term.coeff).terms);
+                }
+                _ => flat.push(term),
+            }
+        }
+
+        let mut collected: Vec<Term> = Vec::with_capacity(flat.len());
+        for term in flat {
+            let can_collect = term.factors.is_empty() || self.rules.contains(Rules::COLLECT);
+            match collected
+                .iter_mut()
+                .find(|other| can_collect && other.factors == term.factors)
+            {
+                Some(other) => other.coeff += term.coeff,
+                None => collected.push(term),
+            }
+        }
+        for term in &mut collected {
+            term.coeff = tidy(term.coeff);
+        }
+        collected.retain(|term| term.coeff != 0.0 || term.has_list());
+        collected.sort_by(|a, b| self.cmp_terms(a, b));
+        Sum { terms: collected }
+    }
+
+    fn multiply(&mut self, a: Sum, b: Sum) -> Sum {
+        let is_single = a.terms.len() == 1 && b.terms.len() == 1;
+        if a.terms.is_empty() || b.terms.is_empty() {
+            // what's left of a list is a list of zeros.
+            let other = match a.terms.is_empty() {
+                true => b,
+                false => a,
+            };
+            return self.collect(other.scale(0.0).terms);
+        }
+        // infinity times a sum isn't the sum of the infinities, which can be infinities of both
+        // signs, so a division by zero isn't multiplied out.
+        let can_expand = !(a.divides_by_zero() || b.divides_by_zero());
+        if is_single || (self.rules.contains(Rules::EXPAND) && can_expand) {
+            let mut terms = Vec::with_capacity(a.terms.len() * b.terms.len());
+            for left in &a.terms {
+                for right in &b.terms {
+                    terms.push(self.multiply_terms(left.clone(), right.clone()));
+                }
+            }
+            return self.collect(terms);
+        }
+        let (a, b) = (self.as_term(a), self.as_term(b));
+        let term = self.multiply_terms(a, b);
+        self.collect(vec![term])
+    }
+
+    /// `sum` as a single term, which a sum of more than one is as a whole.
+    fn as_term(&self, mut sum: Sum) -> Term {
+        match sum.terms.len() {
+            1 => sum.terms.remove(0),
+            _ => Term {
+                coeff: 1.0,
+                factors: vec![Factor {
+                    base: Base::Group(sum),
+                    exp: Sum::number(1.0),
+                }],
+            },
+        }
+    }
+
+    fn multiply_terms(&mut self, mut a: Term, b: Term) -> Term {
+        a.coeff *= b.coeff;
+        for factor in b.factors {
+            let same = a.factors.iter_mut().find(|other| other.base == factor.base);
+            let merged = match same {
+                Some(other) if self.rules.contains(Rules::COLLECT) => {
+                    match self.add_exponents(&other.base, &other.exp, &factor.exp) {
+                        Some(exp) => {
+                            other.exp = exp;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                _ => false,
+            };
+            if !merged {
+                a.factors.push(factor);
+            }
+        }
+        // anything to the zero is one, even infinity, but a list to the zero is a list of ones.
+        a.factors
+            .retain(|factor| factor.exp.as_number() != Some(0.0) || factor.is_list());
+        a.factors.sort_by(|a, b| self.cmp_factors(a, b));
+        a
+    }
+
+    /// The power of `base` that's its powers to `a` and to `b` multiplied, if it has the same
+    /// value.
+    fn add_exponents(&mut self, base: &Base, a: &Sum, b: &Sum) -> Option<Sum> {
+        let (a, b) = (a.as_number()?, b.as_number()?);
+        let sum = a + b;
+        // negative numbers have no powers that aren't whole, so they can't add up to one.
+        if !(is_whole(a) && is_whole(b)) && is_whole(sum) {
+            return None;
+        }
+        if sum == 0.0 && base.is_list() {
+            return None;
+        }
+        // zero has no negative powers, so cancelling them out would give it a value.
+        if a.min(b) < 0.0 && sum >= 0.0 {
+            if !self.rules.contains(Rules::CANCEL) {
+                return None;
+            }
+            let node = self.base_node(base);
+            if !self.nonzero.contains(&node) {
+                self.nonzero.push(node);
+            }
+        }
+        Some(Sum::number(sum))
+    }
+
+    fn power(&mut self, base: Sum, exp: Sum) -> Sum {
+        match exp.as_number() {
+            // anything to the zero is one, even infinity.
+            Some(0.0) if !base.has_list() => Sum::number(1.0),
+            Some(0.0) => self.factor_power(base, exp),
+            Some(1.0) => base,
+            Some(exp) => self.number_power(base, exp),
+            None => self.factor_power(base, exp),
+        }
+    }
+
+    fn number_power(&mut self, base: Sum, exp: f64) -> Sum {
+        if let Some(base) = base.as_number() {
+            let value = base.powf(exp);
+            let is_exact = value.is_finite() && (is_whole(exp) || is_short(value));
+            if self.rules.contains(Rules::FOLD) && is_exact {
+                return Sum::number(value);
+            }
+            return self.factor_power(Sum::number(base), Sum::number(exp));
+        }
+        if !is_whole(exp) {
+            return self.factor_power(base, Sum::number(exp));
+        }
+
+        if let [term] = base.terms.as_slice() {
+            // a whole power of a product is the product of the powers, unless that makes a power
+            // that isn't whole into one, which negative numbers don't have.
+            let coeff = term.coeff.powf(exp);
+            let factors: Option<Vec<_>> = term
+                .factors
+                .iter()
+                .map(|factor| {
+                    let old = factor.exp.as_number()?;
+                    let new = old * exp;
+                    (is_whole(old) || !is_whole(new)).then(|| Factor {
+                        base: factor.base.clone(),
+                        exp: Sum::number(new),
+                    })
+                })
+                .collect();
+            if let (true, Some(mut factors)) = (coeff.is_finite(), factors) {
+                factors.sort_by(|a, b| self.cmp_factors(a, b));
+                return Sum {
+                    terms: vec![Term { coeff, factors }],
+                };
+            }
+        } else if self.rules.contains(Rules::EXPAND) && (2.0..=16.0).contains(&exp) {
+            let mut product = base.clone();
+            for _ in 1..exp as usize {
+                product = self.multiply(product, base.clone());
+            }
+            return product;
+        }
+        self.factor_power(base, Sum::number(exp))
+    }
+
+    /// `base` to the `exp` as a single factor.
+    fn factor_power(&self, base: Sum, exp: Sum) -> Sum {
+        let base = match base.terms.as_slice() {
+            [] => Base::Atom(EvalNode::number(0.0)),
+            [term] if term.factors.is_empty() => Base::Atom(EvalNode::number(term.coeff)),
+            [Term { coeff, factors }]
+                if *coeff == 1.0
+                    && factors.len() == 1
+                    && factors[0].exp.as_number() == Some(1.0) =>
+            {
+                factors[0].base.clone()
+            }
+            _ => Base::Group(base),
+        };
+        Sum::factor(Factor { base, exp })
+    }
+
+    fn cmp_sums(&self, a: &Sum, b: &Sum) -> Ordering {
+        let by_term = a
+            .terms
+            .iter()
+            .zip(&b.terms)
+            .map(|(a, b)| self.cmp_terms(a, b));
+        by_term
+            .fold(Ordering::Equal, Ordering::then)
+            .then(a.terms.len().cmp(&b.terms.len()))
+    }
+
+    /// Higher powers go first, and numbers by themselves last.
+    fn cmp_terms(&self, a: &Term, b: &Term) -> Ordering {
+        let by_factor = a
+            .factors
+            .iter()
+            .zip(&b.factors)
+            .map(|(a, b)| self.cmp_factors(a, b));
+        b.degree()
+            .total_cmp(&a.degree())
+            .then(b.factors.len().cmp(&a.factors.len()))
+            .then(by_factor.fold(Ordering::Equal, Ordering::then))
+            .then(a.coeff.total_cmp(&b.coeff))
+    }
+
+    fn cmp_factors(&self, a: &Factor, b: &Factor) -> Ordering {
+        self.cmp_bases(&a.base, &b.base)
+            .then_with(|| self.cmp_sums(&a.exp, &b.exp))
+    }
+
+    /// Names go first in alphabetical order, then whatever else, then sums.
+    fn cmp_bases(&self, a: &Base, b: &Base) -> Ordering {
+        let rank = |base: &Base| match base {
+            Base::Atom(node) => match node.kind() {
+                EvalKind::Identifier(ident) => (0, self.idents.name(*ident).to_string()),
+                _ => (1, format!("{node:?}")),
+            },
+            Base::Group(_) => (2, String::new()),
+        };
+        match (a, b) {
+            (Base::Group(a), Base::Group(b)) => self.cmp_sums(a, b),
+            _ => rank(a).cmp(&rank(b)),
+        }
+    }
+
+    fn sum_node(&self, sum: &Sum) -> EvalNode {
+        let mut pairs: Vec<_> = sum
+            .terms
+            .iter()
+            .map(|term| {
+                let sign = match term.coeff < 0.0 {
+                    true => AddOrSub::Sub,
+                    false => AddOrSub::Add,
+                };
+                (sign, self.term_node(term.coeff.abs(), &term.factors))
+            })
+            .collect();
+        match pairs.as_slice() {
+            [] => EvalNode::number(0.0),
+            [(AddOrSub::Add, _)] => pairs.remove(0).1,
+            _ => EvalNode::add_sub(pairs),
+        }
+    }
+
+    /// A term, with what's divided by under a fraction.
+    fn term_node(&self, coeff: f64, factors: &[Factor]) -> EvalNode {
+        let (top_coeff, bottom_coeff) = ratio(coeff);
+        let mut top = Vec::new();
+        let mut bottom = Vec::new();
+        if bottom_coeff != 1.0 {
+            bottom.push(EvalNode::number(bottom_coeff));
+        }
+        for factor in factors {
+            match factor.exp.as_number() {
+                Some(exp) if exp < 0.0 => {
+                    bottom.push(self.factor_node(&factor.base, &Sum::number(-exp)));
+                }
+                _ => top.push(self.factor_node(&factor.base, &factor.exp)),
+            }
+        }
+        if top_coeff != 1.0 || top.is_empty() {
+            top.insert(0, EvalNode::number(top_coeff));
+        }
+        match bottom.is_empty() {
+            true => product(top),
+            false => EvalNode::frac(product(top), product(bottom)),
+        }
+    }
+
+    fn factor_node(&self, base: &Base, exp: &Sum) -> EvalNode {
+        let base = self.base_node(base);
+        match exp.as_number() {
+            Some(1.0) => base,
+            _ => EvalNode::power(base, self.sum_node(exp)),
+        }
+    }
+
+    fn base_node(&self, base: &Base) -> EvalNode {
+        match base {
+            Base::Atom(node) => node.clone(),
+            Base::Group(sum) => self.sum_node(sum),
+        }
+    }
+}
@@ -4,11 +4,27 @@ use std::cell::Cell;
 use std::ops::{Deref, DerefMut};
 
 use fast_desmos2_tree::tree::debug::Debugable;
-use fast_desmos2_tree::tree::{EditorTree, EditorTreeSeq, SumOrProd, SumProdIndex, SurroundIndex};
+use std::collections::HashMap;
+
+use fast_desmos2_comms::{List, Value};
+use fast_desmos2_tree::editor::Editor;
+use fast_desmos2_tree::tree::{
+    Completion, EditorHistory, EditorTree, EditorTreeSeq, FractionIndex, PiecewiseIndex,
+    PiecewiseRow, RootIndex, SumOrProd, SumProdIndex, SurroundIndex, TreeAction,
+};
+use fast_desmos2_tree::worksheet::{self, Worksheet};
 
 use crate::builtins::{Builtins, MonadicPervasive};
+use crate::completion;
+use crate::derivative::derivative;
+use crate::eval::{self, CellError, Env, EvalError, EvalResult};
 use crate::parsing;
-use crate::tree::{AddOrSub, CompSet, Conditional, EvalKind, EvalNode, IdentStorer};
+use crate::refactor::{Rename, RenameError};
+use crate::simplify::{Rewriter, Rules};
+use crate::tree::{
+    AddOrSub, CompSet, Conditional, EvalKind, EvalNode, IdentId, IdentStorer, Statement,
+};
+use crate::unparse;
 
 struct IdentStorerGuard {
     used: Cell<bool>,
@@ -71,6 +87,22 @@ fn sqrt(child: impl Into<EditorTreeSeq>) -> EditorTree {
     EditorTree::sqrt(SurroundIndex::Inside, child.into())
 }
 
+fn root(index: impl Into<EditorTreeSeq>, child: impl Into<EditorTreeSeq>) -> EditorTree {
+    EditorTree::root(RootIndex::Inside, index.into(), child.into())
+}
+
+fn piecewise(rows: &[(&str, &str)], default: &str) -> EditorTree {
+    let rows = rows
+        .iter()
+        .map(|(condition, value)| PiecewiseRow::new(str(condition), str(value)))
+        .collect();
+    EditorTree::piecewise(PiecewiseIndex::Default, rows, str(default))
+}
+
+fn frac(top: impl Into<EditorTreeSeq>, bottom: impl Into<EditorTreeSeq>) -> EditorTree {
+    EditorTree::fraction(FractionIndex::Left, top.into(), bottom.into())
+}
+
 fn brackets(child: impl Into<EditorTreeSeq>) -> EditorTree {
     EditorTree::complete_brackets(SurroundIndex::Inside, child.into())
 }
@@ -91,6 +123,10 @@ fn power(power: impl Into<EditorTreeSeq>) -> EditorTree {
     EditorTree::power(power.into())
 }
 
+fn subscript(subscript: impl Into<EditorTreeSeq>) -> EditorTree {
+    EditorTree::subscript(subscript.into())
+}
+
 fn one(child: EditorTree) -> EditorTreeSeq {
     EditorTreeSeq::one(child)
 }
@@ -143,6 +179,15 @@ fn test_sqrt_numbers() {
     assert_eq!(parsed, EvalNode::sqrt(EvalNode::number(0.31)))
 }
 
+#[test]
+fn test_nth_root() {
+    let (parsed, _) = parse(root(str("3"), str("8")));
+    assert_eq!(
+        parsed,
+        EvalNode::root(EvalNode::number(3.0), EvalNode::number(8.0))
+    )
+}
+
 #[test]
 fn test_abs_numbers() {
     let (parsed, _) = parse(abs(str("0.00")));
@@ -161,6 +206,39 @@ fn test_identifier() {
     assert_eq!(ident_id, true_id);
 }
 
+#[test]
+fn test_subscripted_identifier() {
+    let (parsed, idents) = parse(seq(vec![term('x'), subscript(str("max"))]));
+
+    let true_id = idents.convert_parts("x", Some("max"));
+    assert_eq!(idents.len(), 1);
+    assert_eq!(parsed, EvalNode::ident(true_id));
+    assert_eq!(idents.name(true_id).base(), "x");
+    assert_eq!(idents.name(true_id).subscript(), Some("max"));
+    assert_eq!(idents.name(true_id).to_string(), "x_max");
+
+    // the parts are kept apart, so a name spelled with the underscore is another one.
+    assert_ne!(idents.convert_id("x_max"), true_id);
+    assert_ne!(idents.convert_parts("xm", Some("ax")), true_id);
+}
+
+#[test]
+fn test_subscripted_builtin_name() {
+    let (parsed, idents) = parse(seq(vec![
+        term('s'),
+        term('i'),
+        term('n'),
+        subscript(str("1")),
+        paren(str("2")),
+    ]));
+
+    let true_id = idents.convert_parts("sin", Some("1"));
+    assert_eq!(
+        parsed,
+        EvalNode::function_call(true_id, None, vec![EvalNode::number(2.0)])
+    );
+}
+
 #[test]
 fn test_point_literal() {
     let (parsed, _) = parse(paren(str("1.0,2.0")));
@@ -198,6 +276,45 @@ fn test_builtins_call() {
     assert_eq!(params, vec![EvalNode::number(1.0),])
 }
 
+#[test]
+fn test_builtins_names() {
+    for builtins in Builtins::all() {
+        assert_eq!(
+            Builtins::from_str(builtins.as_str().as_bytes()),
+            Some(builtins)
+        );
+    }
+}
+
+#[test]
+fn test_complete_builtins_call() {
+    let mut tree = EditorTreeSeq::new(2, vec![term('s'), term('i')]);
+    let worksheet = Worksheet {
+        cells: vec![
+            worksheet::Cell::new("1", str("sin=2")),
+            worksheet::Cell::new("2", str("sigma=1")),
+        ],
+        ..Worksheet::default()
+    };
+    let completions = completion::completions(&tree, &worksheet);
+    assert_eq!(completions[0], Completion::function("sin"));
+    assert!(completions.contains(&Completion::variable("sigma")));
+    assert!(!completions.contains(&Completion::variable("sin")));
+    assert!(tree.accept_completion(&completions[0]));
+    tree.apply_action(TreeAction::Char('x'));
+
+    let (parsed, idents) = parse(tree);
+    let x = idents.convert_id("x");
+    assert_eq!(
+        parsed,
+        EvalNode::builtins_call(
+            Builtins::MonadicPervasive(MonadicPervasive::Sin),
+            None,
+            vec![EvalNode::ident(x)]
+        )
+    );
+}
+
 #[test]
 fn test_function_call() {
     let (parsed, idents) = parse(seq(vec![term('f'), paren(str("1.0"))]));
@@ -301,6 +418,46 @@ fn test_if_else() {
     )
 }
 
+#[test]
+fn test_piecewise() {
+    let (parsed, _) = parse(piecewise(&[("1=2", "3"), ("4<5", "")], "6"));
+    let cond = |left: f64, comp: CompSet, right: f64| {
+        vec![Conditional::new(
+            EvalNode::number(left),
+            vec![(comp, EvalNode::number(right))],
+        )]
+    };
+
+    assert_eq!(
+        parsed,
+        EvalNode::if_else(
+            cond(1.0, CompSet::EQUAL, 2.0),
+            Some(EvalNode::number(3.0)),
+            Some(EvalNode::if_else(
+                cond(4.0, CompSet::LESS, 5.0),
+                None,
+                Some(EvalNode::number(6.0)),
+            )),
+        )
+    )
+}
+
+#[test]
+fn test_piecewise_without_default() {
+    let (parsed, _) = parse(piecewise(&[("1<2", "3")], ""));
+    assert_eq!(
+        parsed,
+        EvalNode::if_else(
+            vec![Conditional::new(
+                EvalNode::number(1.0),
+                vec![(CompSet::LESS, EvalNode::number(2.0))],
+            )],
+            Some(EvalNode::number(3.0)),
+            None,
+        )
+    )
+}
+
 #[test]
 fn test_sum() {
     let (parsed, idents) = parse(adjoin(vec![
@@ -354,3 +511,1051 @@ fn test_prod_hard() {
         ])
     )
 }
+
+#[test]
+fn test_fraction() {
+    let (parsed, _) = parse(adjoin(vec![one(frac(str("1"), str("2"))), str("3")]));
+    assert_eq!(
+        parsed,
+        EvalNode::multiply(vec![
+            EvalNode::frac(EvalNode::number(1.0), EvalNode::number(2.0)),
+            EvalNode::number(3.0),
+        ])
+    )
+}
+
+#[test]
+fn test_fraction_nested() {
+    let (parsed, _) = parse(one(frac(one(frac(str("1"), str("2"))), str("3+4"))));
+    assert_eq!(
+        parsed,
+        EvalNode::frac(
+            EvalNode::frac(EvalNode::number(1.0), EvalNode::number(2.0)),
+            EvalNode::add_sub(vec![
+                (AddOrSub::Add, EvalNode::number(3.0)),
+                (AddOrSub::Add, EvalNode::number(4.0)),
+            ]),
+        )
+    );
+
+    let (parsed, _) = parse(adjoin(vec![
+        one(frac(str("1"), str("2"))),
+        one(power(str("3"))),
+    ]));
+    assert_eq!(
+        parsed,
+        EvalNode::power(
+            EvalNode::frac(EvalNode::number(1.0), EvalNode::number(2.0)),
+            EvalNode::number(3.0),
+        )
+    );
+}
+
+#[test]
+fn test_fraction_incomplete() {
+    let idents = IdentStorer::default();
+    for tree in [one(frac(str("1"), str(""))), one(frac(str("1+"), str("2")))] {
+        assert!(parsing::parse_statement(&tree, &idents).is_err());
+    }
+}
+
+#[test]
+fn test_explicit_multiply() {
+    let (parsed, _) = parse(str("2*3"));
+    assert_eq!(
+        parsed,
+        EvalNode::multiply(vec![EvalNode::number(2.0), EvalNode::number(3.0)])
+    );
+
+    // the `*` is optional between any two factors, and spaces around it don't matter.
+    let (parsed, idents) = parse(str("2 * x*y 3"));
+    let x = EvalNode::ident(idents.convert_id("x"));
+    let y = EvalNode::ident(idents.convert_id("y"));
+    assert_eq!(
+        parsed,
+        EvalNode::multiply(vec![EvalNode::number(2.0), x, y, EvalNode::number(3.0)])
+    );
+}
+
+/// The editor and the LaTeX importer never put a `*` in a tree; they write `×`.
+#[test]
+fn test_explicit_multiply_typed() {
+    let product = EvalNode::multiply(vec![EvalNode::number(2.0), EvalNode::number(3.0)]);
+
+    let mut tree = EditorTreeSeq::empty();
+    Editor::default()
+        .run_script(&mut tree, &mut EditorHistory::default(), "i 2 * 3")
+        .unwrap();
+    assert_eq!(tree.notation(), "2×3|");
+    assert_eq!(parse(tree).0, product);
+
+    for latex in [r"2\cdot 3", r"2\times 3"] {
+        let tree = EditorTreeSeq::from_latex(latex).unwrap();
+        assert_eq!(parse(tree).0, product, "{latex}");
+    }
+}
+
+#[test]
+fn test_explicit_multiply_incomplete() {
+    let idents = IdentStorer::default();
+    for tree in [str("2*"), str("*2"), str("2**3"), str("2×"), str("2××3")] {
+        assert!(
+            parsing::parse_statement(&tree, &idents).is_err(),
+            "{}",
+            tree.debug(false).render()
+        );
+    }
+}
+
+fn statement(tree: impl Into<EditorTreeSeq>) -> (Statement, IdentStorer) {
+    let idents = IdentStorer::default();
+    let tree = tree.into();
+    let statement = parsing::parse_statement(&tree, &idents).expect("the statement parses");
+    (statement, idents)
+}
+
+#[test]
+fn test_statements() {
+    let (parsed, idents) = statement(str("a=1"));
+    let Statement::Variable(def) = parsed else {
+        panic!("expected a variable, found {parsed:?}");
+    };
+    assert_eq!(def.ident(), idents.convert_id("a"));
+    assert_eq!(def.expr(), &EvalNode::number(1.0));
+
+    let (parsed, idents) = statement(adjoin(vec![str("f"), one(paren(str("x,y"))), str("=x")]));
+    let Statement::Function(def) = parsed else {
+        panic!("expected a function, found {parsed:?}");
+    };
+    let x = idents.convert_id("x");
+    assert_eq!(def.ident(), idents.convert_id("f"));
+    assert_eq!(def.params(), [x, idents.convert_id("y")]);
+    assert_eq!(def.expr(), &EvalNode::ident(x));
+
+    let (parsed, _) = statement(str("1+2"));
+    assert!(matches!(parsed, Statement::Expression(_)));
+}
+
+fn evaluate(tree: impl Into<EditorTreeSeq>) -> EvalResult<Value> {
+    let (parsed, idents) = parse(tree);
+    Env::new(&idents).evaluate(&parsed)
+}
+
+fn number(x: f64) -> EvalResult<Value> {
+    Ok(Value::one_number(x))
+}
+
+#[test]
+fn test_evaluate_arithmetic() {
+    assert_eq!(evaluate(str("1+2*3-4")), number(3.0));
+    assert_eq!(
+        evaluate(adjoin(vec![str("-2"), one(power(str("2")))])),
+        number(-4.0)
+    );
+    assert_eq!(evaluate(one(frac(str("3"), str("4")))), number(0.75));
+    assert_eq!(evaluate(one(root(str("3"), str("-8")))), number(-2.0));
+    assert_eq!(evaluate(one(abs(str("-5")))), number(5.0));
+    assert_eq!(
+        evaluate(one(brackets(str("1...4")))),
+        Ok(Value::Number(List::Flat(vec![1.0, 2.0, 3.0, 4.0])))
+    );
+    let index = |at| evaluate(adjoin(vec![one(brackets(str("4,5"))), one(brackets(at))]));
+    assert_eq!(index(str("2")), number(5.0));
+    // past the ends, and indices that are no number at all.
+    for at in [str("0"), str("3"), one(frac(str("0"), str("0")))] {
+        let value = index(at).unwrap().try_number().unwrap().try_term().unwrap();
+        assert!(value.is_nan());
+    }
+    assert!(matches!(
+        evaluate(adjoin(vec![
+            one(brackets(str("1,2"))),
+            str("+"),
+            one(paren(str("1,2")))
+        ])),
+        Err(EvalError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn test_evaluate_builtins() {
+    assert_eq!(
+        evaluate(adjoin(vec![str("sin"), one(paren(str("0")))])),
+        number(0.0)
+    );
+    assert_eq!(
+        evaluate(adjoin(vec![
+            str("cos"),
+            one(power(str("-1"))),
+            one(paren(str("1")))
+        ])),
+        number(0.0)
+    );
+    assert_eq!(
+        evaluate(adjoin(vec![str("total"), one(paren(str("1,2,3")))])),
+        number(6.0)
+    );
+    assert_eq!(
+        evaluate(adjoin(vec![str("mod"), one(paren(str("-1,3")))])),
+        number(2.0)
+    );
+    assert_eq!(
+        evaluate(adjoin(vec![str("sin"), one(paren(str("1,2")))])),
+        Err(EvalError::Arity {
+            name: "sin".to_string(),
+            expected: 1,
+            found: 2,
+        })
+    );
+}
+
+fn call(name: &str, params: &str) -> EditorTreeSeq {
+    adjoin(vec![str(name), one(paren(str(params)))])
+}
+
+#[test]
+fn test_evaluate_every_builtin() {
+    let monadic = [
+        ("sin", 0.5, 0.5f64.sin()),
+        ("cos", 0.5, 0.5f64.cos()),
+        ("tan", 0.5, 0.5f64.tan()),
+        ("sec", 0.5, 1.0 / 0.5f64.cos()),
+        ("csc", 0.5, 1.0 / 0.5f64.sin()),
+        ("cot", 0.5, 1.0 / 0.5f64.tan()),
+        ("sinh", 0.5, 0.5f64.sinh()),
+        ("cosh", 0.5, 0.5f64.cosh()),
+        ("tanh", 0.5, 0.5f64.tanh()),
+        ("sech", 0.5, 1.0 / 0.5f64.cosh()),
+        ("csch", 0.5, 1.0 / 0.5f64.sinh()),
+        ("coth", 0.5, 1.0 / 0.5f64.tanh()),
+        ("arcsin", 0.5, 0.5f64.asin()),
+        ("arccos", 0.5, 0.5f64.acos()),
+        ("arctan", 0.5, 0.5f64.atan()),
+        ("arcsec", 2.0, 0.5f64.acos()),
+        ("arccsc", 2.0, 0.5f64.asin()),
+        ("arccot", 2.0, 0.5f64.atan()),
+        ("arcsinh", 0.5, 0.5f64.asinh()),
+        ("arccosh", 2.0, 2f64.acosh()),
+        ("arctanh", 0.5, 0.5f64.atanh()),
+        ("arcsech", 0.5, 2f64.acosh()),
+        ("arccsch", 2.0, 0.5f64.asinh()),
+        ("arccoth", 2.0, 0.5f64.atanh()),
+        ("arsinh", 0.5, 0.5f64.asinh()),
+        ("arcosh", 2.0, 2f64.acosh()),
+        ("artanh", 0.5, 0.5f64.atanh()),
+        ("arsech", 0.5, 2f64.acosh()),
+        ("arcsch", 2.0, 0.5f64.asinh()),
+        ("arcoth", 2.0, 0.5f64.atanh()),
+        ("ln", 2.0, 2f64.ln()),
+        ("sign", -0.5, -1.0),
+        ("floor", -0.5, -1.0),
+        ("ceil", -0.5, 0.0),
+        ("round", 2.5, 3.0),
+    ];
+    for (name, x, expected) in monadic {
+        let Ok(Value::Number(List::Term(found))) = evaluate(call(name, &x.to_string())) else {
+            panic!("{name}({x}) isn't a number");
+        };
+        assert!((found - expected).abs() < 1e-12, "{name}({x}) is {found}");
+    }
+
+    let numbers = |xs: &[f64]| Ok(Value::Number(List::Flat(xs.to_vec())));
+    let calls = [
+        ("mod", "7,3", number(1.0)),
+        ("mod", "-7,3", number(2.0)),
+        ("choose", "5,2", number(10.0)),
+        ("permuatation", "5,2", number(20.0)),
+        ("distance", "1,4", number(3.0)),
+        ("mean", "1,2,6", number(3.0)),
+        ("min", "3,1,2", number(1.0)),
+        ("max", "3,1,2", number(3.0)),
+        ("total", "1,2,3", number(6.0)),
+    ];
+    for (name, params, expected) in &calls {
+        assert_eq!(&evaluate(call(name, params)), expected, "{name}({params})");
+    }
+    let list_call =
+        |name: &str, list: &str| adjoin(vec![str(name), one(paren(one(brackets(str(list)))))]);
+    assert_eq!(evaluate(list_call("length", "4,5,6")), number(3.0));
+    assert_eq!(
+        evaluate(list_call("unique", "1,2,1,3,2")),
+        numbers(&[1.0, 2.0, 3.0])
+    );
+    assert_eq!(
+        evaluate(adjoin(vec![
+            str("join"),
+            one(paren(adjoin(vec![one(brackets(str("1,2"))), str(",3")])))
+        ])),
+        numbers(&[1.0, 2.0, 3.0])
+    );
+    assert_eq!(
+        evaluate(list_call("sort", "3,1,2")),
+        numbers(&[1.0, 2.0, 3.0])
+    );
+    assert_eq!(
+        evaluate(call("random", "")),
+        Err(EvalError::Unsupported("`random`"))
+    );
+
+    let tested: Vec<_> = (monadic.iter().map(|(name, ..)| *name))
+        .chain(calls.iter().map(|(name, ..)| *name))
+        .chain(["length", "unique", "join", "sort", "random"])
+        .collect();
+    for builtins in Builtins::all() {
+        assert!(tested.contains(&builtins.as_str()), "{}", builtins.as_str());
+    }
+
+    // the ones taking numbers go over lists.
+    assert_eq!(
+        evaluate(adjoin(vec![
+            str("floor"),
+            one(paren(one(brackets(str("1.5,-1.5")))))
+        ])),
+        numbers(&[1.0, -2.0])
+    );
+    assert_eq!(
+        evaluate(adjoin(vec![
+            str("mod"),
+            one(paren(adjoin(vec![one(brackets(str("5,6,7"))), str(",3")])))
+        ])),
+        numbers(&[2.0, 0.0, 1.0])
+    );
+}
+
+#[test]
+fn test_evaluate_sum_and_piecewise() {
+    assert_eq!(
+        evaluate(adjoin(vec![
+            one(sum(str("4"), str("1"), str("n"))),
+            str("n")
+        ])),
+        number(10.0)
+    );
+    // too many terms to add up while typing.
+    for to in ["10001", r"10\pow{9}", r"2\pow{60}"] {
+        let to = EditorTreeSeq::from_notation_without_caret(to).unwrap();
+        assert_eq!(
+            evaluate(adjoin(vec![one(sum(to, str("1"), str("n"))), str("n")])),
+            Err(EvalError::TooLong)
+        );
+    }
+    assert_eq!(evaluate(one(curly(str("1<2:3,4")))), number(3.0));
+    assert_eq!(evaluate(one(curly(str("1>2:3,4")))), number(4.0));
+    assert_eq!(evaluate(one(curly(str("1<2<3")))), number(1.0));
+    assert!(evaluate(one(curly(str("1>2:3"))))
+        .unwrap()
+        .try_number()
+        .unwrap()
+        .try_term()
+        .unwrap()
+        .is_nan());
+}
+
+fn evaluate_cells(cells: &[EditorTreeSeq]) -> Vec<eval::CellResult> {
+    let worksheet = Worksheet {
+        cells: cells
+            .iter()
+            .enumerate()
+            .map(|(index, content)| worksheet::Cell::new((index + 1).to_string(), content.clone()))
+            .collect(),
+        ..Worksheet::default()
+    };
+    eval::evaluate_worksheet(&worksheet, &HashMap::new())
+}
+
+#[test]
+fn test_evaluate_worksheet() {
+    let results = evaluate_cells(&[
+        str("b=a+1"),
+        str("a=2"),
+        adjoin(vec![
+            str("f"),
+            one(paren(str("x"))),
+            str("=x"),
+            one(power(str("2"))),
+            str("+b"),
+        ]),
+        adjoin(vec![str("f"), one(paren(str("a")))]),
+        str("y=2x"),
+        EditorTreeSeq::empty(),
+        str("c=d"),
+    ]);
+    assert_eq!(
+        results,
+        [
+            Ok(Some(Value::one_number(3.0))),
+            Ok(Some(Value::one_number(2.0))),
+            Ok(None),
+            Ok(Some(Value::one_number(7.0))),
+            Ok(None),
+            Ok(None),
+            Err(CellError::Eval(EvalError::Undefined("d".to_string()))),
+        ]
+    );
+}
+
+#[test]
+fn test_evaluate_worksheet_errors() {
+    let results = evaluate_cells(&[str("a=b"), str("b=a"), str("c=1"), str("c=2"), str("1+")]);
+    assert_eq!(
+        results,
+        [
+            Err(CellError::Eval(EvalError::Cycle("a".to_string()))),
+            Err(CellError::Eval(EvalError::Cycle("b".to_string()))),
+            Err(CellError::Eval(EvalError::Redefined("c".to_string()))),
+            Err(CellError::Eval(EvalError::Redefined("c".to_string()))),
+            Err(CellError::Parse),
+        ]
+    );
+}
+
+#[test]
+fn test_evaluate_worksheet_received() {
+    let worksheet = Worksheet {
+        cells: vec![
+            worksheet::Cell::new("1", str("a=0")),
+            worksheet::Cell::new("2", str("2a")),
+        ],
+        ..Worksheet::default()
+    };
+    let received = HashMap::from([("1".to_string(), Value::one_number(5.0))]);
+    let results = eval::evaluate_worksheet(&worksheet, &received);
+    assert_eq!(results[1], Ok(Some(Value::one_number(10.0))));
+}
+
+/// Renames `from` to `to` in the cell written `notation`, giving its notation afterwards.
+fn renamed(from: &str, to: &str, notation: &str) -> String {
+    let mut tree = EditorTreeSeq::from_notation(notation).unwrap();
+    Rename::new(from, to).unwrap().apply(&mut tree);
+    tree.notation()
+}
+
+#[test]
+fn test_rename() {
+    assert_eq!(renamed("a", "b", "a+2a|"), "b+2b|");
+    // only whole names.
+    assert_eq!(renamed("a", "b", "ab+a\\sub{1}+a|"), "ab+a\\sub{1}+b|");
+    assert_eq!(renamed("a_1", "c", "ab+a\\sub{1}|"), "ab+c|");
+    assert_eq!(
+        renamed("x", "t_{0}", "\\frac{1}{x}+x\\pow{x}|"),
+        "\\frac{1}{t\\sub{0}}+t\\sub{0}\\pow{t\\sub{0}}|"
+    );
+    // builtins that are called, and what's after a dot, aren't the name.
+    assert_eq!(renamed("x", "y", "sin\\paren{x}|"), "sin\\paren{y}|");
+    assert_eq!(renamed("x", "y", "p.x+x|"), "p.x+y|");
+    assert_eq!(
+        renamed("f", "g", "f\\paren{2}+f\\pow{2}\\paren{1}|"),
+        "g\\paren{2}+g\\pow{2}\\paren{1}|"
+    );
+}
+
+#[test]
+fn test_rename_scopes() {
+    // the parameters of a function are other variables.
+    assert_eq!(
+        renamed("x", "y", "f\\paren{x}=x\\pow{2}|"),
+        "f\\paren{x}=x\\pow{2}|"
+    );
+    assert_eq!(renamed("a", "b", "f\\paren{x}=a×x|"), "f\\paren{x}=b×x|");
+    // as is the index of a sum, in the sum.
+    assert_eq!(
+        renamed("n", "k", "\\sum{n}{1}{n}n\\pow{2}+n|"),
+        "\\sum{n}{1}{k}n\\pow{2}+k|"
+    );
+    assert_eq!(
+        renamed("n", "k", "\\prod{i}{1}{n}i×n|"),
+        "\\prod{i}{1}{k}i×k|"
+    );
+}
+
+#[test]
+fn test_rename_caret() {
+    assert_eq!(renamed("ab", "c", "ab+a|b"), "c+c|");
+    assert_eq!(renamed("ab", "cde", "ab+|ab"), "cde+|cde");
+    assert_eq!(renamed("a", "bc", "a+|1"), "bc+|1");
+    assert_eq!(renamed("a", "b", "\\frac{a|}{2}+a"), "\\frac{b|}{2}+b");
+}
+
+#[test]
+fn test_rename_errors() {
+    assert_eq!(
+        Rename::new("a", "1b").unwrap_err(),
+        RenameError::NotAName("1b".to_string())
+    );
+    assert_eq!(
+        Rename::new("a", "x_").unwrap_err(),
+        RenameError::NotAName("x_".to_string())
+    );
+    assert_eq!(
+        Rename::new("a", "sin").unwrap_err(),
+        RenameError::Builtin("sin".to_string())
+    );
+    let cells = [
+        EditorTreeSeq::from_notation("a=1|").unwrap(),
+        EditorTreeSeq::from_notation("b=a|").unwrap(),
+    ];
+    let rename = Rename::new("a", "b").unwrap();
+    assert_eq!(
+        rename.check(&cells),
+        Err(RenameError::Taken("b".to_string()))
+    );
+    assert_eq!(Rename::new("a", "c").unwrap().check(&cells), Ok(()));
+}
+
+/// Parses `notation`, rewrites it with `rules` and writes it back out, with what it assumes
+/// isn't zero.
+fn rewritten(notation: &str, rules: Rules) -> (String, Vec<String>) {
+    let tree = EditorTreeSeq::from_notation_without_caret(notation).unwrap();
+    let (parsed, idents) = parse(tree);
+    let rewritten = Rewriter::new(&idents, rules).rewrite(&parsed);
+    let nonzero = rewritten
+        .nonzero
+        .iter()
+        .map(|node| unparse(node, &idents).notation())
+        .collect();
+    (unparse(&rewritten.expr, &idents).notation(), nonzero)
+}
+
+fn simplified(notation: &str) -> String {
+    let (simplified, nonzero) = rewritten(notation, Rules::SIMPLIFY);
+    assert_eq!(nonzero, Vec::<String>::new(), "{notation}");
+    simplified
+}
+
+#[test]
+fn test_simplify() {
+    assert_eq!(simplified(r"x×2+1"), "2x+1|");
+    assert_eq!(simplified(r"2x+3x-x"), "4x|");
+    assert_eq!(simplified(r"x\pow{1}+y\pow{0}"), "x+1|");
+    assert_eq!(simplified(r"0x+y"), "y|");
+    assert_eq!(simplified(r"x-x"), "0|");
+    assert_eq!(simplified(r"a-\paren{b-c}"), "a-b+c|");
+    assert_eq!(simplified(r"\frac{x}{3}+\frac{x}{6}"), r"\frac{x}{2}|");
+    assert_eq!(simplified(r"0.1+0.2"), r"\frac{3}{10}|");
+    assert_eq!(simplified(r"x\pow{2}x\pow{3}"), r"x\pow{5}|");
+    assert_eq!(simplified(r"\paren{2x\pow{2}}\pow{3}"), r"8x\pow{6}|");
+    assert_eq!(
+        simplified(r"\frac{1}{x+1}+\frac{1}{x+1}"),
+        r"\frac{2}{x+1}|"
+    );
+    // higher powers first, and names before the rest.
+    assert_eq!(simplified(r"1+x+x\pow{2}"), r"x\pow{2}+x+1|");
+    assert_eq!(
+        simplified(r"sin\paren{x\pow{2}}2x"),
+        r"2x×sin\paren{x\pow{2}}|"
+    );
+    // inside what isn't rewritten itself.
+    assert_eq!(simplified(r"f\paren{x+0, 2x-x}"), r"f\paren{x,x}|");
+    assert_eq!(
+        simplified(r"\cases{x<0+0}{1x}{x+x}"),
+        r"\cases{x<0}{x}{2x}|"
+    );
+    assert_eq!(
+        simplified(r"\sum{n}{1}{10}n\pow{2}+0"),
+        r"\sum{n}{1}{10}n\pow{2}|"
+    );
+}
+
+#[test]
+fn test_simplify_lists() {
+    // a list times zero or to the zero is a list of zeros or ones, which doesn't go away.
+    assert_eq!(simplified(r"\brack{1,2}-\brack{1,2}"), r"0×\brack{1,2}|");
+    assert_eq!(simplified(r"0\brack{1...3}+x"), r"x+0×\brack{1...3}|");
+    assert_eq!(
+        simplified(r"sin\paren{\brack{1,2}}×0"),
+        r"0sin\paren{\brack{1,2}}|"
+    );
+    assert_eq!(simplified(r"\brack{1,2}\pow{0}"), r"\brack{1,2}\pow{0}|");
+    assert_eq!(
+        simplified(r"\frac{\brack{1,2}}{\brack{1,2}}"),
+        r"\frac{\brack{1,2}}{\brack{1,2}}|"
+    );
+    assert_eq!(simplified(r"\brack{1,2}+\brack{1,2}"), r"2×\brack{1,2}|");
+    // a list starting a product isn't multiplied by the minus before it.
+    assert_eq!(
+        simplified(r"x-\sqrt{y}\brack{1,2}"),
+        r"-\brack{1,2}\sqrt{y}+x|"
+    );
+}
+
+#[test]
+fn test_simplify_numbers() {
+    assert_eq!(simplified(r"2\pow{3}+\sqrt{4}+sin\paren{0}"), "10|");
+    assert_eq!(simplified(r"\root{3}{8}\abs{-2}"), "4|");
+    // which are only worked out when they're short.
+    assert_eq!(
+        simplified(r"\sqrt{2}+sin\paren{1}"),
+        r"sin\paren{1}+\sqrt{2}|"
+    );
+    assert_eq!(simplified(r"\frac{1}{0}"), r"\frac{1}{0}|");
+    assert_eq!(simplified(r"sin\pow{-1}\paren{x}"), r"arcsin\paren{x}|");
+    assert_eq!(simplified(r"sin\pow{2}\paren{x}"), r"sin\paren{x}\pow{2}|");
+}
+
+#[test]
+fn test_simplify_assumptions() {
+    let simplify = |notation| rewritten(notation, Rules::SIMPLIFY);
+    let strings = |strings: &[&str]| strings.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        simplify(r"\frac{x}{x}"),
+        ("1|".to_string(), strings(&["x|"]))
+    );
+    assert_eq!(
+        simplify(r"\frac{2x\pow{2}×y}{4x×y}"),
+        (r"\frac{x}{2}|".to_string(), strings(&["x|", "y|"]))
+    );
+    assert_eq!(
+        simplify(r"\frac{x+1}{x+1}"),
+        ("1|".to_string(), strings(&["x+1|"]))
+    );
+    // those that were undefined at zero before and still are need nothing.
+    assert_eq!(
+        simplify(r"\frac{1}{x}\frac{1}{x}"),
+        (r"\frac{1}{x\pow{2}}|".to_string(), Vec::new())
+    );
+    assert_eq!(
+        simplify(r"\frac{1}{\frac{1}{x}}"),
+        ("x|".to_string(), Vec::new())
+    );
+}
+
+#[test]
+fn test_rewrite_rules() {
+    assert_eq!(
+        rewritten(r"\paren{x+1}\paren{x-1}", Rules::SIMPLIFY).0,
+        r"\paren{x-1}\paren{x+1}|"
+    );
+    assert_eq!(
+        rewritten(r"\paren{x+1}\paren{x-1}", Rules::SIMPLIFY | Rules::EXPAND).0,
+        r"x\pow{2}-1|"
+    );
+    assert_eq!(
+        rewritten(
+            r"\paren{x+1}\pow{2}+\paren{a+b}c",
+            Rules::SIMPLIFY | Rules::EXPAND
+        )
+        .0,
+        r"a×c+b×c+x\pow{2}+2x+1|"
+    );
+    assert_eq!(rewritten(r"2x+3x+1+2", Rules::empty()).0, "2x+3x+3|");
+    assert_eq!(rewritten(r"2\pow{3}x", Rules::COLLECT).0, r"x×2\pow{3}|");
+    assert_eq!(
+        rewritten(r"\frac{x}{x}+x×x", Rules::FOLD | Rules::COLLECT),
+        (r"x\pow{2}+\frac{x}{x}|".to_string(), Vec::new())
+    );
+}
+
+#[test]
+fn test_unparse() {
+    let idents = IdentStorer::default();
+    let unparsed = |node: EvalNode| unparse(&node, &idents).notation();
+    let x = EvalNode::ident(idents.convert_id("x"));
+    let ab = EvalNode::ident(idents.convert_id("ab"));
+    let x_1 = EvalNode::ident(idents.convert_parts("x", Some("1")));
+    let n = EvalNode::number;
+    let sum =
+        |a: EvalNode, b: EvalNode| EvalNode::add_sub(vec![(AddOrSub::Add, a), (AddOrSub::Add, b)]);
+
+    // only the multiplying that wouldn't read the same otherwise is written.
+    assert_eq!(
+        unparsed(EvalNode::multiply(vec![n(2.0), x.clone(), ab.clone()])),
+        "2x×ab|"
+    );
+    assert_eq!(
+        unparsed(EvalNode::multiply(vec![n(2.0), n(3.0), x_1.clone()])),
+        r"2×3x\sub{1}|"
+    );
+    assert_eq!(
+        unparsed(EvalNode::multiply(vec![x.clone(), sum(x.clone(), n(1.0))])),
+        r"x×\paren{x+1}|"
+    );
+    assert_eq!(
+        unparsed(EvalNode::multiply(vec![n(2.0), sum(x.clone(), n(-1.0))])),
+        r"2\paren{x+\paren{-1}}|"
+    );
+    // and brackets are only added where they're needed.
+    assert_eq!(
+        unparsed(EvalNode::power(
+            EvalNode::frac(n(1.0), x.clone()),
+            EvalNode::power(x.clone(), n(2.0))
+        )),
+        r"\paren{\frac{1}{x}}\pow{x\pow{2}}|"
+    );
+    assert_eq!(
+        unparsed(EvalNode::add_sub(vec![
+            (AddOrSub::Add, n(-1.0)),
+            (AddOrSub::Sub, sum(x.clone(), n(1.0)))
+        ])),
+        r"-1-\paren{x+1}|"
+    );
+    assert_eq!(unparsed(n(f64::NAN)), r"\frac{0}{0}|");
+    assert_eq!(unparsed(n(1e20)), "100000000000000000000|");
+}
+
+#[test]
+fn test_unparse_round_trip() {
+    for notation in [
+        r"1+2x-3ab\sub{1}",
+        r"\frac{1}{x+1}\sqrt{x}\root{3}{x}\abs{x}",
+        r"sin\pow{2}\paren{x}+f\paren{x,y}\pow{2}",
+        r"\paren{1,2}+\brack{1,2,3}\brack{2}+\brack{1...10}",
+        r"\sum{n}{1}{10}n\pow{2}+\prod{i}{1}{n}\paren{i+1}",
+        r"\cases{x<0,y>=1}{1}{x=2}{}{3}",
+    ] {
+        let tree = EditorTreeSeq::from_notation_without_caret(notation).unwrap();
+        let (parsed, idents) = parse(tree);
+        let unparsed = unparse(&parsed, &idents);
+        assert_eq!(
+            parsing::parse(&unparsed, &idents).ok().as_ref(),
+            Some(&parsed),
+            "{notation} became {}",
+            unparsed.notation()
+        );
+    }
+}
+
+/// A random number generator that gives the same numbers every time.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn between(&mut self, low: f64, high: f64) -> f64 {
+        let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        low + unit * (high - low)
+    }
+
+    /// One of a few lists, so that the same ones come up more than once in an expression.
+    fn list(&mut self, idents: &[IdentId]) -> EvalNode {
+        let number = EvalNode::number;
+        match self.below(3) {
+            0 => EvalNode::list_literal(vec![number(1.0), number(2.0)]),
+            1 => EvalNode::list_literal(vec![EvalNode::ident(idents[0]), number(-1.0)]),
+            _ => EvalNode::list_range(number(1.0), None, number(3.0)),
+        }
+    }
+
+    /// An expression in `idents`, of about `depth` levels, with lists in it if `lists` is set.
+    fn expr(&mut self, idents: &[IdentId], lists: bool, depth: u32) -> EvalNode {
+        let exprs = |rng: &mut Self, count: u64| -> Vec<EvalNode> {
+            (0..2 + rng.below(count))
+                .map(|_| rng.expr(idents, lists, depth - 1))
+                .collect()
+        };
+        if depth == 0 || self.below(4) == 0 {
+            if lists && self.below(6) == 0 {
+                return self.list(idents);
+            }
+            return match self.below(3) {
+                0 => EvalNode::number(self.below(5) as f64),
+                _ => EvalNode::ident(idents[self.below(idents.len() as u64) as usize]),
+            };
+        }
+        match self.below(8) {
+            0 | 1 => {
+                let pairs = exprs(self, 2)
+                    .into_iter()
+                    .map(|expr| match self.below(2) {
+                        0 => (AddOrSub::Add, expr),
+                        _ => (AddOrSub::Sub, expr),
+                    })
+                    .collect();
+                EvalNode::add_sub(pairs)
+            }
+            2 | 3 => EvalNode::multiply(exprs(self, 2)),
+            4 => EvalNode::frac(
+                self.expr(idents, lists, depth - 1),
+                self.expr(idents, lists, depth - 1),
+            ),
+            5 => {
+                let power = EvalNode::number(self.below(6) as f64 - 2.0);
+                EvalNode::power(self.expr(idents, lists, depth - 1), power)
+            }
+            6 => EvalNode::sqrt(self.expr(idents, lists, depth - 1)),
+            _ => {
+                let func = [MonadicPervasive::Sin, MonadicPervasive::Cos][self.below(2) as usize];
+                let params = vec![self.expr(idents, lists, depth - 1)];
+                EvalNode::builtins_call(Builtins::MonadicPervasive(func), None, params)
+            }
+        }
+    }
+}
+
+/// Rewrites random expressions, checking that they evaluate to the same at random points,
+/// before and after being written out and parsed back.
+#[test]
+fn test_rewrite_random() {
+    let idents = IdentStorer::default();
+    let names = [idents.convert_id("x"), idents.convert_id("y")];
+    let env = Env::new(&idents);
+    let mut rng = Xorshift(0x2545_f491_4f6c_dd1d);
+    let mut compared = 0;
+    for _ in 0..400 {
+        let expr = rng.expr(&names, true, 4);
+        for rules in [Rules::SIMPLIFY, Rules::SIMPLIFY | Rules::EXPAND] {
+            let rewritten = Rewriter::new(&idents, rules).rewrite(&expr);
+            let tree = unparse(&rewritten.expr, &idents);
+            let parsed = parsing::parse(&tree, &idents).unwrap();
+            assert_eq!(parsed, rewritten.expr, "{}", tree.notation());
+
+            for _ in 0..4 {
+                let point: Vec<_> = names
+                    .iter()
+                    .map(|name| (*name, Value::one_number(rng.between(-3.0, 3.0))))
+                    .collect();
+                let value = |node: &EvalNode| {
+                    let value = env.evaluate_with(node, point.clone()).ok()?;
+                    match value.try_number().ok()? {
+                        List::Term(x) => Some(vec![x]),
+                        List::Flat(xs) => Some(xs),
+                        List::Staggered(_) => None,
+                    }
+                };
+                let Some(before) = value(&expr).filter(|xs| xs.iter().all(|x| x.is_finite()))
+                else {
+                    continue;
+                };
+                if rewritten
+                    .nonzero
+                    .iter()
+                    .any(|node| value(node).is_some_and(|xs| xs.contains(&0.0)))
+                {
+                    continue;
+                }
+                let after = value(&rewritten.expr).unwrap_or_default();
+                // lists of numbers aren't worked out, so what's zero in one can come out a little
+                // below zero once multiplied out, where its root has no value.
+                let is_close = |(before, after): (&f64, &f64)| {
+                    (before - after).abs() <= 1e-6 * (1.0 + before.abs())
+                        || (*before == 0.0 && after.is_nan())
+                };
+                assert!(
+                    before.len() == after.len() && before.iter().zip(&after).all(is_close),
+                    "{expr:?} became {} at {point:?}: {before:?} and {after:?}",
+                    tree.notation()
+                );
+                compared += 1;
+            }
+        }
+    }
+    assert!(compared > 1000, "{compared}");
+}
+
+/// The simplified derivative by `var` of the last of `cells`, with what the others define in
+/// scope.
+fn derived(cells: &[&str], var: &str) -> EvalResult<String> {
+    let idents = IdentStorer::default();
+    let statements: Vec<_> = cells
+        .iter()
+        .map(|cell| {
+            let tree = EditorTreeSeq::from_notation_without_caret(cell).unwrap();
+            parsing::parse_statement(&tree, &idents).unwrap()
+        })
+        .collect();
+    let (last, defined) = statements.split_last().unwrap();
+    let mut env = Env::new(&idents);
+    for statement in defined {
+        match statement {
+            Statement::Variable(def) => env.define(def),
+            Statement::Function(def) => env.define_function(def),
+            Statement::Expression(_) => {}
+        }
+    }
+    let derived = derivative(&env, last, idents.convert_id(var))?;
+    let simplified = Rewriter::new(&idents, Rules::SIMPLIFY).rewrite(&derived);
+    Ok(unparse(&simplified.expr, &idents).notation())
+}
+
+#[test]
+fn test_derivative() {
+    let derive = |cell| derived(&[cell], "x").unwrap();
+    assert_eq!(derive(r"sin\paren{x\pow{2}}"), r"2x×cos\paren{x\pow{2}}|");
+    assert_eq!(derive(r"3x\pow{4}-2x+7"), r"12x\pow{3}-2|");
+    assert_eq!(derive(r"\frac{1}{x}"), r"-\frac{1}{x\pow{2}}|");
+    assert_eq!(derive(r"\sqrt{x}"), r"\frac{1}{2\sqrt{x}}|");
+    assert_eq!(derive(r"\root{3}{x}"), r"\frac{1}{3\root{3}{x}\pow{2}}|");
+    assert_eq!(derive(r"x\pow{n}"), r"n×x\pow{n-1}|");
+    assert_eq!(derive(r"a×x\pow{2}"), r"2a×x|");
+    assert_eq!(derive(r"\abs{x}"), r"sign\paren{x}|");
+    assert_eq!(
+        derive(r"sin\pow{2}\paren{x}"),
+        r"2cos\paren{x}sin\paren{x}|"
+    );
+    assert_eq!(
+        derive(r"sin\pow{-1}\paren{x}"),
+        r"\frac{1}{\sqrt{-x\pow{2}+1}}|"
+    );
+    assert_eq!(derive(r"\frac{x}{x+1}"), r"\frac{1}{\paren{x+1}\pow{2}}|");
+    assert_eq!(derive(r"\cases{x>0}{x\pow{2}}{0}"), r"\cases{x>0}{2x}{0}|");
+    assert_eq!(
+        derive(r"\sum{n}{1}{3}x\pow{n}"),
+        r"\sum{n}{1}{3}n×x\pow{n-1}|"
+    );
+    assert_eq!(derive(r"\paren{x,2x}"), r"\paren{1,2}|");
+    assert_eq!(derive(r"2\pow{x}"), r"ln\paren{2}2\pow{x}|");
+    assert_eq!(derive(r"2\pow{3x}"), r"3ln\paren{2}2\pow{3x}|");
+    assert_eq!(derive(r"x\pow{x}"), r"x\pow{x}×\paren{ln\paren{x}+1}|");
+    assert_eq!(derive(r"ln\paren{x}"), r"\frac{1}{x}|");
+    // a function's parameters don't depend on the variable.
+    assert_eq!(derive(r"f\paren{t}=t×x\pow{2}"), r"2t×x|");
+    assert_eq!(
+        derived(&[r"f\paren{t}=t×x\pow{2}"], "t").unwrap(),
+        r"x\pow{2}|"
+    );
+}
+
+#[test]
+fn test_derivative_inlining() {
+    assert_eq!(
+        derived(&[r"f\paren{t}=sin\paren{t}", r"f\paren{x\pow{2}}"], "x").unwrap(),
+        r"2x×cos\paren{x\pow{2}}|"
+    );
+    assert_eq!(
+        derived(&[r"a=x\pow{2}", r"b=3a", r"b+a"], "x").unwrap(),
+        "8x|"
+    );
+    // only what's differentiated is inlined.
+    assert_eq!(
+        derived(&[r"a=x\pow{2}", r"a×x"], "x").unwrap(),
+        r"2x\pow{2}+a|"
+    );
+    assert_eq!(
+        derived(
+            &[
+                r"g\paren{x}=x\pow{3}",
+                r"f\paren{x,y}=g\paren{x}y",
+                r"f\paren{2x,x}"
+            ],
+            "x"
+        )
+        .unwrap(),
+        r"24x\pow{3}+g\paren{2x}|"
+    );
+    // calling what isn't a function multiplies by it.
+    assert_eq!(derived(&[r"a=x", r"a\paren{x+1}"], "x").unwrap(), "a+x+1|");
+    assert_eq!(
+        derived(&[r"f\paren{x}=x", r"f\pow{2}\paren{x}"], "x").unwrap(),
+        r"2f\paren{x}|"
+    );
+}
+
+#[test]
+fn test_derivative_errors() {
+    assert_eq!(
+        derived(&[r"a=b", r"b=a+x", r"a"], "x"),
+        Err(EvalError::Cycle("a".to_string()))
+    );
+    assert_eq!(
+        derived(&[r"f\paren{t}=t", r"f\paren{x,x}"], "x"),
+        Err(EvalError::Arity {
+            name: "f".to_string(),
+            expected: 1,
+            found: 2
+        })
+    );
+    assert_eq!(
+        derived(&[r"mod\paren{x,2}"], "x"),
+        Err(EvalError::Unsupported(
+            "differentiating builtins that take more than a number"
+        ))
+    );
+}
+
+/// Compares `derived` at `x` with the slope of `node` around it, if `node` is smooth enough
+/// there for the slope to be worked out well.
+fn check_slope(env: &Env, x_id: IdentId, node: &EvalNode, derived: &EvalNode, x: f64) -> bool {
+    let value = |node: &EvalNode, x: f64| {
+        env.evaluate_with(node, [(x_id, Value::one_number(x))])
+            .ok()
+            .and_then(|value| value.try_number().ok()?.try_term())
+            .filter(|value| value.is_finite())
+    };
+    let slope = |h: f64| Some((value(node, x + h)? - value(node, x - h)?) / (2.0 * h));
+    let (Some(coarse), Some(fine)) = (slope(1e-3), slope(1e-4)) else {
+        return false;
+    };
+    if (coarse - fine).abs() > 1e-4 * (1.0 + fine.abs()) {
+        return false;
+    }
+    let exact = value(derived, x).unwrap_or(f64::NAN);
+    assert!(
+        (exact - fine).abs() <= 1e-5 * (1.0 + fine.abs()),
+        "{node:?} has the derivative {derived:?}, {exact} at {x} and not {fine}"
+    );
+    true
+}
+
+#[test]
+fn test_derivative_monadic() {
+    let idents = IdentStorer::default();
+    let x = idents.convert_id("x");
+    let env = Env::new(&idents);
+    let mut rng = Xorshift(0x9e37_79b9_7f4a_7c15);
+    for func in MonadicPervasive::ALL {
+        // an argument that goes past where most of them are defined, and isn't one to one.
+        let param = EvalNode::add_sub(vec![
+            (
+                AddOrSub::Add,
+                EvalNode::frac(
+                    EvalNode::power(EvalNode::ident(x), EvalNode::number(2.0)),
+                    EvalNode::number(3.0),
+                ),
+            ),
+            (AddOrSub::Sub, EvalNode::number(0.5)),
+        ]);
+        let node = EvalNode::builtins_call(Builtins::MonadicPervasive(*func), None, vec![param]);
+        let derived = derivative(&env, &Statement::Expression(node.clone()), x).unwrap();
+        let compared = (0..200)
+            .filter(|_| check_slope(&env, x, &node, &derived, rng.between(-3.0, 3.0)))
+            .count();
+        let is_flat = matches!(
+            func,
+            MonadicPervasive::Sign
+                | MonadicPervasive::Floor
+                | MonadicPervasive::Ceil
+                | MonadicPervasive::Round
+        );
+        assert!(
+            is_flat || compared > 20,
+            "{func:?} was only compared {compared} times"
+        );
+    }
+}
+
+/// Differentiates random expressions, checking the derivatives against their slopes at random
+/// points, before and after simplifying.
+#[test]
+fn test_derivative_random() {
+    let idents = IdentStorer::default();
+    let names = [idents.convert_id("x"), idents.convert_id("a")];
+    let env = Env::new(&idents);
+    let mut rng = Xorshift(0x5851_f42d_4c95_7f2d);
+    let mut compared = 0;
+    for _ in 0..300 {
+        // without what's multiplied by zero and the like, where the chain rule has no answer.
+        let expr = Rewriter::new(&idents, Rules::SIMPLIFY)
+            .rewrite(&rng.expr(&names, false, 4))
+            .expr;
+        let derived = derivative(&env, &Statement::Expression(expr.clone()), names[0]).unwrap();
+        let simplified = Rewriter::new(&idents, Rules::SIMPLIFY).rewrite(&derived);
+        for _ in 0..4 {
+            // `a` is a constant.
+            let a = rng.between(-3.0, 3.0);
+            let x = rng.between(-3.0, 3.0);
+            let mut env = Env::new(&idents);
+            env.set(names[1], Value::one_number(a));
+            for derived in [&derived, &simplified.expr] {
+                compared += usize::from(check_slope(&env, names[0], &expr, derived, x));
+            }
+        }
+    }
+    assert!(compared > 1000, "{compared}");
+}
@@ -36,10 +36,18 @@ impl EvalNode {
         Self::new(EvalKind::Abs(node))
     }
 
+    pub fn frac(top: EvalNode, bottom: EvalNode) -> Self {
+        Self::new(EvalKind::Frac { top, bottom })
+    }
+
     pub fn sqrt(node: EvalNode) -> Self {
         Self::new(EvalKind::Sqrt(node))
     }
 
+    pub fn root(index: EvalNode, expr: EvalNode) -> Self {
+        Self::new(EvalKind::Root { index, expr })
+    }
+
     pub fn list_literal(nodes: Vec<Self>) -> Self {
         Self::new(EvalKind::List(nodes))
     }
@@ -107,7 +115,7 @@ impl EvalNode {
 
 #[derive(Clone, Default)]
 pub struct IdentStorer {
-    ids: FrozenVec<Box<str>>,
+    ids: FrozenVec<Box<IdentName>>,
 }
 
 impl std::fmt::Debug for IdentStorer {
@@ -118,15 +126,31 @@ impl std::fmt::Debug for IdentStorer {
 
 impl IdentStorer {
     pub fn convert_id(&self, ident: &str) -> IdentId {
-        if let Some(id) = self.ids.iter().position(|x| x == ident) {
+        self.convert_parts(ident, None)
+    }
+
+    /// The id of an identifier made of a `base` and maybe a subscript, like `x_1`.
+    pub fn convert_parts(&self, base: &str, subscript: Option<&str>) -> IdentId {
+        let is_same = |name: &IdentName| &*name.base == base && name.subscript() == subscript;
+        if let Some(id) = self.ids.iter().position(is_same) {
             IdentId(id)
         } else {
             let new_id = self.ids.len();
-            self.ids.push(ident.to_string().into_boxed_str());
+            self.ids.push(Box::new(IdentName {
+                base: base.into(),
+                subscript: subscript.map(Into::into),
+            }));
             IdentId(new_id)
         }
     }
 
+    /// The name an id was made from.
+    pub fn name(&self, ident: IdentId) -> &IdentName {
+        self.ids
+            .get(ident.0)
+            .unwrap_or_else(|| unreachable!("ids only come from `convert_parts`"))
+    }
+
     pub fn len(&self) -> usize {
         self.ids.len()
     }
@@ -136,7 +160,35 @@ impl IdentStorer {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// The name of an identifier, in the parts it's written with. It's shown with an underscore
+/// before the subscript, like `x_1`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdentName {
+    base: Box<str>,
+    subscript: Option<Box<str>>,
+}
+
+impl IdentName {
+    /// The letters of the name.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn subscript(&self) -> Option<&str> {
+        self.subscript.as_deref()
+    }
+}
+
+impl std::fmt::Display for IdentName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subscript {
+            Some(subscript) => write!(f, "{}_{subscript}", self.base),
+            None => write!(f, "{}", self.base),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct IdentId(usize);
 
 impl IdentId {
@@ -212,6 +264,51 @@ impl VarDef {
     pub fn new(ident: IdentId, expr: EvalNode) -> Self {
         Self { ident, expr }
     }
+
+    pub fn ident(&self) -> IdentId {
+        self.ident
+    }
+
+    pub fn expr(&self) -> &EvalNode {
+        &self.expr
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDef {
+    ident: IdentId,
+    params: Vec<IdentId>,
+    expr: EvalNode,
+}
+
+impl FunctionDef {
+    pub fn new(ident: IdentId, params: Vec<IdentId>, expr: EvalNode) -> Self {
+        Self {
+            ident,
+            params,
+            expr,
+        }
+    }
+
+    pub fn ident(&self) -> IdentId {
+        self.ident
+    }
+
+    pub fn params(&self) -> &[IdentId] {
+        &self.params
+    }
+
+    pub fn expr(&self) -> &EvalNode {
+        &self.expr
+    }
+}
+
+/// What a whole cell says.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Expression(EvalNode),
+    Variable(VarDef),
+    Function(FunctionDef),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -224,6 +321,14 @@ impl Conditional {
     pub fn new(expr: EvalNode, comps: Vec<(CompSet, EvalNode)>) -> Self {
         Self { expr, comps }
     }
+
+    pub fn expr(&self) -> &EvalNode {
+        &self.expr
+    }
+
+    pub fn comps(&self) -> &[(CompSet, EvalNode)] {
+        &self.comps
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -257,6 +362,10 @@ pub enum EvalKind {
         bottom: EvalNode,
     },
     Sqrt(EvalNode),
+    Root {
+        index: EvalNode,
+        expr: EvalNode,
+    },
     Power {
         base: EvalNode,
         power: EvalNode,
@@ -0,0 +1,359 @@
+//! Writing expressions back out as trees, the opposite of parsing, so that what's worked out from a
+//! cell can be edited like one that was typed in.
+//!
+//! Brackets are only added where the parser needs them, and multiplication is only written out
+//! where leaving it out would read differently, like between two letters.
+
+use fast_desmos2_tree::tree::{
+    EditorTree, EditorTreeKind, EditorTreeSeq, FractionIndex, PiecewiseIndex, PiecewiseRow,
+    RootIndex, SumOrProd, SumProdIndex, SurroundIndex,
+};
+
+use crate::tree::{
+    AddOrSub, CompSet, Conditional, Element, EvalKind, EvalNode, IdentId, IdentStorer, VarDef,
+};
+
+/// The tree of `node`, which parses back to an expression with the same value.
+pub fn unparse(node: &EvalNode, idents: &IdentStorer) -> EditorTreeSeq {
+    let children = Unparser { idents }.seq(node);
+    EditorTreeSeq::new(children.len(), children)
+}
+
+/// Where an expression is written, from the loosest place to the tightest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Place {
+    /// Anywhere a whole expression goes, like a sequence of its own.
+    Whole,
+    /// A term being added or subtracted.
+    Term,
+    /// One of the things being multiplied.
+    Factor,
+    /// Something with a power, an index or a `.x` after it.
+    Base,
+}
+
+struct Unparser<'a> {
+    idents: &'a IdentStorer,
+}
+
+impl Unparser<'_> {
+    fn seq(&self, node: &EvalNode) -> Vec<EditorTree> {
+        let mut out = Vec::new();
+        self.write(node, Place::Whole, &mut out);
+        out
+    }
+
+    fn tree_seq(&self, node: &EvalNode) -> EditorTreeSeq {
+        EditorTreeSeq::first(self.seq(node))
+    }
+
+    /// Writes `node`, in brackets if it can't go in `place` without them.
+    fn write(&self, node: &EvalNode, place: Place, out: &mut Vec<EditorTree>) {
+        if place > loosest_place(node) {
+            let inner = self.tree_seq(node);
+            out.push(EditorTree::complete_paren(SurroundIndex::Left, inner));
+            return;
+        }
+        match node.kind() {
+            EvalKind::Identifier(ident) => self.ident(*ident, out),
+            EvalKind::Number(x) => number(*x, out),
+            EvalKind::BuiltinsCall {
+                builtins,
+                power,
+                params,
+            } => {
+                out.extend(builtins.as_str().chars().map(EditorTree::terminal));
+                self.call(power.as_ref(), params, out);
+            }
+            EvalKind::FunctionCall {
+                ident,
+                power,
+                params,
+            } => {
+                self.ident(*ident, out);
+                self.call(power.as_ref(), params, out);
+            }
+            EvalKind::Abs(inner) => {
+                out.push(EditorTree::complete_abs(
+                    SurroundIndex::Left,
+                    self.tree_seq(inner),
+                ));
+            }
+            EvalKind::Point(x, y) => {
+                let inner = self.separated([x, y], ',');
+                out.push(EditorTree::complete_paren(SurroundIndex::Left, inner));
+            }
+            EvalKind::List(items) => {
+                let inner = self.separated(items, ',');
+                out.push(EditorTree::complete_brackets(SurroundIndex::Left, inner));
+            }
+            EvalKind::SumProd {
+                kind,
+                ident,
+                from,
+                to,
+                expr,
+            } => {
+                let mut ident_seq = Vec::new();
+                self.ident(*ident, &mut ident_seq);
+                let (top, bottom, ident) = (
+                    self.tree_seq(to),
+                    self.tree_seq(from),
+                    EditorTreeSeq::first(ident_seq),
+                );
+                out.push(match kind {
+                    SumOrProd::Sum => EditorTree::sum(SumProdIndex::Left, top, bottom, ident),
+                    SumOrProd::Prod => EditorTree::prod(SumProdIndex::Left, top, bottom, ident),
+                });
+                self.write(expr, Place::Factor, out);
+            }
+            EvalKind::Multiply(factors) => self.multiply(factors, out),
+            EvalKind::AddSub(pairs) => {
+                for (index, (sign, term)) in pairs.iter().enumerate() {
+                    match sign {
+                        AddOrSub::Add if index == 0 => {}
+                        AddOrSub::Add => out.push(EditorTree::terminal('+')),
+                        AddOrSub::Sub => out.push(EditorTree::terminal('-')),
+                    }
+                    // a negative number can only come first, where its sign reads the same.
+                    let place = match index == 0 && *sign == AddOrSub::Add {
+                        true => Place::Whole,
+                        false => Place::Term,
+                    };
+                    self.write(term, place, out);
+                }
+            }
+            EvalKind::Frac { top, bottom } => out.push(EditorTree::fraction(
+                FractionIndex::Left,
+                self.tree_seq(top),
+                self.tree_seq(bottom),
+            )),
+            EvalKind::Sqrt(inner) => {
+                out.push(EditorTree::sqrt(SurroundIndex::Left, self.tree_seq(inner)));
+            }
+            EvalKind::Root { index, expr } => out.push(EditorTree::root(
+                RootIndex::Left,
+                self.tree_seq(index),
+                self.tree_seq(expr),
+            )),
+            EvalKind::Power { base, power } => {
+                self.write(base, Place::Base, out);
+                out.push(EditorTree::power(self.tree_seq(power)));
+            }
+            EvalKind::For { expr, defs } => self.defs(expr, " for ", defs, out),
+            EvalKind::ListComp { expr, defs } => {
+                let mut inner = Vec::new();
+                self.defs(expr, " for ", defs, &mut inner);
+                out.push(EditorTree::complete_brackets(
+                    SurroundIndex::Left,
+                    EditorTreeSeq::first(inner),
+                ));
+            }
+            EvalKind::With { expr, defs } => self.defs(expr, " with ", defs, out),
+            EvalKind::ListRange { from, next, to } => {
+                let mut inner = Vec::new();
+                self.write(from, Place::Whole, &mut inner);
+                if let Some(next) = next {
+                    inner.push(EditorTree::terminal(','));
+                    self.write(next, Place::Whole, &mut inner);
+                }
+                inner.extend("...".chars().map(EditorTree::terminal));
+                self.write(to, Place::Whole, &mut inner);
+                out.push(EditorTree::complete_brackets(
+                    SurroundIndex::Left,
+                    EditorTreeSeq::first(inner),
+                ));
+            }
+            EvalKind::IfElse { .. } => out.push(self.piecewise(node)),
+            EvalKind::ElemAccess { expr, element } => {
+                self.write(expr, Place::Base, out);
+                out.push(EditorTree::terminal('.'));
+                out.push(EditorTree::terminal(match element {
+                    Element::X => 'x',
+                    Element::Y => 'y',
+                }));
+            }
+            EvalKind::ListIndexing { expr, index } => {
+                self.write(expr, Place::Base, out);
+                out.push(EditorTree::complete_brackets(
+                    SurroundIndex::Left,
+                    self.tree_seq(index),
+                ));
+            }
+        }
+    }
+
+    /// Writes a name, along with its subscript.
+    fn ident(&self, ident: IdentId, out: &mut Vec<EditorTree>) {
+        let name = self.idents.name(ident);
+        out.extend(name.base().chars().map(EditorTree::terminal));
+        if let Some(subscript) = name.subscript() {
+            out.push(EditorTree::subscript(EditorTreeSeq::str(subscript)));
+        }
+    }
+
+    /// Writes what comes after the name of a function being called.
+    fn call(&self, power: Option<&EvalNode>, params: &[EvalNode], out: &mut Vec<EditorTree>) {
+        if let Some(power) = power {
+            out.push(EditorTree::power(self.tree_seq(power)));
+        }
+        out.push(EditorTree::complete_paren(
+            SurroundIndex::Left,
+            self.separated(params, ','),
+        ));
+    }
+
+    fn separated<'n>(
+        &self,
+        nodes: impl IntoIterator<Item = &'n EvalNode>,
+        separator: char,
+    ) -> EditorTreeSeq {
+        let mut out = Vec::new();
+        for (index, node) in nodes.into_iter().enumerate() {
+            if index > 0 {
+                out.push(EditorTree::terminal(separator));
+            }
+            self.write(node, Place::Whole, &mut out);
+        }
+        EditorTreeSeq::first(out)
+    }
+
+    fn multiply(&self, factors: &[EvalNode], out: &mut Vec<EditorTree>) {
+        let start = out.len();
+        for (index, factor) in factors.iter().enumerate() {
+            let mut written = Vec::new();
+            // a sum or product takes everything multiplied after it.
+            let is_last = index + 1 == factors.len();
+            match factor.kind() {
+                EvalKind::SumProd { .. } if !is_last => {
+                    let inner = self.tree_seq(factor);
+                    written.push(EditorTree::complete_paren(SurroundIndex::Left, inner));
+                }
+                _ => self.write(factor, Place::Factor, &mut written),
+            }
+            // what's before the product, like a minus, is never multiplied.
+            if let (Some(before), Some(after)) = (out[start..].last(), written.first()) {
+                if needs_times(before, after) {
+                    out.push(EditorTree::terminal('×'));
+                }
+            }
+            out.extend(written);
+        }
+    }
+
+    fn defs(&self, expr: &EvalNode, word: &str, defs: &[VarDef], out: &mut Vec<EditorTree>) {
+        self.write(expr, Place::Whole, out);
+        out.extend(word.chars().map(EditorTree::terminal));
+        for (index, def) in defs.iter().enumerate() {
+            if index > 0 {
+                out.push(EditorTree::terminal(','));
+            }
+            self.ident(def.ident(), out);
+            out.push(EditorTree::terminal('='));
+            self.write(def.expr(), Place::Whole, out);
+        }
+    }
+
+    /// A piecewise node with a row for each branch in the chain of `if_else`s starting at `node`.
+    fn piecewise(&self, mut node: &EvalNode) -> EditorTree {
+        let mut rows = Vec::new();
+        let default = loop {
+            let EvalKind::IfElse { conds, yes, no } = node.kind() else {
+                break self.tree_seq(node);
+            };
+            let value = yes
+                .as_ref()
+                .map_or_else(EditorTreeSeq::empty, |yes| self.tree_seq(yes));
+            rows.push(PiecewiseRow::new(self.conditions(conds), value));
+            match no {
+                Some(no) => node = no,
+                None => break EditorTreeSeq::empty(),
+            }
+        };
+        EditorTree::piecewise(PiecewiseIndex::Left, rows, default)
+    }
+
+    fn conditions(&self, conds: &[Conditional]) -> EditorTreeSeq {
+        let mut out = Vec::new();
+        for (index, cond) in conds.iter().enumerate() {
+            if index > 0 {
+                out.push(EditorTree::terminal(','));
+            }
+            self.write(cond.expr(), Place::Whole, &mut out);
+            for (set, right) in cond.comps() {
+                let symbol = match *set {
+                    CompSet::LESS => "<",
+                    CompSet::MORE => ">",
+                    CompSet::LESS_OR_EQUAL => "<=",
+                    CompSet::MORE_OR_EQUAL => ">=",
+                    _ => "=",
+                };
+                out.extend(symbol.chars().map(EditorTree::terminal));
+                self.write(right, Place::Whole, &mut out);
+            }
+        }
+        EditorTreeSeq::first(out)
+    }
+}
+
+/// The tightest place `node` can be written in without brackets.
+fn loosest_place(node: &EvalNode) -> Place {
+    match node.kind() {
+        EvalKind::AddSub(_) => Place::Whole,
+        EvalKind::Number(x) if x.is_sign_negative() || !x.is_finite() => Place::Whole,
+        EvalKind::Multiply(_) | EvalKind::Frac { .. } | EvalKind::SumProd { .. } => Place::Factor,
+        EvalKind::For { .. } | EvalKind::With { .. } => Place::Whole,
+        EvalKind::Power { .. }
+        | EvalKind::BuiltinsCall { power: Some(_), .. }
+        | EvalKind::FunctionCall { power: Some(_), .. } => Place::Factor,
+        _ => Place::Base,
+    }
+}
+
+/// Writes a number so that it parses back the same. Those without digits are written as
+/// fractions that work out to them.
+fn number(x: f64, out: &mut Vec<EditorTree>) {
+    if x.is_nan() || x.is_infinite() {
+        let top = if x.is_nan() { "0" } else { "1" };
+        if x == f64::NEG_INFINITY {
+            out.push(EditorTree::terminal('-'));
+        }
+        out.push(EditorTree::fraction(
+            FractionIndex::Left,
+            EditorTreeSeq::str(top),
+            EditorTreeSeq::str("0"),
+        ));
+        return;
+    }
+    if x.is_sign_negative() && x != 0.0 {
+        out.push(EditorTree::terminal('-'));
+    }
+    // `Display` for floats never uses an exponent, which the parser doesn't know.
+    let digits = format!("{}", x.abs());
+    out.extend(digits.chars().map(EditorTree::terminal));
+}
+
+/// Whether writing `after` right after `before` would read as something else than multiplying
+/// them, like `ab` being one name or `a(b)` a call.
+fn needs_times(before: &EditorTree, after: &EditorTree) -> bool {
+    let before_char = before.is_terminal_then(|term| term.ch());
+    let is_name_end = before_char.is_some_and(|ch| ch.is_ascii_alphabetic())
+        || matches!(
+            before.kind(),
+            EditorTreeKind::Subscript(_) | EditorTreeKind::Power(_)
+        );
+    match (before_char, after.kind()) {
+        // a number before a name is the usual way to write it, like `2x`.
+        (Some(before), EditorTreeKind::Terminal(after)) => {
+            let after = after.ch();
+            let is_number_part = |ch: char| ch.is_ascii_digit() || ch == '.';
+            (before.is_ascii_alphanumeric() || before == '.')
+                && (after.is_ascii_alphanumeric() || after == '.')
+                && !(is_number_part(before) && after.is_ascii_alphabetic())
+        }
+        (_, EditorTreeKind::Paren(_)) => is_name_end,
+        // a list after anything is indexing it.
+        (_, EditorTreeKind::Bracket(_)) => true,
+        _ => false,
+    }
+}